@@ -28,6 +28,15 @@ PROVIDE (esp_rom_md5_init   = 0x4005da7c);
 PROVIDE (esp_rom_md5_update = 0x4005da9c);
 PROVIDE (esp_rom_md5_final  = 0x4005db1c);
 
+PROVIDE(esp_rom_spiflash_read = 0x40062ed8);
+PROVIDE(esp_rom_spiflash_unlock = 0x400628b0);
+PROVIDE(esp_rom_spiflash_erase_sector = 0x40062ccc);
+PROVIDE(esp_rom_spiflash_write = 0x40062d50);
+
+PROVIDE(Cache_Read_Disable_rom = 0x40009ab8);
+PROVIDE(Cache_Read_Enable_rom = 0x40009a84);
+PROVIDE(Cache_Flush_rom = 0x40009a14);
+
 memcmp = 0x4000c260;
 memcpy = 0x4000c2c8;
 memmove = 0x4000c3c0;
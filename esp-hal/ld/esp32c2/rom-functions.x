@@ -17,6 +17,11 @@ PROVIDE(esp_rom_mbedtls_md5_starts_ret = 0x40002be4);
 PROVIDE(esp_rom_mbedtls_md5_update_ret = 0x40002be8);
 PROVIDE(esp_rom_mbedtls_md5_finish_ret = 0x40002bec);
 
+PROVIDE(esp_rom_spiflash_read = 0x4000013c);
+PROVIDE(esp_rom_spiflash_unlock = 0x40000140);
+PROVIDE(esp_rom_spiflash_erase_sector = 0x40000130);
+PROVIDE(esp_rom_spiflash_write = 0x40000138);
+
 memset = 0x40000488;
 memcpy = 0x4000048c;
 memmove = 0x40000490;
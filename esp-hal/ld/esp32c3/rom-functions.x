@@ -24,6 +24,11 @@ PROVIDE(esp_rom_md5_init = 0x40000614);
 PROVIDE(esp_rom_md5_update = 0x40000618);
 PROVIDE(esp_rom_md5_final = 0x4000061c);
 
+PROVIDE(esp_rom_spiflash_read = 0x40000130);
+PROVIDE(esp_rom_spiflash_unlock = 0x40000140);
+PROVIDE(esp_rom_spiflash_erase_sector = 0x40000128);
+PROVIDE(esp_rom_spiflash_write = 0x4000012c);
+
 memset = 0x40000354;
 memcpy = 0x40000358;
 memmove = 0x4000035c;
@@ -21,6 +21,11 @@ PROVIDE(esp_rom_md5_init = 0x4000074c);
 PROVIDE(esp_rom_md5_update = 0x40000750);
 PROVIDE(esp_rom_md5_final = 0x40000754);
 
+PROVIDE(esp_rom_spiflash_read = 0x40000150);
+PROVIDE(esp_rom_spiflash_unlock = 0x40000154);
+PROVIDE(esp_rom_spiflash_erase_sector = 0x40000144);
+PROVIDE(esp_rom_spiflash_write = 0x4000014c);
+
 memset = 0x400004a8;
 memcpy = 0x400004ac;
 memmove = 0x400004b0;
@@ -21,6 +21,11 @@ PROVIDE(esp_rom_md5_init = 0x40000718);
 PROVIDE(esp_rom_md5_update = 0x4000071c);
 PROVIDE(esp_rom_md5_final = 0x40000720);
 
+PROVIDE(esp_rom_spiflash_read = 0x4000012c);
+PROVIDE(esp_rom_spiflash_unlock = 0x40000130);
+PROVIDE(esp_rom_spiflash_erase_sector = 0x40000120);
+PROVIDE(esp_rom_spiflash_write = 0x40000128);
+
 memset = 0x400004a0;
 memcpy = 0x400004a4;
 memmove = 0x400004a8;
@@ -28,6 +28,11 @@ PROVIDE(esp_rom_md5_final = 0x4000530c);
 PROVIDE(esp_rom_md5_init = 0x4000526c);
 PROVIDE(esp_rom_md5_update = 0x4000528c);
 
+PROVIDE(esp_rom_spiflash_read = 0x4001728c);
+PROVIDE(esp_rom_spiflash_unlock = 0x40016e88);
+PROVIDE(esp_rom_spiflash_erase_sector = 0x4001716c);
+PROVIDE(esp_rom_spiflash_write = 0x400171cc);
+
 memcmp = 0x4001ab40;
 memcpy = 0x4001aba8;
 memmove = 0x4001acb0;
@@ -36,6 +36,11 @@ PROVIDE (esp_rom_opiflash_exec_cmd = 0x400008b8);
 PROVIDE( esp_rom_spi_set_dtr_swap_mode = 0x4000093c );
 PROVIDE( esp_rom_opiflash_pin_config = 0x40000894 );
 
+PROVIDE(esp_rom_spiflash_read = 0x40000a20);
+PROVIDE(esp_rom_spiflash_unlock = 0x40000a2c);
+PROVIDE(esp_rom_spiflash_erase_sector = 0x400009fc);
+PROVIDE(esp_rom_spiflash_write = 0x40000a14);
+
 memset = 0x400011e8;
 memcpy = 0x400011f4;
 memmove = 0x40001200;
@@ -0,0 +1,262 @@
+use core::marker::PhantomData;
+
+use super::AdcCharacteristics;
+#[cfg(any(esp32c2, esp32c3, esp32c6, esp32s3))]
+use crate::analog::adc::{AdcCalBasic, AdcCalEfuse, AdcCalSource, AdcConfig, CalibrationAccess};
+use crate::analog::adc::{AdcCalScheme, Attenuation};
+#[cfg(any(esp32, esp32s2))]
+use crate::efuse::Efuse;
+
+/// The calibration scheme selected by [`AdcCalAuto`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AdcCalKind {
+    /// No calibration data is available, the nominal input range of the
+    /// attenuation is assumed (a reference voltage of 1100 mV on the ESP32)
+    Nominal,
+    /// The reference voltage measured during production (ESP32)
+    Vref,
+    /// Readings at two reference voltages measured during production (ESP32
+    /// and ESP32-S2)
+    TwoPoint,
+    /// Line fitting through the reference point measured during production
+    Line,
+    /// Line fitting through a reading of the internal reference voltage,
+    /// measured at runtime as no reference point is available
+    InternalReference,
+    /// Curve fitting on top of line fitting
+    Curve,
+}
+
+/// Millivolt ADC calibration scheme selecting the best scheme available
+///
+/// Depending on the chip and the calibration data burned into its eFuses
+/// during production, in order of preference:
+///
+/// - ESP32: two point, reference voltage, nominal (without the lookup table
+///   ESP-IDF uses for high readings with 11 dB attenuation)
+/// - ESP32-S2: two point, nominal
+/// - ESP32-C3, ESP32-C6, ESP32-S3: curve fitting, internal reference
+/// - ESP32-C2: line fitting, internal reference
+/// - ESP32-H2: nominal
+///
+/// Readings are returned in mV, [`AdcCalAuto::kind`] reports the selected
+/// scheme.
+#[derive(Clone, Copy)]
+pub struct AdcCalAuto<ADCI> {
+    kind: AdcCalKind,
+
+    /// Initial code set to the ADC unit
+    cal_val: u16,
+
+    characteristics: AdcCharacteristics,
+
+    /// Shift of the readings to 12 bits
+    #[cfg(esp32)]
+    shift: u8,
+
+    _phantom: PhantomData<ADCI>,
+}
+
+impl<ADCI> AdcCalAuto<ADCI> {
+    fn new(kind: AdcCalKind, cal_val: u16, characteristics: AdcCharacteristics) -> Self {
+        Self {
+            kind,
+            cal_val,
+            characteristics,
+            #[cfg(esp32)]
+            shift: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// The selected calibration scheme
+    pub fn kind(&self) -> AdcCalKind {
+        self.kind
+    }
+
+    /// The characteristics used to convert readings into millivolts
+    pub fn characteristics(&self) -> AdcCharacteristics {
+        self.characteristics
+    }
+
+    /// Scale readings of the given resolution to the 12 bits the
+    /// characteristics are made for
+    #[cfg(esp32)]
+    pub(crate) fn set_resolution(&mut self, resolution: crate::analog::adc::Resolution) {
+        self.shift = 3 - resolution as u8;
+    }
+}
+
+impl<ADCI> crate::private::Sealed for AdcCalAuto<ADCI> {}
+
+impl<ADCI> AdcCalScheme<ADCI> for AdcCalAuto<ADCI>
+where
+    ADCI: AdcHasAutoCal,
+{
+    fn new_cal(atten: Attenuation) -> Self {
+        ADCI::auto_cal(atten)
+    }
+
+    fn adc_cal(&self) -> u16 {
+        self.cal_val
+    }
+
+    fn adc_val(&self, val: u16) -> u16 {
+        #[cfg(esp32)]
+        let val = val << self.shift;
+
+        self.characteristics.raw_to_millivolts(val)
+    }
+}
+
+/// ADC units supporting [`AdcCalAuto`]
+#[doc(hidden)]
+pub trait AdcHasAutoCal: Sized {
+    /// Select the best calibration scheme for the given attenuation
+    fn auto_cal(atten: Attenuation) -> AdcCalAuto<Self>;
+}
+
+/// Line or curve fitting with the reference point from eFuse, falling back to
+/// a reference point measured at runtime
+#[cfg(any(esp32c2, esp32c3, esp32c6, esp32s3))]
+#[cfg_attr(esp32c2, allow(unused_variables))]
+fn efuse_auto_cal<ADCI>(atten: Attenuation, curve: Option<&'static [i64]>) -> AdcCalAuto<ADCI>
+where
+    ADCI: AdcCalEfuse + CalibrationAccess,
+{
+    let cal_val = AdcCalBasic::<ADCI>::new_cal(atten).adc_cal();
+
+    let (kind, characteristics) = match ADCI::get_cal_code(atten) {
+        Some(code) => {
+            let line = AdcCharacteristics::line(code, ADCI::get_cal_mv(atten));
+
+            #[cfg(any(esp32c3, esp32c6, esp32s3))]
+            if let Some(curve) = curve {
+                return AdcCalAuto::new(AdcCalKind::Curve, cal_val, line.with_curve(curve));
+            }
+
+            (AdcCalKind::Line, line)
+        }
+        None => {
+            // see `AdcCalLine`, the reference voltage is 1000..=1200 mV
+            let code = AdcConfig::<ADCI>::adc_calibrate(atten, AdcCalSource::Ref);
+            (
+                AdcCalKind::InternalReference,
+                AdcCharacteristics::line(code, 1100),
+            )
+        }
+    };
+
+    AdcCalAuto::new(kind, cal_val, characteristics)
+}
+
+/// Two point or reference voltage characterization of an ESP32 ADC unit
+#[cfg(esp32)]
+fn esp32_auto_cal<ADCI>(unit: u8, atten: Attenuation) -> AdcCalAuto<ADCI> {
+    let (kind, characteristics) = if let Some((low, high)) = Efuse::get_adc_two_point(unit) {
+        (
+            AdcCalKind::TwoPoint,
+            AdcCharacteristics::from_esp32_efuse_two_point(unit, atten, low, high),
+        )
+    } else if let Some(vref) = Efuse::get_adc_vref() {
+        (
+            AdcCalKind::Vref,
+            AdcCharacteristics::from_esp32_efuse_vref(unit, atten, vref),
+        )
+    } else {
+        (
+            AdcCalKind::Nominal,
+            AdcCharacteristics::from_esp32_efuse_vref(unit, atten, 0),
+        )
+    };
+
+    AdcCalAuto::new(kind, 0, characteristics)
+}
+
+/// Two point characterization of an ESP32-S2 ADC unit
+#[cfg(esp32s2)]
+fn esp32s2_auto_cal<ADCI>(unit: u8, atten: Attenuation) -> AdcCalAuto<ADCI> {
+    match Efuse::get_rtc_calib_two_point(unit, atten) {
+        Some((low, high)) => AdcCalAuto::new(
+            AdcCalKind::TwoPoint,
+            0,
+            AdcCharacteristics::from_esp32s2_efuse_two_point(unit, atten, low, high),
+        ),
+        None => AdcCalAuto::new(AdcCalKind::Nominal, 0, AdcCharacteristics::nominal(atten)),
+    }
+}
+
+#[cfg(esp32)]
+mod impls {
+    use super::*;
+
+    impl AdcHasAutoCal for crate::peripherals::ADC1 {
+        fn auto_cal(atten: Attenuation) -> AdcCalAuto<Self> {
+            esp32_auto_cal(1, atten)
+        }
+    }
+
+    impl AdcHasAutoCal for crate::peripherals::ADC2 {
+        fn auto_cal(atten: Attenuation) -> AdcCalAuto<Self> {
+            esp32_auto_cal(2, atten)
+        }
+    }
+}
+
+#[cfg(esp32s2)]
+mod impls {
+    use super::*;
+
+    impl AdcHasAutoCal for crate::peripherals::ADC1 {
+        fn auto_cal(atten: Attenuation) -> AdcCalAuto<Self> {
+            esp32s2_auto_cal(1, atten)
+        }
+    }
+
+    impl AdcHasAutoCal for crate::peripherals::ADC2 {
+        fn auto_cal(atten: Attenuation) -> AdcCalAuto<Self> {
+            esp32s2_auto_cal(2, atten)
+        }
+    }
+}
+
+#[cfg(esp32c2)]
+mod impls {
+    use super::*;
+
+    impl AdcHasAutoCal for crate::peripherals::ADC1 {
+        fn auto_cal(atten: Attenuation) -> AdcCalAuto<Self> {
+            efuse_auto_cal(atten, None)
+        }
+    }
+}
+
+#[cfg(any(esp32c3, esp32c6, esp32s3))]
+mod impls {
+    use super::{super::curve::coefficients, *};
+
+    impl AdcHasAutoCal for crate::peripherals::ADC1 {
+        fn auto_cal(atten: Attenuation) -> AdcCalAuto<Self> {
+            efuse_auto_cal(atten, Some(coefficients::<Self>(atten)))
+        }
+    }
+
+    #[cfg(any(esp32c3, esp32s3))]
+    impl AdcHasAutoCal for crate::peripherals::ADC2 {
+        fn auto_cal(atten: Attenuation) -> AdcCalAuto<Self> {
+            efuse_auto_cal(atten, Some(coefficients::<Self>(atten)))
+        }
+    }
+}
+
+#[cfg(esp32h2)]
+mod impls {
+    use super::*;
+
+    impl AdcHasAutoCal for crate::peripherals::ADC1 {
+        fn auto_cal(atten: Attenuation) -> AdcCalAuto<Self> {
+            AdcCalAuto::new(AdcCalKind::Nominal, 0, AdcCharacteristics::nominal(atten))
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use crate::analog::adc::Attenuation;
+
+/// Fixed-point scale of the slope, i.e. 16 fractional bits.
+const COEFF_A_SCALE: i64 = 1 << 16;
+
+/// Fixed-point scale of the error polynomial's coefficients, i.e. 52
+/// fractional bits.
+pub(super) const COEFF_MUL: i64 = 1 << 52;
+
+/// Conversion of raw ADC readings into millivolts
+///
+/// The characteristics are a line `mV = a * raw + b`, optionally followed by
+/// the error correction of curve fitting (see [`super::AdcCalCurve`]).
+///
+/// They don't access the hardware: they can be built from eFuse values
+/// recorded on another device, of any chip, to check the conversion of known
+/// readings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdcCharacteristics {
+    /// Slope in mV per step, with 16 fractional bits
+    coeff_a: u32,
+    /// Offset in mV
+    coeff_b: i32,
+    /// Added to the scaled reading before dropping the fractional bits, half
+    /// a millivolt to round like ESP-IDF or zero to truncate like line fitting
+    rounding: i64,
+    /// Coefficients of the error polynomial, empty without curve fitting
+    curve: &'static [i64],
+}
+
+impl AdcCharacteristics {
+    /// A line through the origin and the reference point `(code, mv)`
+    ///
+    /// This is line fitting, the zero offset is taken care of by the initial
+    /// code of the ADC. Readings are truncated to whole millivolts, exactly
+    /// like the line fitting calibration scheme does.
+    pub fn line(code: u16, mv: u16) -> Self {
+        Self {
+            coeff_a: (mv as i64 * COEFF_A_SCALE / code.max(1) as i64) as u32,
+            coeff_b: 0,
+            rounding: 0,
+            curve: &[],
+        }
+    }
+
+    /// A line through the readings `low_code` at `low_mv` and `high_code` at
+    /// `high_mv`
+    ///
+    /// Readings are rounded to the nearest millivolt.
+    pub fn two_point(low_code: u16, low_mv: u16, high_code: u16, high_mv: u16) -> Self {
+        let delta_code = (high_code as i64 - low_code as i64).max(1);
+        let delta_mv = high_mv as i64 - low_mv as i64;
+
+        Self {
+            coeff_a: (delta_mv * COEFF_A_SCALE / delta_code) as u32,
+            coeff_b: ((low_mv as i64 * high_code as i64 - high_mv as i64 * low_code as i64)
+                / delta_code) as i32,
+            rounding: COEFF_A_SCALE / 2,
+            curve: &[],
+        }
+    }
+
+    /// The characteristics of an uncalibrated ADC, spreading the nominal input
+    /// range of the attenuation over the output range
+    #[cfg(not(esp32))]
+    pub fn nominal(atten: Attenuation) -> Self {
+        Self::line(MAX_CODE, NOMINAL_RANGE_MV[atten as usize])
+    }
+
+    /// The characteristics of an ESP32 ADC unit from the raw `ADC_VREF`
+    /// eFuse value, the measured reference voltage
+    ///
+    /// A raw value of 0 results in the nominal reference voltage of 1100 mV.
+    ///
+    /// ESP-IDF converts readings above 2880 with 11 dB attenuation through a
+    /// lookup table of the non-linear end of the range instead, which isn't
+    /// implemented: such readings are converted along the line and can differ
+    /// from the voltages reported by ESP-IDF.
+    ///
+    /// see <https://github.com/espressif/esp-idf/blob/903af13e8/components/esp_adc/deprecated/esp32/esp_adc_cal_legacy.c#L231>
+    pub fn from_esp32_efuse_vref(unit: u8, atten: Attenuation, vref: u8) -> Self {
+        let vref = (1100 + decode_sign_magnitude(vref as u32, 5) * 7) as u32;
+
+        let (scales, offsets) = if unit == 1 {
+            (ADC1_VREF_ATTEN_SCALE, ADC1_VREF_ATTEN_OFFSET)
+        } else {
+            (ADC2_VREF_ATTEN_SCALE, ADC2_VREF_ATTEN_OFFSET)
+        };
+
+        Self {
+            coeff_a: vref * scales[atten as usize] / 4096,
+            coeff_b: offsets[atten as usize] as i32,
+            rounding: COEFF_A_SCALE / 2,
+            curve: &[],
+        }
+    }
+
+    /// The characteristics of an ESP32 ADC unit from the raw `ADCn_TP_LOW`
+    /// and `ADCn_TP_HIGH` eFuse values, its readings at 150 mV and 850 mV
+    ///
+    /// see <https://github.com/espressif/esp-idf/blob/903af13e8/components/esp_adc/deprecated/esp32/esp_adc_cal_legacy.c#L210>
+    pub fn from_esp32_efuse_two_point(unit: u8, atten: Attenuation, low: u8, high: u16) -> Self {
+        let (low_offset, high_offset, scales, offsets) = if unit == 1 {
+            (278, 3265, ADC1_TP_ATTEN_SCALE, ADC1_TP_ATTEN_OFFSET)
+        } else {
+            (421, 3406, ADC2_TP_ATTEN_SCALE, ADC2_TP_ATTEN_OFFSET)
+        };
+
+        let low = (low_offset + decode_twos_complement(low as u32, 7) * 4) as u32;
+        let high = (high_offset + decode_twos_complement(high as u32, 9) * 4) as u32;
+
+        let delta_x = (high - low).max(1);
+        let delta_v = 850 - 150;
+
+        Self {
+            coeff_a: (delta_v * scales[atten as usize] + delta_x / 2) / delta_x,
+            coeff_b: 850 - ((delta_v * high + delta_x / 2) / delta_x) as i32
+                + offsets[atten as usize] as i32,
+            rounding: COEFF_A_SCALE / 2,
+            curve: &[],
+        }
+    }
+
+    /// The characteristics of an ESP32-S2 ADC unit from the raw
+    /// `RTCCALIB_V1IDX_AnmL` and `RTCCALIB_V1IDX_AnmH` eFuse values, its
+    /// readings at a low and a high reference voltage
+    ///
+    /// see <https://github.com/espressif/esp-idf/blob/903af13e8/components/efuse/esp32s2/esp_efuse_rtc_table.c>
+    pub fn from_esp32s2_efuse_two_point(unit: u8, atten: Attenuation, low: u8, high: u8) -> Self {
+        let (low_base, high_base) = if unit == 1 {
+            (ADC1_V1_LOW_BASE, ADC1_V1_HIGH_BASE)
+        } else {
+            (ADC2_V1_LOW_BASE, ADC2_V1_HIGH_BASE)
+        };
+
+        let low = low_base[atten as usize] + decode_sign_magnitude(low as u32, 6) * 4;
+        let high = high_base[atten as usize] + decode_sign_magnitude(high as u32, 8) * 4;
+
+        Self::two_point(
+            low as u16,
+            V1_LOW_MV,
+            high as u16,
+            V1_HIGH_MV[atten as usize],
+        )
+    }
+
+    /// Apply the error polynomial of curve fitting after the line
+    #[cfg(any(esp32c3, esp32c6, esp32s3))]
+    pub(super) fn with_curve(self, curve: &'static [i64]) -> Self {
+        Self { curve, ..self }
+    }
+
+    /// Convert a raw reading into millivolts
+    pub fn raw_to_millivolts(&self, raw: u16) -> u16 {
+        let mv = (self.coeff_a as i64 * raw as i64 + self.rounding) / COEFF_A_SCALE
+            + self.coeff_b as i64;
+        let mv = mv.clamp(0, u16::MAX as i64) as u16;
+
+        (mv as i32 - curve_error(mv, self.curve)).clamp(0, u16::MAX as i32) as u16
+    }
+}
+
+/// Evaluate the error polynomial `coeff[0] + coeff[1] * val + ... + coeff[n] *
+/// val^n` of curve fitting
+pub(super) fn curve_error(val: u16, coeff: &[i64]) -> i32 {
+    if val == 0 || coeff.is_empty() {
+        return 0;
+    }
+
+    let mut var = 1i64;
+    let mut err = (var * coeff[0] / COEFF_MUL) as i32;
+
+    for coeff in &coeff[1..] {
+        var *= val as i64;
+        err += (var * *coeff / COEFF_MUL) as i32;
+    }
+
+    err
+}
+
+/// Decode a sign-magnitude eFuse value of `bits` bits
+fn decode_sign_magnitude(value: u32, bits: u32) -> i32 {
+    let magnitude = (value & ((1 << (bits - 1)) - 1)) as i32;
+
+    if value & (1 << (bits - 1)) != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// Decode a two's complement eFuse value of `bits` bits, the way ESP-IDF
+/// does
+fn decode_twos_complement(value: u32, bits: u32) -> i32 {
+    let mask = (1 << (bits - 1)) - 1;
+
+    if value & (1 << (bits - 1)) != 0 {
+        -(((!value).wrapping_add(1) & mask) as i32)
+    } else {
+        (value & mask) as i32
+    }
+}
+
+// Scales and offsets of the ESP32 characteristics per attenuation, see
+// <https://github.com/espressif/esp-idf/blob/903af13e8/components/esp_adc/deprecated/esp32/esp_adc_cal_legacy.c#L72-L80>
+const ADC1_TP_ATTEN_SCALE: [u32; 4] = [65504, 86975, 120389, 224310];
+const ADC2_TP_ATTEN_SCALE: [u32; 4] = [65467, 86861, 120416, 224708];
+const ADC1_TP_ATTEN_OFFSET: [u32; 4] = [0, 1, 27, 54];
+const ADC2_TP_ATTEN_OFFSET: [u32; 4] = [0, 9, 26, 66];
+
+const ADC1_VREF_ATTEN_SCALE: [u32; 4] = [57431, 76236, 105481, 196602];
+const ADC2_VREF_ATTEN_SCALE: [u32; 4] = [57236, 76175, 105481, 197071];
+const ADC1_VREF_ATTEN_OFFSET: [u32; 4] = [75, 78, 107, 142];
+const ADC2_VREF_ATTEN_OFFSET: [u32; 4] = [63, 66, 89, 128];
+
+// Bases of the ESP32-S2 version 1 reference readings per attenuation and the
+// voltages they were taken at, see
+// <https://github.com/espressif/esp-idf/blob/903af13e8/components/efuse/esp32s2/esp_efuse_rtc_table.c#L30-L48>
+const ADC1_V1_LOW_BASE: [i32; 4] = [2231, 1643, 1290, 701];
+const ADC2_V1_LOW_BASE: [i32; 4] = [2305, 1693, 1343, 723];
+const ADC1_V1_HIGH_BASE: [i32; 4] = [5775, 5693, 5723, 6209];
+const ADC2_V1_HIGH_BASE: [i32; 4] = [5817, 5703, 5731, 6157];
+const V1_LOW_MV: u16 = 250;
+const V1_HIGH_MV: [u16; 4] = [600, 800, 1000, 2000];
+
+cfg_if::cfg_if! {
+    if #[cfg(esp32s2)] {
+        const MAX_CODE: u16 = 0x1fff;
+        const NOMINAL_RANGE_MV: [u16; 4] = [750, 1050, 1300, 2500];
+    } else if #[cfg(esp32h2)] {
+        const MAX_CODE: u16 = 0xfff;
+        const NOMINAL_RANGE_MV: [u16; 4] = [750, 1050, 1300, 3300];
+    } else if #[cfg(not(esp32))] {
+        const MAX_CODE: u16 = 0xfff;
+        const NOMINAL_RANGE_MV: [u16; 4] = [750, 1050, 1300, 2500];
+    }
+}
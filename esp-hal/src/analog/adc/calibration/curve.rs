@@ -1,5 +1,6 @@
 use core::marker::PhantomData;
 
+use super::characteristics::{curve_error, COEFF_MUL};
 use crate::analog::adc::{
     AdcCalEfuse,
     AdcCalLine,
@@ -9,8 +10,6 @@ use crate::analog::adc::{
     CalibrationAccess,
 };
 
-const COEFF_MUL: i64 = 1 << 52;
-
 /// Integer type for the error polynomial's coefficients. Despite
 /// the type, this is a fixed-point number with 52 fractional bits.
 type CurveCoeff = i64;
@@ -67,17 +66,9 @@ where
     ADCI: AdcCalEfuse + AdcHasLineCal + AdcHasCurveCal + CalibrationAccess,
 {
     fn new_cal(atten: Attenuation) -> Self {
-        let line = AdcCalLine::<ADCI>::new_cal(atten);
-
-        let coeff = ADCI::CURVES_COEFFS
-            .iter()
-            .find(|item| item.atten == atten)
-            .expect("No curve coefficients for given attenuation")
-            .coeff;
-
         Self {
-            line,
-            coeff,
+            line: AdcCalLine::<ADCI>::new_cal(atten),
+            coeff: coefficients::<ADCI>(atten),
             _phantom: PhantomData,
         }
     }
@@ -89,25 +80,20 @@ where
     fn adc_val(&self, val: u16) -> u16 {
         let val = self.line.adc_val(val);
 
-        let err = if val == 0 {
-            0
-        } else {
-            // err = coeff[0] + coeff[1] * val + coeff[2] * val^2 + ... + coeff[n] * val^n
-            let mut var = 1i64;
-            let mut err = (var * self.coeff[0] / COEFF_MUL) as i32;
-
-            for coeff in &self.coeff[1..] {
-                var *= val as i64;
-                err += (var * *coeff / COEFF_MUL) as i32;
-            }
-
-            err
-        };
-
-        (val as i32 - err) as u16
+        (val as i32 - curve_error(val, self.coeff)) as u16
     }
 }
 
+/// The coefficients of the error polynomial of `ADCI` for the given
+/// attenuation
+pub(super) fn coefficients<ADCI: AdcHasCurveCal>(atten: Attenuation) -> &'static [CurveCoeff] {
+    ADCI::CURVES_COEFFS
+        .iter()
+        .find(|item| item.atten == atten)
+        .expect("No curve coefficients for given attenuation")
+        .coeff
+}
+
 macro_rules! coeff_tables {
     ($($(#[$($meta:meta)*])* $name:ident [ $($att:ident => [ $($val:literal,)* ],)* ];)*) => {
         $(
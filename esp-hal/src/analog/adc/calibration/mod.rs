@@ -1,13 +1,19 @@
 #[cfg(any(esp32c3, esp32c6, esp32s3))]
 pub use self::curve::{AdcCalCurve, AdcHasCurveCal};
+pub use self::{
+    auto::{AdcCalAuto, AdcCalKind, AdcHasAutoCal},
+    characteristics::AdcCharacteristics,
+};
 #[cfg(any(esp32c2, esp32c3, esp32c6, esp32s3))]
 pub use self::{
     basic::AdcCalBasic,
     line::{AdcCalLine, AdcHasLineCal},
 };
 
+mod auto;
 #[cfg(any(esp32c2, esp32c3, esp32c6, esp32s3))]
 mod basic;
+mod characteristics;
 #[cfg(any(esp32c3, esp32c6, esp32s3))]
 mod curve;
 #[cfg(any(esp32c2, esp32c3, esp32c6, esp32s3))]
@@ -0,0 +1,1116 @@
+//! # ADC continuous mode
+//!
+//! In continuous mode the digital controller of the ADC steps through a
+//! pattern table of channels, triggering a conversion at a fixed sample rate,
+//! and hands the results to a DMA channel: GDMA on the ESP32-C3, ESP32-C6,
+//! ESP32-H2 and ESP32-S3, I2S0 on the ESP32. This allows sampling several
+//! channels at rates which are impossible to reach by polling
+//! [Adc::read_oneshot](super::Adc::read_oneshot).
+//!
+//! The samples are received into a circular buffer and popped as [AdcSample]s,
+//! holding the channel they were taken from and the raw conversion result.
+//! The sample rate is shared by all entries of the pattern table, i.e. with
+//! four entries each channel is sampled at a quarter of it.
+//!
+//! Only ADC1 can be used in continuous mode.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! let mut config = AdcContinuousConfig::new(80.kHz());
+//! let _pin2 = config.enable_pin(
+//!     io.pins.gpio2,
+//!     Attenuation::Attenuation11dB,
+//!     Resolution::Resolution12Bit,
+//! );
+//! let _pin3 = config.enable_pin(
+//!     io.pins.gpio3,
+//!     Attenuation::Attenuation11dB,
+//!     Resolution::Resolution12Bit,
+//! );
+//!
+//! let (_, mut tx_descriptors, mut rx_buffer, mut rx_descriptors) = dma_buffers!(0, 4 * 4092);
+//! let mut adc = AdcContinuous::new(
+//!     peripherals.ADC1,
+//!     config,
+//!     dma_channel.configure(
+//!         false,
+//!         &mut tx_descriptors,
+//!         &mut rx_descriptors,
+//!         DmaPriority::Priority0,
+//!     ),
+//! )
+//! .unwrap();
+//!
+//! let mut transfer = adc.read_circular(&mut rx_buffer).unwrap();
+//! let mut samples = [AdcSample::default(); 256];
+//!
+//! loop {
+//!     let count = transfer.pop(&mut samples).unwrap();
+//!     for sample in &samples[..count] {
+//!         println!("channel {}: {}", sample.channel, sample.value);
+//!     }
+//! }
+//! ```
+//!
+//! On the ESP32 the driver additionally takes the I2S0 peripheral.
+//!
+//! On the ESP32-C6, ESP32-H2 and ESP32-S3 two threshold [Monitor]s compare
+//! the samples of a channel against a high and a low limit, e.g. to wait for
+//! a battery voltage to drop while sampling continues in the background:
+//!
+//! ```no_run
+//! adc.enable_monitor(Monitor::Monitor0, &pin2, 2000, 0xfff);
+//! let mut transfer = adc.read_circular(&mut rx_buffer).unwrap();
+//! let events = transfer.wait_for_monitor(Monitor::Monitor0).await;
+//! if events.contains(Threshold::Low) {
+//!     println!("battery low");
+//! }
+//! ```
+
+use core::marker::PhantomData;
+
+use embedded_dma::WriteBuffer;
+#[cfg(any(esp32c6, esp32h2, esp32s3))]
+use enumset::{EnumSet, EnumSetType};
+use fugit::HertzU32;
+
+use super::{AdcChannel, AdcPin, Attenuation, Resolution};
+#[cfg(esp32)]
+use crate::peripherals::I2S0;
+use crate::{
+    dma::{AdcPeripheral, Channel, ChannelTypes, DmaError, RxPrivate},
+    gpio::AnalogPin,
+    into_ref,
+    peripheral::{Peripheral, PeripheralRef},
+    peripherals::ADC1,
+    Mode,
+};
+
+/// The maximum number of entries of the pattern table
+#[cfg(any(esp32, esp32s3))]
+pub const MAX_PATTERN_LEN: usize = 16;
+/// The maximum number of entries of the pattern table
+#[cfg(not(any(esp32, esp32s3)))]
+pub const MAX_PATTERN_LEN: usize = 8;
+
+/// The lowest supported sample rate in Hz
+#[cfg(esp32)]
+pub const MIN_SAMPLE_RATE: u32 = 20_000;
+/// The lowest supported sample rate in Hz
+#[cfg(not(esp32))]
+pub const MIN_SAMPLE_RATE: u32 = 611;
+
+/// The highest supported sample rate in Hz
+#[cfg(esp32)]
+pub const MAX_SAMPLE_RATE: u32 = 2_000_000;
+/// The highest supported sample rate in Hz
+#[cfg(not(esp32))]
+pub const MAX_SAMPLE_RATE: u32 = 83_333;
+
+// size of a conversion result in the DMA buffer
+#[cfg(esp32)]
+const SAMPLE_BYTES: usize = 2;
+#[cfg(not(esp32))]
+const SAMPLE_BYTES: usize = 4;
+
+// channel field of data format type 2 and of the pattern table entries
+#[cfg(esp32s3)]
+const CHANNEL_MASK: u32 = 0b1111;
+#[cfg(not(any(esp32, esp32s3)))]
+const CHANNEL_MASK: u32 = 0b111;
+
+/// ADC continuous mode errors
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// The sample rate is outside of [MIN_SAMPLE_RATE] and [MAX_SAMPLE_RATE]
+    InvalidSampleRate,
+    /// No pin has been added to the pattern table
+    EmptyPattern,
+    /// The size of the buffer isn't a multiple of 4 bytes
+    IllegalArgument,
+    /// An error occurred in the DMA channel
+    DmaError(DmaError),
+}
+
+impl From<DmaError> for Error {
+    fn from(value: DmaError) -> Self {
+        Error::DmaError(value)
+    }
+}
+
+/// A conversion result read in continuous mode
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AdcSample {
+    /// The channel the sample was taken from, see [AdcChannel::CHANNEL]
+    pub channel: u8,
+    /// The raw conversion result
+    pub value: u16,
+}
+
+impl AdcSample {
+    // data format type 1: channel in bits 15..12, data in bits 11..0
+    #[cfg(esp32)]
+    fn decode(raw: [u8; SAMPLE_BYTES]) -> Self {
+        let raw = u16::from_le_bytes(raw);
+
+        Self {
+            channel: (raw >> 12) as u8,
+            value: raw & 0xfff,
+        }
+    }
+
+    // data format type 2: unit above the channel, channel from bit 13, data
+    // in bits 11..0
+    #[cfg(not(esp32))]
+    fn decode(raw: [u8; SAMPLE_BYTES]) -> Self {
+        let raw = u32::from_le_bytes(raw);
+
+        Self {
+            channel: ((raw >> 13) & CHANNEL_MASK) as u8,
+            value: (raw & 0xfff) as u16,
+        }
+    }
+}
+
+/// One of the two threshold monitors of the digital controller
+#[cfg(any(esp32c6, esp32h2, esp32s3))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Monitor {
+    /// Threshold monitor 0
+    Monitor0 = 0,
+    /// Threshold monitor 1
+    Monitor1 = 1,
+}
+
+/// The thresholds of a monitor crossed by the samples of its channel
+#[cfg(any(esp32c6, esp32h2, esp32s3))]
+#[derive(Debug, EnumSetType)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Threshold {
+    /// A sample was above the high threshold
+    High,
+    /// A sample was below the low threshold
+    Low,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PatternEntry {
+    channel: u8,
+    attenuation: Attenuation,
+    #[cfg_attr(not(esp32), allow(unused))]
+    resolution: Resolution,
+}
+
+impl PatternEntry {
+    // channel in bits 7..4, bit width in bits 3..2, attenuation in bits 1..0
+    #[cfg(esp32)]
+    fn bits(&self) -> u32 {
+        ((self.channel as u32 & 0b1111) << 4)
+            | ((self.resolution as u32 & 0b11) << 2)
+            | (self.attenuation as u32 & 0b11)
+    }
+
+    // unit above the channel (always ADC1), channel from bit 2, attenuation in
+    // bits 1..0
+    #[cfg(not(esp32))]
+    fn bits(&self) -> u32 {
+        ((self.channel as u32 & CHANNEL_MASK) << 2) | (self.attenuation as u32 & 0b11)
+    }
+}
+
+/// Configuration for the ADC continuous mode
+pub struct AdcContinuousConfig {
+    sample_rate: HertzU32,
+    pattern: [Option<PatternEntry>; MAX_PATTERN_LEN],
+    pattern_len: usize,
+}
+
+impl AdcContinuousConfig {
+    /// Create a configuration converting `sample_rate` samples per second,
+    /// with an empty pattern table
+    pub fn new(sample_rate: impl Into<HertzU32>) -> Self {
+        Self {
+            sample_rate: sample_rate.into(),
+            pattern: [None; MAX_PATTERN_LEN],
+            pattern_len: 0,
+        }
+    }
+
+    /// Enable the specified pin and append it to the pattern table
+    ///
+    /// Panics if the pattern table is full.
+    pub fn enable_pin<PIN>(
+        &mut self,
+        pin: PIN,
+        attenuation: Attenuation,
+        resolution: Resolution,
+    ) -> AdcPin<PIN, ADC1>
+    where
+        PIN: AdcChannel + AnalogPin,
+    {
+        // TODO revert this on drop
+        pin.set_analog(crate::private::Internal);
+        self.push(PIN::CHANNEL, attenuation, resolution);
+
+        AdcPin {
+            pin,
+            cal_scheme: (),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Append another entry for an already enabled pin, e.g. to sample it more
+    /// often than the other pins
+    ///
+    /// Panics if the pattern table is full.
+    pub fn repeat_pin<PIN, CS>(
+        &mut self,
+        _pin: &AdcPin<PIN, ADC1, CS>,
+        attenuation: Attenuation,
+        resolution: Resolution,
+    ) where
+        PIN: AdcChannel,
+    {
+        self.push(PIN::CHANNEL, attenuation, resolution);
+    }
+
+    fn push(&mut self, channel: u8, attenuation: Attenuation, resolution: Resolution) {
+        assert!(
+            self.pattern_len < MAX_PATTERN_LEN,
+            "The pattern table is limited to {} entries",
+            MAX_PATTERN_LEN
+        );
+
+        self.pattern[self.pattern_len] = Some(PatternEntry {
+            channel,
+            attenuation,
+            resolution,
+        });
+        self.pattern_len += 1;
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if !(MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE).contains(&self.sample_rate.raw()) {
+            return Err(Error::InvalidSampleRate);
+        }
+
+        if self.pattern_len == 0 {
+            return Err(Error::EmptyPattern);
+        }
+
+        Ok(())
+    }
+
+    /// The pattern table as register values, four entries per register
+    fn pattern_table(&self) -> [u32; MAX_PATTERN_LEN / 4] {
+        let mut table = [0; MAX_PATTERN_LEN / 4];
+
+        for (idx, entry) in self.pattern.iter().flatten().enumerate() {
+            table[idx / 4] |= entry.bits() << ll::pattern_shift(idx % 4);
+        }
+
+        table
+    }
+}
+
+/// ADC1 sampling in continuous mode
+pub struct AdcContinuous<'d, CH, DmaMode>
+where
+    CH: ChannelTypes,
+    DmaMode: Mode,
+{
+    _adc: PeripheralRef<'d, ADC1>,
+    #[cfg(esp32)]
+    _i2s: PeripheralRef<'d, I2S0>,
+    rx_channel: CH::Rx<'d>,
+    phantom: PhantomData<DmaMode>,
+}
+
+impl<'d, CH, DmaMode> AdcContinuous<'d, CH, DmaMode>
+where
+    CH: ChannelTypes,
+    DmaMode: Mode,
+{
+    /// Configure ADC1 for continuous mode, receiving the samples through
+    /// `channel`
+    #[cfg(not(esp32))]
+    pub fn new(
+        adc: impl Peripheral<P = ADC1> + 'd,
+        config: AdcContinuousConfig,
+        channel: Channel<'d, CH, DmaMode>,
+    ) -> Result<Self, Error>
+    where
+        CH::P: AdcPeripheral,
+    {
+        into_ref!(adc);
+        config.validate()?;
+
+        let mut rx_channel = channel.rx;
+        rx_channel.init_channel();
+        ll::configure(&config);
+
+        Ok(Self {
+            _adc: adc,
+            rx_channel,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Configure ADC1 for continuous mode, receiving the samples through
+    /// I2S0 and its DMA `channel`
+    #[cfg(esp32)]
+    pub fn new(
+        adc: impl Peripheral<P = ADC1> + 'd,
+        i2s: impl Peripheral<P = I2S0> + 'd,
+        config: AdcContinuousConfig,
+        channel: Channel<'d, CH, DmaMode>,
+    ) -> Result<Self, Error>
+    where
+        CH::P: AdcPeripheral,
+    {
+        into_ref!(adc, i2s);
+        config.validate()?;
+
+        let mut rx_channel = channel.rx;
+        rx_channel.init_channel();
+        ll::configure(&config);
+
+        Ok(Self {
+            _adc: adc,
+            _i2s: i2s,
+            rx_channel,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Start sampling into the circular `buffer`
+    ///
+    /// Returns [AdcContinuousTransfer] to pop the samples, sampling stops when
+    /// it is dropped.
+    pub fn read_circular<'t, RXBUF>(
+        &'t mut self,
+        buffer: &'t mut RXBUF,
+    ) -> Result<AdcContinuousTransfer<'t, 'd, CH, DmaMode>, Error>
+    where
+        RXBUF: WriteBuffer<Word = u8>,
+    {
+        let (ptr, len) = unsafe { buffer.write_buffer() };
+
+        if len % 4 != 0 {
+            return Err(Error::IllegalArgument);
+        }
+
+        ll::reset();
+
+        unsafe {
+            self.rx_channel
+                .prepare_transfer_without_start(true, ll::DMA_PERIPHERAL, ptr, len)
+                .and_then(|_| self.rx_channel.start_transfer())?;
+        }
+
+        ll::start(len);
+
+        Ok(AdcContinuousTransfer { adc: self })
+    }
+
+    /// Compare the samples of `pin` against the thresholds of `monitor`
+    ///
+    /// The monitor raises [Threshold::High] for every sample above `high` and
+    /// [Threshold::Low] for every sample below `low`, the raw values are
+    /// compared. Pass `0` or the largest conversion result to not use the
+    /// respective threshold.
+    #[cfg(any(esp32c6, esp32h2, esp32s3))]
+    pub fn enable_monitor<PIN, CS>(
+        &mut self,
+        monitor: Monitor,
+        _pin: &AdcPin<PIN, ADC1, CS>,
+        low: u16,
+        high: u16,
+    ) where
+        PIN: AdcChannel,
+    {
+        ll::enable_monitor(monitor, PIN::CHANNEL, low, high);
+    }
+
+    /// Stop comparing samples against the thresholds of `monitor`
+    #[cfg(any(esp32c6, esp32h2, esp32s3))]
+    pub fn disable_monitor(&mut self, monitor: Monitor) {
+        ll::disable_monitor(monitor);
+    }
+}
+
+/// An in-progress continuous mode transfer
+#[must_use]
+pub struct AdcContinuousTransfer<'t, 'd, CH, DmaMode>
+where
+    CH: ChannelTypes,
+    DmaMode: Mode,
+{
+    adc: &'t mut AdcContinuous<'d, CH, DmaMode>,
+}
+
+impl<'t, 'd, CH, DmaMode> AdcContinuousTransfer<'t, 'd, CH, DmaMode>
+where
+    CH: ChannelTypes,
+    DmaMode: Mode,
+{
+    /// Number of samples which can be popped
+    pub fn available(&mut self) -> usize {
+        self.adc.rx_channel.available() / SAMPLE_BYTES
+    }
+
+    /// Pop up to `samples.len()` samples, without waiting for more to arrive
+    ///
+    /// Returns the number of samples popped.
+    pub fn pop(&mut self, samples: &mut [AdcSample]) -> Result<usize, Error> {
+        let count = usize::min(self.available(), samples.len());
+
+        for sample in samples[..count].iter_mut() {
+            let mut raw = [0u8; SAMPLE_BYTES];
+            self.adc.rx_channel.pop(&mut raw)?;
+            *sample = AdcSample::decode(raw);
+        }
+
+        Ok(count)
+    }
+
+    /// Whether the buffer has been filled completely before samples were
+    /// popped, i.e. samples have been overwritten
+    pub fn is_overrun(&self) -> bool {
+        self.adc.rx_channel.is_overrun()
+    }
+
+    /// The thresholds of `monitor` crossed since its events were last cleared
+    #[cfg(any(esp32c6, esp32h2, esp32s3))]
+    pub fn monitor_events(&self, monitor: Monitor) -> EnumSet<Threshold> {
+        ll::monitor_events(monitor)
+    }
+
+    /// Clear the events of `monitor`
+    #[cfg(any(esp32c6, esp32h2, esp32s3))]
+    pub fn clear_monitor_events(&mut self, monitor: Monitor) {
+        ll::clear_monitor_events(monitor);
+    }
+}
+
+impl<'t, 'd, CH, DmaMode> Drop for AdcContinuousTransfer<'t, 'd, CH, DmaMode>
+where
+    CH: ChannelTypes,
+    DmaMode: Mode,
+{
+    fn drop(&mut self) {
+        ll::stop();
+    }
+}
+
+#[cfg(feature = "async")]
+mod asynch {
+    use super::*;
+    use crate::{dma::asynch::DmaRxDoneChFuture, Async};
+
+    impl<'t, 'd, CH> AdcContinuousTransfer<'t, 'd, CH, Async>
+    where
+        CH: ChannelTypes,
+    {
+        /// Number of samples which can be popped.
+        /// Will wait for more than 0 samples available.
+        pub async fn available_async(&mut self) -> Result<usize, Error> {
+            loop {
+                let res = self.available();
+
+                if res != 0 {
+                    break Ok(res);
+                }
+
+                DmaRxDoneChFuture::new(&mut self.adc.rx_channel).await?;
+            }
+        }
+
+        /// Pop up to `samples.len()` samples.
+        /// Will wait for more than 0 samples available.
+        pub async fn pop_async(&mut self, samples: &mut [AdcSample]) -> Result<usize, Error> {
+            self.available_async().await?;
+            self.pop(samples)
+        }
+
+        /// Wait until the samples cross a threshold of `monitor`
+        ///
+        /// Returns the crossed thresholds and clears the events of `monitor`,
+        /// immediately if a threshold has been crossed since they were last
+        /// cleared.
+        /// Sampling continues while waiting, the buffer has to be large
+        /// enough or samples are overwritten.
+        #[cfg(any(esp32c6, esp32h2, esp32s3))]
+        pub async fn wait_for_monitor(&mut self, monitor: Monitor) -> EnumSet<Threshold> {
+            MonitorFuture::new(monitor).await;
+
+            let events = self.monitor_events(monitor);
+            self.clear_monitor_events(monitor);
+            events
+        }
+    }
+
+    #[cfg(any(esp32c6, esp32h2, esp32s3))]
+    struct MonitorFuture {
+        monitor: Monitor,
+    }
+
+    #[cfg(any(esp32c6, esp32h2, esp32s3))]
+    impl MonitorFuture {
+        fn new(monitor: Monitor) -> Self {
+            super::super::asynch::bind_interrupt();
+            ll::listen_monitor(monitor);
+
+            Self { monitor }
+        }
+    }
+
+    #[cfg(any(esp32c6, esp32h2, esp32s3))]
+    impl core::future::Future for MonitorFuture {
+        type Output = ();
+
+        fn poll(
+            self: core::pin::Pin<&mut Self>,
+            cx: &mut core::task::Context<'_>,
+        ) -> core::task::Poll<Self::Output> {
+            super::super::asynch::MONITOR_WAKERS[self.monitor as usize].register(cx.waker());
+
+            if ll::is_listening_monitor(self.monitor) {
+                core::task::Poll::Pending
+            } else {
+                core::task::Poll::Ready(())
+            }
+        }
+    }
+}
+
+#[cfg(esp32)]
+mod ll {
+    use super::AdcContinuousConfig;
+    use crate::{
+        dma::DmaPeripheral,
+        peripherals::{APB_CTRL, I2S0, SENS},
+        system::{Peripheral, PeripheralClockControl},
+    };
+
+    pub(super) const DMA_PERIPHERAL: DmaPeripheral = DmaPeripheral::I2s0;
+
+    // I2S0 is clocked from PLL_D2, the dividers for a given sample rate are
+    // calculated the same way as in ESP-IDF
+    const I2S_CLOCK: u32 = 160_000_000;
+    const I2S_BCLK_DIVIDER: u32 = 16;
+
+    pub(super) fn pattern_shift(position: usize) -> usize {
+        24 - position * 8
+    }
+
+    pub(super) fn configure(config: &AdcContinuousConfig) {
+        let sens = unsafe { &*SENS::PTR };
+        let syscon = unsafe { &*APB_CTRL::PTR };
+        let i2s = unsafe { &*I2S0::PTR };
+
+        PeripheralClockControl::enable(Peripheral::I2s0);
+
+        // hand ADC1 to the digital controller and power it up
+        sens.sar_read_ctrl()
+            .modify(|_, w| w.sar1_dig_force().set_bit());
+        sens.sar_meas_start1().modify(|_, w| {
+            w.meas1_start_force()
+                .set_bit()
+                .sar1_en_pad_force()
+                .set_bit()
+        });
+        sens.sar_touch_ctrl1()
+            .modify(|_, w| w.xpd_hall_force().set_bit().hall_phase_force().set_bit());
+        sens.sar_meas_wait2()
+            .modify(|_, w| unsafe { w.force_xpd_sar().bits(0b11) });
+
+        syscon.saradc_fsm().modify(|_, w| unsafe {
+            w.saradc_rstb_wait()
+                .bits(8)
+                .saradc_start_wait()
+                .bits(5)
+                .saradc_standby_wait()
+                .bits(100)
+                .saradc_sample_cycle()
+                .bits(2)
+        });
+
+        // single unit mode on ADC1, data format type 1, results go to I2S0
+        syscon.saradc_ctrl().modify(|_, w| unsafe {
+            w.saradc_work_mode()
+                .bits(0)
+                .saradc_sar_sel()
+                .clear_bit()
+                .saradc_data_sar_sel()
+                .clear_bit()
+                .saradc_data_to_i2s()
+                .set_bit()
+                .saradc_sar_clk_div()
+                .bits(4)
+                .saradc_sar1_patt_len()
+                .bits(config.pattern_len as u8 - 1)
+        });
+
+        // the digital controller delivers ADC1 results inverted
+        syscon.saradc_ctrl2().modify(|_, w| {
+            w.saradc_meas_num_limit()
+                .clear_bit()
+                .saradc_sar1_inv()
+                .set_bit()
+        });
+
+        let table = config.pattern_table();
+        syscon
+            .saradc_sar1_patt_tab1()
+            .write(|w| unsafe { w.bits(table[0]) });
+        syscon
+            .saradc_sar1_patt_tab2()
+            .write(|w| unsafe { w.bits(table[1]) });
+        syscon
+            .saradc_sar1_patt_tab3()
+            .write(|w| unsafe { w.bits(table[2]) });
+        syscon
+            .saradc_sar1_patt_tab4()
+            .write(|w| unsafe { w.bits(table[3]) });
+
+        let mclk_divider =
+            (I2S_CLOCK / (config.sample_rate.raw() * 2 * I2S_BCLK_DIVIDER)).clamp(2, 255);
+
+        i2s.clkm_conf().modify(|_, w| unsafe {
+            w.clka_ena()
+                .clear_bit()
+                .clk_en()
+                .set_bit()
+                .clkm_div_num()
+                .bits(mclk_divider as u8)
+                .clkm_div_a()
+                .bits(1)
+                .clkm_div_b()
+                .bits(0)
+        });
+        i2s.sample_rate_conf().modify(|_, w| unsafe {
+            w.rx_bck_div_num()
+                .bits(I2S_BCLK_DIVIDER as u8)
+                .rx_bits_mod()
+                .bits(16)
+        });
+
+        i2s.conf().modify(|_, w| {
+            w.rx_slave_mod()
+                .clear_bit()
+                .rx_msb_shift()
+                .clear_bit()
+                .rx_short_sync()
+                .clear_bit()
+                .rx_mono()
+                .clear_bit()
+        });
+        i2s.fifo_conf().modify(|_, w| unsafe {
+            w.dscr_en()
+                .set_bit()
+                .rx_fifo_mod()
+                .bits(1)
+                .rx_fifo_mod_force_en()
+                .set_bit()
+        });
+        i2s.conf_chan()
+            .modify(|_, w| unsafe { w.rx_chan_mod().bits(1) });
+        i2s.conf2()
+            .modify(|_, w| w.camera_en().clear_bit().lcd_en().set_bit());
+    }
+
+    pub(super) fn reset() {
+        let syscon = unsafe { &*APB_CTRL::PTR };
+        let i2s = unsafe { &*I2S0::PTR };
+
+        syscon
+            .saradc_ctrl()
+            .modify(|_, w| w.saradc_sar1_patt_p_clear().set_bit());
+        syscon
+            .saradc_ctrl()
+            .modify(|_, w| w.saradc_sar1_patt_p_clear().clear_bit());
+
+        i2s.conf()
+            .modify(|_, w| w.rx_reset().set_bit().rx_fifo_reset().set_bit());
+        i2s.conf()
+            .modify(|_, w| w.rx_reset().clear_bit().rx_fifo_reset().clear_bit());
+
+        i2s.lc_conf().modify(|_, w| w.in_rst().set_bit());
+        i2s.lc_conf().modify(|_, w| w.in_rst().clear_bit());
+
+        i2s.int_clr().write(|w| {
+            w.in_done()
+                .clear_bit_by_one()
+                .in_suc_eof()
+                .clear_bit_by_one()
+        });
+    }
+
+    pub(super) fn start(len: usize) {
+        let i2s = unsafe { &*I2S0::PTR };
+
+        // the eof_num counts in words
+        i2s.rxeof_num()
+            .modify(|_, w| unsafe { w.rx_eof_num().bits((len / 4) as u32) });
+        i2s.conf().modify(|_, w| w.rx_start().set_bit());
+    }
+
+    pub(super) fn stop() {
+        let i2s = unsafe { &*I2S0::PTR };
+        i2s.conf().modify(|_, w| w.rx_start().clear_bit());
+    }
+}
+
+#[cfg(not(esp32))]
+mod ll {
+    #[cfg(any(esp32c6, esp32h2, esp32s3))]
+    use enumset::EnumSet;
+
+    use super::{AdcContinuousConfig, SAMPLE_BYTES};
+    #[cfg(any(esp32c6, esp32h2, esp32s3))]
+    use super::{Monitor, Threshold};
+    use crate::{
+        dma::DmaPeripheral,
+        peripherals::APB_SARADC,
+        system::{Peripheral, PeripheralClockControl},
+    };
+
+    pub(super) const DMA_PERIPHERAL: DmaPeripheral = DmaPeripheral::Adc;
+
+    // the clock of the digital controller is divided by 16 from the 80 MHz
+    // APB clock (PLL_F96M on the ESP32-H2), every conversion takes two cycles
+    #[cfg(not(esp32h2))]
+    const ADC_CLOCK: u32 = 80_000_000;
+    #[cfg(esp32h2)]
+    const ADC_CLOCK: u32 = 96_000_000;
+    const ADC_CLOCK_DIVIDER: u32 = 16;
+
+    // the largest number of samples between two EOFs
+    const MAX_EOF_NUM: usize = 0xffff;
+
+    pub(super) fn pattern_shift(position: usize) -> usize {
+        18 - position * 6
+    }
+
+    pub(super) fn configure(config: &AdcContinuousConfig) {
+        let sar_adc = unsafe { &*APB_SARADC::PTR };
+
+        PeripheralClockControl::enable(Peripheral::ApbSarAdc);
+
+        #[cfg(esp32s3)]
+        sar_adc.apb_adc_clkm_conf().modify(|_, w| unsafe {
+            w.clkm_div_num()
+                .bits(ADC_CLOCK_DIVIDER as u8 - 1)
+                .clkm_div_b()
+                .bits(1)
+                .clkm_div_a()
+                .bits(0)
+                .clk_sel()
+                .bits(2)
+                .clk_en()
+                .set_bit()
+        });
+
+        #[cfg(esp32c3)]
+        sar_adc.clkm_conf().modify(|_, w| unsafe {
+            w.clkm_div_num()
+                .bits(ADC_CLOCK_DIVIDER as u8 - 1)
+                .clkm_div_b()
+                .bits(1)
+                .clkm_div_a()
+                .bits(0)
+                .clk_sel()
+                .bits(2)
+                .clk_en()
+                .set_bit()
+        });
+
+        #[cfg(any(esp32c6, esp32h2))]
+        unsafe { &*crate::peripherals::PCR::PTR }
+            .saradc_clkm_conf()
+            .modify(|_, w| unsafe {
+                w.saradc_clkm_div_num()
+                    .bits(ADC_CLOCK_DIVIDER as u8 - 1)
+                    .saradc_clkm_div_b()
+                    .bits(1)
+                    .saradc_clkm_div_a()
+                    .bits(0)
+                    .saradc_clkm_sel()
+                    .bits(1)
+                    .saradc_clkm_en()
+                    .set_bit()
+            });
+
+        // hand ADC1 from the RTC to the digital controller and power it up
+        #[cfg(esp32s3)]
+        {
+            let sensors = unsafe { &*crate::peripherals::SENS::PTR };
+
+            sensors
+                .sar_meas1_mux()
+                .modify(|_, w| w.sar1_dig_force().set_bit());
+            sensors.sar_meas1_ctrl2().modify(|_, w| {
+                w.meas1_start_force()
+                    .set_bit()
+                    .sar1_en_pad_force()
+                    .set_bit()
+            });
+            sensors
+                .sar_peri_clk_gate_conf()
+                .modify(|_, w| w.saradc_clk_en().set_bit());
+            sensors
+                .sar_power_xpd_sar()
+                .modify(|_, w| unsafe { w.force_xpd_sar().bits(0b11) });
+        }
+
+        // conversions are triggered by the timer instead of software
+        #[cfg(not(esp32s3))]
+        sar_adc.onetime_sample().modify(|_, w| {
+            w.saradc1_onetime_sample()
+                .clear_bit()
+                .saradc2_onetime_sample()
+                .clear_bit()
+        });
+
+        #[cfg(not(esp32s3))]
+        sar_adc.ctrl().modify(|_, w| unsafe {
+            w.start_force()
+                .clear_bit()
+                .sar_clk_gated()
+                .set_bit()
+                .xpd_sar_force()
+                .bits(0b11)
+                .sar_clk_div()
+                .bits(1)
+                .sar_patt_len()
+                .bits(config.pattern_len as u8 - 1)
+        });
+
+        // single unit mode on ADC1
+        #[cfg(esp32s3)]
+        sar_adc.ctrl().modify(|_, w| unsafe {
+            w.start_force()
+                .clear_bit()
+                .work_mode()
+                .bits(0)
+                .sar_sel()
+                .clear_bit()
+                .sar_clk_gated()
+                .set_bit()
+                .xpd_sar_force()
+                .bits(0b11)
+                .sar_clk_div()
+                .bits(1)
+                .sar1_patt_len()
+                .bits(config.pattern_len as u8 - 1)
+        });
+
+        let table = config.pattern_table();
+
+        #[cfg(not(esp32s3))]
+        {
+            sar_adc
+                .sar_patt_tab1()
+                .write(|w| unsafe { w.bits(table[0]) });
+            sar_adc
+                .sar_patt_tab2()
+                .write(|w| unsafe { w.bits(table[1]) });
+        }
+
+        #[cfg(esp32s3)]
+        {
+            sar_adc
+                .sar1_patt_tab1()
+                .write(|w| unsafe { w.bits(table[0]) });
+            sar_adc
+                .sar1_patt_tab2()
+                .write(|w| unsafe { w.bits(table[1]) });
+            sar_adc
+                .sar1_patt_tab3()
+                .write(|w| unsafe { w.bits(table[2]) });
+            sar_adc
+                .sar1_patt_tab4()
+                .write(|w| unsafe { w.bits(table[3]) });
+        }
+
+        let interval = ADC_CLOCK / ADC_CLOCK_DIVIDER / 2 / config.sample_rate.raw();
+
+        sar_adc.ctrl2().modify(|_, w| unsafe {
+            w.meas_num_limit()
+                .clear_bit()
+                .timer_target()
+                .bits(interval.min(0xfff) as u16)
+        });
+    }
+
+    pub(super) fn reset() {
+        let sar_adc = unsafe { &*APB_SARADC::PTR };
+
+        stop();
+
+        sar_adc
+            .dma_conf()
+            .modify(|_, w| w.apb_adc_reset_fsm().set_bit());
+        sar_adc
+            .dma_conf()
+            .modify(|_, w| w.apb_adc_reset_fsm().clear_bit());
+
+        #[cfg(not(esp32s3))]
+        {
+            sar_adc.ctrl().modify(|_, w| w.sar_patt_p_clear().set_bit());
+            sar_adc
+                .ctrl()
+                .modify(|_, w| w.sar_patt_p_clear().clear_bit());
+        }
+
+        #[cfg(esp32s3)]
+        {
+            sar_adc
+                .ctrl()
+                .modify(|_, w| w.sar1_patt_p_clear().set_bit());
+            sar_adc
+                .ctrl()
+                .modify(|_, w| w.sar1_patt_p_clear().clear_bit());
+        }
+    }
+
+    pub(super) fn start(len: usize) {
+        let sar_adc = unsafe { &*APB_SARADC::PTR };
+
+        let eof_num = usize::min(len / SAMPLE_BYTES, MAX_EOF_NUM);
+        sar_adc.dma_conf().modify(|_, w| unsafe {
+            w.apb_adc_eof_num()
+                .bits(eof_num as u16)
+                .apb_adc_trans()
+                .set_bit()
+        });
+
+        sar_adc.ctrl2().modify(|_, w| w.timer_en().set_bit());
+    }
+
+    pub(super) fn stop() {
+        let sar_adc = unsafe { &*APB_SARADC::PTR };
+
+        sar_adc.ctrl2().modify(|_, w| w.timer_en().clear_bit());
+        sar_adc
+            .dma_conf()
+            .modify(|_, w| w.apb_adc_trans().clear_bit());
+    }
+
+    #[cfg(any(esp32c6, esp32h2, esp32s3))]
+    pub(super) fn enable_monitor(monitor: Monitor, channel: u8, low: u16, high: u16) {
+        let sar_adc = unsafe { &*APB_SARADC::PTR };
+
+        // the channel is selected like in the pattern table, the unit bit
+        // above it is cleared for ADC1
+        match monitor {
+            Monitor::Monitor0 => sar_adc.thres0_ctrl().modify(|_, w| unsafe {
+                w.thres0_channel()
+                    .bits(channel)
+                    .thres0_high()
+                    .bits(high)
+                    .thres0_low()
+                    .bits(low)
+            }),
+            Monitor::Monitor1 => sar_adc.thres1_ctrl().modify(|_, w| unsafe {
+                w.thres1_channel()
+                    .bits(channel)
+                    .thres1_high()
+                    .bits(high)
+                    .thres1_low()
+                    .bits(low)
+            }),
+        }
+
+        clear_monitor_events(monitor);
+
+        sar_adc.thres_ctrl().modify(|_, w| {
+            w.thres_all_en().clear_bit();
+            match monitor {
+                Monitor::Monitor0 => w.thres0_en().set_bit(),
+                Monitor::Monitor1 => w.thres1_en().set_bit(),
+            }
+        });
+    }
+
+    #[cfg(any(esp32c6, esp32h2, esp32s3))]
+    pub(super) fn disable_monitor(monitor: Monitor) {
+        let sar_adc = unsafe { &*APB_SARADC::PTR };
+
+        sar_adc.thres_ctrl().modify(|_, w| match monitor {
+            Monitor::Monitor0 => w.thres0_en().clear_bit(),
+            Monitor::Monitor1 => w.thres1_en().clear_bit(),
+        });
+        clear_monitor_events(monitor);
+    }
+
+    #[cfg(any(esp32c6, esp32h2, esp32s3))]
+    pub(super) fn monitor_events(monitor: Monitor) -> EnumSet<Threshold> {
+        let sar_adc = unsafe { &*APB_SARADC::PTR };
+        let raw = sar_adc.int_raw().read();
+
+        let (high, low) = match monitor {
+            Monitor::Monitor0 => (
+                raw.thres0_high().bit_is_set(),
+                raw.thres0_low().bit_is_set(),
+            ),
+            Monitor::Monitor1 => (
+                raw.thres1_high().bit_is_set(),
+                raw.thres1_low().bit_is_set(),
+            ),
+        };
+
+        let mut events = EnumSet::new();
+        if high {
+            events |= Threshold::High;
+        }
+        if low {
+            events |= Threshold::Low;
+        }
+        events
+    }
+
+    #[cfg(any(esp32c6, esp32h2, esp32s3))]
+    pub(super) fn clear_monitor_events(monitor: Monitor) {
+        let sar_adc = unsafe { &*APB_SARADC::PTR };
+
+        sar_adc.int_clr().write(|w| match monitor {
+            Monitor::Monitor0 => w
+                .thres0_high()
+                .clear_bit_by_one()
+                .thres0_low()
+                .clear_bit_by_one(),
+            Monitor::Monitor1 => w
+                .thres1_high()
+                .clear_bit_by_one()
+                .thres1_low()
+                .clear_bit_by_one(),
+        });
+    }
+
+    #[cfg(all(feature = "async", any(esp32c6, esp32h2, esp32s3)))]
+    pub(super) fn listen_monitor(monitor: Monitor) {
+        let sar_adc = unsafe { &*APB_SARADC::PTR };
+
+        sar_adc.int_ena().modify(|_, w| match monitor {
+            Monitor::Monitor0 => w.thres0_high().set_bit().thres0_low().set_bit(),
+            Monitor::Monitor1 => w.thres1_high().set_bit().thres1_low().set_bit(),
+        });
+    }
+
+    #[cfg(all(feature = "async", any(esp32c6, esp32h2, esp32s3)))]
+    pub(super) fn is_listening_monitor(monitor: Monitor) -> bool {
+        let sar_adc = unsafe { &*APB_SARADC::PTR };
+        let ena = sar_adc.int_ena().read();
+
+        match monitor {
+            Monitor::Monitor0 => ena.thres0_high().bit_is_set() || ena.thres0_low().bit_is_set(),
+            Monitor::Monitor1 => ena.thres1_high().bit_is_set() || ena.thres1_low().bit_is_set(),
+        }
+    }
+}
@@ -1,9 +1,12 @@
+pub use self::calibration::*;
 use super::{AdcConfig, Attenuation};
 use crate::{
     peripheral::PeripheralRef,
     peripherals::{ADC1, ADC2, RTC_IO, SENS},
 };
 
+mod calibration;
+
 pub(super) const NUM_ATTENS: usize = 10;
 
 /// The sampling/readout resolution of the ADC.
@@ -282,9 +285,13 @@ where
     /// This method takes an [AdcPin](super::AdcPin) reference, as it is
     /// expected that the ADC will be able to sample whatever channel
     /// underlies the pin.
-    pub fn read_oneshot<PIN>(&mut self, _pin: &mut super::AdcPin<PIN, ADCI>) -> nb::Result<u16, ()>
+    pub fn read_oneshot<PIN, CS>(
+        &mut self,
+        pin: &mut super::AdcPin<PIN, ADCI, CS>,
+    ) -> nb::Result<u16, ()>
     where
         PIN: super::AdcChannel,
+        CS: super::AdcCalScheme<ADCI>,
     {
         if self.attenuations[PIN::CHANNEL as usize].is_none() {
             panic!("Channel {} is not configured reading!", PIN::CHANNEL);
@@ -316,11 +323,28 @@ where
         // Get converted value
         let converted_value = ADCI::read_data_sar();
 
+        // Postprocess converted value according to calibration scheme used for pin
+        let converted_value = pin.cal_scheme.adc_val(converted_value);
+
         // Mark that no conversions are currently in progress
         self.active_channel = None;
 
         Ok(converted_value)
     }
+
+    /// Request a conversion of a pin enabled with
+    /// [enable_pin_with_auto_cal](super::AdcConfig::enable_pin_with_auto_cal)
+    /// and return the reading in millivolts
+    pub fn read_millivolts<PIN>(
+        &mut self,
+        pin: &mut super::AdcPin<PIN, ADCI, AdcCalAuto<ADCI>>,
+    ) -> nb::Result<u16, ()>
+    where
+        PIN: super::AdcChannel,
+        ADCI: AdcHasAutoCal,
+    {
+        self.read_oneshot(pin)
+    }
 }
 
 impl<'d, ADC1> Adc<'d, ADC1> {
@@ -340,15 +364,16 @@ impl<'d, ADC1> Adc<'d, ADC1> {
 }
 
 #[cfg(feature = "embedded-hal-02")]
-impl<'d, ADCI, PIN> embedded_hal_02::adc::OneShot<ADCI, u16, super::AdcPin<PIN, ADCI>>
+impl<'d, ADCI, PIN, CS> embedded_hal_02::adc::OneShot<ADCI, u16, super::AdcPin<PIN, ADCI, CS>>
     for Adc<'d, ADCI>
 where
     PIN: embedded_hal_02::adc::Channel<ADCI, ID = u8> + super::AdcChannel,
     ADCI: RegisterAccess,
+    CS: super::AdcCalScheme<ADCI>,
 {
     type Error = ();
 
-    fn read(&mut self, pin: &mut super::AdcPin<PIN, ADCI>) -> nb::Result<u16, Self::Error> {
+    fn read(&mut self, pin: &mut super::AdcPin<PIN, ADCI, CS>) -> nb::Result<u16, Self::Error> {
         self.read_oneshot(pin)
     }
 }
@@ -20,6 +20,19 @@
 //!     delay.delay_ms(1500u32);
 //! }
 //! ```
+//!
+//! On RISC-V devices an ADC created with `Adc::new_async` provides
+//! `read_oneshot_async`, which waits for the conversion done interrupt instead
+//! of polling. The ESP32, ESP32-S2 and ESP32-S3 run oneshot conversions on the
+//! RTC controller, which has no conversion done interrupt, so async oneshot
+//! reads are not available there. The ESP32 and ESP32-S3 can await samples
+//! in continuous mode instead.
+//!
+//! Pins enabled with `AdcConfig::enable_pin_with_auto_cal` are read in
+//! millivolts with `Adc::read_millivolts`, using the best calibration scheme
+//! supported by the eFuse data of the chip.
+//!
+//! To sample several pins at a fixed rate, see the `continuous` module.
 
 use core::marker::PhantomData;
 
@@ -31,6 +44,9 @@ use crate::gpio::AnalogPin;
 #[cfg_attr(any(esp32s2, esp32s3), path = "xtensa.rs")]
 mod implementation;
 
+#[cfg(any(esp32, esp32c3, esp32c6, esp32h2, esp32s3))]
+pub mod continuous;
+
 /// The attenuation of the ADC pin.
 ///
 /// The effective measurement range for a given attuenation is dependent on the
@@ -61,7 +77,6 @@ pub enum AdcCalSource {
 /// An I/O pin which can be read using the ADC.
 pub struct AdcPin<PIN, ADCI, CS = ()> {
     pub pin: PIN,
-    #[cfg_attr(esp32, allow(unused))]
     pub cal_scheme: CS,
     _phantom: PhantomData<ADCI>,
 }
@@ -107,6 +122,35 @@ impl<ADCI> AdcConfig<ADCI> {
         }
     }
 
+    /// Enable the specified pin with the given attenuation, calibrated with
+    /// the best scheme the chip and its eFuse data support
+    ///
+    /// Readings of the pin are in millivolts, see [AdcCalAuto].
+    pub fn enable_pin_with_auto_cal<PIN>(
+        &mut self,
+        pin: PIN,
+        attenuation: Attenuation,
+    ) -> AdcPin<PIN, ADCI, AdcCalAuto<ADCI>>
+    where
+        ADCI: AdcHasAutoCal,
+        PIN: AdcChannel + AnalogPin,
+    {
+        // TODO revert this on drop
+        pin.set_analog(crate::private::Internal);
+        self.attenuations[PIN::CHANNEL as usize] = Some(attenuation);
+
+        #[allow(unused_mut)]
+        let mut cal_scheme = ADCI::auto_cal(attenuation);
+        #[cfg(esp32)]
+        cal_scheme.set_resolution(self.resolution);
+
+        AdcPin {
+            pin,
+            cal_scheme,
+            _phantom: PhantomData,
+        }
+    }
+
     /// Enable the specified pin with the given attenuation and calibration
     /// scheme
     #[cfg(not(esp32))]
@@ -226,3 +270,104 @@ macro_rules! impl_adc_interface {
 }
 
 pub(crate) use impl_adc_interface;
+
+#[cfg(all(feature = "async", any(riscv, esp32s3)))]
+pub(crate) mod asynch {
+    use core::task::Poll;
+
+    use embassy_sync::waitqueue::AtomicWaker;
+    use procmacros::handler;
+
+    use crate::peripherals::{Interrupt, APB_SARADC};
+
+    #[cfg(any(esp32c2, esp32c3, esp32s3))]
+    const INTERRUPT: Interrupt = Interrupt::APB_ADC;
+    #[cfg(any(esp32c6, esp32h2))]
+    const INTERRUPT: Interrupt = Interrupt::APB_SARADC;
+
+    #[cfg(riscv)]
+    pub(crate) static ADC1_DONE_WAKER: AtomicWaker = AtomicWaker::new();
+    #[cfg(esp32c3)]
+    pub(crate) static ADC2_DONE_WAKER: AtomicWaker = AtomicWaker::new();
+    #[cfg(any(esp32c6, esp32h2, esp32s3))]
+    pub(crate) static MONITOR_WAKERS: [AtomicWaker; 2] = [AtomicWaker::new(), AtomicWaker::new()];
+
+    /// Bind the interrupt handler shared by the ADC units and the monitors
+    pub(crate) fn bind_interrupt() {
+        unsafe {
+            crate::interrupt::bind_interrupt(INTERRUPT, adc_interrupt_handler.handler());
+        }
+        crate::interrupt::enable(INTERRUPT, adc_interrupt_handler.priority()).unwrap();
+    }
+
+    /// Resolves once the handler cleared the conversion done interrupt enable
+    /// of `ADCI`
+    #[cfg(riscv)]
+    pub(crate) struct AdcDoneFuture<ADCI> {
+        phantom: core::marker::PhantomData<ADCI>,
+    }
+
+    #[cfg(riscv)]
+    impl<ADCI: super::RegisterAccess> AdcDoneFuture<ADCI> {
+        pub(crate) fn new() -> Self {
+            ADCI::listen_done();
+
+            Self {
+                phantom: core::marker::PhantomData,
+            }
+        }
+    }
+
+    #[cfg(riscv)]
+    impl<ADCI: super::RegisterAccess> core::future::Future for AdcDoneFuture<ADCI> {
+        type Output = ();
+
+        fn poll(
+            self: core::pin::Pin<&mut Self>,
+            cx: &mut core::task::Context<'_>,
+        ) -> core::task::Poll<Self::Output> {
+            ADCI::waker().register(cx.waker());
+
+            if ADCI::is_listening_done() {
+                Poll::Pending
+            } else {
+                Poll::Ready(())
+            }
+        }
+    }
+
+    #[handler]
+    fn adc_interrupt_handler() {
+        let sar_adc = unsafe { &*APB_SARADC::PTR };
+        let status = sar_adc.int_st().read();
+
+        #[cfg(riscv)]
+        if status.adc1_done().bit_is_set() {
+            sar_adc.int_ena().modify(|_, w| w.adc1_done().clear_bit());
+            ADC1_DONE_WAKER.wake();
+        }
+
+        #[cfg(esp32c3)]
+        if status.adc2_done().bit_is_set() {
+            sar_adc.int_ena().modify(|_, w| w.adc2_done().clear_bit());
+            ADC2_DONE_WAKER.wake();
+        }
+
+        #[cfg(any(esp32c6, esp32h2, esp32s3))]
+        {
+            if status.thres0_high().bit_is_set() || status.thres0_low().bit_is_set() {
+                sar_adc
+                    .int_ena()
+                    .modify(|_, w| w.thres0_high().clear_bit().thres0_low().clear_bit());
+                MONITOR_WAKERS[0].wake();
+            }
+
+            if status.thres1_high().bit_is_set() || status.thres1_low().bit_is_set() {
+                sar_adc
+                    .int_ena()
+                    .modify(|_, w| w.thres1_high().clear_bit().thres1_low().clear_bit());
+                MONITOR_WAKERS[1].wake();
+            }
+        }
+    }
+}
@@ -1,4 +1,5 @@
-#[cfg(not(esp32h2))]
+use core::marker::PhantomData;
+
 pub use self::calibration::*;
 use super::{AdcCalSource, AdcConfig, Attenuation};
 #[cfg(any(esp32c6, esp32h2))]
@@ -174,6 +175,16 @@ pub trait RegisterAccess {
 
     /// Set calibration parameter to ADC hardware
     fn set_init_code(data: u16);
+
+    /// Enable the interrupt raised once sampling is done
+    fn listen_done();
+
+    /// Check if the interrupt raised once sampling is done is enabled
+    fn is_listening_done() -> bool;
+
+    /// The waker woken by the interrupt handler once sampling is done
+    #[cfg(feature = "async")]
+    fn waker() -> &'static embassy_sync::waitqueue::AtomicWaker;
 }
 
 impl RegisterAccess for crate::peripherals::ADC1 {
@@ -241,6 +252,23 @@ impl RegisterAccess for crate::peripherals::ADC1 {
             lsb as _,
         );
     }
+
+    fn listen_done() {
+        let sar_adc = unsafe { &*APB_SARADC::PTR };
+
+        sar_adc.int_ena().modify(|_, w| w.adc1_done().set_bit());
+    }
+
+    fn is_listening_done() -> bool {
+        let sar_adc = unsafe { &*APB_SARADC::PTR };
+
+        sar_adc.int_ena().read().adc1_done().bit_is_set()
+    }
+
+    #[cfg(feature = "async")]
+    fn waker() -> &'static embassy_sync::waitqueue::AtomicWaker {
+        &super::asynch::ADC1_DONE_WAKER
+    }
 }
 
 impl super::CalibrationAccess for crate::peripherals::ADC1 {
@@ -347,6 +375,23 @@ impl RegisterAccess for crate::peripherals::ADC2 {
             lsb as _,
         );
     }
+
+    fn listen_done() {
+        let sar_adc = unsafe { &*APB_SARADC::PTR };
+
+        sar_adc.int_ena().modify(|_, w| w.adc2_done().set_bit());
+    }
+
+    fn is_listening_done() -> bool {
+        let sar_adc = unsafe { &*APB_SARADC::PTR };
+
+        sar_adc.int_ena().read().adc2_done().bit_is_set()
+    }
+
+    #[cfg(feature = "async")]
+    fn waker() -> &'static embassy_sync::waitqueue::AtomicWaker {
+        &super::asynch::ADC2_DONE_WAKER
+    }
 }
 
 #[cfg(esp32c3)]
@@ -391,13 +436,14 @@ impl super::CalibrationAccess for crate::peripherals::ADC2 {
 }
 
 /// Analog-to-Digital Converter peripheral driver.
-pub struct Adc<'d, ADCI> {
+pub struct Adc<'d, ADCI, DM: crate::Mode = crate::Blocking> {
     _adc: PeripheralRef<'d, ADCI>,
     attenuations: [Option<Attenuation>; NUM_ATTENS],
     active_channel: Option<u8>,
+    _mode: PhantomData<DM>,
 }
 
-impl<'d, ADCI> Adc<'d, ADCI>
+impl<'d, ADCI> Adc<'d, ADCI, crate::Blocking>
 where
     ADCI: RegisterAccess + 'd,
 {
@@ -406,6 +452,60 @@ where
     pub fn new(
         adc_instance: impl crate::peripheral::Peripheral<P = ADCI> + 'd,
         config: AdcConfig<ADCI>,
+    ) -> Self {
+        Self::new_internal(adc_instance, config)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'d, ADCI> Adc<'d, ADCI, crate::Async>
+where
+    ADCI: RegisterAccess + 'd,
+{
+    /// Configure a given ADC instance using the provided configuration, and
+    /// initialize the ADC for async use
+    ///
+    /// This binds the interrupt handler shared by all ADC units.
+    pub fn new_async(
+        adc_instance: impl crate::peripheral::Peripheral<P = ADCI> + 'd,
+        config: AdcConfig<ADCI>,
+    ) -> Self {
+        super::asynch::bind_interrupt();
+
+        Self::new_internal(adc_instance, config)
+    }
+
+    /// Sample the specified pin, waiting for the conversion done interrupt
+    /// instead of polling
+    ///
+    /// A conversion of another pin started by
+    /// [read_oneshot](Self::read_oneshot) has to be completed first.
+    pub async fn read_oneshot_async<PIN, CS>(
+        &mut self,
+        pin: &mut super::AdcPin<PIN, ADCI, CS>,
+    ) -> u16
+    where
+        PIN: super::AdcChannel,
+        CS: super::AdcCalScheme<ADCI>,
+    {
+        loop {
+            match self.read_oneshot(pin) {
+                Ok(value) => return value,
+                Err(nb::Error::WouldBlock) => super::asynch::AdcDoneFuture::<ADCI>::new().await,
+                Err(nb::Error::Other(())) => unreachable!(),
+            }
+        }
+    }
+}
+
+impl<'d, ADCI, DM> Adc<'d, ADCI, DM>
+where
+    ADCI: RegisterAccess + 'd,
+    DM: crate::Mode,
+{
+    fn new_internal(
+        adc_instance: impl crate::peripheral::Peripheral<P = ADCI> + 'd,
+        config: AdcConfig<ADCI>,
     ) -> Self {
         PeripheralClockControl::enable(Peripheral::ApbSarAdc);
 
@@ -420,6 +520,7 @@ where
             _adc: adc_instance.into_ref(),
             attenuations: config.attenuations,
             active_channel: None,
+            _mode: PhantomData,
         }
     }
 
@@ -496,6 +597,20 @@ where
 
         Ok(converted_value)
     }
+
+    /// Request a conversion of a pin enabled with
+    /// [enable_pin_with_auto_cal](super::AdcConfig::enable_pin_with_auto_cal)
+    /// and return the reading in millivolts
+    pub fn read_millivolts<PIN>(
+        &mut self,
+        pin: &mut super::AdcPin<PIN, ADCI, AdcCalAuto<ADCI>>,
+    ) -> nb::Result<u16, ()>
+    where
+        PIN: super::AdcChannel,
+        ADCI: AdcHasAutoCal,
+    {
+        self.read_oneshot(pin)
+    }
 }
 
 #[cfg(any(esp32c2, esp32c3, esp32c6))]
@@ -529,11 +644,12 @@ impl super::AdcCalEfuse for crate::peripherals::ADC2 {
 }
 
 #[cfg(feature = "embedded-hal-02")]
-impl<'d, ADCI, PIN, CS> embedded_hal_02::adc::OneShot<ADCI, u16, super::AdcPin<PIN, ADCI, CS>>
-    for Adc<'d, ADCI>
+impl<'d, ADCI, DM, PIN, CS> embedded_hal_02::adc::OneShot<ADCI, u16, super::AdcPin<PIN, ADCI, CS>>
+    for Adc<'d, ADCI, DM>
 where
     PIN: embedded_hal_02::adc::Channel<ADCI, ID = u8> + super::AdcChannel,
     ADCI: RegisterAccess,
+    DM: crate::Mode,
     CS: super::AdcCalScheme<ADCI>,
 {
     type Error = ();
@@ -1,4 +1,3 @@
-#[cfg(esp32s3)]
 pub use self::calibration::*;
 use super::{AdcCalScheme, AdcCalSource, AdcChannel, AdcConfig, AdcPin, Attenuation};
 #[cfg(esp32s3)]
@@ -536,6 +535,20 @@ where
         Ok(converted_value)
     }
 
+    /// Request a conversion of a pin enabled with
+    /// [enable_pin_with_auto_cal](super::AdcConfig::enable_pin_with_auto_cal)
+    /// and return the reading in millivolts
+    pub fn read_millivolts<PIN>(
+        &mut self,
+        pin: &mut AdcPin<PIN, ADCI, AdcCalAuto<ADCI>>,
+    ) -> nb::Result<u16, ()>
+    where
+        PIN: AdcChannel,
+        ADCI: AdcHasAutoCal,
+    {
+        self.read_oneshot(pin)
+    }
+
     fn start_sample<PIN, CS>(&mut self, pin: &mut AdcPin<PIN, ADCI, CS>)
     where
         PIN: AdcChannel,
@@ -429,6 +429,15 @@ pub trait AesPeripheral: PeripheralMarker {}
 #[doc(hidden)]
 pub trait LcdCamPeripheral: PeripheralMarker {}
 
+/// Marks channels as usable for UHCI
+#[doc(hidden)]
+pub trait UhciPeripheral: PeripheralMarker {}
+
+/// Marks channels as usable for the ADC digital controller
+#[cfg(any(esp32, esp32c3, esp32c6, esp32h2, esp32s3))]
+#[doc(hidden)]
+pub trait AdcPeripheral: PeripheralMarker {}
+
 /// DMA Rx
 #[doc(hidden)]
 pub trait Rx: RxPrivate {}
@@ -482,6 +491,14 @@ pub trait RxPrivate: crate::private::Sealed {
 
     fn drain_buffer(&mut self, dst: &mut [u8]) -> Result<usize, DmaError>;
 
+    /// A circular transfer has filled every descriptor without any of them
+    /// being popped, i.e. received data is being overwritten
+    fn is_overrun(&self) -> bool;
+
+    /// Number of bytes received by a non-circular transfer, up to and
+    /// including the descriptor which has been closed by an EOF
+    fn received_len(&self) -> usize;
+
     /// Descriptor error detected
     fn has_error(&self) -> bool;
 
@@ -769,6 +786,14 @@ where
         self.available
     }
 
+    fn is_overrun(&self) -> bool {
+        !self.last_seen_handled_descriptor_ptr.is_null()
+            && self.descriptors.iter().all(|descr| {
+                let dw0 = unsafe { (descr as *const DmaDescriptor).read_volatile() };
+                dw0.owner() == Owner::Cpu && !dw0.is_empty()
+            })
+    }
+
     fn pop(&mut self, data: &mut [u8]) -> Result<usize, DmaError> {
         let avail = self.available;
 
@@ -779,11 +804,12 @@ where
         unsafe {
             let dst = data.as_mut_ptr();
             let src = self.read_buffer_start;
-            let count = self.available;
+            let count = data.len();
             core::ptr::copy_nonoverlapping(src, dst, count);
+            self.read_buffer_start = src.add(count);
         }
 
-        self.available = 0;
+        self.available -= data.len();
         Ok(data.len())
     }
 
@@ -818,6 +844,24 @@ where
         Ok(len)
     }
 
+    fn received_len(&self) -> usize {
+        let mut len = 0;
+        for descriptor in self.descriptors.iter() {
+            let dw0 = unsafe { core::ptr::addr_of!(*descriptor).read_volatile() };
+            if dw0.owner() == Owner::Dma {
+                break;
+            }
+
+            len += dw0.len();
+
+            if dw0.flags.suc_eof() || dw0.next.is_null() {
+                break;
+            }
+        }
+
+        len
+    }
+
     fn is_listening_eof(&self) -> bool {
         R::is_listening_in_eof()
     }
@@ -933,6 +977,10 @@ pub trait TxPrivate: crate::private::Sealed {
 
     fn has_error(&self) -> bool;
 
+    /// A circular transfer has sent the whole buffer since data has last been
+    /// pushed, i.e. stale data is being sent. Clears the condition.
+    fn take_underrun(&mut self) -> bool;
+
     fn push(&mut self, data: &[u8]) -> Result<usize, DmaError>;
 
     fn push_with(&mut self, f: impl FnOnce(&mut [u8]) -> usize) -> Result<usize, DmaError>;
@@ -1088,6 +1136,7 @@ where
     pub(crate) last_seen_handled_descriptor_ptr: *mut DmaDescriptor,
     pub(crate) buffer_start: *const u8,
     pub(crate) buffer_len: usize,
+    pub(crate) underrun: bool,
     pub(crate) _phantom: PhantomData<R>,
 }
 
@@ -1107,6 +1156,7 @@ where
             last_seen_handled_descriptor_ptr: core::ptr::null_mut(),
             buffer_start: core::ptr::null_mut(),
             buffer_len: 0,
+            underrun: false,
             _phantom: PhantomData,
         }
     }
@@ -1160,6 +1210,7 @@ where
         self.last_seen_handled_descriptor_ptr = self.descriptors.as_mut_ptr();
         self.buffer_start = data;
         self.buffer_len = len;
+        self.underrun = false;
 
         self.tx_impl
             .prepare_transfer_without_start(self.descriptors, circular, peri, data, len)
@@ -1234,6 +1285,7 @@ where
             }
 
             if self.available >= self.buffer_len {
+                self.underrun = true;
                 unsafe {
                     let dw0 = self.write_descr_ptr.read_volatile();
                     let segment_len = dw0.len();
@@ -1326,6 +1378,10 @@ where
         R::has_out_descriptor_error()
     }
 
+    fn take_underrun(&mut self) -> bool {
+        core::mem::take(&mut self.underrun)
+    }
+
     #[cfg(feature = "async")]
     fn waker() -> &'static embassy_sync::waitqueue::AtomicWaker {
         T::waker()
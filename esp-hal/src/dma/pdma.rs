@@ -862,6 +862,9 @@ pub struct I2s0DmaSuitablePeripheral {}
 impl PeripheralMarker for I2s0DmaSuitablePeripheral {}
 impl I2sPeripheral for I2s0DmaSuitablePeripheral {}
 impl I2s0Peripheral for I2s0DmaSuitablePeripheral {}
+// the ADC digital controller of the ESP32 hands its data to I2S0
+#[cfg(esp32)]
+impl AdcPeripheral for I2s0DmaSuitablePeripheral {}
 
 ImplI2sChannel!(0, "I2S0");
 
@@ -0,0 +1,450 @@
+//! # SPI Flash Storage
+//!
+//! ## Overview
+//! The `flash` module provides access to the SPI flash chip which the
+//! application itself is executed from. It can be used to persist data, such
+//! as configuration or calibration values, across resets.
+//!
+//! The [FlashStorage] driver implements the [ReadNorFlash], [NorFlash] and
+//! [MultiwriteNorFlash] traits from the [embedded-storage] crate, so it can be
+//! used with any crate built on top of these traits.
+//!
+//! All accesses are performed through the flash routines in ROM, using the
+//! `SPI1` peripheral. While the flash chip is busy the instruction and data
+//! caches cannot be used, so every operation is executed from RAM with
+//! interrupts disabled. On multi-core chips the other core is stalled for the
+//! duration of the operation, and on the ESP32 the flash caches of both cores
+//! are disabled and flushed.
+//!
+//! Reads and writes must be word (4 byte) aligned, erases must be aligned to
+//! [FLASH_SECTOR_SIZE].
+//!
+//! ⚠️ The driver does not prevent you from overwriting the bootloader,
+//! partition table or application image. Make sure to only access regions
+//! which are reserved for data in your partition table.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! let mut flash = FlashStorage::new(peripherals.SPI1);
+//!
+//! let mut bytes = [0u8; 32];
+//! flash.read(0x9000, &mut bytes).unwrap();
+//!
+//! flash.erase(0x9000, 0x9000 + FLASH_SECTOR_SIZE).unwrap();
+//! flash.write(0x9000, &bytes).unwrap();
+//! ```
+//!
+//! The [partitions] module builds on top of this driver to locate partitions
+//! and to perform over-the-air updates, and the [nvs] module implements a
+//! key-value store compatible with ESP-IDF's NVS library.
+//!
+//! [embedded-storage]: https://docs.rs/embedded-storage/latest/embedded_storage/
+
+use embedded_storage::nor_flash::{
+    check_erase,
+    check_read,
+    check_write,
+    ErrorType,
+    MultiwriteNorFlash,
+    NorFlash,
+    NorFlashError,
+    NorFlashErrorKind,
+    ReadNorFlash,
+};
+use procmacros::ram;
+
+use crate::{
+    peripheral::{Peripheral, PeripheralRef},
+    peripherals::SPI1,
+};
+
+pub mod nvs;
+pub mod partitions;
+
+/// Size of a flash sector, the smallest erasable unit
+pub const FLASH_SECTOR_SIZE: u32 = 4096;
+
+const WORD_SIZE: u32 = 4;
+
+// Data is copied through an on-stack buffer so the ROM functions always operate
+// on word-aligned RAM, regardless of where the caller's buffer is located.
+const BUFFER_WORDS: usize = 64;
+const BUFFER_SIZE: usize = BUFFER_WORDS * WORD_SIZE as usize;
+
+// The second stage bootloader image header contains the configured flash size.
+#[cfg(any(esp32, esp32s2))]
+const BOOTLOADER_OFFSET: u32 = 0x1000;
+#[cfg(not(any(esp32, esp32s2)))]
+const BOOTLOADER_OFFSET: u32 = 0x0;
+
+/// Flash storage error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FlashStorageError {
+    /// The ROM reported an error while accessing the flash chip
+    IoError,
+    /// The flash chip did not finish the operation in time
+    IoTimeout,
+    /// The flash chip could not be unlocked for writing
+    CantUnlock,
+    /// The offset or length is not properly aligned
+    NotAligned,
+    /// The accessed region is out of bounds of the flash chip
+    OutOfBounds,
+    /// The ROM returned an unknown status code
+    Other(i32),
+}
+
+impl FlashStorageError {
+    fn check(status: i32) -> Result<(), Self> {
+        match status {
+            0 => Ok(()),
+            1 => Err(Self::IoError),
+            2 => Err(Self::IoTimeout),
+            other => Err(Self::Other(other)),
+        }
+    }
+}
+
+impl NorFlashError for FlashStorageError {
+    fn kind(&self) -> NorFlashErrorKind {
+        match self {
+            Self::NotAligned => NorFlashErrorKind::NotAligned,
+            Self::OutOfBounds => NorFlashErrorKind::OutOfBounds,
+            _ => NorFlashErrorKind::Other,
+        }
+    }
+}
+
+impl From<NorFlashErrorKind> for FlashStorageError {
+    fn from(kind: NorFlashErrorKind) -> Self {
+        match kind {
+            NorFlashErrorKind::NotAligned => Self::NotAligned,
+            NorFlashErrorKind::OutOfBounds => Self::OutOfBounds,
+            _ => Self::IoError,
+        }
+    }
+}
+
+/// SPI flash storage driver
+pub struct FlashStorage<'d> {
+    _spi1: PeripheralRef<'d, SPI1>,
+    capacity: usize,
+    unlocked: bool,
+}
+
+impl<'d> FlashStorage<'d> {
+    /// Create a new flash storage driver
+    ///
+    /// The capacity of the flash chip is taken from the header of the second
+    /// stage bootloader image.
+    pub fn new(spi1: impl Peripheral<P = SPI1> + 'd) -> Self {
+        crate::into_ref!(spi1);
+
+        let mut storage = Self {
+            _spi1: spi1,
+            capacity: 0,
+            unlocked: false,
+        };
+
+        let mut header = [0u32; 1];
+        unwrap!(storage.read_words(BOOTLOADER_OFFSET, &mut header));
+
+        // The upper nibble of the fourth header byte encodes the flash size,
+        // starting at 1MB and doubling for every step. Unknown encodings are
+        // treated as the smallest size.
+        let mb = match header[0].to_le_bytes()[3] >> 4 {
+            size @ 0..=7 => 1 << size,
+            _ => 1,
+        };
+        storage.capacity = mb * 1024 * 1024;
+
+        storage
+    }
+
+    fn read_words(&mut self, offset: u32, words: &mut [u32]) -> Result<(), FlashStorageError> {
+        let status = with_flash_access(|| unsafe {
+            spiflash_read(offset, words.as_mut_ptr(), (words.len() as u32) * WORD_SIZE)
+        });
+
+        FlashStorageError::check(status)
+    }
+
+    fn write_words(&mut self, offset: u32, words: &[u32]) -> Result<(), FlashStorageError> {
+        self.unlock()?;
+
+        let status = with_flash_access(|| unsafe {
+            spiflash_write(offset, words.as_ptr(), (words.len() as u32) * WORD_SIZE)
+        });
+
+        FlashStorageError::check(status)
+    }
+
+    fn erase_sector(&mut self, sector: u32) -> Result<(), FlashStorageError> {
+        self.unlock()?;
+
+        let status = with_flash_access(|| unsafe { spiflash_erase_sector(sector) });
+
+        FlashStorageError::check(status)
+    }
+
+    fn unlock(&mut self) -> Result<(), FlashStorageError> {
+        if !self.unlocked {
+            if with_flash_access(|| unsafe { spiflash_unlock() }) != 0 {
+                return Err(FlashStorageError::CantUnlock);
+            }
+            self.unlocked = true;
+        }
+
+        Ok(())
+    }
+}
+
+impl ErrorType for FlashStorage<'_> {
+    type Error = FlashStorageError;
+}
+
+impl ReadNorFlash for FlashStorage<'_> {
+    const READ_SIZE: usize = WORD_SIZE as usize;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        check_read(self, offset, bytes.len())?;
+
+        let mut buffer = [0u32; BUFFER_WORDS];
+        let mut address = offset;
+
+        for chunk in bytes.chunks_mut(BUFFER_SIZE) {
+            let words = &mut buffer[..chunk.len() / WORD_SIZE as usize];
+            self.read_words(address, words)?;
+
+            for (dst, word) in chunk.chunks_exact_mut(WORD_SIZE as usize).zip(words.iter()) {
+                dst.copy_from_slice(&word.to_le_bytes());
+            }
+
+            address += chunk.len() as u32;
+        }
+
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+impl NorFlash for FlashStorage<'_> {
+    const WRITE_SIZE: usize = WORD_SIZE as usize;
+    const ERASE_SIZE: usize = FLASH_SECTOR_SIZE as usize;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        check_erase(self, from, to)?;
+
+        for sector in (from / FLASH_SECTOR_SIZE)..(to / FLASH_SECTOR_SIZE) {
+            self.erase_sector(sector)?;
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        check_write(self, offset, bytes.len())?;
+
+        let mut buffer = [0u32; BUFFER_WORDS];
+        let mut address = offset;
+
+        for chunk in bytes.chunks(BUFFER_SIZE) {
+            let words = &mut buffer[..chunk.len() / WORD_SIZE as usize];
+
+            for (word, src) in words.iter_mut().zip(chunk.chunks_exact(WORD_SIZE as usize)) {
+                *word = u32::from_le_bytes([src[0], src[1], src[2], src[3]]);
+            }
+
+            self.write_words(address, words)?;
+
+            address += chunk.len() as u32;
+        }
+
+        Ok(())
+    }
+}
+
+// NOR flash bits can only be cleared by a write, so writing the same word
+// repeatedly without erasing it is well defined.
+impl MultiwriteNorFlash for FlashStorage<'_> {}
+
+/// Runs `f` with exclusive access to the flash chip.
+///
+/// Interrupts are disabled for the duration of the call, and on multi-core
+/// chips the other core is stalled so it can't fetch instructions or data
+/// from flash while the chip is busy.
+fn with_flash_access<R>(f: impl FnOnce() -> R) -> R {
+    critical_section::with(|_| {
+        #[cfg(multi_core)]
+        let stall = OtherCoreStall::stall();
+
+        let result = f();
+
+        #[cfg(multi_core)]
+        stall.resume();
+
+        result
+    })
+}
+
+/// Saved stall configuration of the core we are not running on.
+#[cfg(multi_core)]
+struct OtherCoreStall {
+    c0: u8,
+    c1: u8,
+}
+
+#[cfg(multi_core)]
+impl OtherCoreStall {
+    fn stall() -> Self {
+        let rtc_control = unsafe { &*crate::peripherals::RTC_CNTL::PTR };
+
+        match crate::get_core() {
+            crate::Cpu::ProCpu => {
+                let stall = Self {
+                    c0: rtc_control.options0().read().sw_stall_appcpu_c0().bits(),
+                    c1: rtc_control
+                        .sw_cpu_stall()
+                        .read()
+                        .sw_stall_appcpu_c1()
+                        .bits(),
+                };
+
+                rtc_control
+                    .sw_cpu_stall()
+                    .modify(|_, w| unsafe { w.sw_stall_appcpu_c1().bits(0x21) });
+                rtc_control
+                    .options0()
+                    .modify(|_, w| unsafe { w.sw_stall_appcpu_c0().bits(0x02) });
+
+                stall
+            }
+            crate::Cpu::AppCpu => {
+                let stall = Self {
+                    c0: rtc_control.options0().read().sw_stall_procpu_c0().bits(),
+                    c1: rtc_control
+                        .sw_cpu_stall()
+                        .read()
+                        .sw_stall_procpu_c1()
+                        .bits(),
+                };
+
+                rtc_control
+                    .sw_cpu_stall()
+                    .modify(|_, w| unsafe { w.sw_stall_procpu_c1().bits(0x21) });
+                rtc_control
+                    .options0()
+                    .modify(|_, w| unsafe { w.sw_stall_procpu_c0().bits(0x02) });
+
+                stall
+            }
+        }
+    }
+
+    /// Restore the previous state, so a core which was parked by the user
+    /// stays parked.
+    fn resume(self) {
+        let rtc_control = unsafe { &*crate::peripherals::RTC_CNTL::PTR };
+
+        match crate::get_core() {
+            crate::Cpu::ProCpu => {
+                rtc_control
+                    .sw_cpu_stall()
+                    .modify(|_, w| unsafe { w.sw_stall_appcpu_c1().bits(self.c1) });
+                rtc_control
+                    .options0()
+                    .modify(|_, w| unsafe { w.sw_stall_appcpu_c0().bits(self.c0) });
+            }
+            crate::Cpu::AppCpu => {
+                rtc_control
+                    .sw_cpu_stall()
+                    .modify(|_, w| unsafe { w.sw_stall_procpu_c1().bits(self.c1) });
+                rtc_control
+                    .options0()
+                    .modify(|_, w| unsafe { w.sw_stall_procpu_c0().bits(self.c0) });
+            }
+        }
+    }
+}
+
+extern "C" {
+    fn esp_rom_spiflash_read(src_addr: u32, data: *mut u32, len: u32) -> i32;
+    fn esp_rom_spiflash_unlock() -> i32;
+    fn esp_rom_spiflash_erase_sector(sector_number: u32) -> i32;
+    fn esp_rom_spiflash_write(dest_addr: u32, data: *const u32, len: u32) -> i32;
+}
+
+#[cfg(esp32)]
+extern "C" {
+    fn Cache_Read_Disable_rom(cpu: u32);
+    fn Cache_Read_Enable_rom(cpu: u32);
+    fn Cache_Flush_rom(cpu: u32);
+}
+
+// The ESP32 flash cache does not arbitrate with SPI1 accesses, so it must be
+// disabled while the ROM talks to the flash chip. Both cores have their own
+// cache, and the other core has been stalled at this point.
+#[cfg(esp32)]
+#[inline(always)]
+unsafe fn disable_cache() {
+    Cache_Read_Disable_rom(0);
+    Cache_Read_Disable_rom(1);
+}
+
+#[cfg(esp32)]
+#[inline(always)]
+unsafe fn enable_cache() {
+    Cache_Flush_rom(0);
+    Cache_Flush_rom(1);
+    Cache_Read_Enable_rom(0);
+    Cache_Read_Enable_rom(1);
+}
+
+#[cfg(not(esp32))]
+#[inline(always)]
+unsafe fn disable_cache() {}
+
+#[cfg(not(esp32))]
+#[inline(always)]
+unsafe fn enable_cache() {}
+
+#[ram]
+unsafe fn spiflash_read(src_addr: u32, data: *mut u32, len: u32) -> i32 {
+    disable_cache();
+    let status = esp_rom_spiflash_read(src_addr, data, len);
+    enable_cache();
+
+    status
+}
+
+#[ram]
+unsafe fn spiflash_unlock() -> i32 {
+    disable_cache();
+    let status = esp_rom_spiflash_unlock();
+    enable_cache();
+
+    status
+}
+
+#[ram]
+unsafe fn spiflash_erase_sector(sector_number: u32) -> i32 {
+    disable_cache();
+    let status = esp_rom_spiflash_erase_sector(sector_number);
+    enable_cache();
+
+    status
+}
+
+#[ram]
+unsafe fn spiflash_write(dest_addr: u32, data: *const u32, len: u32) -> i32 {
+    disable_cache();
+    let status = esp_rom_spiflash_write(dest_addr, data, len);
+    enable_cache();
+
+    status
+}
@@ -0,0 +1,223 @@
+//! NVS entries
+//!
+//! Every item stored in NVS starts with a 32 byte entry describing it. Values
+//! of up to 8 bytes are stored inline, strings and blobs are stored in the
+//! entries following it.
+
+use super::Error;
+use crate::rom::crc;
+
+/// Size of a single entry
+pub(crate) const ENTRY_SIZE: usize = 32;
+
+/// Maximum length of a key, excluding the terminating zero
+pub const MAX_KEY_LEN: usize = 15;
+
+/// Chunk index of items which are not part of a blob
+pub(crate) const CHUNK_ANY: u8 = 0xFF;
+
+/// Type of an item stored in NVS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ItemType {
+    /// `u8` value, also used for namespace entries
+    U8,
+    /// `i8` value
+    I8,
+    /// `u16` value
+    U16,
+    /// `i16` value
+    I16,
+    /// `u32` value
+    U32,
+    /// `i32` value
+    I32,
+    /// `u64` value
+    U64,
+    /// `i64` value
+    I64,
+    /// Zero terminated string
+    Str,
+    /// Blob stored in a single item (NVS version 1)
+    Blob,
+    /// Chunk of a blob (NVS version 2)
+    BlobData,
+    /// Index describing the chunks of a blob (NVS version 2)
+    BlobIndex,
+}
+
+impl ItemType {
+    pub(crate) fn from_raw(raw: u8) -> Option<Self> {
+        Some(match raw {
+            0x01 => Self::U8,
+            0x11 => Self::I8,
+            0x02 => Self::U16,
+            0x12 => Self::I16,
+            0x04 => Self::U32,
+            0x14 => Self::I32,
+            0x08 => Self::U64,
+            0x18 => Self::I64,
+            0x21 => Self::Str,
+            0x41 => Self::Blob,
+            0x42 => Self::BlobData,
+            0x48 => Self::BlobIndex,
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn to_raw(self) -> u8 {
+        match self {
+            Self::U8 => 0x01,
+            Self::I8 => 0x11,
+            Self::U16 => 0x02,
+            Self::I16 => 0x12,
+            Self::U32 => 0x04,
+            Self::I32 => 0x14,
+            Self::U64 => 0x08,
+            Self::I64 => 0x18,
+            Self::Str => 0x21,
+            Self::Blob => 0x41,
+            Self::BlobData => 0x42,
+            Self::BlobIndex => 0x48,
+        }
+    }
+}
+
+/// Raw representation of an item header
+///
+/// Layout:
+/// - `0`: namespace index
+/// - `1`: type
+/// - `2`: span, the number of entries used including this one
+/// - `3`: chunk index
+/// - `4..8`: CRC32 over bytes `0..4` and `8..32`
+/// - `8..24`: zero terminated key
+/// - `24..32`: inline value, or size and CRC32 of the data which follows
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Item {
+    pub(crate) raw: [u8; ENTRY_SIZE],
+}
+
+impl Item {
+    pub(crate) fn new(
+        ns: u8,
+        ty: ItemType,
+        span: u8,
+        chunk_index: u8,
+        key: &str,
+    ) -> Result<Self, Error> {
+        check_key(key)?;
+
+        let mut raw = [0xFF; ENTRY_SIZE];
+        raw[0] = ns;
+        raw[1] = ty.to_raw();
+        raw[2] = span;
+        raw[3] = chunk_index;
+        raw[8..24].fill(0);
+        raw[8..][..key.len()].copy_from_slice(key.as_bytes());
+
+        Ok(Self { raw })
+    }
+
+    pub(crate) fn from_bytes(raw: [u8; ENTRY_SIZE]) -> Self {
+        Self { raw }
+    }
+
+    pub(crate) fn ns(&self) -> u8 {
+        self.raw[0]
+    }
+
+    pub(crate) fn item_type(&self) -> Option<ItemType> {
+        ItemType::from_raw(self.raw[1])
+    }
+
+    pub(crate) fn span(&self) -> usize {
+        self.raw[2] as usize
+    }
+
+    pub(crate) fn chunk_index(&self) -> u8 {
+        self.raw[3]
+    }
+
+    pub(crate) fn key_matches(&self, key: &str) -> bool {
+        let stored = &self.raw[8..24];
+        let len = stored.iter().position(|&b| b == 0).unwrap_or(stored.len());
+
+        &stored[..len] == key.as_bytes()
+    }
+
+    pub(crate) fn data(&self) -> [u8; 8] {
+        let mut data = [0u8; 8];
+        data.copy_from_slice(&self.raw[24..32]);
+        data
+    }
+
+    pub(crate) fn set_data(&mut self, data: [u8; 8]) {
+        self.raw[24..32].copy_from_slice(&data);
+    }
+
+    /// Size of the data following a string, blob or blob chunk item
+    pub(crate) fn var_len_size(&self) -> usize {
+        u16::from_le_bytes([self.raw[24], self.raw[25]]) as usize
+    }
+
+    /// CRC32 of the data following a string, blob or blob chunk item
+    pub(crate) fn var_len_crc(&self) -> u32 {
+        u32::from_le_bytes([self.raw[28], self.raw[29], self.raw[30], self.raw[31]])
+    }
+
+    pub(crate) fn set_var_len(&mut self, size: u16, crc: u32) {
+        self.raw[24..26].copy_from_slice(&size.to_le_bytes());
+        self.raw[26..28].copy_from_slice(&[0xFF, 0xFF]);
+        self.raw[28..32].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    /// Total size, chunk count and first chunk index of a blob index item
+    pub(crate) fn blob_index(&self) -> (usize, u8, u8) {
+        let size = u32::from_le_bytes([self.raw[24], self.raw[25], self.raw[26], self.raw[27]]);
+
+        (size as usize, self.raw[28], self.raw[29])
+    }
+
+    pub(crate) fn set_blob_index(&mut self, size: u32, chunk_count: u8, chunk_start: u8) {
+        self.raw[24..28].copy_from_slice(&size.to_le_bytes());
+        self.raw[28] = chunk_count;
+        self.raw[29] = chunk_start;
+        self.raw[30..32].copy_from_slice(&[0xFF, 0xFF]);
+    }
+
+    pub(crate) fn update_crc(&mut self) {
+        let crc = self.calculate_crc();
+        self.raw[4..8].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    pub(crate) fn is_crc_valid(&self) -> bool {
+        u32::from_le_bytes([self.raw[4], self.raw[5], self.raw[6], self.raw[7]])
+            == self.calculate_crc()
+    }
+
+    fn calculate_crc(&self) -> u32 {
+        let crc = crc::crc32_le(u32::MAX, &self.raw[0..4]);
+        crc::crc32_le(crc, &self.raw[8..32])
+    }
+}
+
+/// CRC32 of the data following a variable length item
+pub(crate) fn data_crc(parts: &[&[u8]]) -> u32 {
+    parts
+        .iter()
+        .fold(u32::MAX, |crc, part| crc::crc32_le(crc, part))
+}
+
+pub(crate) fn check_key(key: &str) -> Result<(), Error> {
+    if key.is_empty() || key.len() > MAX_KEY_LEN || key.as_bytes().contains(&0) {
+        return Err(Error::InvalidKey);
+    }
+
+    Ok(())
+}
+
+/// Number of entries needed for an item with `len` bytes of data
+pub(crate) fn span_for(len: usize) -> usize {
+    1 + len.div_ceil(ENTRY_SIZE)
+}
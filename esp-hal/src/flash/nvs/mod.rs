@@ -0,0 +1,976 @@
+//! # Non-Volatile Storage
+//!
+//! ## Overview
+//! The `nvs` module implements a key-value store which is compatible with the
+//! NVS library of ESP-IDF. Partitions written by ESP-IDF applications or
+//! generated with `nvs_partition_gen.py` can be read, and data written by this
+//! driver can be read by ESP-IDF.
+//!
+//! Keys are organized in namespaces. Both namespace names and keys are limited
+//! to [MAX_KEY_LEN] bytes. Supported values are the integer types `u8` to
+//! `i64`, strings and blobs.
+//!
+//! The storage works on top of any [MultiwriteNorFlash] implementation, such
+//! as [FlashStorage](crate::flash::FlashStorage). The partition is divided
+//! into pages of one flash sector each. Items are appended to the active page,
+//! and updating or removing a value only marks its entries as erased. When no
+//! empty page is left, the page with the most erased entries is compacted into
+//! the single page which is always kept in reserve. This spreads the wear over
+//! the whole partition, and a power loss at any point leaves the storage in a
+//! consistent state.
+//!
+//! Only the page format is supported, NVS encryption is not.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! let mut flash = FlashStorage::new(peripherals.SPI1);
+//! let table = PartitionTable::read(&mut flash).unwrap();
+//! let partition = table
+//!     .find(PartitionType::Data(DataPartitionSubType::Nvs))
+//!     .unwrap();
+//!
+//! let mut nvs = Nvs::from_partition(&mut flash, &partition).unwrap();
+//!
+//! let boot_count: u32 = nvs.get("app", "boot_count").unwrap_or(0);
+//! nvs.set("app", "boot_count", boot_count + 1).unwrap();
+//!
+//! let mut buffer = [0u8; 32];
+//! let ssid = nvs.get_str("wifi", "ssid", &mut buffer).unwrap();
+//! ```
+
+use embedded_storage::nor_flash::{MultiwriteNorFlash, NorFlashError, NorFlashErrorKind};
+
+pub use self::item::{ItemType, MAX_KEY_LEN};
+use self::{
+    item::{check_key, data_crc, span_for, Item, CHUNK_ANY, ENTRY_SIZE},
+    page::{
+        entry_offset,
+        Bitmap,
+        EntryState,
+        PageHeader,
+        PageState,
+        BITMAP_OFFSET,
+        BITMAP_SIZE,
+        ENTRY_COUNT,
+        HEADER_SIZE,
+        PAGE_SIZE,
+    },
+};
+use super::partitions::PartitionEntry;
+
+mod item;
+mod page;
+
+/// Namespace index used by the entries defining the namespaces
+const NAMESPACE_NS: u8 = 0;
+const MAX_NAMESPACES: u8 = 254;
+
+/// Maximum size of a string or of a single blob chunk
+const MAX_VAR_LEN: usize = (ENTRY_COUNT - 1) * ENTRY_SIZE;
+
+// Blob chunks are numbered starting at one of two bases, alternating every
+// time the blob is rewritten, so the chunks of the old and the new value can
+// coexist until the new index has been written.
+const CHUNK_START_0: u8 = 0;
+const CHUNK_START_1: u8 = 128;
+const MAX_CHUNKS: u8 = 127;
+
+/// NVS errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// Accessing the flash failed
+    Flash(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] NorFlashErrorKind),
+    /// The partition is not aligned to pages or has less than two pages
+    InvalidPartition,
+    /// The key or namespace name is empty or longer than [MAX_KEY_LEN]
+    InvalidKey,
+    /// The key doesn't exist
+    NotFound,
+    /// The key exists, but holds a value of a different type
+    TypeMismatch,
+    /// The value is too long to be stored
+    ValueTooLong,
+    /// The buffer is too small to hold the value
+    BufferTooSmall,
+    /// There is not enough free space left in the partition
+    NotEnoughSpace,
+    /// All namespace indices are in use
+    TooManyNamespaces,
+    /// The stored data doesn't match its checksum
+    Corrupted,
+}
+
+impl Error {
+    fn flash<E: NorFlashError>(error: E) -> Self {
+        Self::Flash(error.kind())
+    }
+}
+
+/// Integer types which can be stored inline in an item
+pub trait Primitive: crate::private::Sealed + Sized {
+    #[doc(hidden)]
+    const ITEM_TYPE: ItemType;
+
+    #[doc(hidden)]
+    fn to_data(self) -> [u8; 8];
+
+    #[doc(hidden)]
+    fn from_data(data: [u8; 8]) -> Self;
+}
+
+macro_rules! impl_primitive {
+    ($($ty:ty => $item:ident),+) => {
+        $(
+            impl crate::private::Sealed for $ty {}
+
+            impl Primitive for $ty {
+                const ITEM_TYPE: ItemType = ItemType::$item;
+
+                fn to_data(self) -> [u8; 8] {
+                    // Unused bytes are left erased
+                    let mut data = [0xFF; 8];
+                    data[..core::mem::size_of::<$ty>()].copy_from_slice(&self.to_le_bytes());
+                    data
+                }
+
+                fn from_data(data: [u8; 8]) -> Self {
+                    let mut bytes = [0u8; core::mem::size_of::<$ty>()];
+                    bytes.copy_from_slice(&data[..core::mem::size_of::<$ty>()]);
+                    <$ty>::from_le_bytes(bytes)
+                }
+            }
+        )+
+    };
+}
+
+impl_primitive!(
+    u8 => U8,
+    i8 => I8,
+    u16 => U16,
+    i16 => I16,
+    u32 => U32,
+    i32 => I32,
+    u64 => U64,
+    i64 => I64
+);
+
+/// Location of an item in the partition
+#[derive(Clone, Copy)]
+struct Found {
+    page: u32,
+    seq: u32,
+    index: usize,
+    item: Item,
+}
+
+/// The page items are currently appended to
+#[derive(Clone, Copy)]
+struct ActivePage {
+    page: u32,
+    next_free: usize,
+}
+
+/// ESP-IDF compatible non-volatile key-value storage
+pub struct Nvs<F> {
+    flash: F,
+    offset: u32,
+    page_count: u32,
+    active: Option<ActivePage>,
+}
+
+impl<F> Nvs<F>
+where
+    F: MultiwriteNorFlash,
+{
+    /// Open the storage in the region of `size` bytes at `offset`
+    ///
+    /// Pages which have been left in an intermediate state by a power loss are
+    /// repaired.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the flash's read or write size is larger than 4 bytes, or if
+    /// the erase size is larger than a page.
+    pub fn new(flash: F, offset: u32, size: u32) -> Result<Self, Error> {
+        assert!(F::READ_SIZE <= 4 && 4 % F::READ_SIZE == 0);
+        assert!(F::WRITE_SIZE <= 4 && 4 % F::WRITE_SIZE == 0);
+        assert!(PAGE_SIZE as usize % F::ERASE_SIZE == 0);
+
+        if offset % PAGE_SIZE != 0 || size % PAGE_SIZE != 0 || size / PAGE_SIZE < 2 {
+            return Err(Error::InvalidPartition);
+        }
+
+        let mut nvs = Self {
+            flash,
+            offset,
+            page_count: size / PAGE_SIZE,
+            active: None,
+        };
+        nvs.recover()?;
+
+        Ok(nvs)
+    }
+
+    /// Open the storage in the given partition
+    pub fn from_partition(flash: F, partition: &PartitionEntry) -> Result<Self, Error> {
+        Self::new(flash, partition.offset(), partition.size())
+    }
+
+    /// Release the underlying flash
+    pub fn release(self) -> F {
+        self.flash
+    }
+
+    /// Read an integer value
+    pub fn get<T: Primitive>(&mut self, namespace: &str, key: &str) -> Result<T, Error> {
+        let found = self.find_existing(namespace, key)?;
+        if found.item.item_type() != Some(T::ITEM_TYPE) {
+            return Err(Error::TypeMismatch);
+        }
+
+        Ok(T::from_data(found.item.data()))
+    }
+
+    /// Store an integer value, replacing any previous value of `key`
+    pub fn set<T: Primitive>(&mut self, namespace: &str, key: &str, value: T) -> Result<(), Error> {
+        check_key(key)?;
+        let ns = self.namespace(namespace, true)?;
+        let data = value.to_data();
+
+        // Don't wear the flash if the value didn't change
+        if let Some(old) = self.find_key(ns, key)? {
+            if old.item.item_type() == Some(T::ITEM_TYPE) && old.item.data() == data {
+                return Ok(());
+            }
+        }
+
+        let location = self.write_primitive(ns, T::ITEM_TYPE, key, data)?;
+        self.erase_key_except(ns, key, Some(location))
+    }
+
+    /// Read a string into `buffer`
+    ///
+    /// The buffer must have room for the terminating zero stored with the
+    /// string.
+    pub fn get_str<'b>(
+        &mut self,
+        namespace: &str,
+        key: &str,
+        buffer: &'b mut [u8],
+    ) -> Result<&'b str, Error> {
+        let found = self.find_existing(namespace, key)?;
+        if found.item.item_type() != Some(ItemType::Str) {
+            return Err(Error::TypeMismatch);
+        }
+
+        let len = self.read_var_data(&found, buffer)?;
+        let len = buffer[..len].iter().position(|&b| b == 0).unwrap_or(len);
+
+        core::str::from_utf8(&buffer[..len]).map_err(|_| Error::Corrupted)
+    }
+
+    /// Store a string, replacing any previous value of `key`
+    ///
+    /// Strings are limited to 3999 bytes.
+    pub fn set_str(&mut self, namespace: &str, key: &str, value: &str) -> Result<(), Error> {
+        check_key(key)?;
+        let ns = self.namespace(namespace, true)?;
+
+        let location =
+            self.write_var(ns, ItemType::Str, key, CHUNK_ANY, &[value.as_bytes(), &[0]])?;
+        self.erase_key_except(ns, key, Some(location))
+    }
+
+    /// Read a blob into `buffer`, returning the part of the buffer holding the
+    /// blob
+    pub fn get_blob<'b>(
+        &mut self,
+        namespace: &str,
+        key: &str,
+        buffer: &'b mut [u8],
+    ) -> Result<&'b [u8], Error> {
+        let found = self.find_existing(namespace, key)?;
+
+        match found.item.item_type() {
+            Some(ItemType::Blob) => {
+                let len = self.read_var_data(&found, buffer)?;
+                Ok(&buffer[..len])
+            }
+            Some(ItemType::BlobIndex) => {
+                let (size, chunk_count, chunk_start) = found.item.blob_index();
+                if size > buffer.len() {
+                    return Err(Error::BufferTooSmall);
+                }
+
+                let ns = found.item.ns();
+                let mut offset = 0;
+                for chunk in 0..chunk_count {
+                    let chunk_index = chunk_start.wrapping_add(chunk);
+                    let data = self
+                        .find(|_, _, item| {
+                            item.ns() == ns
+                                && item.item_type() == Some(ItemType::BlobData)
+                                && item.chunk_index() == chunk_index
+                                && item.key_matches(key)
+                        })?
+                        .ok_or(Error::Corrupted)?;
+
+                    if offset + data.item.var_len_size() > size {
+                        return Err(Error::Corrupted);
+                    }
+                    offset += self.read_var_data(&data, &mut buffer[offset..size])?;
+                }
+
+                if offset != size {
+                    return Err(Error::Corrupted);
+                }
+
+                Ok(&buffer[..size])
+            }
+            _ => Err(Error::TypeMismatch),
+        }
+    }
+
+    /// Store a blob, replacing any previous value of `key`
+    ///
+    /// Blobs are split into chunks which may span multiple pages.
+    pub fn set_blob(&mut self, namespace: &str, key: &str, value: &[u8]) -> Result<(), Error> {
+        check_key(key)?;
+        let ns = self.namespace(namespace, true)?;
+
+        let chunk_start = match self.find_key(ns, key)? {
+            Some(old)
+                if old.item.item_type() == Some(ItemType::BlobIndex)
+                    && old.item.blob_index().2 == CHUNK_START_0 =>
+            {
+                CHUNK_START_1
+            }
+            _ => CHUNK_START_0,
+        };
+        let in_new_range =
+            |count: u8| move |chunk: u8| chunk >= chunk_start && chunk - chunk_start < count;
+
+        let chunk_count = match self.write_blob_chunks(ns, key, value, chunk_start) {
+            Ok(count) => count,
+            Err(error) => {
+                // Don't leave the chunks of the incomplete value behind
+                self.erase_chunks(ns, key, in_new_range(MAX_CHUNKS))?;
+                return Err(error);
+            }
+        };
+
+        let mut index = Item::new(ns, ItemType::BlobIndex, 1, CHUNK_ANY, key)?;
+        index.set_blob_index(value.len() as u32, chunk_count, chunk_start);
+        index.update_crc();
+
+        let (page, entry, _) = self.reserve(1)?;
+        self.write_item(page, entry, &index, &[])?;
+
+        self.erase_key_except(ns, key, Some((page, entry)))?;
+        let in_range = in_new_range(chunk_count);
+        self.erase_chunks(ns, key, |chunk| !in_range(chunk))
+    }
+
+    /// Remove `key` and its value
+    pub fn remove(&mut self, namespace: &str, key: &str) -> Result<(), Error> {
+        self.find_existing(namespace, key)?;
+
+        let ns = self.namespace(namespace, false)?;
+        self.erase_key_except(ns, key, None)
+    }
+
+    /// Whether `key` exists
+    pub fn contains_key(&mut self, namespace: &str, key: &str) -> Result<bool, Error> {
+        match self.find_existing(namespace, key) {
+            Ok(_) => Ok(true),
+            Err(Error::NotFound) => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Type of the value stored for `key`
+    pub fn item_type(&mut self, namespace: &str, key: &str) -> Result<ItemType, Error> {
+        self.find_existing(namespace, key)?
+            .item
+            .item_type()
+            .ok_or(Error::Corrupted)
+    }
+
+    fn find_existing(&mut self, namespace: &str, key: &str) -> Result<Found, Error> {
+        check_key(key)?;
+        let ns = self.find_namespace(namespace)?.ok_or(Error::NotFound)?;
+
+        self.find_key(ns, key)?.ok_or(Error::NotFound)
+    }
+
+    fn find_namespace(&mut self, name: &str) -> Result<Option<u8>, Error> {
+        check_key(name)?;
+
+        Ok(self
+            .find(|_, _, item| {
+                item.ns() == NAMESPACE_NS
+                    && item.item_type() == Some(ItemType::U8)
+                    && item.key_matches(name)
+            })?
+            .map(|found| found.item.data()[0]))
+    }
+
+    /// Look up the index of a namespace, creating it if `create` is set
+    fn namespace(&mut self, name: &str, create: bool) -> Result<u8, Error> {
+        if let Some(index) = self.find_namespace(name)? {
+            return Ok(index);
+        }
+        if !create {
+            return Err(Error::NotFound);
+        }
+
+        let mut used = [0u32; 8];
+        self.find(|_, _, item| {
+            if item.ns() == NAMESPACE_NS && item.item_type() == Some(ItemType::U8) {
+                let index = item.data()[0] as usize;
+                used[index / 32] |= 1 << (index % 32);
+            }
+            false
+        })?;
+
+        let index = (1..=MAX_NAMESPACES)
+            .find(|&index| used[index as usize / 32] & (1 << (index % 32)) == 0)
+            .ok_or(Error::TooManyNamespaces)?;
+
+        let mut data = [0xFF; 8];
+        data[0] = index;
+        self.write_primitive(NAMESPACE_NS, ItemType::U8, name, data)?;
+
+        Ok(index)
+    }
+
+    fn find_key(&mut self, ns: u8, key: &str) -> Result<Option<Found>, Error> {
+        self.find(|_, _, item| {
+            item.ns() == ns && item.item_type() != Some(ItemType::BlobData) && item.key_matches(key)
+        })
+    }
+
+    /// Find the most recently written item for which `matches` returns true
+    fn find(
+        &mut self,
+        mut matches: impl FnMut(u32, usize, &Item) -> bool,
+    ) -> Result<Option<Found>, Error> {
+        let mut found: Option<Found> = None;
+
+        for page in 0..self.page_count {
+            let header = self.read_header(page)?;
+            if !matches!(header.state, PageState::Active | PageState::Full) {
+                continue;
+            }
+
+            self.scan_page(page, |index, item| {
+                let newer =
+                    found.map_or(true, |found| (header.seq, index) > (found.seq, found.index));
+                if newer && matches(page, index, item) {
+                    found = Some(Found {
+                        page,
+                        seq: header.seq,
+                        index,
+                        item: *item,
+                    });
+                }
+            })?;
+        }
+
+        Ok(found)
+    }
+
+    /// Call `f` for every valid item in `page`
+    fn scan_page(&mut self, page: u32, mut f: impl FnMut(usize, &Item)) -> Result<(), Error> {
+        let bitmap = self.read_bitmap(page)?;
+
+        let mut index = 0;
+        while index < ENTRY_COUNT {
+            if bitmap.get(index) == EntryState::Written {
+                let item = Item::from_bytes(self.read_entry(page, index)?);
+                let span = item.span();
+
+                if item.is_crc_valid() && span >= 1 && index + span <= ENTRY_COUNT {
+                    f(index, &item);
+                    index += span;
+                    continue;
+                }
+            }
+
+            index += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Erase all items of `key` except for the one at `keep`, including the
+    /// chunks of blobs
+    fn erase_key_except(
+        &mut self,
+        ns: u8,
+        key: &str,
+        keep: Option<(u32, usize)>,
+    ) -> Result<(), Error> {
+        while let Some(found) = self.find(|page, index, item| {
+            item.ns() == ns
+                && item.item_type() != Some(ItemType::BlobData)
+                && item.key_matches(key)
+                && keep != Some((page, index))
+        })? {
+            self.erase_item(&found)?;
+
+            if found.item.item_type() == Some(ItemType::BlobIndex) {
+                let (_, count, start) = found.item.blob_index();
+                self.erase_chunks(ns, key, |chunk| chunk >= start && chunk - start < count)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn erase_chunks(
+        &mut self,
+        ns: u8,
+        key: &str,
+        matches: impl Fn(u8) -> bool,
+    ) -> Result<(), Error> {
+        while let Some(found) = self.find(|_, _, item| {
+            item.ns() == ns
+                && item.item_type() == Some(ItemType::BlobData)
+                && item.key_matches(key)
+                && matches(item.chunk_index())
+        })? {
+            self.erase_item(&found)?;
+        }
+
+        Ok(())
+    }
+
+    fn erase_item(&mut self, found: &Found) -> Result<(), Error> {
+        let mut bitmap = self.read_bitmap(found.page)?;
+        for index in found.index..found.index + found.item.span() {
+            bitmap.set(index, EntryState::Erased);
+        }
+
+        self.write_bitmap(found.page, &bitmap)
+    }
+
+    /// Read the data following a variable length item into `buffer`
+    fn read_var_data(&mut self, found: &Found, buffer: &mut [u8]) -> Result<usize, Error> {
+        let size = found.item.var_len_size();
+        if span_for(size) != found.item.span() {
+            return Err(Error::Corrupted);
+        }
+        if size > buffer.len() {
+            return Err(Error::BufferTooSmall);
+        }
+
+        for (offset, chunk) in buffer[..size].chunks_mut(ENTRY_SIZE).enumerate() {
+            let entry = self.read_entry(found.page, found.index + 1 + offset)?;
+            chunk.copy_from_slice(&entry[..chunk.len()]);
+        }
+
+        if data_crc(&[&buffer[..size]]) != found.item.var_len_crc() {
+            return Err(Error::Corrupted);
+        }
+
+        Ok(size)
+    }
+
+    fn write_blob_chunks(
+        &mut self,
+        ns: u8,
+        key: &str,
+        value: &[u8],
+        chunk_start: u8,
+    ) -> Result<u8, Error> {
+        let mut count = 0;
+        let mut offset = 0;
+
+        while offset < value.len() {
+            if count == MAX_CHUNKS {
+                return Err(Error::ValueTooLong);
+            }
+
+            // Fill the rest of the active page, as long as at least one data
+            // entry fits
+            let (_, _, free) = self.reserve(2)?;
+            let len = usize::min(value.len() - offset, (free - 1) * ENTRY_SIZE);
+
+            self.write_var(
+                ns,
+                ItemType::BlobData,
+                key,
+                chunk_start + count,
+                &[&value[offset..][..len]],
+            )?;
+
+            offset += len;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    fn write_primitive(
+        &mut self,
+        ns: u8,
+        ty: ItemType,
+        key: &str,
+        data: [u8; 8],
+    ) -> Result<(u32, usize), Error> {
+        let mut item = Item::new(ns, ty, 1, CHUNK_ANY, key)?;
+        item.set_data(data);
+        item.update_crc();
+
+        let (page, index, _) = self.reserve(1)?;
+        self.write_item(page, index, &item, &[])?;
+
+        Ok((page, index))
+    }
+
+    fn write_var(
+        &mut self,
+        ns: u8,
+        ty: ItemType,
+        key: &str,
+        chunk_index: u8,
+        parts: &[&[u8]],
+    ) -> Result<(u32, usize), Error> {
+        let len = parts.iter().map(|part| part.len()).sum();
+        if len > MAX_VAR_LEN {
+            return Err(Error::ValueTooLong);
+        }
+
+        let span = span_for(len);
+        let mut item = Item::new(ns, ty, span as u8, chunk_index, key)?;
+        item.set_var_len(len as u16, data_crc(parts));
+        item.update_crc();
+
+        let (page, index, _) = self.reserve(span)?;
+        self.write_item(page, index, &item, parts)?;
+
+        Ok((page, index))
+    }
+
+    /// Write an item and its data, and only then mark its entries as written
+    fn write_item(
+        &mut self,
+        page: u32,
+        index: usize,
+        item: &Item,
+        parts: &[&[u8]],
+    ) -> Result<(), Error> {
+        self.write(self.entry_address(page, index), &item.raw)?;
+
+        let mut next = index + 1;
+        let mut entry = [0xFF; ENTRY_SIZE];
+        let mut fill = 0;
+        for &byte in parts.iter().flat_map(|part| part.iter()) {
+            entry[fill] = byte;
+            fill += 1;
+
+            if fill == ENTRY_SIZE {
+                self.write(self.entry_address(page, next), &entry)?;
+                next += 1;
+                entry = [0xFF; ENTRY_SIZE];
+                fill = 0;
+            }
+        }
+        if fill > 0 {
+            self.write(self.entry_address(page, next), &entry)?;
+            next += 1;
+        }
+
+        self.mark_written(page, index, next - index)?;
+
+        if let Some(active) = self.active.as_mut() {
+            if active.page == page {
+                active.next_free = next;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn mark_written(&mut self, page: u32, index: usize, span: usize) -> Result<(), Error> {
+        let mut bitmap = self.read_bitmap(page)?;
+        for index in index..index + span {
+            bitmap.set(index, EntryState::Written);
+        }
+
+        self.write_bitmap(page, &bitmap)
+    }
+
+    /// Find room for at least `min_span` entries in the active page, returns
+    /// the page, the first free entry and the number of free entries
+    fn reserve(&mut self, min_span: usize) -> Result<(u32, usize, usize), Error> {
+        if min_span > ENTRY_COUNT {
+            return Err(Error::ValueTooLong);
+        }
+
+        loop {
+            if let Some(active) = self.active {
+                let free = ENTRY_COUNT - active.next_free;
+                if free >= min_span {
+                    return Ok((active.page, active.next_free, free));
+                }
+
+                self.set_page_state(active.page, PageState::Full)?;
+                self.active = None;
+            }
+
+            self.activate_page()?;
+        }
+    }
+
+    /// Start writing to a new page
+    ///
+    /// One empty page is always kept in reserve. Once it is the only one left,
+    /// the full page with the most erased entries is compacted into it.
+    fn activate_page(&mut self) -> Result<(), Error> {
+        let (empty, empty_count, next_seq) = self.scan_headers()?;
+
+        let empty = empty.ok_or(Error::NotEnoughSpace)?;
+        if empty_count > 1 {
+            self.initialize_page(empty, next_seq)
+        } else {
+            self.collect(empty, next_seq)
+        }
+    }
+
+    /// Returns the first empty page, the number of empty pages and the
+    /// sequence number for the next page to activate
+    fn scan_headers(&mut self) -> Result<(Option<u32>, usize, u32), Error> {
+        let mut empty = None;
+        let mut empty_count = 0;
+        let mut next_seq = 0;
+
+        for page in 0..self.page_count {
+            let header = self.read_header(page)?;
+            if header.state == PageState::Empty {
+                empty_count += 1;
+                empty.get_or_insert(page);
+            } else {
+                next_seq = u32::max(next_seq, header.seq.wrapping_add(1));
+            }
+        }
+
+        Ok((empty, empty_count, next_seq))
+    }
+
+    /// Move the live items of the full page with the most erased entries into
+    /// the `reserved` page, and erase it
+    fn collect(&mut self, reserved: u32, seq: u32) -> Result<(), Error> {
+        let mut victim = None;
+        let mut most_erased = 0;
+
+        for page in 0..self.page_count {
+            if self.read_header(page)?.state != PageState::Full {
+                continue;
+            }
+
+            let erased = self.read_bitmap(page)?.erased_count();
+            if erased > most_erased {
+                victim = Some(page);
+                most_erased = erased;
+            }
+        }
+
+        let victim = victim.ok_or(Error::NotEnoughSpace)?;
+
+        self.set_page_state(victim, PageState::Freeing)?;
+        self.initialize_page(reserved, seq)?;
+        self.copy_items(victim, reserved, false)?;
+        self.erase_page(victim)
+    }
+
+    /// Copy all items from `src` to the active page `dst`
+    ///
+    /// If `skip_existing` is set, items already present in `dst` are skipped,
+    /// which is used to resume an interrupted compaction.
+    fn copy_items(&mut self, src: u32, dst: u32, skip_existing: bool) -> Result<(), Error> {
+        let bitmap = self.read_bitmap(src)?;
+
+        let mut index = 0;
+        while index < ENTRY_COUNT {
+            if bitmap.get(index) != EntryState::Written {
+                index += 1;
+                continue;
+            }
+
+            let item = Item::from_bytes(self.read_entry(src, index)?);
+            let span = item.span();
+            if !item.is_crc_valid() || span == 0 || index + span > ENTRY_COUNT {
+                index += 1;
+                continue;
+            }
+
+            if skip_existing && self.page_contains(dst, &item)? {
+                index += span;
+                continue;
+            }
+
+            let next_free = self
+                .active
+                .filter(|active| active.page == dst)
+                .map(|active| active.next_free)
+                .ok_or(Error::NotEnoughSpace)?;
+            if next_free + span > ENTRY_COUNT {
+                return Err(Error::NotEnoughSpace);
+            }
+
+            for offset in 0..span {
+                let entry = self.read_entry(src, index + offset)?;
+                self.write(self.entry_address(dst, next_free + offset), &entry)?;
+            }
+            self.mark_written(dst, next_free, span)?;
+            self.active = Some(ActivePage {
+                page: dst,
+                next_free: next_free + span,
+            });
+
+            index += span;
+        }
+
+        Ok(())
+    }
+
+    fn page_contains(&mut self, page: u32, item: &Item) -> Result<bool, Error> {
+        let mut contains = false;
+        self.scan_page(page, |_, other| {
+            contains |= other.raw[0..4] == item.raw[0..4] && other.raw[8..24] == item.raw[8..24];
+        })?;
+
+        Ok(contains)
+    }
+
+    /// Bring the partition into a consistent state after a power loss
+    fn recover(&mut self) -> Result<(), Error> {
+        let mut freeing = None;
+
+        for page in 0..self.page_count {
+            match self.read_header(page)?.state {
+                PageState::Corrupt => self.erase_page(page)?,
+                PageState::Empty if !self.is_page_erased(page)? => self.erase_page(page)?,
+                PageState::Freeing => freeing = Some(page),
+                PageState::Active => self.active = Some(self.load_active(page)?),
+                _ => {}
+            }
+        }
+
+        // Finish a compaction which was interrupted
+        if let Some(freeing) = freeing {
+            // The power loss may have happened before the reserved page was
+            // initialized, which must not trigger another compaction
+            if self.active.is_none() {
+                let (empty, _, next_seq) = self.scan_headers()?;
+                self.initialize_page(empty.ok_or(Error::NotEnoughSpace)?, next_seq)?;
+            }
+            let dst = self
+                .active
+                .map(|active| active.page)
+                .ok_or(Error::NotEnoughSpace)?;
+
+            self.copy_items(freeing, dst, true)?;
+            self.erase_page(freeing)?;
+        }
+
+        Ok(())
+    }
+
+    /// Find the first free entry of the active page
+    ///
+    /// Entries which were written, but never marked as written in the bitmap
+    /// are left over from an interrupted write and are marked as erased.
+    fn load_active(&mut self, page: u32) -> Result<ActivePage, Error> {
+        let mut bitmap = self.read_bitmap(page)?;
+        let mut next_free = bitmap.first_unused();
+
+        for index in next_free..ENTRY_COUNT {
+            if self.read_entry(page, index)? != [0xFF; ENTRY_SIZE] {
+                for leftover in next_free..=index {
+                    bitmap.set(leftover, EntryState::Erased);
+                }
+                next_free = index + 1;
+            }
+        }
+        self.write_bitmap(page, &bitmap)?;
+
+        Ok(ActivePage { page, next_free })
+    }
+
+    fn is_page_erased(&mut self, page: u32) -> Result<bool, Error> {
+        let mut chunk = [0u8; ENTRY_SIZE];
+
+        for offset in (0..PAGE_SIZE).step_by(ENTRY_SIZE) {
+            self.read(self.page_address(page) + offset, &mut chunk)?;
+            if chunk != [0xFF; ENTRY_SIZE] {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn initialize_page(&mut self, page: u32, seq: u32) -> Result<(), Error> {
+        self.write(self.page_address(page), &PageHeader::active(seq))?;
+        self.active = Some(ActivePage { page, next_free: 0 });
+
+        Ok(())
+    }
+
+    fn erase_page(&mut self, page: u32) -> Result<(), Error> {
+        let address = self.page_address(page);
+        self.flash
+            .erase(address, address + PAGE_SIZE)
+            .map_err(Error::flash)
+    }
+
+    fn set_page_state(&mut self, page: u32, state: PageState) -> Result<(), Error> {
+        self.write(self.page_address(page), &state.to_raw().to_le_bytes())
+    }
+
+    fn read_header(&mut self, page: u32) -> Result<PageHeader, Error> {
+        let mut raw = [0u8; HEADER_SIZE];
+        self.read(self.page_address(page), &mut raw)?;
+
+        Ok(PageHeader::from_bytes(&raw))
+    }
+
+    fn read_bitmap(&mut self, page: u32) -> Result<Bitmap, Error> {
+        let mut raw = [0u8; BITMAP_SIZE];
+        self.read(self.page_address(page) + BITMAP_OFFSET, &mut raw)?;
+
+        Ok(Bitmap { raw })
+    }
+
+    fn write_bitmap(&mut self, page: u32, bitmap: &Bitmap) -> Result<(), Error> {
+        self.write(self.page_address(page) + BITMAP_OFFSET, &bitmap.raw)
+    }
+
+    fn read_entry(&mut self, page: u32, index: usize) -> Result<[u8; ENTRY_SIZE], Error> {
+        let mut raw = [0u8; ENTRY_SIZE];
+        self.read(self.entry_address(page, index), &mut raw)?;
+
+        Ok(raw)
+    }
+
+    fn page_address(&self, page: u32) -> u32 {
+        self.offset + page * PAGE_SIZE
+    }
+
+    fn entry_address(&self, page: u32, index: usize) -> u32 {
+        self.page_address(page) + entry_offset(index)
+    }
+
+    fn read(&mut self, address: u32, bytes: &mut [u8]) -> Result<(), Error> {
+        self.flash.read(address, bytes).map_err(Error::flash)
+    }
+
+    fn write(&mut self, address: u32, bytes: &[u8]) -> Result<(), Error> {
+        self.flash.write(address, bytes).map_err(Error::flash)
+    }
+}
@@ -0,0 +1,174 @@
+//! NVS pages
+//!
+//! The NVS partition is divided into pages of one flash sector each. Every
+//! page starts with a 32 byte header followed by a 32 byte bitmap holding the
+//! state of each of the 126 entries in the page.
+
+use super::item::ENTRY_SIZE;
+use crate::rom::crc;
+
+/// Size of a page, equal to one flash sector
+pub(crate) const PAGE_SIZE: u32 = 4096;
+
+/// Number of entries in a page
+pub(crate) const ENTRY_COUNT: usize = 126;
+
+pub(crate) const HEADER_SIZE: usize = 32;
+pub(crate) const BITMAP_OFFSET: u32 = 32;
+pub(crate) const BITMAP_SIZE: usize = 32;
+pub(crate) const ENTRIES_OFFSET: u32 = 64;
+
+/// Page format version 2, supporting multi-page blobs
+const VERSION_2: u8 = 0xFE;
+
+/// State of a page
+///
+/// Each state is reached from the previous one by clearing bits, so states
+/// can be advanced without erasing the page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PageState {
+    /// Erased and not yet in use
+    Empty,
+    /// Items are currently being written to this page
+    Active,
+    /// No more items fit into this page
+    Full,
+    /// Items are being moved out of this page before it is erased
+    Freeing,
+    /// The header is damaged, the page needs to be erased
+    Corrupt,
+}
+
+impl PageState {
+    pub(crate) fn from_raw(raw: u32) -> Self {
+        match raw {
+            0xFFFF_FFFF => Self::Empty,
+            0xFFFF_FFFE => Self::Active,
+            0xFFFF_FFFC => Self::Full,
+            0xFFFF_FFF8 => Self::Freeing,
+            _ => Self::Corrupt,
+        }
+    }
+
+    pub(crate) fn to_raw(self) -> u32 {
+        match self {
+            Self::Empty => 0xFFFF_FFFF,
+            Self::Active => 0xFFFF_FFFE,
+            Self::Full => 0xFFFF_FFFC,
+            Self::Freeing => 0xFFFF_FFF8,
+            Self::Corrupt => 0xFFFF_FFF0,
+        }
+    }
+}
+
+/// State of an entry, as stored in the page's bitmap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EntryState {
+    Empty,
+    Written,
+    Erased,
+}
+
+impl EntryState {
+    fn from_raw(raw: u8) -> Self {
+        match raw {
+            0b11 => Self::Empty,
+            0b10 => Self::Written,
+            // ESP-IDF treats the unused `0b01` encoding as erased, too
+            _ => Self::Erased,
+        }
+    }
+
+    fn to_raw(self) -> u8 {
+        match self {
+            Self::Empty => 0b11,
+            Self::Written => 0b10,
+            Self::Erased => 0b00,
+        }
+    }
+}
+
+/// Decoded page header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PageHeader {
+    pub(crate) state: PageState,
+    pub(crate) seq: u32,
+}
+
+impl PageHeader {
+    pub(crate) fn from_bytes(raw: &[u8; HEADER_SIZE]) -> Self {
+        let word = |offset: usize| {
+            u32::from_le_bytes([
+                raw[offset],
+                raw[offset + 1],
+                raw[offset + 2],
+                raw[offset + 3],
+            ])
+        };
+
+        let mut state = PageState::from_raw(word(0));
+        if state != PageState::Empty && word(28) != header_crc(raw) {
+            state = PageState::Corrupt;
+        }
+
+        Self {
+            state,
+            seq: word(4),
+        }
+    }
+
+    /// Encode a header for a freshly activated page
+    pub(crate) fn active(seq: u32) -> [u8; HEADER_SIZE] {
+        let mut raw = [0xFF; HEADER_SIZE];
+        raw[0..4].copy_from_slice(&PageState::Active.to_raw().to_le_bytes());
+        raw[4..8].copy_from_slice(&seq.to_le_bytes());
+        raw[8] = VERSION_2;
+
+        let crc = header_crc(&raw);
+        raw[28..32].copy_from_slice(&crc.to_le_bytes());
+
+        raw
+    }
+}
+
+fn header_crc(raw: &[u8; HEADER_SIZE]) -> u32 {
+    crc::crc32_le(u32::MAX, &raw[4..28])
+}
+
+/// Entry state bitmap of a page
+#[derive(Clone, Copy)]
+pub(crate) struct Bitmap {
+    pub(crate) raw: [u8; BITMAP_SIZE],
+}
+
+impl Bitmap {
+    pub(crate) fn get(&self, index: usize) -> EntryState {
+        EntryState::from_raw((self.raw[index / 4] >> ((index % 4) * 2)) & 0b11)
+    }
+
+    pub(crate) fn set(&mut self, index: usize, state: EntryState) {
+        let shift = (index % 4) * 2;
+        self.raw[index / 4] &= !(0b11 << shift);
+        self.raw[index / 4] |= state.to_raw() << shift;
+    }
+
+    /// Index of the first entry after the last non-empty one
+    pub(crate) fn first_unused(&self) -> usize {
+        (0..ENTRY_COUNT)
+            .rev()
+            .find(|&index| self.get(index) != EntryState::Empty)
+            .map_or(0, |index| index + 1)
+    }
+
+    /// Number of erased entries
+    pub(crate) fn erased_count(&self) -> usize {
+        (0..ENTRY_COUNT)
+            .filter(|&index| self.get(index) == EntryState::Erased)
+            .count()
+    }
+}
+
+/// Offset of entry `index` within a page
+pub(crate) fn entry_offset(index: usize) -> u32 {
+    ENTRIES_OFFSET + (index * ENTRY_SIZE) as u32
+}
@@ -0,0 +1,404 @@
+//! # Partition Table
+//!
+//! ## Overview
+//! The `partitions` module decodes the binary partition table written by the
+//! ESP-IDF tooling (`gen_esp32part.py`, `espflash`, ...) which describes how
+//! the flash chip is divided into application and data partitions.
+//!
+//! The table is located at [PARTITION_TABLE_OFFSET] and consists of 32 byte
+//! entries, optionally followed by an entry holding the MD5 checksum of all
+//! preceding entries. When present, the checksum is verified using the MD5
+//! implementation in [ROM](crate::rom::md5).
+//!
+//! Parsing only operates on a byte buffer, a table can either be read from any
+//! [ReadNorFlash] implementation with [PartitionTable::read] or decoded from a
+//! captured image with [PartitionTable::parse].
+//!
+//! Over-the-air updates on top of the `otadata` and `ota_N` partitions are
+//! provided by the [ota] module.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! let mut flash = FlashStorage::new(peripherals.SPI1);
+//! let table = PartitionTable::read(&mut flash).unwrap();
+//!
+//! for partition in table.iter() {
+//!     println!("{:?}", partition);
+//! }
+//!
+//! let nvs = table
+//!     .find(PartitionType::Data(DataPartitionSubType::Nvs))
+//!     .unwrap();
+//! println!("NVS at {:#x}, {} bytes", nvs.offset(), nvs.size());
+//! ```
+
+use embedded_storage::nor_flash::{NorFlashError, NorFlashErrorKind, ReadNorFlash};
+
+use crate::rom::md5;
+
+pub mod ota;
+
+/// Offset of the partition table in flash
+pub const PARTITION_TABLE_OFFSET: u32 = 0x8000;
+
+/// Maximum length of the partition table, including the MD5 entry
+pub const PARTITION_TABLE_MAX_LEN: usize = 0xC00;
+
+const ENTRY_SIZE: usize = 32;
+const MAX_ENTRIES: usize = PARTITION_TABLE_MAX_LEN / ENTRY_SIZE;
+
+const ENTRY_MAGIC: [u8; 2] = [0xAA, 0x50];
+const MD5_MAGIC: [u8; 2] = [0xEB, 0xEB];
+const END_MAGIC: [u8; 2] = [0xFF, 0xFF];
+
+const FLAG_ENCRYPTED: u32 = 1 << 0;
+const FLAG_READONLY: u32 = 1 << 1;
+
+/// Partition table and OTA errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// Accessing the flash failed
+    Flash(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] NorFlashErrorKind),
+    /// The partition table contains an invalid entry
+    InvalidPartitionTable,
+    /// The MD5 checksum of the partition table doesn't match its content
+    InvalidMd5,
+    /// The partition table has more entries than fit into
+    /// [PARTITION_TABLE_MAX_LEN]
+    TooManyEntries,
+    /// The requested partition doesn't exist
+    PartitionNotFound,
+    /// The requested OTA slot doesn't exist
+    InvalidSlot,
+    /// The image doesn't fit into the target partition
+    ImageTooLarge,
+}
+
+impl Error {
+    pub(crate) fn flash<E: NorFlashError>(error: E) -> Self {
+        Self::Flash(error.kind())
+    }
+}
+
+/// Type and subtype of a partition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PartitionType {
+    /// Application partition
+    App(AppPartitionSubType),
+    /// Data partition
+    Data(DataPartitionSubType),
+    /// Custom partition type, as raw type and subtype
+    Custom(u8, u8),
+}
+
+/// Subtypes of application partitions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AppPartitionSubType {
+    /// Factory application, booted if no OTA slot is selected
+    Factory,
+    /// OTA application slot `ota_N`
+    Ota(u8),
+    /// Test application
+    Test,
+    /// Unknown subtype
+    Unknown(u8),
+}
+
+/// Subtypes of data partitions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DataPartitionSubType {
+    /// OTA selection data (`otadata`)
+    Ota,
+    /// PHY initialization data
+    Phy,
+    /// Non-volatile storage
+    Nvs,
+    /// Core dump
+    CoreDump,
+    /// NVS encryption keys
+    NvsKeys,
+    /// Emulated eFuse values
+    EfuseEm,
+    /// Undefined data
+    Undefined,
+    /// ESPHTTPD file system
+    EspHttpd,
+    /// FAT file system
+    Fat,
+    /// SPIFFS file system
+    Spiffs,
+    /// LittleFS file system
+    LittleFs,
+    /// Unknown subtype
+    Unknown(u8),
+}
+
+impl PartitionType {
+    /// Decode the raw type and subtype bytes of a partition entry
+    pub fn from_raw(ty: u8, subtype: u8) -> Self {
+        match ty {
+            0x00 => Self::App(match subtype {
+                0x00 => AppPartitionSubType::Factory,
+                0x10..=0x1F => AppPartitionSubType::Ota(subtype - 0x10),
+                0x20 => AppPartitionSubType::Test,
+                other => AppPartitionSubType::Unknown(other),
+            }),
+            0x01 => Self::Data(match subtype {
+                0x00 => DataPartitionSubType::Ota,
+                0x01 => DataPartitionSubType::Phy,
+                0x02 => DataPartitionSubType::Nvs,
+                0x03 => DataPartitionSubType::CoreDump,
+                0x04 => DataPartitionSubType::NvsKeys,
+                0x05 => DataPartitionSubType::EfuseEm,
+                0x06 => DataPartitionSubType::Undefined,
+                0x80 => DataPartitionSubType::EspHttpd,
+                0x81 => DataPartitionSubType::Fat,
+                0x82 => DataPartitionSubType::Spiffs,
+                0x83 => DataPartitionSubType::LittleFs,
+                other => DataPartitionSubType::Unknown(other),
+            }),
+            other => Self::Custom(other, subtype),
+        }
+    }
+
+    /// Encode the type as raw type and subtype bytes
+    pub fn to_raw(self) -> (u8, u8) {
+        match self {
+            Self::App(subtype) => (
+                0x00,
+                match subtype {
+                    AppPartitionSubType::Factory => 0x00,
+                    AppPartitionSubType::Ota(n) => 0x10 + n,
+                    AppPartitionSubType::Test => 0x20,
+                    AppPartitionSubType::Unknown(other) => other,
+                },
+            ),
+            Self::Data(subtype) => (
+                0x01,
+                match subtype {
+                    DataPartitionSubType::Ota => 0x00,
+                    DataPartitionSubType::Phy => 0x01,
+                    DataPartitionSubType::Nvs => 0x02,
+                    DataPartitionSubType::CoreDump => 0x03,
+                    DataPartitionSubType::NvsKeys => 0x04,
+                    DataPartitionSubType::EfuseEm => 0x05,
+                    DataPartitionSubType::Undefined => 0x06,
+                    DataPartitionSubType::EspHttpd => 0x80,
+                    DataPartitionSubType::Fat => 0x81,
+                    DataPartitionSubType::Spiffs => 0x82,
+                    DataPartitionSubType::LittleFs => 0x83,
+                    DataPartitionSubType::Unknown(other) => other,
+                },
+            ),
+            Self::Custom(ty, subtype) => (ty, subtype),
+        }
+    }
+}
+
+/// A single entry of the partition table
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PartitionEntry {
+    ty: PartitionType,
+    offset: u32,
+    size: u32,
+    label: [u8; 16],
+    flags: u32,
+}
+
+impl PartitionEntry {
+    /// Decode a raw 32 byte partition table entry
+    ///
+    /// Returns `None` if the entry doesn't start with the partition entry
+    /// magic.
+    pub fn from_bytes(bytes: &[u8; ENTRY_SIZE]) -> Option<Self> {
+        if bytes[0..2] != ENTRY_MAGIC {
+            return None;
+        }
+
+        let word = |offset: usize| {
+            u32::from_le_bytes([
+                bytes[offset],
+                bytes[offset + 1],
+                bytes[offset + 2],
+                bytes[offset + 3],
+            ])
+        };
+
+        let mut label = [0u8; 16];
+        label.copy_from_slice(&bytes[12..28]);
+
+        Some(Self {
+            ty: PartitionType::from_raw(bytes[2], bytes[3]),
+            offset: word(4),
+            size: word(8),
+            label,
+            flags: word(28),
+        })
+    }
+
+    /// Type and subtype of the partition
+    pub fn partition_type(&self) -> PartitionType {
+        self.ty
+    }
+
+    /// Offset of the partition in flash
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Size of the partition in bytes
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Raw label of the partition, padded with zeros
+    pub fn label(&self) -> &[u8; 16] {
+        &self.label
+    }
+
+    /// Label of the partition as a string
+    ///
+    /// Returns an empty string if the label is not valid UTF-8.
+    pub fn label_as_str(&self) -> &str {
+        let len = self
+            .label
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(self.label.len());
+        core::str::from_utf8(&self.label[..len]).unwrap_or("")
+    }
+
+    /// Whether the partition is encrypted
+    pub fn is_encrypted(&self) -> bool {
+        self.flags & FLAG_ENCRYPTED != 0
+    }
+
+    /// Whether the partition is marked read-only
+    pub fn is_readonly(&self) -> bool {
+        self.flags & FLAG_READONLY != 0
+    }
+}
+
+impl core::fmt::Debug for PartitionEntry {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PartitionEntry")
+            .field("label", &self.label_as_str())
+            .field("type", &self.ty)
+            .field("offset", &self.offset)
+            .field("size", &self.size)
+            .field("flags", &self.flags)
+            .finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for PartitionEntry {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(
+            fmt,
+            "PartitionEntry {{ label: {}, type: {}, offset: {=u32:#x}, size: {=u32:#x}, flags: {=u32:#x} }}",
+            self.label_as_str(),
+            self.ty,
+            self.offset,
+            self.size,
+            self.flags
+        )
+    }
+}
+
+/// A decoded and verified partition table
+pub struct PartitionTable {
+    raw: [u8; PARTITION_TABLE_MAX_LEN],
+    len: usize,
+}
+
+impl PartitionTable {
+    /// Read the partition table from [PARTITION_TABLE_OFFSET] of the given
+    /// flash
+    pub fn read<F: ReadNorFlash>(flash: &mut F) -> Result<Self, Error> {
+        let mut raw = [0xFF; PARTITION_TABLE_MAX_LEN];
+        flash
+            .read(PARTITION_TABLE_OFFSET, &mut raw)
+            .map_err(Error::flash)?;
+
+        Self::parse(&raw)
+    }
+
+    /// Decode a partition table from its binary representation
+    ///
+    /// Parsing stops at the first unused (erased) entry or at the MD5 entry.
+    /// If the MD5 entry is present, the checksum of all preceding entries is
+    /// verified.
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        let mut table = Self {
+            raw: [0xFF; PARTITION_TABLE_MAX_LEN],
+            len: 0,
+        };
+
+        for (index, entry) in bytes.chunks_exact(ENTRY_SIZE).enumerate() {
+            match [entry[0], entry[1]] {
+                ENTRY_MAGIC => {
+                    if index >= MAX_ENTRIES - 1 {
+                        return Err(Error::TooManyEntries);
+                    }
+
+                    table.raw[index * ENTRY_SIZE..][..ENTRY_SIZE].copy_from_slice(entry);
+                    table.len += 1;
+                }
+                MD5_MAGIC => {
+                    let digest = md5::compute(&bytes[..index * ENTRY_SIZE]);
+                    if digest.0[..] != entry[16..32] {
+                        return Err(Error::InvalidMd5);
+                    }
+
+                    break;
+                }
+                END_MAGIC => break,
+                _ => return Err(Error::InvalidPartitionTable),
+            }
+        }
+
+        Ok(table)
+    }
+
+    /// Number of partitions in the table
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the table contains no partitions
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the partition at `index`
+    pub fn get(&self, index: usize) -> Option<PartitionEntry> {
+        if index >= self.len {
+            return None;
+        }
+
+        let mut entry = [0u8; ENTRY_SIZE];
+        entry.copy_from_slice(&self.raw[index * ENTRY_SIZE..][..ENTRY_SIZE]);
+        PartitionEntry::from_bytes(&entry)
+    }
+
+    /// Iterate over all partitions in the table
+    pub fn iter(&self) -> impl Iterator<Item = PartitionEntry> + '_ {
+        (0..self.len).filter_map(|index| self.get(index))
+    }
+
+    /// Find the first partition of the given type
+    pub fn find(&self, ty: PartitionType) -> Option<PartitionEntry> {
+        self.iter().find(|entry| entry.partition_type() == ty)
+    }
+
+    /// Find the partition with the given label
+    pub fn find_by_label(&self, label: &str) -> Option<PartitionEntry> {
+        self.iter().find(|entry| entry.label_as_str() == label)
+    }
+}
@@ -0,0 +1,491 @@
+//! # Over-The-Air Updates
+//!
+//! ## Overview
+//! The second stage bootloader decides which `ota_N` application partition to
+//! boot by looking at the `otadata` partition. It consists of two flash
+//! sectors, each holding an [OtaSelectEntry]. The valid entry with the highest
+//! sequence number wins, and the sequence number selects the slot:
+//! `slot = (seq - 1) % number_of_ota_slots`.
+//! Entries whose image has been marked [OtaImageState::Invalid] or
+//! [OtaImageState::Aborted] are ignored.
+//!
+//! When selecting a new slot, the entry which is *not* currently active is
+//! rewritten, so a power loss during the update leaves the previous selection
+//! intact.
+//!
+//! [Ota] reads and modifies the selection, [OtaWriter] streams a new image into
+//! the inactive slot and selects it once the image has been written
+//! completely.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! let mut flash = FlashStorage::new(peripherals.SPI1);
+//! let table = PartitionTable::read(&mut flash).unwrap();
+//! let mut ota = Ota::new(&mut flash, &table).unwrap();
+//!
+//! let mut writer = ota.begin_update().unwrap();
+//! while let Some(chunk) = receive_image_chunk() {
+//!     writer.write(chunk).unwrap();
+//! }
+//! writer.finish().unwrap();
+//!
+//! software_reset();
+//! ```
+
+use embedded_storage::nor_flash::NorFlash;
+
+use super::{
+    AppPartitionSubType,
+    DataPartitionSubType,
+    Error,
+    PartitionEntry,
+    PartitionTable,
+    PartitionType,
+};
+use crate::rom::crc;
+
+/// Size of a single `otadata` sector
+pub const OTADATA_SECTOR_SIZE: u32 = 0x1000;
+
+/// Maximum number of OTA application slots (`ota_0` to `ota_15`)
+pub const MAX_OTA_SLOTS: usize = 16;
+
+const SELECT_ENTRY_SIZE: usize = 32;
+
+// Size of the staging buffer used by the writer, must be a multiple of the
+// flash's write size.
+const WRITE_BUFFER_SIZE: usize = 64;
+
+/// State of an OTA image, used by the bootloader's rollback support
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OtaImageState {
+    /// The image has been selected but not yet booted
+    New,
+    /// The image has been booted once and needs to be marked valid
+    PendingVerify,
+    /// The image has been marked valid and will keep being booted
+    Valid,
+    /// The image has been marked invalid and will not be booted
+    Invalid,
+    /// The image failed to mark itself valid and will not be booted
+    Aborted,
+    /// No state, used when rollback support is disabled
+    Undefined,
+}
+
+impl OtaImageState {
+    fn from_raw(raw: u32) -> Self {
+        match raw {
+            0x0 => Self::New,
+            0x1 => Self::PendingVerify,
+            0x2 => Self::Valid,
+            0x3 => Self::Invalid,
+            0x4 => Self::Aborted,
+            _ => Self::Undefined,
+        }
+    }
+
+    fn to_raw(self) -> u32 {
+        match self {
+            Self::New => 0x0,
+            Self::PendingVerify => 0x1,
+            Self::Valid => 0x2,
+            Self::Invalid => 0x3,
+            Self::Aborted => 0x4,
+            Self::Undefined => u32::MAX,
+        }
+    }
+}
+
+/// A single entry of the `otadata` partition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OtaSelectEntry {
+    seq: u32,
+    state: OtaImageState,
+    crc: u32,
+}
+
+impl OtaSelectEntry {
+    /// Create a new entry with a correct checksum
+    pub fn new(seq: u32, state: OtaImageState) -> Self {
+        Self {
+            seq,
+            state,
+            crc: Self::checksum(seq),
+        }
+    }
+
+    /// Decode a raw `otadata` entry
+    pub fn from_bytes(bytes: &[u8; SELECT_ENTRY_SIZE]) -> Self {
+        let word = |offset: usize| {
+            u32::from_le_bytes([
+                bytes[offset],
+                bytes[offset + 1],
+                bytes[offset + 2],
+                bytes[offset + 3],
+            ])
+        };
+
+        Self {
+            seq: word(0),
+            state: OtaImageState::from_raw(word(24)),
+            crc: word(28),
+        }
+    }
+
+    /// Encode the entry in the format expected by the bootloader
+    ///
+    /// The unused sequence label is left erased.
+    pub fn to_bytes(&self) -> [u8; SELECT_ENTRY_SIZE] {
+        let mut bytes = [0xFF; SELECT_ENTRY_SIZE];
+        bytes[0..4].copy_from_slice(&self.seq.to_le_bytes());
+        bytes[24..28].copy_from_slice(&self.state.to_raw().to_le_bytes());
+        bytes[28..32].copy_from_slice(&self.crc.to_le_bytes());
+        bytes
+    }
+
+    /// Sequence number of the entry
+    pub fn seq(&self) -> u32 {
+        self.seq
+    }
+
+    /// Image state of the entry
+    pub fn state(&self) -> OtaImageState {
+        self.state
+    }
+
+    /// Whether the entry has been written, its checksum is correct and its
+    /// image has not been marked [OtaImageState::Invalid] or
+    /// [OtaImageState::Aborted]
+    pub fn is_valid(&self) -> bool {
+        self.seq != u32::MAX
+            && self.crc == Self::checksum(self.seq)
+            && !matches!(self.state, OtaImageState::Invalid | OtaImageState::Aborted)
+    }
+
+    /// The slot selected by this entry, given the number of OTA slots
+    pub fn slot(&self, slot_count: u8) -> u8 {
+        (self.seq.wrapping_sub(1) % slot_count as u32) as u8
+    }
+
+    // Same checksum as `bootloader_common_ota_select_crc` in ESP-IDF
+    fn checksum(seq: u32) -> u32 {
+        crc::crc32_le(u32::MAX, &seq.to_le_bytes())
+    }
+}
+
+/// Index of the entry the bootloader considers active, if any
+pub fn active_entry(entries: &[OtaSelectEntry; 2]) -> Option<usize> {
+    match (entries[0].is_valid(), entries[1].is_valid()) {
+        (true, true) if entries[1].seq > entries[0].seq => Some(1),
+        (true, _) => Some(0),
+        (false, true) => Some(1),
+        (false, false) => None,
+    }
+}
+
+/// The smallest sequence number greater than `current` which selects `slot`
+pub fn next_sequence(current: u32, slot: u8, slot_count: u8) -> u32 {
+    let count = slot_count as u32;
+    let step = (slot as u32 + count - current % count) % count;
+
+    current + 1 + step
+}
+
+/// OTA slot selection on top of the `otadata` partition
+pub struct Ota<'f, F> {
+    flash: &'f mut F,
+    otadata: PartitionEntry,
+    slots: [Option<PartitionEntry>; MAX_OTA_SLOTS],
+    slot_count: u8,
+    has_factory: bool,
+}
+
+impl<'f, F> Ota<'f, F>
+where
+    F: NorFlash,
+{
+    /// Create a new instance from the `otadata` and `ota_N` partitions in
+    /// `table`
+    pub fn new(flash: &'f mut F, table: &PartitionTable) -> Result<Self, Error> {
+        let otadata = table
+            .find(PartitionType::Data(DataPartitionSubType::Ota))
+            .ok_or(Error::PartitionNotFound)?;
+
+        let mut slots = [None; MAX_OTA_SLOTS];
+        let mut has_factory = false;
+
+        for entry in table.iter() {
+            match entry.partition_type() {
+                PartitionType::App(AppPartitionSubType::Ota(n)) => {
+                    if let Some(slot) = slots.get_mut(n as usize) {
+                        *slot = Some(entry);
+                    }
+                }
+                PartitionType::App(AppPartitionSubType::Factory) => has_factory = true,
+                _ => {}
+            }
+        }
+
+        // The bootloader requires the OTA slots to be numbered consecutively
+        let slot_count = slots.iter().take_while(|slot| slot.is_some()).count() as u8;
+        if slot_count == 0 {
+            return Err(Error::PartitionNotFound);
+        }
+
+        Ok(Self {
+            flash,
+            otadata,
+            slots,
+            slot_count,
+            has_factory,
+        })
+    }
+
+    /// Number of OTA slots
+    pub fn slot_count(&self) -> u8 {
+        self.slot_count
+    }
+
+    /// The partition of the given OTA slot
+    pub fn slot_partition(&self, slot: u8) -> Option<PartitionEntry> {
+        if slot < self.slot_count {
+            self.slots[slot as usize]
+        } else {
+            None
+        }
+    }
+
+    /// Read both entries of the `otadata` partition
+    pub fn read_entries(&mut self) -> Result<[OtaSelectEntry; 2], Error> {
+        let mut entries = [OtaSelectEntry::new(u32::MAX, OtaImageState::Undefined); 2];
+
+        for (index, entry) in entries.iter_mut().enumerate() {
+            let mut bytes = [0u8; SELECT_ENTRY_SIZE];
+            self.flash
+                .read(
+                    self.otadata.offset() + index as u32 * OTADATA_SECTOR_SIZE,
+                    &mut bytes,
+                )
+                .map_err(Error::flash)?;
+            *entry = OtaSelectEntry::from_bytes(&bytes);
+        }
+
+        Ok(entries)
+    }
+
+    /// The currently selected OTA slot
+    ///
+    /// Returns `None` if no slot has been selected yet, in which case the
+    /// bootloader boots the factory application, or `ota_0` if there is none.
+    pub fn current_slot(&mut self) -> Result<Option<u8>, Error> {
+        let entries = self.read_entries()?;
+
+        Ok(active_entry(&entries).map(|index| entries[index].slot(self.slot_count)))
+    }
+
+    /// The image state of the currently selected OTA slot
+    pub fn current_state(&mut self) -> Result<Option<OtaImageState>, Error> {
+        let entries = self.read_entries()?;
+
+        Ok(active_entry(&entries).map(|index| entries[index].state()))
+    }
+
+    /// The slot which is not currently booted, and which the next update
+    /// should be written to
+    pub fn next_slot(&mut self) -> Result<u8, Error> {
+        let next = match self.current_slot()? {
+            Some(slot) => slot + 1,
+            // Without a factory application the bootloader runs `ota_0`
+            None if !self.has_factory => 1,
+            None => 0,
+        };
+
+        Ok(next % self.slot_count)
+    }
+
+    /// Select the slot the bootloader will boot next
+    pub fn set_current_slot(&mut self, slot: u8) -> Result<(), Error> {
+        self.set_current_slot_with_state(slot, OtaImageState::Undefined)
+    }
+
+    /// Select the slot the bootloader will boot next, with the given image
+    /// state
+    ///
+    /// Use [OtaImageState::New] if the bootloader has been built with
+    /// rollback support.
+    pub fn set_current_slot_with_state(
+        &mut self,
+        slot: u8,
+        state: OtaImageState,
+    ) -> Result<(), Error> {
+        if slot >= self.slot_count {
+            return Err(Error::InvalidSlot);
+        }
+
+        let entries = self.read_entries()?;
+        let (current_seq, sector) = match active_entry(&entries) {
+            Some(index) => (entries[index].seq(), index ^ 1),
+            None => (0, 0),
+        };
+
+        let entry = OtaSelectEntry::new(next_sequence(current_seq, slot, self.slot_count), state);
+        self.write_entry(sector, &entry)
+    }
+
+    /// Update the image state of the currently selected slot
+    ///
+    /// Used by applications to mark themselves [OtaImageState::Valid] after a
+    /// successful boot when the bootloader has rollback support enabled.
+    ///
+    /// Like ESP-IDF, the active entry is rewritten in place. Marking it
+    /// [OtaImageState::Invalid] or [OtaImageState::Aborted] therefore rolls
+    /// back to the slot selected by the other entry.
+    pub fn set_current_state(&mut self, state: OtaImageState) -> Result<(), Error> {
+        let entries = self.read_entries()?;
+        let index = active_entry(&entries).ok_or(Error::InvalidSlot)?;
+
+        let entry = OtaSelectEntry::new(entries[index].seq(), state);
+        self.write_entry(index, &entry)
+    }
+
+    /// Start writing a new image into the slot returned by
+    /// [Ota::next_slot]
+    pub fn begin_update(&mut self) -> Result<OtaWriter<'_, 'f, F>, Error> {
+        let slot = self.next_slot()?;
+        self.begin_update_slot(slot)
+    }
+
+    /// Start writing a new image into the given slot
+    pub fn begin_update_slot(&mut self, slot: u8) -> Result<OtaWriter<'_, 'f, F>, Error> {
+        let partition = self.slot_partition(slot).ok_or(Error::InvalidSlot)?;
+
+        debug_assert!(WRITE_BUFFER_SIZE % F::WRITE_SIZE == 0);
+
+        Ok(OtaWriter {
+            ota: self,
+            slot,
+            partition,
+            written: 0,
+            erased: 0,
+            buffer: [0xFF; WRITE_BUFFER_SIZE],
+            buffered: 0,
+        })
+    }
+
+    fn write_entry(&mut self, sector: usize, entry: &OtaSelectEntry) -> Result<(), Error> {
+        let offset = self.otadata.offset() + sector as u32 * OTADATA_SECTOR_SIZE;
+
+        self.flash
+            .erase(offset, offset + OTADATA_SECTOR_SIZE)
+            .map_err(Error::flash)?;
+        self.flash
+            .write(offset, &entry.to_bytes())
+            .map_err(Error::flash)
+    }
+}
+
+/// Streams an application image into an OTA slot
+///
+/// The slot is erased on the fly as the image is written. Once all data has
+/// been written, [OtaWriter::finish] selects the slot for the next boot.
+/// Dropping the writer without finishing it leaves the current selection
+/// untouched.
+pub struct OtaWriter<'o, 'f, F> {
+    ota: &'o mut Ota<'f, F>,
+    slot: u8,
+    partition: PartitionEntry,
+    written: u32,
+    erased: u32,
+    buffer: [u8; WRITE_BUFFER_SIZE],
+    buffered: usize,
+}
+
+impl<'o, 'f, F> OtaWriter<'o, 'f, F>
+where
+    F: NorFlash,
+{
+    /// The slot being written
+    pub fn slot(&self) -> u8 {
+        self.slot
+    }
+
+    /// Number of image bytes written so far
+    pub fn len(&self) -> u32 {
+        self.written + self.buffered as u32
+    }
+
+    /// Whether no data has been written yet
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Append `data` to the image
+    pub fn write(&mut self, mut data: &[u8]) -> Result<(), Error> {
+        if self.len() + data.len() as u32 > self.partition.size() {
+            return Err(Error::ImageTooLarge);
+        }
+
+        while !data.is_empty() {
+            let count = usize::min(WRITE_BUFFER_SIZE - self.buffered, data.len());
+            self.buffer[self.buffered..][..count].copy_from_slice(&data[..count]);
+            self.buffered += count;
+            data = &data[count..];
+
+            if self.buffered == WRITE_BUFFER_SIZE {
+                self.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write any remaining data and select the slot for the next boot
+    pub fn finish(self) -> Result<(), Error> {
+        self.finish_with_state(OtaImageState::Undefined)
+    }
+
+    /// Write any remaining data and select the slot for the next boot, with
+    /// the given image state
+    pub fn finish_with_state(mut self, state: OtaImageState) -> Result<(), Error> {
+        // Pad the last write to the flash's write size with erased bytes
+        let padded = self.buffered.div_ceil(F::WRITE_SIZE) * F::WRITE_SIZE;
+        self.buffer[self.buffered..padded].fill(0xFF);
+        self.buffered = padded;
+        self.flush()?;
+
+        self.ota.set_current_slot_with_state(self.slot, state)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        if self.buffered == 0 {
+            return Ok(());
+        }
+
+        let end = self.written + self.buffered as u32;
+        while self.erased < end {
+            let offset = self.partition.offset() + self.erased;
+            self.ota
+                .flash
+                .erase(offset, offset + F::ERASE_SIZE as u32)
+                .map_err(Error::flash)?;
+            self.erased += F::ERASE_SIZE as u32;
+        }
+
+        self.ota
+            .flash
+            .write(
+                self.partition.offset() + self.written,
+                &self.buffer[..self.buffered],
+            )
+            .map_err(Error::flash)?;
+
+        self.written = end;
+        self.buffered = 0;
+
+        Ok(())
+    }
+}
@@ -11,6 +11,15 @@
 //! multiple I2C peripheral instances on `ESP32`, `ESP32H2`, `ESP32S2`, and
 //! `ESP32S3` chips
 //!
+//! Transfers of any length are split into chunks fitting the hardware's
+//! command list and FIFO. Arbitrary sequences of reads and writes, joined by
+//! repeated STARTs, and 10-bit addresses are available through the
+//! [`embedded_hal::i2c::I2c`] and `embedded_hal_async::i2c::I2c` traits. If a
+//! transfer times out, the driver clocks SCL to free a slave holding SDA low
+//! before returning [`Error::TimeOut`].
+//!
+//! The peripherals can also act as a slave device, see the [`slave`] module.
+//!
 //! ## Example
 //! Following code shows how to read data from a BMP180 sensor using I2C.
 //!
@@ -33,16 +42,31 @@
 //! }
 //! ```
 
+pub mod slave;
+
 use core::marker::PhantomData;
 
 use fugit::HertzU32;
+use portable_atomic::{AtomicU8, Ordering};
 
 use crate::{
     clock::Clocks,
-    gpio::{InputPin, InputSignal, OutputPin, OutputSignal},
+    gpio::{
+        Bank0GpioRegisterAccess,
+        BankGpioRegisterAccess,
+        InputPin,
+        InputSignal,
+        OutputPin,
+        OutputSignal,
+        OutputSignalType,
+        Pin,
+    },
     interrupt::InterruptHandler,
     peripheral::{Peripheral, PeripheralRef},
-    peripherals::i2c0::{RegisterBlock, COMD},
+    peripherals::{
+        i2c0::{RegisterBlock, COMD},
+        GPIO,
+    },
     system::PeripheralClockControl,
 };
 
@@ -54,6 +78,27 @@ cfg_if::cfg_if! {
     }
 }
 
+cfg_if::cfg_if! {
+    if #[cfg(i2c1)] {
+        const NUM_I2C: usize = 2;
+    } else {
+        const NUM_I2C: usize = 1;
+    }
+}
+
+// SDA and SCL pads of each peripheral, remembered for bus recovery
+const NO_PIN: u8 = u8::MAX;
+const INIT_PIN: AtomicU8 = AtomicU8::new(NO_PIN);
+static SDA_PINS: [AtomicU8; NUM_I2C] = [INIT_PIN; NUM_I2C];
+static SCL_PINS: [AtomicU8; NUM_I2C] = [INIT_PIN; NUM_I2C];
+
+// Chunk size of reads and writes, leaving room for a 10-bit address in a
+// command's length or, on ESP32 and ESP32-S2, in the FIFO
+#[cfg(any(esp32, esp32s2))]
+const I2C_CHUNK_SIZE: usize = 30;
+#[cfg(not(any(esp32, esp32s2)))]
+const I2C_CHUNK_SIZE: usize = 253;
+
 /// I2C-specific transmission errors
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -274,6 +319,33 @@ impl From<u32> for Opcode {
     }
 }
 
+/// Address of the slave device
+#[derive(Clone, Copy)]
+enum I2cAddress {
+    SevenBit(u8),
+    TenBit(u16),
+}
+
+impl I2cAddress {
+    /// Encodes the address bytes in write direction
+    fn encode(self, buffer: &mut [u8; 2]) -> &[u8] {
+        match self {
+            I2cAddress::SevenBit(address) => {
+                buffer[0] = address << 1 | OperationType::Write as u8;
+                &buffer[..1]
+            }
+            I2cAddress::TenBit(address) => {
+                // `0b11110` marker followed by the upper two bits, then the
+                // lower eight bits
+                buffer[0] =
+                    0b1111_0000 | ((address >> 7) as u8 & 0b110) | OperationType::Write as u8;
+                buffer[1] = address as u8;
+                &buffer[..2]
+            }
+        }
+    }
+}
+
 /// I2C peripheral container (I2C)
 pub struct I2C<'d, T, DM: crate::Mode> {
     peripheral: PeripheralRef<'d, T>,
@@ -286,12 +358,16 @@ where
 {
     /// Reads enough bytes from slave with `address` to fill `buffer`
     pub fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Error> {
+        let mut address_bytes = [0; 2];
+        let address = I2cAddress::SevenBit(address).encode(&mut address_bytes);
         self.peripheral.master_read(address, buffer)
     }
 
     /// Writes bytes to slave with address `address`
     pub fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Error> {
-        self.peripheral.master_write(addr, bytes)
+        let mut address_bytes = [0; 2];
+        let address = I2cAddress::SevenBit(addr).encode(&mut address_bytes);
+        self.peripheral.master_write(address, bytes)
     }
 
     /// Writes bytes to slave with address `address` and then reads enough bytes
@@ -302,6 +378,8 @@ where
         bytes: &[u8],
         buffer: &mut [u8],
     ) -> Result<(), Error> {
+        let mut address_bytes = [0; 2];
+        let address = I2cAddress::SevenBit(address).encode(&mut address_bytes);
         self.peripheral.master_write_read(address, bytes, buffer)
     }
 }
@@ -314,6 +392,8 @@ where
     type Error = Error;
 
     fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        let mut address_bytes = [0; 2];
+        let address = I2cAddress::SevenBit(address).encode(&mut address_bytes);
         self.peripheral.master_read(address, buffer)
     }
 }
@@ -326,7 +406,9 @@ where
     type Error = Error;
 
     fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Self::Error> {
-        self.peripheral.master_write(addr, bytes)
+        let mut address_bytes = [0; 2];
+        let address = I2cAddress::SevenBit(addr).encode(&mut address_bytes);
+        self.peripheral.master_write(address, bytes)
     }
 }
 
@@ -343,6 +425,8 @@ where
         bytes: &[u8],
         buffer: &mut [u8],
     ) -> Result<(), Self::Error> {
+        let mut address_bytes = [0; 2];
+        let address = I2cAddress::SevenBit(address).encode(&mut address_bytes);
         self.peripheral.master_write_read(address, bytes, buffer)
     }
 }
@@ -362,45 +446,25 @@ where
         address: u8,
         operations: &mut [embedded_hal::i2c::Operation<'_>],
     ) -> Result<(), Self::Error> {
-        use embedded_hal::i2c::Operation;
-        let mut last_op = LastOpWas::None;
-        let mut op_iter = operations.iter_mut().peekable();
-        while let Some(op) = op_iter.next() {
-            // Clear all I2C interrupts
-            self.peripheral.clear_all_interrupts();
+        let mut address_bytes = [0; 2];
+        let address = I2cAddress::SevenBit(address).encode(&mut address_bytes);
+        self.peripheral.transaction(address, operations)
+    }
+}
 
-            // TODO somehow know that we can combine a write and a read into one transaction
-            let cmd_iterator = &mut self.peripheral.register_block().comd_iter();
-            match op {
-                Operation::Write(bytes) => {
-                    // execute a write operation:
-                    // - issue START/RSTART if op is different from previous
-                    // - issue STOP if op is the last one
-                    self.peripheral.write_operation(
-                        address,
-                        bytes,
-                        last_op != LastOpWas::Write,
-                        op_iter.peek().is_none(),
-                        cmd_iterator,
-                    )?;
-                    last_op = LastOpWas::Write;
-                }
-                Operation::Read(buffer) => {
-                    // execute a read operation:
-                    // - issue START/RSTART if op is different from previous
-                    // - issue STOP if op is the last one
-                    self.peripheral.read_operation(
-                        address,
-                        buffer,
-                        last_op != LastOpWas::Read,
-                        op_iter.peek().is_none(),
-                        cmd_iterator,
-                    )?;
-                    last_op = LastOpWas::Read;
-                }
-            }
-        }
-        Ok(())
+#[cfg(feature = "embedded-hal")]
+impl<T, DM: crate::Mode> embedded_hal::i2c::I2c<embedded_hal::i2c::TenBitAddress> for I2C<'_, T, DM>
+where
+    T: Instance,
+{
+    fn transaction(
+        &mut self,
+        address: u16,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let mut address_bytes = [0; 2];
+        let address = I2cAddress::TenBit(address).encode(&mut address_bytes);
+        self.peripheral.transaction(address, operations)
     }
 }
 
@@ -431,33 +495,7 @@ where
             phantom: PhantomData,
         };
 
-        // avoid SCL/SDA going low during configuration
-        scl.set_output_high(true, crate::private::Internal);
-        sda.set_output_high(true, crate::private::Internal);
-
-        scl.set_to_open_drain_output(crate::private::Internal);
-        scl.enable_input(true, crate::private::Internal);
-        scl.internal_pull_up(true, crate::private::Internal);
-        scl.connect_peripheral_to_output(
-            i2c.peripheral.scl_output_signal(),
-            crate::private::Internal,
-        );
-        scl.connect_input_to_peripheral(
-            i2c.peripheral.scl_input_signal(),
-            crate::private::Internal,
-        );
-
-        sda.set_to_open_drain_output(crate::private::Internal);
-        sda.enable_input(true, crate::private::Internal);
-        sda.internal_pull_up(true, crate::private::Internal);
-        sda.connect_peripheral_to_output(
-            i2c.peripheral.sda_output_signal(),
-            crate::private::Internal,
-        );
-        sda.connect_input_to_peripheral(
-            i2c.peripheral.sda_input_signal(),
-            crate::private::Internal,
-        );
+        configure_pins(&*i2c.peripheral, sda, scl);
 
         i2c.peripheral.setup(frequency, clocks, timeout);
 
@@ -724,9 +762,9 @@ mod asynch {
             Ok(())
         }
 
-        async fn write_operation<'a, I>(
+        async fn write_chunk<'a, I>(
             &self,
-            address: u8,
+            address: &[u8],
             bytes: &[u8],
             start: bool,
             stop: bool,
@@ -735,13 +773,20 @@ mod asynch {
         where
             I: Iterator<Item = &'a COMD>,
         {
+            // Short circuit for zero length writes without start or end as that would be an
+            // invalid operation
+            if bytes.is_empty() && !start && !stop {
+                return Ok(());
+            }
+
             // Reset FIFO and command list
             self.peripheral.reset_fifo();
             self.peripheral.reset_command_list();
             if start {
                 add_cmd(cmd_iterator, Command::Start)?;
             }
-            self.peripheral.setup_write(address, bytes, cmd_iterator)?;
+            self.peripheral
+                .setup_write(if start { address } else { &[] }, bytes, cmd_iterator)?;
             add_cmd(
                 cmd_iterator,
                 if stop { Command::Stop } else { Command::End },
@@ -755,12 +800,13 @@ mod asynch {
             Ok(())
         }
 
-        async fn read_operation<'a, I>(
+        async fn read_chunk<'a, I>(
             &self,
-            address: u8,
+            address: &[u8],
             buffer: &mut [u8],
             start: bool,
             stop: bool,
+            will_continue: bool,
             cmd_iterator: &mut I,
         ) -> Result<(), Error>
         where
@@ -772,7 +818,12 @@ mod asynch {
             if start {
                 add_cmd(cmd_iterator, Command::Start)?;
             }
-            self.peripheral.setup_read(address, buffer, cmd_iterator)?;
+            self.peripheral.setup_read(
+                if start { address } else { &[] },
+                buffer,
+                will_continue,
+                cmd_iterator,
+            )?;
             add_cmd(
                 cmd_iterator,
                 if stop { Command::Stop } else { Command::End },
@@ -783,84 +834,145 @@ mod asynch {
             Ok(())
         }
 
-        /// Send data bytes from the `bytes` array to a target slave with the
-        /// address `addr`
-        async fn master_write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Error> {
-            // Clear all I2C interrupts
-            self.peripheral.clear_all_interrupts();
-            self.write_operation(
-                addr,
-                bytes,
-                true,
-                true,
-                &mut self.peripheral.register_block().comd_iter(),
-            )
-            .await?;
+        /// Writes `bytes`, split into chunks the hardware can handle, see
+        /// [`Instance::write_operation`]
+        async fn write_operation(
+            &self,
+            address: &[u8],
+            bytes: &[u8],
+            start: bool,
+            stop: bool,
+        ) -> Result<(), Error> {
+            if bytes.is_empty() {
+                self.peripheral.clear_all_interrupts();
+                return self
+                    .write_chunk(
+                        address,
+                        bytes,
+                        start,
+                        stop,
+                        &mut self.peripheral.register_block().comd_iter(),
+                    )
+                    .await;
+            }
+
+            let mut chunks = bytes.chunks(I2C_CHUNK_SIZE).peekable();
+            let mut start = start;
+            while let Some(chunk) = chunks.next() {
+                self.peripheral.clear_all_interrupts();
+                self.write_chunk(
+                    address,
+                    chunk,
+                    start,
+                    stop && chunks.peek().is_none(),
+                    &mut self.peripheral.register_block().comd_iter(),
+                )
+                .await?;
+                start = false;
+            }
+
             Ok(())
         }
 
-        /// Read bytes from a target slave with the address `addr`
-        /// The number of read bytes is deterimed by the size of the `buffer`
-        /// argument
-        async fn master_read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Error> {
-            // Clear all I2C interrupts
-            self.peripheral.clear_all_interrupts();
-            self.read_operation(
-                addr,
-                buffer,
-                true,
-                true,
-                &mut self.peripheral.register_block().comd_iter(),
-            )
-            .await?;
+        /// Reads into `buffer`, split into chunks the hardware can handle,
+        /// see [`Instance::read_operation`]
+        async fn read_operation(
+            &self,
+            address: &[u8],
+            buffer: &mut [u8],
+            start: bool,
+            stop: bool,
+            will_continue: bool,
+        ) -> Result<(), Error> {
+            // Reads of zero bytes can't be expressed, only address the slave in
+            // read direction and finish the transaction
+            if buffer.is_empty() {
+                if !start {
+                    return self.write_operation(address, &[], false, stop).await;
+                }
+                if address.len() > 1 {
+                    self.write_operation(address, &[], true, false).await?;
+                }
+                let header = [address[0] | OperationType::Read as u8];
+                return self.write_operation(&header, &[], true, stop).await;
+            }
+
+            let mut chunks = buffer.chunks_mut(I2C_CHUNK_SIZE).peekable();
+            let mut start = start;
+            while let Some(chunk) = chunks.next() {
+                let last = chunks.peek().is_none();
+                self.peripheral.clear_all_interrupts();
+                self.read_chunk(
+                    address,
+                    chunk,
+                    start,
+                    stop && last,
+                    will_continue || !last,
+                    &mut self.peripheral.register_block().comd_iter(),
+                )
+                .await?;
+                start = false;
+            }
+
             Ok(())
         }
 
-        /// Write bytes from the `bytes` array first and then read n bytes into
-        /// the `buffer` array with n being the size of the array.
-        async fn master_write_read(
-            &mut self,
-            addr: u8,
-            bytes: &[u8],
-            buffer: &mut [u8],
+        /// Executes the operations of an embedded-hal transaction, see
+        /// [`Instance::transaction`]
+        async fn transaction_impl(
+            &self,
+            address: &[u8],
+            operations: &mut [Operation<'_>],
         ) -> Result<(), Error> {
-            // it would be possible to combine the write and read
-            // in one transaction but filling the tx fifo with
-            // the current code is somewhat slow even in release mode
-            // which can cause issues
-
-            // Clear all I2C interrupts
-            self.peripheral.clear_all_interrupts();
-            self.write_operation(
-                addr,
-                bytes,
-                true,
-                false,
-                &mut self.peripheral.register_block().comd_iter(),
-            )
-            .await?;
-            self.peripheral.clear_all_interrupts();
-            self.read_operation(
-                addr,
-                buffer,
-                true,
-                true,
-                &mut self.peripheral.register_block().comd_iter(),
-            )
-            .await?;
+            let mut last_op = LastOpWas::None;
+            let mut op_iter = operations.iter_mut().peekable();
+            while let Some(op) = op_iter.next() {
+                let next_op = op_iter.peek().map(|op| match op {
+                    Operation::Write(_) => LastOpWas::Write,
+                    Operation::Read(_) => LastOpWas::Read,
+                });
+
+                match op {
+                    Operation::Write(bytes) => {
+                        self.write_operation(
+                            address,
+                            bytes,
+                            last_op != LastOpWas::Write,
+                            next_op.is_none(),
+                        )
+                        .await?;
+                        last_op = LastOpWas::Write;
+                    }
+                    Operation::Read(buffer) => {
+                        self.read_operation(
+                            address,
+                            buffer,
+                            last_op != LastOpWas::Read,
+                            next_op.is_none(),
+                            next_op == Some(LastOpWas::Read),
+                        )
+                        .await?;
+                        last_op = LastOpWas::Read;
+                    }
+                }
+            }
+
             Ok(())
         }
 
         /// Writes bytes to slave with address `address`
         pub async fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Error> {
-            self.master_write(addr, bytes).await?;
-            Ok(())
+            let mut address_bytes = [0; 2];
+            let address = I2cAddress::SevenBit(addr).encode(&mut address_bytes);
+            self.write_operation(address, bytes, true, true).await
         }
 
         /// Reads enough bytes from slave with `address` to fill `buffer`
         pub async fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Error> {
-            self.master_read(addr, buffer).await?;
-            Ok(())
+            let mut address_bytes = [0; 2];
+            let address = I2cAddress::SevenBit(addr).encode(&mut address_bytes);
+            self.read_operation(address, buffer, true, true, false)
+                .await
         }
 
         /// Writes bytes to slave with address `address` and then reads enough
@@ -871,8 +983,11 @@ mod asynch {
             bytes: &[u8],
             buffer: &mut [u8],
         ) -> Result<(), Error> {
-            self.master_write_read(addr, bytes, buffer).await?;
-            Ok(())
+            let mut address_bytes = [0; 2];
+            let address = I2cAddress::SevenBit(addr).encode(&mut address_bytes);
+            self.write_operation(address, bytes, true, false).await?;
+            self.read_operation(address, buffer, true, true, false)
+                .await
         }
     }
 
@@ -886,42 +1001,26 @@ mod asynch {
             address: u8,
             operations: &mut [Operation<'_>],
         ) -> Result<(), Self::Error> {
-            let mut last_op = LastOpWas::None;
-            let mut op_iter = operations.iter_mut().peekable();
-            while let Some(op) = op_iter.next() {
-                // Clear all I2C interrupts
-                self.peripheral.clear_all_interrupts();
+            let mut address_bytes = [0; 2];
+            let address = I2cAddress::SevenBit(address).encode(&mut address_bytes);
+            self.transaction_impl(address, operations).await
+        }
+    }
 
-                let cmd_iterator = &mut self.peripheral.register_block().comd_iter();
-                match op {
-                    Operation::Write(bytes) => {
-                        self.write_operation(
-                            address,
-                            bytes,
-                            last_op != LastOpWas::Write,
-                            op_iter.peek().is_none(),
-                            cmd_iterator,
-                        )
-                        .await?;
-                        last_op = LastOpWas::Write;
-                    }
-                    Operation::Read(buffer) => {
-                        // execute a read operation:
-                        // - issue START/RSTART if op is different from previous
-                        // - issue STOP if op is the last one
-                        self.read_operation(
-                            address,
-                            buffer,
-                            last_op != LastOpWas::Read,
-                            op_iter.peek().is_none(),
-                            cmd_iterator,
-                        )
-                        .await?;
-                        last_op = LastOpWas::Read;
-                    }
-                }
-            }
-            Ok(())
+    #[cfg(feature = "embedded-hal")]
+    impl<'d, T> embedded_hal_async::i2c::I2c<embedded_hal::i2c::TenBitAddress>
+        for I2C<'d, T, crate::Async>
+    where
+        T: Instance,
+    {
+        async fn transaction(
+            &mut self,
+            address: u16,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            let mut address_bytes = [0; 2];
+            let address = I2cAddress::TenBit(address).encode(&mut address_bytes);
+            self.transaction_impl(address, operations).await
         }
     }
 
@@ -1388,85 +1487,131 @@ pub trait Instance: crate::private::Sealed {
         }
     }
 
-    fn setup_write<'a, I>(&self, addr: u8, bytes: &[u8], cmd_iterator: &mut I) -> Result<(), Error>
+    /// Adds the WRITE command for the address bytes and `bytes` and loads the
+    /// address into the FIFO. `address` is empty when continuing a write.
+    fn setup_write<'a, I>(
+        &self,
+        address: &[u8],
+        bytes: &[u8],
+        cmd_iterator: &mut I,
+    ) -> Result<(), Error>
     where
         I: Iterator<Item = &'a COMD>,
     {
-        if bytes.len() > 254 {
-            // we could support more by adding multiple write operations
+        let length = address.len() + bytes.len();
+        if length > 255 {
             return Err(Error::ExceedingFifo);
         }
 
-        // WRITE command
-        add_cmd(
-            cmd_iterator,
-            Command::Write {
-                ack_exp: Ack::Ack,
-                ack_check_en: true,
-                length: 1 + bytes.len() as u8,
-            },
-        )?;
+        if length > 0 {
+            // WRITE command
+            add_cmd(
+                cmd_iterator,
+                Command::Write {
+                    ack_exp: Ack::Ack,
+                    ack_check_en: true,
+                    length: length as u8,
+                },
+            )?;
+        }
 
         self.update_config();
 
         // Load address and R/W bit into FIFO
-        write_fifo(
-            self.register_block(),
-            addr << 1 | OperationType::Write as u8,
-        );
+        for byte in address {
+            write_fifo(self.register_block(), *byte);
+        }
 
         Ok(())
     }
 
+    /// Adds the commands for addressing the slave and reading `buffer` and
+    /// loads the address into the FIFO. `address` holds the address bytes in
+    /// write direction and is empty when continuing a read. The last byte is
+    /// not acknowledged unless `will_continue` is set.
     fn setup_read<'a, I>(
         &self,
-        addr: u8,
+        address: &[u8],
         buffer: &mut [u8],
+        will_continue: bool,
         cmd_iterator: &mut I,
     ) -> Result<(), Error>
     where
         I: Iterator<Item = &'a COMD>,
     {
         if buffer.len() > 254 {
-            // we could support more by adding multiple read operations
             return Err(Error::ExceedingFifo);
         }
 
-        // WRITE command
-        add_cmd(
-            cmd_iterator,
-            Command::Write {
-                ack_exp: Ack::Ack,
-                ack_check_en: true,
-                length: 1,
-            },
-        )?;
+        if let Some(header) = address.first() {
+            if address.len() > 1 {
+                // A 10-bit address is sent in write direction, followed by a
+                // repeated START and its first byte in read direction
+                add_cmd(
+                    cmd_iterator,
+                    Command::Write {
+                        ack_exp: Ack::Ack,
+                        ack_check_en: true,
+                        length: address.len() as u8,
+                    },
+                )?;
+                add_cmd(cmd_iterator, Command::Start)?;
+            }
 
-        if buffer.len() > 1 {
-            // READ command (N - 1)
+            // WRITE command
+            add_cmd(
+                cmd_iterator,
+                Command::Write {
+                    ack_exp: Ack::Ack,
+                    ack_check_en: true,
+                    length: 1,
+                },
+            )?;
+
+            self.update_config();
+
+            // Load address and R/W bit into FIFO
+            if address.len() > 1 {
+                for byte in address {
+                    write_fifo(self.register_block(), *byte);
+                }
+            }
+            write_fifo(self.register_block(), header | OperationType::Read as u8);
+        }
+
+        if will_continue {
+            // READ command, the slave keeps sending
             add_cmd(
                 cmd_iterator,
                 Command::Read {
                     ack_value: Ack::Ack,
-                    length: buffer.len() as u8 - 1,
+                    length: buffer.len() as u8,
                 },
             )?;
-        }
+        } else {
+            if buffer.len() > 1 {
+                // READ command (N - 1)
+                add_cmd(
+                    cmd_iterator,
+                    Command::Read {
+                        ack_value: Ack::Ack,
+                        length: buffer.len() as u8 - 1,
+                    },
+                )?;
+            }
 
-        // READ w/o ACK
-        add_cmd(
-            cmd_iterator,
-            Command::Read {
-                ack_value: Ack::Nack,
-                length: 1,
-            },
-        )?;
+            // READ w/o ACK
+            add_cmd(
+                cmd_iterator,
+                Command::Read {
+                    ack_value: Ack::Nack,
+                    length: 1,
+                },
+            )?;
+        }
 
         self.update_config();
 
-        // Load address and R/W bit into FIFO
-        write_fifo(self.register_block(), addr << 1 | OperationType::Read as u8);
-
         Ok(())
     }
 
@@ -1564,6 +1709,7 @@ pub trait Instance: crate::private::Sealed {
                 // Handle error cases
                 if interrupts.time_out().bit_is_set() {
                     self.reset();
+                    self.clear_bus();
                     return Err(Error::TimeOut);
                 } else if interrupts.ack_err().bit_is_set() {
                     self.reset();
@@ -1577,6 +1723,7 @@ pub trait Instance: crate::private::Sealed {
                 // Handle error cases
                 if interrupts.time_out().bit_is_set() {
                     self.reset();
+                    self.clear_bus();
                     return Err(Error::TimeOut);
                 } else if interrupts.nack().bit_is_set() {
                     self.reset();
@@ -1770,9 +1917,9 @@ pub trait Instance: crate::private::Sealed {
             .write(|w| w.rxfifo_full().clear_bit_by_one());
     }
 
-    fn write_operation<'a, I>(
+    fn write_chunk<'a, I>(
         &self,
-        address: u8,
+        address: &[u8],
         bytes: &[u8],
         start: bool,
         stop: bool,
@@ -1781,6 +1928,12 @@ pub trait Instance: crate::private::Sealed {
     where
         I: Iterator<Item = &'a COMD>,
     {
+        // Short circuit for zero length writes without start or end as that would be an
+        // invalid operation
+        if bytes.is_empty() && !start && !stop {
+            return Ok(());
+        }
+
         // Reset FIFO and command list
         self.reset_fifo();
         self.reset_command_list();
@@ -1788,7 +1941,7 @@ pub trait Instance: crate::private::Sealed {
         if start {
             add_cmd(cmd_iterator, Command::Start)?;
         }
-        self.setup_write(address, bytes, cmd_iterator)?;
+        self.setup_write(if start { address } else { &[] }, bytes, cmd_iterator)?;
         add_cmd(
             cmd_iterator,
             if stop { Command::Stop } else { Command::End },
@@ -1802,12 +1955,13 @@ pub trait Instance: crate::private::Sealed {
         Ok(())
     }
 
-    fn read_operation<'a, I>(
+    fn read_chunk<'a, I>(
         &self,
-        address: u8,
+        address: &[u8],
         buffer: &mut [u8],
         start: bool,
         stop: bool,
+        will_continue: bool,
         cmd_iterator: &mut I,
     ) -> Result<(), Error>
     where
@@ -1820,7 +1974,12 @@ pub trait Instance: crate::private::Sealed {
         if start {
             add_cmd(cmd_iterator, Command::Start)?;
         }
-        self.setup_read(address, buffer, cmd_iterator)?;
+        self.setup_read(
+            if start { address } else { &[] },
+            buffer,
+            will_continue,
+            cmd_iterator,
+        )?;
         add_cmd(
             cmd_iterator,
             if stop { Command::Stop } else { Command::End },
@@ -1831,69 +1990,278 @@ pub trait Instance: crate::private::Sealed {
         Ok(())
     }
 
+    /// Writes `bytes`, split into chunks the hardware can handle
+    ///
+    /// - issues START/RSTART and the address if `start` is set
+    /// - issues STOP after the last chunk if `stop` is set, otherwise the bus
+    ///   is held for the next operation
+    fn write_operation(
+        &self,
+        address: &[u8],
+        bytes: &[u8],
+        start: bool,
+        stop: bool,
+    ) -> Result<(), Error> {
+        if bytes.is_empty() {
+            self.clear_all_interrupts();
+            return self.write_chunk(
+                address,
+                bytes,
+                start,
+                stop,
+                &mut self.register_block().comd_iter(),
+            );
+        }
+
+        let mut chunks = bytes.chunks(I2C_CHUNK_SIZE).peekable();
+        let mut start = start;
+        while let Some(chunk) = chunks.next() {
+            self.clear_all_interrupts();
+            self.write_chunk(
+                address,
+                chunk,
+                start,
+                stop && chunks.peek().is_none(),
+                &mut self.register_block().comd_iter(),
+            )?;
+            start = false;
+        }
+
+        Ok(())
+    }
+
+    /// Reads into `buffer`, split into chunks the hardware can handle
+    ///
+    /// - issues START/RSTART and the address if `start` is set
+    /// - issues STOP after the last chunk if `stop` is set, otherwise the bus
+    ///   is held for the next operation
+    /// - acknowledges the last byte if `will_continue` is set, i.e. the next
+    ///   operation is a read as well
+    fn read_operation(
+        &self,
+        address: &[u8],
+        buffer: &mut [u8],
+        start: bool,
+        stop: bool,
+        will_continue: bool,
+    ) -> Result<(), Error> {
+        // Reads of zero bytes can't be expressed, only address the slave in
+        // read direction and finish the transaction
+        if buffer.is_empty() {
+            if !start {
+                return self.write_operation(address, &[], false, stop);
+            }
+            if address.len() > 1 {
+                // A 10-bit address is sent in write direction first
+                self.write_operation(address, &[], true, false)?;
+            }
+            let header = [address[0] | OperationType::Read as u8];
+            return self.write_operation(&header, &[], true, stop);
+        }
+
+        let mut chunks = buffer.chunks_mut(I2C_CHUNK_SIZE).peekable();
+        let mut start = start;
+        while let Some(chunk) = chunks.next() {
+            let last = chunks.peek().is_none();
+            self.clear_all_interrupts();
+            self.read_chunk(
+                address,
+                chunk,
+                start,
+                stop && last,
+                will_continue || !last,
+                &mut self.register_block().comd_iter(),
+            )?;
+            start = false;
+        }
+
+        Ok(())
+    }
+
+    /// Executes the operations of an embedded-hal transaction
+    ///
+    /// Adjacent operations of the same type are merged, a change of the
+    /// direction issues a repeated START and a STOP is sent after the last
+    /// operation.
+    #[cfg(feature = "embedded-hal")]
+    fn transaction(
+        &self,
+        address: &[u8],
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<(), Error> {
+        use embedded_hal::i2c::Operation;
+
+        let mut last_op = LastOpWas::None;
+        let mut op_iter = operations.iter_mut().peekable();
+        while let Some(op) = op_iter.next() {
+            let next_op = op_iter.peek().map(|op| match op {
+                Operation::Write(_) => LastOpWas::Write,
+                Operation::Read(_) => LastOpWas::Read,
+            });
+
+            match op {
+                Operation::Write(bytes) => {
+                    self.write_operation(
+                        address,
+                        bytes,
+                        last_op != LastOpWas::Write,
+                        next_op.is_none(),
+                    )?;
+                    last_op = LastOpWas::Write;
+                }
+                Operation::Read(buffer) => {
+                    self.read_operation(
+                        address,
+                        buffer,
+                        last_op != LastOpWas::Read,
+                        next_op.is_none(),
+                        next_op == Some(LastOpWas::Read),
+                    )?;
+                    last_op = LastOpWas::Read;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Send data bytes from the `bytes` array to a target slave with the
     /// address `addr`
-    fn master_write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Error> {
-        // Clear all I2C interrupts
-        self.clear_all_interrupts();
-        self.write_operation(
-            addr,
-            bytes,
-            true,
-            true,
-            &mut self.register_block().comd_iter(),
-        )?;
-        Ok(())
+    fn master_write(&mut self, address: &[u8], bytes: &[u8]) -> Result<(), Error> {
+        self.write_operation(address, bytes, true, true)
     }
 
     /// Read bytes from a target slave with the address `addr`
     /// The number of read bytes is deterimed by the size of the `buffer`
     /// argument
-    fn master_read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Error> {
-        // Clear all I2C interrupts
-        self.clear_all_interrupts();
-        self.read_operation(
-            addr,
-            buffer,
-            true,
-            true,
-            &mut self.register_block().comd_iter(),
-        )?;
-        Ok(())
+    fn master_read(&mut self, address: &[u8], buffer: &mut [u8]) -> Result<(), Error> {
+        self.read_operation(address, buffer, true, true, false)
     }
 
     /// Write bytes from the `bytes` array first and then read n bytes into
     /// the `buffer` array with n being the size of the array.
     fn master_write_read(
         &mut self,
-        addr: u8,
+        address: &[u8],
         bytes: &[u8],
         buffer: &mut [u8],
     ) -> Result<(), Error> {
-        // it would be possible to combine the write and read
-        // in one transaction but filling the tx fifo with
-        // the current code is somewhat slow even in release mode
-        // which can cause issues
+        self.write_operation(address, bytes, true, false)?;
+        self.read_operation(address, buffer, true, true, false)
+    }
+
+    /// Frees a slave holding SDA low by clocking SCL up to nine times and
+    /// sending a STOP condition, see section 3.1.16 of the I2C-bus
+    /// specification
+    ///
+    /// Not all chips can clock SCL from the controller, so both pads are
+    /// driven as GPIOs meanwhile.
+    fn clear_bus(&self) {
+        const BUS_CLEAR_PULSES: u8 = 9;
+        // Half a clock period at 100 kHz
+        const HALF_PERIOD_US: u32 = 5;
+
+        let sda = SDA_PINS[self.i2c_number()].load(Ordering::Relaxed);
+        let scl = SCL_PINS[self.i2c_number()].load(Ordering::Relaxed);
+        if sda == NO_PIN || scl == NO_PIN {
+            return;
+        }
 
-        // Clear all I2C interrupts
-        self.clear_all_interrupts();
-        self.write_operation(
-            addr,
-            bytes,
-            true,
-            false,
-            &mut self.register_block().comd_iter(),
-        )?;
-        self.clear_all_interrupts();
-        self.read_operation(
-            addr,
-            buffer,
-            true,
-            true,
-            &mut self.register_block().comd_iter(),
-        )?;
-        Ok(())
+        // The pads are open-drain already, so a high level releases the line
+        set_pin_level(sda, true);
+        set_pin_level(scl, true);
+        connect_pin_to_output(sda, OutputSignal::GPIO);
+        connect_pin_to_output(scl, OutputSignal::GPIO);
+        crate::rom::ets_delay_us(HALF_PERIOD_US);
+
+        for _ in 0..BUS_CLEAR_PULSES {
+            if is_pin_high(sda) {
+                break;
+            }
+            set_pin_level(scl, false);
+            crate::rom::ets_delay_us(HALF_PERIOD_US);
+            set_pin_level(scl, true);
+            crate::rom::ets_delay_us(HALF_PERIOD_US);
+        }
+
+        // STOP condition, SDA rises while SCL is high
+        set_pin_level(scl, false);
+        crate::rom::ets_delay_us(HALF_PERIOD_US);
+        set_pin_level(sda, false);
+        crate::rom::ets_delay_us(HALF_PERIOD_US);
+        set_pin_level(scl, true);
+        crate::rom::ets_delay_us(HALF_PERIOD_US);
+        set_pin_level(sda, true);
+        crate::rom::ets_delay_us(HALF_PERIOD_US);
+
+        connect_pin_to_output(sda, self.sda_output_signal());
+        connect_pin_to_output(scl, self.scl_output_signal());
+    }
+}
+
+/// Connects SDA and SCL as open-drain signals of the given I2C peripheral
+fn configure_pins<T, SDA, SCL>(
+    i2c: &T,
+    mut sda: PeripheralRef<'_, SDA>,
+    mut scl: PeripheralRef<'_, SCL>,
+) where
+    T: Instance,
+    SDA: OutputPin + InputPin,
+    SCL: OutputPin + InputPin,
+{
+    // avoid SCL/SDA going low during configuration
+    scl.set_output_high(true, crate::private::Internal);
+    sda.set_output_high(true, crate::private::Internal);
+
+    scl.set_to_open_drain_output(crate::private::Internal);
+    scl.enable_input(true, crate::private::Internal);
+    scl.internal_pull_up(true, crate::private::Internal);
+    scl.connect_peripheral_to_output(i2c.scl_output_signal(), crate::private::Internal);
+    scl.connect_input_to_peripheral(i2c.scl_input_signal(), crate::private::Internal);
+
+    sda.set_to_open_drain_output(crate::private::Internal);
+    sda.enable_input(true, crate::private::Internal);
+    sda.internal_pull_up(true, crate::private::Internal);
+    sda.connect_peripheral_to_output(i2c.sda_output_signal(), crate::private::Internal);
+    sda.connect_input_to_peripheral(i2c.sda_input_signal(), crate::private::Internal);
+
+    SDA_PINS[i2c.i2c_number()].store(sda.number(crate::private::Internal), Ordering::Relaxed);
+    SCL_PINS[i2c.i2c_number()].store(scl.number(crate::private::Internal), Ordering::Relaxed);
+}
+
+fn set_pin_level(pin: u8, high: bool) {
+    let mask = 1 << (pin % 32);
+
+    #[cfg(not(any(esp32c2, esp32c3, esp32c6, esp32h2)))]
+    if pin >= 32 {
+        if high {
+            crate::gpio::Bank1GpioRegisterAccess::write_output_set(mask);
+        } else {
+            crate::gpio::Bank1GpioRegisterAccess::write_output_clear(mask);
+        }
+        return;
+    }
+
+    if high {
+        Bank0GpioRegisterAccess::write_output_set(mask);
+    } else {
+        Bank0GpioRegisterAccess::write_output_clear(mask);
+    }
+}
+
+fn is_pin_high(pin: u8) -> bool {
+    #[cfg(not(any(esp32c2, esp32c3, esp32c6, esp32h2)))]
+    if pin >= 32 {
+        return crate::gpio::Bank1GpioRegisterAccess::read_input() & (1 << (pin % 32)) != 0;
     }
+
+    Bank0GpioRegisterAccess::read_input() & (1 << (pin % 32)) != 0
+}
+
+fn connect_pin_to_output(pin: u8, signal: OutputSignal) {
+    unsafe { &*GPIO::PTR }
+        .func_out_sel_cfg(pin as usize)
+        .modify(|_, w| unsafe { w.out_sel().bits(signal as OutputSignalType) });
 }
 
 fn add_cmd<'a, I>(cmd_iterator: &mut I, command: Command) -> Result<(), Error>
@@ -0,0 +1,724 @@
+//! # I2C Slave Driver
+//!
+//! ## Overview
+//!
+//! Besides acting as a bus master, every I2C peripheral can respond to a
+//! master as a slave device with a 7-bit or 10-bit address. The master drives
+//! the bus, so the driver doesn't start transfers on its own:
+//! [`I2cSlave::read`] waits for data written by the master and
+//! [`I2cSlave::write`] provides the data the master reads next.
+//!
+//! On chips supporting it, the slave holds SCL low (clock stretching) when the
+//! master requests data before it was provided, or when the RX FIFO is full.
+//! This makes the common "write register address, repeated START, read
+//! register contents" access pattern work: [`I2cSlave::read`] returns once the
+//! master switches to reading and the bus is held until [`I2cSlave::write`]
+//! provides the answer.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! let mut i2c = I2cSlave::new(
+//!     peripherals.I2C0,
+//!     io.pins.gpio1,
+//!     io.pins.gpio2,
+//!     SlaveAddress::SevenBit(0x42),
+//!     &clocks,
+//!     None,
+//! );
+//!
+//! let mut registers = [0u8; 16];
+//! loop {
+//!     let mut request = [0u8; 17];
+//!     let len = i2c.read(&mut request).unwrap();
+//!     if len == 0 {
+//!         continue;
+//!     }
+//!
+//!     let register = request[0] as usize % registers.len();
+//!     if len > 1 {
+//!         // register write
+//!         registers[register] = request[1];
+//!     } else {
+//!         // register read
+//!         i2c.write(&registers[register..][..1]).unwrap();
+//!     }
+//! }
+//! ```
+//!
+//! ## Limitations
+//!
+//! The ESP32 and ESP32-S2 don't support clock stretching in slave mode. On
+//! these chips at most 32 bytes can be provided by [`I2cSlave::write`] and the
+//! data has to be in place before the master starts reading it.
+
+use core::marker::PhantomData;
+
+use enumset::{EnumSet, EnumSetType};
+use fugit::RateExtU32;
+
+use super::{configure_pins, read_fifo, write_fifo, Instance};
+use crate::{
+    clock::Clocks,
+    gpio::{InputPin, OutputPin},
+    interrupt::InterruptHandler,
+    peripheral::{Peripheral, PeripheralRef},
+    system::PeripheralClockControl,
+};
+
+/// Size of the hardware FIFOs
+const FIFO_SIZE: usize = 32;
+
+/// Value sent to the master when it reads more data than was provided
+#[cfg(not(any(esp32, esp32s2)))]
+const PADDING_BYTE: u8 = 0xff;
+
+/// I2C slave errors
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// The data to write doesn't fit into the FIFO
+    ExceedingFifo,
+    /// The master wrote more data than fits into the buffer or the RX FIFO
+    Overrun,
+    /// The bus was held in the same state for longer than the timeout
+    TimeOut,
+}
+
+#[cfg(feature = "embedded-hal")]
+impl embedded_hal::i2c::Error for Error {
+    fn kind(&self) -> embedded_hal::i2c::ErrorKind {
+        use embedded_hal::i2c::ErrorKind;
+
+        match self {
+            Self::Overrun => ErrorKind::Overrun,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// Address the slave responds to
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SlaveAddress {
+    /// 7-bit address
+    SevenBit(u8),
+    /// 10-bit address
+    TenBit(u16),
+}
+
+/// I2C slave events
+#[derive(EnumSetType, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Event {
+    /// The master addressed the slave for reading while no data was provided
+    /// and SCL is held low until [`I2cSlave::write`] is called
+    #[cfg(not(any(esp32, esp32s2)))]
+    AddressMatch,
+    /// Data written by the master is available in the RX FIFO
+    RxFifoWatermark,
+    /// The master ended the transaction with a STOP condition
+    Stop,
+}
+
+/// I2C peripheral operating as a slave
+pub struct I2cSlave<'d, T, DM: crate::Mode> {
+    peripheral: PeripheralRef<'d, T>,
+    phantom: PhantomData<DM>,
+}
+
+impl<'d, T, DM: crate::Mode> I2cSlave<'d, T, DM>
+where
+    T: Instance,
+{
+    fn new_internal<SDA: OutputPin + InputPin, SCL: OutputPin + InputPin>(
+        i2c: impl Peripheral<P = T> + 'd,
+        sda: impl Peripheral<P = SDA> + 'd,
+        scl: impl Peripheral<P = SCL> + 'd,
+        address: SlaveAddress,
+        clocks: &Clocks,
+        isr: Option<InterruptHandler>,
+    ) -> Self {
+        crate::into_ref!(i2c, sda, scl);
+
+        PeripheralClockControl::enable(match i2c.i2c_number() {
+            0 => crate::system::Peripheral::I2cExt0,
+            #[cfg(i2c1)]
+            1 => crate::system::Peripheral::I2cExt1,
+            _ => unreachable!(), // will never happen
+        });
+
+        let mut i2c = I2cSlave {
+            peripheral: i2c,
+            phantom: PhantomData,
+        };
+
+        configure_pins(&*i2c.peripheral, sda, scl);
+
+        // The bus timing only determines when SDA is sampled and driven, use
+        // fast-mode timing which also covers slower masters
+        i2c.peripheral.setup(400u32.kHz(), clocks, None);
+        i2c.init_slave(address);
+
+        if let Some(interrupt) = isr {
+            unsafe {
+                crate::interrupt::bind_interrupt(T::interrupt(), interrupt.handler());
+                crate::interrupt::enable(T::interrupt(), interrupt.priority()).unwrap();
+            }
+        }
+
+        i2c
+    }
+
+    fn init_slave(&mut self, address: SlaveAddress) {
+        let register_block = self.peripheral.register_block();
+
+        register_block.ctr().modify(|_, w| w.ms_mode().clear_bit());
+
+        // Start sending from the TX FIFO as soon as the master reads
+        #[cfg(not(any(esp32, esp32s2)))]
+        register_block
+            .ctr()
+            .modify(|_, w| w.slv_tx_auto_start_en().set_bit());
+
+        // Don't put the address byte into the RX FIFO
+        register_block
+            .fifo_conf()
+            .modify(|_, w| w.fifo_addr_cfg_en().clear_bit());
+
+        self.set_address(address);
+
+        #[cfg(not(any(esp32, esp32s2)))]
+        self.set_clock_stretching(true);
+
+        self.peripheral.update_config();
+        self.peripheral.reset_fifo();
+    }
+
+    /// Change the address the slave responds to
+    pub fn set_address(&mut self, address: SlaveAddress) {
+        let (bits, ten_bit) = match address {
+            SlaveAddress::SevenBit(address) => (address as u16 & 0x7f, false),
+            // The hardware expects the two address bytes swapped: the
+            // `0b11110xx` marker followed by the lower 8 bits
+            SlaveAddress::TenBit(address) => (
+                ((address & 0xff) << 7) | (((address >> 8) & 0x3) | 0x78),
+                true,
+            ),
+        };
+
+        let register_block = self.peripheral.register_block();
+        register_block
+            .slave_addr()
+            .write(|w| unsafe { w.slave_addr().bits(bits).addr_10bit_en().bit(ten_bit) });
+
+        #[cfg(not(any(esp32, esp32s2)))]
+        register_block
+            .ctr()
+            .modify(|_, w| w.addr_10bit_rw_check_en().bit(ten_bit));
+
+        self.peripheral.update_config();
+    }
+
+    /// Enable or disable clock stretching
+    ///
+    /// Clock stretching is enabled by default. Without it, the master reads
+    /// `0xFF` if no data was provided in time.
+    #[cfg(not(any(esp32, esp32s2)))]
+    pub fn set_clock_stretching(&mut self, enable: bool) {
+        self.peripheral
+            .register_block()
+            .scl_stretch_conf()
+            .modify(|_, w| unsafe {
+                w.stretch_protect_num()
+                    .bits(500)
+                    .slave_scl_stretch_en()
+                    .bit(enable)
+            });
+        self.peripheral.update_config();
+    }
+
+    /// Listen for the given events
+    pub fn listen(&mut self, events: EnumSet<Event>) {
+        let register_block = self.peripheral.register_block();
+
+        for event in events {
+            match event {
+                #[cfg(not(any(esp32, esp32s2)))]
+                Event::AddressMatch => register_block
+                    .int_ena()
+                    .modify(|_, w| w.slave_stretch().set_bit()),
+                #[cfg(esp32)]
+                Event::RxFifoWatermark => register_block
+                    .int_ena()
+                    .modify(|_, w| w.rxfifo_full().set_bit()),
+                #[cfg(not(esp32))]
+                Event::RxFifoWatermark => register_block
+                    .int_ena()
+                    .modify(|_, w| w.rxfifo_wm().set_bit()),
+                Event::Stop => register_block
+                    .int_ena()
+                    .modify(|_, w| w.trans_complete().set_bit()),
+            }
+        }
+    }
+
+    /// Stop listening for the given events
+    pub fn unlisten(&mut self, events: EnumSet<Event>) {
+        let register_block = self.peripheral.register_block();
+
+        for event in events {
+            match event {
+                #[cfg(not(any(esp32, esp32s2)))]
+                Event::AddressMatch => register_block
+                    .int_ena()
+                    .modify(|_, w| w.slave_stretch().clear_bit()),
+                #[cfg(esp32)]
+                Event::RxFifoWatermark => register_block
+                    .int_ena()
+                    .modify(|_, w| w.rxfifo_full().clear_bit()),
+                #[cfg(not(esp32))]
+                Event::RxFifoWatermark => register_block
+                    .int_ena()
+                    .modify(|_, w| w.rxfifo_wm().clear_bit()),
+                Event::Stop => register_block
+                    .int_ena()
+                    .modify(|_, w| w.trans_complete().clear_bit()),
+            }
+        }
+    }
+
+    /// Gets the pending events
+    pub fn interrupts(&mut self) -> EnumSet<Event> {
+        let mut res = EnumSet::new();
+        let raw = self.peripheral.register_block().int_raw().read();
+
+        #[cfg(not(any(esp32, esp32s2)))]
+        if raw.slave_stretch().bit_is_set() && self.stretched_for_read() {
+            res.insert(Event::AddressMatch);
+        }
+        #[cfg(esp32)]
+        if raw.rxfifo_full().bit_is_set() {
+            res.insert(Event::RxFifoWatermark);
+        }
+        #[cfg(not(esp32))]
+        if raw.rxfifo_wm().bit_is_set() {
+            res.insert(Event::RxFifoWatermark);
+        }
+        if raw.trans_complete().bit_is_set() {
+            res.insert(Event::Stop);
+        }
+
+        res
+    }
+
+    /// Resets the given pending events
+    ///
+    /// Clearing [`Event::AddressMatch`] doesn't release SCL, this is done by
+    /// [`I2cSlave::write`].
+    pub fn clear_interrupts(&mut self, events: EnumSet<Event>) {
+        let register_block = self.peripheral.register_block();
+
+        for event in events {
+            match event {
+                #[cfg(not(any(esp32, esp32s2)))]
+                Event::AddressMatch => register_block
+                    .int_clr()
+                    .write(|w| w.slave_stretch().clear_bit_by_one()),
+                #[cfg(esp32)]
+                Event::RxFifoWatermark => register_block
+                    .int_clr()
+                    .write(|w| w.rxfifo_full().clear_bit_by_one()),
+                #[cfg(not(esp32))]
+                Event::RxFifoWatermark => register_block
+                    .int_clr()
+                    .write(|w| w.rxfifo_wm().clear_bit_by_one()),
+                Event::Stop => register_block
+                    .int_clr()
+                    .write(|w| w.trans_complete().clear_bit_by_one()),
+            }
+        }
+    }
+
+    /// Returns `true` if SCL is held low because the master wants to read
+    #[cfg(not(any(esp32, esp32s2)))]
+    fn stretched_for_read(&self) -> bool {
+        self.peripheral
+            .register_block()
+            .sr()
+            .read()
+            .stretch_cause()
+            .bits()
+            == 0
+    }
+
+    /// Releases SCL after clock stretching
+    #[cfg(not(any(esp32, esp32s2)))]
+    fn release_scl(&self) {
+        let register_block = self.peripheral.register_block();
+        register_block
+            .int_clr()
+            .write(|w| w.slave_stretch().clear_bit_by_one());
+        register_block
+            .scl_stretch_conf()
+            .modify(|_, w| w.slave_scl_stretch_clr().set_bit());
+    }
+
+    fn check_errors(&self) -> Result<(), Error> {
+        let register_block = self.peripheral.register_block();
+        let interrupts = register_block.int_raw().read();
+
+        if interrupts.time_out().bit_is_set() {
+            self.peripheral.reset();
+            return Err(Error::TimeOut);
+        } else if interrupts.rxfifo_ovf().bit_is_set() {
+            self.peripheral.reset();
+            return Err(Error::Overrun);
+        }
+
+        Ok(())
+    }
+
+    fn rx_fifo_count(&self) -> usize {
+        self.peripheral
+            .register_block()
+            .sr()
+            .read()
+            .rxfifo_cnt()
+            .bits() as usize
+    }
+
+    /// Moves received bytes to `buffer`, returns `true` once the master ended
+    /// the write with a STOP or switched to reading
+    fn read_step(&self, buffer: &mut [u8], count: &mut usize) -> Result<bool, Error> {
+        let register_block = self.peripheral.register_block();
+
+        self.check_errors()?;
+
+        // Check for the end of the transfer before draining the FIFO, so no byte
+        // received in between gets lost
+        let interrupts = register_block.int_raw().read();
+        let stopped = interrupts.trans_complete().bit_is_set();
+        let sr = register_block.sr().read();
+        let read_requested = sr.bus_busy().bit_is_set()
+            && sr.slave_addressed().bit_is_set()
+            && sr.slave_rw().bit_is_set();
+
+        while self.rx_fifo_count() > 0 {
+            if *count >= buffer.len() {
+                self.peripheral.reset();
+                return Err(Error::Overrun);
+            }
+            buffer[*count] = read_fifo(register_block);
+            *count += 1;
+        }
+
+        #[cfg(esp32)]
+        register_block
+            .int_clr()
+            .write(|w| w.rxfifo_full().clear_bit_by_one());
+        #[cfg(not(esp32))]
+        register_block
+            .int_clr()
+            .write(|w| w.rxfifo_wm().clear_bit_by_one());
+
+        #[cfg(not(any(esp32, esp32s2)))]
+        if interrupts.slave_stretch().bit_is_set() {
+            if self.stretched_for_read() {
+                return Ok(true);
+            }
+            // SCL was held because the RX FIFO was full
+            self.release_scl();
+        }
+
+        if stopped {
+            register_block
+                .int_clr()
+                .write(|w| w.trans_complete().clear_bit_by_one());
+            return Ok(true);
+        }
+
+        Ok(read_requested)
+    }
+
+    /// Loads the TX FIFO and releases SCL if the master is already waiting,
+    /// returns the number of bytes written to the FIFO
+    fn start_write(&self, bytes: &[u8]) -> Result<usize, Error> {
+        #[cfg(any(esp32, esp32s2))]
+        if bytes.len() > FIFO_SIZE {
+            return Err(Error::ExceedingFifo);
+        }
+
+        let register_block = self.peripheral.register_block();
+
+        // Drop data left over from a previous read of the master
+        register_block
+            .fifo_conf()
+            .modify(|_, w| w.tx_fifo_rst().set_bit());
+        register_block
+            .fifo_conf()
+            .modify(|_, w| w.tx_fifo_rst().clear_bit());
+
+        // A STOP seen so far belongs to the preceding write of the master
+        register_block
+            .int_clr()
+            .write(|w| w.trans_complete().clear_bit_by_one());
+
+        let count = bytes.len().min(FIFO_SIZE);
+        for byte in &bytes[..count] {
+            write_fifo(register_block, *byte);
+        }
+
+        #[cfg(not(any(esp32, esp32s2)))]
+        if register_block.int_raw().read().slave_stretch().bit_is_set() {
+            self.release_scl();
+        }
+
+        Ok(count)
+    }
+
+    /// Keeps the TX FIFO filled, returns `true` once the master ended the
+    /// transaction
+    fn write_step(&self, bytes: &[u8], index: &mut usize) -> Result<bool, Error> {
+        let register_block = self.peripheral.register_block();
+
+        self.check_errors()?;
+
+        #[cfg(not(any(esp32, esp32s2)))]
+        {
+            while *index < bytes.len()
+                && (register_block.sr().read().txfifo_cnt().bits() as usize) < FIFO_SIZE
+            {
+                write_fifo(register_block, bytes[*index]);
+                *index += 1;
+            }
+
+            register_block
+                .int_clr()
+                .write(|w| w.txfifo_wm().clear_bit_by_one());
+
+            // The master reads more than was provided, keep the bus going
+            if register_block.int_raw().read().slave_stretch().bit_is_set()
+                && register_block.sr().read().txfifo_cnt().bits() == 0
+            {
+                write_fifo(register_block, PADDING_BYTE);
+                self.release_scl();
+            }
+        }
+
+        if register_block
+            .int_raw()
+            .read()
+            .trans_complete()
+            .bit_is_set()
+        {
+            register_block
+                .int_clr()
+                .write(|w| w.trans_complete().clear_bit_by_one());
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+}
+
+impl<'d, T> I2cSlave<'d, T, crate::Blocking>
+where
+    T: Instance,
+{
+    /// Create a new I2C slave instance
+    /// This will enable the peripheral but the peripheral won't get
+    /// automatically disabled when this gets dropped.
+    pub fn new<SDA: OutputPin + InputPin, SCL: OutputPin + InputPin>(
+        i2c: impl Peripheral<P = T> + 'd,
+        sda: impl Peripheral<P = SDA> + 'd,
+        scl: impl Peripheral<P = SCL> + 'd,
+        address: SlaveAddress,
+        clocks: &Clocks,
+        isr: Option<InterruptHandler>,
+    ) -> Self {
+        Self::new_internal(i2c, sda, scl, address, clocks, isr)
+    }
+
+    /// Waits for the master to write data and stores it in `buffer`
+    ///
+    /// Returns the number of bytes received once the master ends the write
+    /// with a STOP condition or switches to reading with a repeated START.
+    /// In the latter case, call [`I2cSlave::write`] to answer.
+    pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
+        let mut count = 0;
+        while !self.read_step(buffer, &mut count)? {}
+        Ok(count)
+    }
+
+    /// Provides `bytes` to the master and waits until it ended the read
+    ///
+    /// If the master reads more data than provided, `0xFF` is sent.
+    pub fn write(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let mut index = self.start_write(bytes)?;
+        while !self.write_step(bytes, &mut index)? {}
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+mod asynch {
+    use core::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use embassy_sync::waitqueue::AtomicWaker;
+    use procmacros::handler;
+
+    use super::*;
+    use crate::{i2c::NUM_I2C, peripherals::i2c0::RegisterBlock};
+
+    const INIT: AtomicWaker = AtomicWaker::new();
+    static WAKERS: [AtomicWaker; NUM_I2C] = [INIT; NUM_I2C];
+
+    /// Resolves on the next slave interrupt
+    struct I2cSlaveFuture<'a, T>
+    where
+        T: Instance,
+    {
+        instance: &'a T,
+    }
+
+    impl<'a, T> I2cSlaveFuture<'a, T>
+    where
+        T: Instance,
+    {
+        fn new(instance: &'a T, tx: bool) -> Self {
+            instance.register_block().int_ena().modify(|_, w| {
+                #[cfg(not(any(esp32, esp32s2)))]
+                w.slave_stretch().set_bit().txfifo_wm().bit(tx);
+                #[cfg(esp32)]
+                w.rxfifo_full().set_bit();
+                #[cfg(not(esp32))]
+                w.rxfifo_wm().set_bit();
+                #[cfg(any(esp32, esp32s2))]
+                let _ = tx;
+                w.trans_complete()
+                    .set_bit()
+                    .time_out()
+                    .set_bit()
+                    .rxfifo_ovf()
+                    .set_bit()
+            });
+
+            Self { instance }
+        }
+
+        fn is_done(&self) -> bool {
+            // the interrupt handler disables the interrupts of the future once
+            // one fired, `time_out` isn't used by `I2cSlave::listen`
+            self.instance
+                .register_block()
+                .int_ena()
+                .read()
+                .time_out()
+                .bit_is_clear()
+        }
+    }
+
+    impl<'a, T> core::future::Future for I2cSlaveFuture<'a, T>
+    where
+        T: Instance,
+    {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+            WAKERS[self.instance.i2c_number()].register(ctx.waker());
+
+            if self.is_done() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        }
+    }
+
+    impl<'d, T> I2cSlave<'d, T, crate::Async>
+    where
+        T: Instance,
+    {
+        /// Create a new I2C slave instance
+        /// This will enable the peripheral but the peripheral won't get
+        /// automatically disabled when this gets dropped.
+        pub fn new_async<SDA: OutputPin + InputPin, SCL: OutputPin + InputPin>(
+            i2c: impl Peripheral<P = T> + 'd,
+            sda: impl Peripheral<P = SDA> + 'd,
+            scl: impl Peripheral<P = SCL> + 'd,
+            address: SlaveAddress,
+            clocks: &Clocks,
+        ) -> Self {
+            let handler = Some(match T::I2C_NUMBER {
+                0 => i2c0_slave_handler,
+                #[cfg(i2c1)]
+                1 => i2c1_slave_handler,
+                _ => panic!("Unexpected I2C peripheral"),
+            });
+            Self::new_internal(i2c, sda, scl, address, clocks, handler)
+        }
+
+        /// Waits for the master to write data and stores it in `buffer`
+        ///
+        /// Returns the number of bytes received once the master ends the
+        /// write with a STOP condition or switches to reading with a
+        /// repeated START. In the latter case, call [`I2cSlave::write`] to
+        /// answer.
+        pub async fn read(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
+            let mut count = 0;
+            while !self.read_step(buffer, &mut count)? {
+                I2cSlaveFuture::new(&*self.peripheral, false).await;
+            }
+            Ok(count)
+        }
+
+        /// Provides `bytes` to the master and waits until it ended the read
+        ///
+        /// If the master reads more data than provided, `0xFF` is sent.
+        pub async fn write(&mut self, bytes: &[u8]) -> Result<(), Error> {
+            let mut index = self.start_write(bytes)?;
+            while !self.write_step(bytes, &mut index)? {
+                I2cSlaveFuture::new(&*self.peripheral, index < bytes.len()).await;
+            }
+            Ok(())
+        }
+    }
+
+    /// Disables the interrupts enabled by [I2cSlaveFuture], leaving the
+    /// other bits alone
+    fn disable_future_interrupts(regs: &RegisterBlock) {
+        regs.int_ena().modify(|_, w| {
+            #[cfg(not(any(esp32, esp32s2)))]
+            w.slave_stretch().clear_bit().txfifo_wm().clear_bit();
+            #[cfg(esp32)]
+            w.rxfifo_full().clear_bit();
+            #[cfg(not(esp32))]
+            w.rxfifo_wm().clear_bit();
+            w.trans_complete()
+                .clear_bit()
+                .time_out()
+                .clear_bit()
+                .rxfifo_ovf()
+                .clear_bit()
+        });
+    }
+
+    #[handler]
+    fn i2c0_slave_handler() {
+        disable_future_interrupts(unsafe { &*crate::peripherals::I2C0::PTR });
+
+        WAKERS[0].wake();
+    }
+
+    #[cfg(i2c1)]
+    #[handler]
+    fn i2c1_slave_handler() {
+        disable_future_interrupts(unsafe { &*crate::peripherals::I2C1::PTR });
+
+        WAKERS[1].wake();
+    }
+}
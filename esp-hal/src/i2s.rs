@@ -19,6 +19,55 @@
 //!     - `DMA`
 //!     - `system` (to configure and enable the I2S peripheral)
 //!
+//! ## Standards
+//!
+//! Besides the Philips standard, the left-justified (MSB) standard and PCM
+//! with short or long frame sync are supported on all chips. Chips other than
+//! ESP32 and ESP32-S2 support TDM with up to 16 slots, and I2S0 of ESP32,
+//! ESP32-C3, ESP32-C6, ESP32-H2 and ESP32-S3 supports PDM.
+//!
+//! ```no_run
+//! // four slot TDM codec, the DMA buffer contains the samples of slots 0-3
+//! let i2s = I2s::new(
+//!     peripherals.I2S0,
+//!     Standard::Tdm(TdmConfig::new(4).with_format(TdmFormat::PcmShort)),
+//!     DataFormat::Data16Channel16,
+//!     48000.Hz(),
+//!     dma_channel.configure(
+//!         false,
+//!         &mut tx_descriptors,
+//!         &mut rx_descriptors,
+//!         DmaPriority::Priority0,
+//!     ),
+//!     &clocks,
+//! );
+//! ```
+//!
+//! ## Full-duplex
+//!
+//! [I2sDuplex] combines the TX and RX channels, the receiver then shares
+//! BCLK and WS of the transmitter. Its circular transfer starts both
+//! directions on the same frame, keeps a common stream position and reports
+//! underruns, overruns and descriptor errors as [I2sDuplexEvent]s.
+//!
+//! ```no_run
+//! let mut duplex = I2sDuplex::new(i2s_tx, i2s_rx);
+//! let mut transfer = duplex.transfer_circular(tx_buffer, rx_buffer).unwrap();
+//!
+//! loop {
+//!     if !transfer.events().is_empty() {
+//!         transfer.resync().unwrap();
+//!     }
+//!
+//!     let avail = transfer.rx_available();
+//!     if avail > 0 {
+//!         let mut rcv = [0u8; 5000];
+//!         transfer.pop(&mut rcv[..avail]).unwrap();
+//!         transfer.push(&rcv[..avail]).unwrap();
+//!     }
+//! }
+//! ```
+//!
 //! ## Examples
 //!
 //! ### initialization
@@ -142,9 +191,113 @@ impl From<DmaError> for Error {
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Standard {
+    /// Philips standard, WS changes one BCLK before the MSB of a channel
+    Philips,
+    /// Left-justified (MSB) standard, WS changes together with the MSB of a
+    /// channel
+    Msb,
+    /// PCM standard with a WS pulse of one BCLK before the first channel
+    PcmShort,
+    /// PCM standard with WS active during the first channel
+    PcmLong,
+    /// Time-division multiplexing of up to 16 channels
+    #[cfg(not(any(esp32, esp32s2)))]
+    Tdm(TdmConfig),
+    /// Pulse density modulation
+    ///
+    /// Only supported by I2S0 and with [DataFormat::Data16Channel16]. The
+    /// transmitter converts PCM samples to a PDM signal on DOUT, with WS as the
+    /// PDM clock. The receiver converts the PDM signal of e.g. a MEMS
+    /// microphone on DIN to PCM samples with the hardware filter (ESP32,
+    /// ESP32-S3 and ESP32-C6 only).
+    #[cfg(any(esp32, esp32c3, esp32c6, esp32h2, esp32s3))]
+    Pdm,
+}
+
+impl Standard {
+    /// Returns the number of channel slots in a frame and the width of a
+    /// slot in bits
+    fn frame(&self, data_format: &DataFormat) -> (u8, u8) {
+        match self {
+            #[cfg(not(any(esp32, esp32s2)))]
+            Standard::Tdm(config) => (
+                config.slots,
+                config.slot_bits.unwrap_or(data_format.channel_bits()),
+            ),
+            // PDM runs at 128 BCLK cycles per sample
+            #[cfg(any(esp32, esp32c3, esp32c6, esp32h2, esp32s3))]
+            Standard::Pdm => (2, 64),
+            _ => (2, data_format.channel_bits()),
+        }
+    }
+}
+
+/// Frame sync format of a TDM frame
+#[cfg(not(any(esp32, esp32s2)))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TdmFormat {
+    /// WS changes one BCLK before the MSB of the first slot and in the middle
+    /// of the frame
     Philips,
-    // Tdm,
-    // Pdm,
+    /// WS changes together with the MSB of the first slot and in the middle of
+    /// the frame
+    Msb,
+    /// WS pulse of one BCLK before the first slot
+    PcmShort,
+    /// WS active during the first slot
+    PcmLong,
+}
+
+/// Configuration of [Standard::Tdm]
+#[cfg(not(any(esp32, esp32s2)))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TdmConfig {
+    /// Frame sync format
+    pub format: TdmFormat,
+    /// Total number of slots in a frame, 1 to 16
+    pub slots: u8,
+    /// Slots which carry data, bit `n` enables slot `n`
+    ///
+    /// Disabled slots are skipped in the DMA buffer and transmitted as zeros.
+    pub slot_mask: u16,
+    /// Width of a slot in bits, [DataFormat::channel_bits] if `None`
+    pub slot_bits: Option<u8>,
+}
+
+#[cfg(not(any(esp32, esp32s2)))]
+impl TdmConfig {
+    /// Create a configuration with the given number of slots, all of them
+    /// enabled, in Philips format
+    pub const fn new(slots: u8) -> Self {
+        assert!(matches!(slots, 1..=16));
+
+        Self {
+            format: TdmFormat::Philips,
+            slots,
+            slot_mask: ((1u32 << slots) - 1) as u16,
+            slot_bits: None,
+        }
+    }
+
+    /// Set the frame sync format
+    pub const fn with_format(mut self, format: TdmFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set the slots which carry data
+    pub const fn with_slot_mask(mut self, slot_mask: u16) -> Self {
+        self.slot_mask = slot_mask;
+        self
+    }
+
+    /// Set the width of a slot in bits
+    pub const fn with_slot_bits(mut self, slot_bits: u8) -> Self {
+        self.slot_bits = Some(slot_bits);
+        self
+    }
 }
 
 /// Supported data formats
@@ -305,14 +458,18 @@ where
         // could be configured totally independently but for now handle all
         // the targets the same and force same configuration for both, TX and RX
 
+        #[cfg(any(esp32, esp32c3, esp32c6, esp32h2, esp32s3))]
+        if matches!(standard, Standard::Pdm) {
+            assert!(
+                data_format == DataFormat::Data16Channel16,
+                "PDM requires 16-bit data"
+            );
+        }
+
         channel.tx.init_channel();
         PeripheralClockControl::enable(I::get_peripheral());
-        I::set_clock(calculate_clock(
-            sample_rate,
-            2,
-            data_format.channel_bits(),
-            clocks,
-        ));
+        let (slots, slot_bits) = standard.frame(&data_format);
+        I::set_clock(calculate_clock(sample_rate, slots, slot_bits, clocks));
         I::configure(&standard, &data_format);
         I::set_master();
         I::update();
@@ -407,6 +564,12 @@ where
         I: I2s1Instance,
         CH::P: I2sPeripheral + I2s1Peripheral,
     {
+        #[cfg(any(esp32, esp32s3))]
+        assert!(
+            !matches!(standard, Standard::Pdm),
+            "PDM is only supported by I2S0"
+        );
+
         Self::new_internal(i2s, standard, data_format, sample_rate, channel, clocks)
     }
 
@@ -759,6 +922,290 @@ where
     }
 }
 
+/// Conditions detected while an [I2sDuplexTransfer] is running
+#[derive(EnumSetType, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum I2sDuplexEvent {
+    /// TX has sent the whole buffer since data has last been pushed, stale
+    /// samples have been transmitted
+    TxUnderrun,
+    /// RX has filled the whole buffer without data being popped, received
+    /// samples have been overwritten
+    RxOverrun,
+    /// The TX DMA channel reported a descriptor error
+    TxDescriptorError,
+    /// The RX DMA channel reported a descriptor error
+    RxDescriptorError,
+}
+
+/// I2S TX and RX channels operated as one full-duplex stream
+///
+/// The receiver shares BCLK and WS of the transmitter, so both directions run
+/// on the same frame clock.
+pub struct I2sDuplex<'d, T, CH, DmaMode>
+where
+    T: RegisterAccess,
+    CH: ChannelTypes,
+    DmaMode: Mode,
+{
+    tx: I2sTx<'d, T, CH, DmaMode>,
+    rx: I2sRx<'d, T, CH, DmaMode>,
+}
+
+impl<'d, T, CH, DmaMode> core::fmt::Debug for I2sDuplex<'d, T, CH, DmaMode>
+where
+    T: RegisterAccess,
+    CH: ChannelTypes,
+    DmaMode: Mode,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("I2sDuplex").finish()
+    }
+}
+
+impl<'d, T, CH, DmaMode> I2sDuplex<'d, T, CH, DmaMode>
+where
+    T: RegisterAccess,
+    CH: ChannelTypes,
+    DmaMode: Mode,
+{
+    /// Combine the TX and RX channels of the same I2S peripheral
+    pub fn new(tx: I2sTx<'d, T, CH, DmaMode>, rx: I2sRx<'d, T, CH, DmaMode>) -> Self {
+        T::set_shared_clock(true);
+        Self { tx, rx }
+    }
+
+    /// Release the TX and RX channels, the receiver uses its own BCLK and WS
+    /// again
+    pub fn split(self) -> (I2sTx<'d, T, CH, DmaMode>, I2sRx<'d, T, CH, DmaMode>) {
+        T::set_shared_clock(false);
+        (self.tx, self.rx)
+    }
+
+    /// Continuously write `tx_words` and read into `rx_words`, starting both
+    /// directions on the same frame
+    ///
+    /// Returns [I2sDuplexTransfer] which represents the in-progress DMA
+    /// transfers
+    pub fn transfer_circular<'t, TXBUF, RXBUF>(
+        &'t mut self,
+        tx_words: &'t TXBUF,
+        rx_words: &'t mut RXBUF,
+    ) -> Result<I2sDuplexTransfer<'t, 'd, T, CH, DmaMode>, Error>
+    where
+        TXBUF: ReadBuffer<Word = u8>,
+        RXBUF: WriteBuffer<Word = u8>,
+    {
+        let (tx_ptr, tx_len) = unsafe { tx_words.read_buffer() };
+        let (rx_ptr, rx_len) = unsafe { rx_words.write_buffer() };
+
+        if rx_len % 4 != 0 {
+            return Err(Error::IllegalArgument);
+        }
+
+        let mut transfer = I2sDuplexTransfer {
+            duplex: self,
+            tx_ptr,
+            tx_len,
+            rx_ptr,
+            rx_len,
+            tx_position: 0,
+            rx_position: 0,
+            events: EnumSet::empty(),
+        };
+        transfer.start()?;
+
+        Ok(transfer)
+    }
+}
+
+/// An in-progress full-duplex circular DMA transfer
+///
+/// TX and RX keep a shared position in bytes since the transfer has been
+/// started: data pushed at [I2sDuplexTransfer::tx_position] is transmitted in
+/// the same frame in which the data popped at the same
+/// [I2sDuplexTransfer::rx_position] is received, apart from the constant
+/// latency of the external devices. This only holds while no
+/// [I2sDuplexEvent::TxUnderrun] or [I2sDuplexEvent::RxOverrun] has been
+/// detected, use [I2sDuplexTransfer::resync] to restore it.
+#[must_use]
+pub struct I2sDuplexTransfer<'t, 'd, T, CH, DmaMode>
+where
+    T: RegisterAccess,
+    CH: ChannelTypes,
+    DmaMode: Mode,
+{
+    duplex: &'t mut I2sDuplex<'d, T, CH, DmaMode>,
+    tx_ptr: *const u8,
+    tx_len: usize,
+    rx_ptr: *mut u8,
+    rx_len: usize,
+    tx_position: u64,
+    rx_position: u64,
+    events: EnumSet<I2sDuplexEvent>,
+}
+
+impl<'t, 'd, T, CH, DmaMode> I2sDuplexTransfer<'t, 'd, T, CH, DmaMode>
+where
+    T: RegisterAccess,
+    CH: ChannelTypes,
+    DmaMode: Mode,
+{
+    fn start(&mut self) -> Result<(), Error> {
+        T::reset_tx();
+        T::reset_rx();
+
+        self.duplex
+            .tx
+            .tx_channel
+            .prepare_transfer_without_start(T::get_dma_peripheral(), true, self.tx_ptr, self.tx_len)
+            .and_then(|_| self.duplex.tx.tx_channel.start_transfer())?;
+        unsafe {
+            self.duplex
+                .rx
+                .rx_channel
+                .prepare_transfer_without_start(
+                    true,
+                    T::get_dma_peripheral(),
+                    self.rx_ptr,
+                    self.rx_len,
+                )
+                .and_then(|_| self.duplex.rx.rx_channel.start_transfer())?;
+        }
+
+        // the receiver is clocked by the transmitter, starting it first makes
+        // it capture from the first frame the transmitter sends
+        T::rx_start(self.rx_len);
+        T::tx_start();
+
+        // the whole buffer is sent before the first pushed byte
+        self.tx_position = self.tx_len as u64;
+        self.rx_position = 0;
+        self.events = EnumSet::empty();
+
+        Ok(())
+    }
+
+    fn stop_peripheral(&mut self) {
+        T::tx_stop();
+        T::reset_rx();
+    }
+
+    fn poll_events(&mut self) {
+        let tx = &mut self.duplex.tx.tx_channel;
+        let rx = &mut self.duplex.rx.rx_channel;
+
+        tx.available();
+        if tx.take_underrun() {
+            self.events |= I2sDuplexEvent::TxUnderrun;
+        }
+        if rx.is_overrun() {
+            self.events |= I2sDuplexEvent::RxOverrun;
+        }
+        if tx.has_error() {
+            self.events |= I2sDuplexEvent::TxDescriptorError;
+        }
+        if rx.has_error() {
+            self.events |= I2sDuplexEvent::RxDescriptorError;
+        }
+    }
+
+    /// Conditions detected since the transfer has been started or the events
+    /// have been cleared
+    pub fn events(&mut self) -> EnumSet<I2sDuplexEvent> {
+        self.poll_events();
+        self.events
+    }
+
+    /// Clear the given events
+    pub fn clear_events(&mut self, events: EnumSet<I2sDuplexEvent>) {
+        self.events &= !events;
+    }
+
+    /// Stream position of the next byte to be pushed
+    pub fn tx_position(&self) -> u64 {
+        self.tx_position
+    }
+
+    /// Stream position of the next byte to be popped
+    pub fn rx_position(&self) -> u64 {
+        self.rx_position
+    }
+
+    /// Amount of bytes which can be pushed
+    pub fn tx_available(&mut self) -> usize {
+        self.poll_events();
+        self.duplex.tx.tx_channel.available()
+    }
+
+    /// Amount of bytes which can be popped
+    pub fn rx_available(&mut self) -> usize {
+        self.poll_events();
+        self.duplex.rx.rx_channel.available()
+    }
+
+    /// Push bytes into the TX buffer
+    pub fn push(&mut self, data: &[u8]) -> Result<usize, Error> {
+        self.poll_events();
+        let written = self.duplex.tx.tx_channel.push(data)?;
+        self.tx_position += written as u64;
+        Ok(written)
+    }
+
+    /// Push bytes into the TX buffer via the given closure.
+    /// The closure *must* return the actual number of bytes written.
+    /// The closure *might* get called with a slice which is smaller than the
+    /// total available buffer.
+    pub fn push_with(&mut self, f: impl FnOnce(&mut [u8]) -> usize) -> Result<usize, Error> {
+        self.poll_events();
+        let written = self.duplex.tx.tx_channel.push_with(f)?;
+        self.tx_position += written as u64;
+        Ok(written)
+    }
+
+    /// Pop received bytes from the RX buffer
+    pub fn pop(&mut self, data: &mut [u8]) -> Result<usize, Error> {
+        self.poll_events();
+        let read = self.duplex.rx.rx_channel.pop(data)?;
+        self.rx_position += read as u64;
+        Ok(read)
+    }
+
+    /// Restart both directions on the same frame from the start of their
+    /// buffers, resetting the stream positions and events
+    ///
+    /// The content of the TX buffer is sent again.
+    pub fn resync(&mut self) -> Result<(), Error> {
+        self.stop_peripheral();
+        self.start()
+    }
+
+    /// Stop the transfer
+    pub fn stop(mut self) -> Result<(), Error> {
+        self.stop_peripheral();
+        self.poll_events();
+
+        if self.events.contains(I2sDuplexEvent::TxDescriptorError)
+            || self.events.contains(I2sDuplexEvent::RxDescriptorError)
+        {
+            Err(Error::DmaError(DmaError::DescriptorError))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<'t, 'd, T, CH, DmaMode> Drop for I2sDuplexTransfer<'t, 'd, T, CH, DmaMode>
+where
+    T: RegisterAccess,
+    CH: ChannelTypes,
+    DmaMode: Mode,
+{
+    fn drop(&mut self) {
+        self.stop_peripheral();
+    }
+}
+
 pub trait RegisterAccess: RegisterAccessPrivate {}
 
 mod private {
@@ -767,6 +1214,8 @@ mod private {
     use enumset::EnumSet;
     use fugit::HertzU32;
 
+    #[cfg(not(any(esp32, esp32s2)))]
+    use super::TdmFormat;
     use super::{
         DataFormat,
         I2sInterrupt,
@@ -1019,9 +1468,19 @@ mod private {
             });
         }
 
-        fn configure(_standard: &Standard, data_format: &DataFormat) {
+        fn configure(standard: &Standard, data_format: &DataFormat) {
             let i2s = Self::register_block();
 
+            let (msb_shift, short_sync) = match standard {
+                Standard::Philips => (true, false),
+                Standard::Msb | Standard::PcmLong => (false, false),
+                Standard::PcmShort => (false, true),
+                #[cfg(esp32)]
+                Standard::Pdm => (true, false),
+            };
+            #[cfg(esp32)]
+            let pdm = matches!(standard, Standard::Pdm);
+
             let fifo_mod = match data_format {
                 DataFormat::Data32Channel32 => 2,
                 DataFormat::Data16Channel16 => 0,
@@ -1038,13 +1497,13 @@ mod private {
                     .rx_slave_mod()
                     .clear_bit()
                     .tx_msb_shift()
-                    .set_bit() // ?
+                    .bit(msb_shift)
                     .rx_msb_shift()
-                    .set_bit() // ?
+                    .bit(msb_shift)
                     .tx_short_sync()
-                    .bit(false) //??
+                    .bit(short_sync)
                     .rx_short_sync()
-                    .bit(false) //??
+                    .bit(short_sync)
                     .tx_msb_right()
                     .clear_bit()
                     .rx_msb_right()
@@ -1085,6 +1544,29 @@ mod private {
 
             i2s.conf2()
                 .modify(|_, w| w.camera_en().clear_bit().lcd_en().clear_bit());
+
+            #[cfg(esp32)]
+            if matches!(Self::get_peripheral(), Peripheral::I2s0) {
+                let i2s = unsafe { &*I2S0::PTR };
+
+                // 128x oversampling in both directions
+                i2s.pdm_conf().modify(|_, w| unsafe {
+                    w.tx_pdm_en()
+                        .bit(pdm)
+                        .rx_pdm_en()
+                        .bit(pdm)
+                        .pcm2pdm_conv_en()
+                        .bit(pdm)
+                        .pdm2pcm_conv_en()
+                        .bit(pdm)
+                        .tx_pdm_sinc_osr2()
+                        .bits(2)
+                        .rx_pdm_sinc_dsr_16_en()
+                        .set_bit()
+                });
+                i2s.pdm_freq_conf()
+                    .modify(|_, w| unsafe { w.tx_pdm_fp().bits(960).tx_pdm_fs().bits(480) });
+            }
         }
 
         fn set_master() {
@@ -1093,6 +1575,12 @@ mod private {
                 .modify(|_, w| w.rx_slave_mod().clear_bit().tx_slave_mod().clear_bit());
         }
 
+        /// Let the receiver use BCLK and WS of the transmitter
+        fn set_shared_clock(enable: bool) {
+            let i2s = Self::register_block();
+            i2s.conf().modify(|_, w| w.sig_loopback().bit(enable));
+        }
+
         fn update() {
             // nothing to do
         }
@@ -1480,24 +1968,55 @@ mod private {
             });
         }
 
-        fn configure(_standard: &Standard, data_format: &DataFormat) {
+        fn configure(standard: &Standard, data_format: &DataFormat) {
             let i2s = Self::register_block();
 
+            let pdm = matches!(standard, Standard::Pdm);
+            // in PDM mode the slot width only affects the clock, the PCM side
+            // still uses the channel width of the data format
+            let (slots, slot_bits) = if pdm {
+                (2, data_format.channel_bits())
+            } else {
+                standard.frame(data_format)
+            };
+            let (format, slot_mask) = match standard {
+                Standard::Philips | Standard::Pdm => (TdmFormat::Philips, 0b11),
+                Standard::Msb => (TdmFormat::Msb, 0b11),
+                Standard::PcmShort => (TdmFormat::PcmShort, 0b11),
+                Standard::PcmLong => (TdmFormat::PcmLong, 0b11),
+                Standard::Tdm(config) => (
+                    config.format,
+                    config.slot_mask & ((1u32 << slots) - 1) as u16,
+                ),
+            };
+
+            let half_frame_bits = (slots as u16 * slot_bits as u16 / 2 - 1) as u8;
+            let ws_width = match format {
+                TdmFormat::PcmShort => 0,
+                TdmFormat::PcmLong => slot_bits - 1,
+                TdmFormat::Philips | TdmFormat::Msb => half_frame_bits,
+            };
+            let msb_shift = format != TdmFormat::Msb;
+            let ws_idle_pol = matches!(format, TdmFormat::PcmShort | TdmFormat::PcmLong);
+            // total channel number and the 16 channel enable bits
+            let tdm_ctrl = slot_mask as u32 | ((slots as u32 - 1) << 16);
+
             #[allow(clippy::useless_conversion)]
             i2s.tx_conf1().modify(|_, w| unsafe {
                 w.tx_tdm_ws_width()
-                    .bits((data_format.channel_bits() - 1).into())
+                    .bits(ws_width.into())
                     .tx_bits_mod()
                     .bits(data_format.data_bits() - 1)
                     .tx_tdm_chan_bits()
-                    .bits(data_format.channel_bits() - 1)
+                    .bits(slot_bits - 1)
                     .tx_half_sample_bits()
-                    .bits(data_format.channel_bits() - 1)
+                    .bits(half_frame_bits)
             });
             #[cfg(not(esp32h2))]
-            i2s.tx_conf1().modify(|_, w| w.tx_msb_shift().set_bit());
+            i2s.tx_conf1()
+                .modify(|_, w| w.tx_msb_shift().bit(msb_shift));
             #[cfg(esp32h2)]
-            i2s.tx_conf().modify(|_, w| w.tx_msb_shift().set_bit());
+            i2s.tx_conf().modify(|_, w| w.tx_msb_shift().bit(msb_shift));
             i2s.tx_conf().modify(|_, w| unsafe {
                 w.tx_mono()
                     .clear_bit()
@@ -1508,71 +2027,40 @@ mod private {
                     .tx_chan_equal()
                     .clear_bit()
                     .tx_tdm_en()
-                    .set_bit()
+                    .bit(!pdm)
                     .tx_pdm_en()
-                    .clear_bit()
+                    .bit(pdm)
                     .tx_pcm_bypass()
                     .set_bit()
                     .tx_big_endian()
                     .clear_bit()
                     .tx_bit_order()
                     .clear_bit()
+                    .tx_ws_idle_pol()
+                    .bit(ws_idle_pol)
                     .tx_chan_mod()
                     .bits(0)
             });
 
-            i2s.tx_tdm_ctrl().modify(|_, w| unsafe {
-                w.tx_tdm_tot_chan_num()
-                    .bits(1)
-                    .tx_tdm_chan0_en()
-                    .set_bit()
-                    .tx_tdm_chan1_en()
-                    .set_bit()
-                    .tx_tdm_chan2_en()
-                    .clear_bit()
-                    .tx_tdm_chan3_en()
-                    .clear_bit()
-                    .tx_tdm_chan4_en()
-                    .clear_bit()
-                    .tx_tdm_chan5_en()
-                    .clear_bit()
-                    .tx_tdm_chan6_en()
-                    .clear_bit()
-                    .tx_tdm_chan7_en()
-                    .clear_bit()
-                    .tx_tdm_chan8_en()
-                    .clear_bit()
-                    .tx_tdm_chan9_en()
-                    .clear_bit()
-                    .tx_tdm_chan10_en()
-                    .clear_bit()
-                    .tx_tdm_chan11_en()
-                    .clear_bit()
-                    .tx_tdm_chan12_en()
-                    .clear_bit()
-                    .tx_tdm_chan13_en()
-                    .clear_bit()
-                    .tx_tdm_chan14_en()
-                    .clear_bit()
-                    .tx_tdm_chan15_en()
-                    .clear_bit()
-            });
+            i2s.tx_tdm_ctrl()
+                .modify(|r, w| unsafe { w.bits((r.bits() & !0xf_ffff) | tdm_ctrl) });
 
             #[allow(clippy::useless_conversion)]
             i2s.rx_conf1().modify(|_, w| unsafe {
                 w.rx_tdm_ws_width()
-                    .bits((data_format.channel_bits() - 1).into())
+                    .bits(ws_width.into())
                     .rx_bits_mod()
                     .bits(data_format.data_bits() - 1)
                     .rx_tdm_chan_bits()
-                    .bits(data_format.channel_bits() - 1)
+                    .bits(slot_bits - 1)
                     .rx_half_sample_bits()
-                    .bits(data_format.channel_bits() - 1)
+                    .bits(half_frame_bits)
             });
             #[cfg(not(esp32h2))]
-            i2s.rx_conf1().modify(|_, w| w.rx_msb_shift().set_bit());
+            i2s.rx_conf1()
+                .modify(|_, w| w.rx_msb_shift().bit(msb_shift));
             #[cfg(esp32h2)]
-            i2s.rx_conf().modify(|_, w| w.rx_msb_shift().set_bit());
+            i2s.rx_conf().modify(|_, w| w.rx_msb_shift().bit(msb_shift));
 
             i2s.rx_conf().modify(|_, w| unsafe {
                 w.rx_mono()
@@ -1582,53 +2070,44 @@ mod private {
                     .rx_stop_mode()
                     .bits(2)
                     .rx_tdm_en()
-                    .set_bit()
+                    .bit(!pdm)
                     .rx_pdm_en()
-                    .clear_bit()
+                    .bit(pdm)
                     .rx_pcm_bypass()
                     .set_bit()
                     .rx_big_endian()
                     .clear_bit()
                     .rx_bit_order()
                     .clear_bit()
+                    .rx_ws_idle_pol()
+                    .bit(ws_idle_pol)
             });
 
-            i2s.rx_tdm_ctrl().modify(|_, w| unsafe {
-                w.rx_tdm_tot_chan_num()
-                    .bits(1)
-                    .rx_tdm_pdm_chan0_en()
-                    .set_bit()
-                    .rx_tdm_pdm_chan1_en()
-                    .set_bit()
-                    .rx_tdm_pdm_chan2_en()
-                    .clear_bit()
-                    .rx_tdm_pdm_chan3_en()
-                    .clear_bit()
-                    .rx_tdm_pdm_chan4_en()
-                    .clear_bit()
-                    .rx_tdm_pdm_chan5_en()
-                    .clear_bit()
-                    .rx_tdm_pdm_chan6_en()
-                    .clear_bit()
-                    .rx_tdm_pdm_chan7_en()
-                    .clear_bit()
-                    .rx_tdm_chan8_en()
-                    .clear_bit()
-                    .rx_tdm_chan9_en()
-                    .clear_bit()
-                    .rx_tdm_chan10_en()
-                    .clear_bit()
-                    .rx_tdm_chan11_en()
-                    .clear_bit()
-                    .rx_tdm_chan12_en()
-                    .clear_bit()
-                    .rx_tdm_chan13_en()
-                    .clear_bit()
-                    .rx_tdm_chan14_en()
-                    .clear_bit()
-                    .rx_tdm_chan15_en()
-                    .clear_bit()
-            });
+            i2s.rx_tdm_ctrl()
+                .modify(|r, w| unsafe { w.bits((r.bits() & !0xf_ffff) | tdm_ctrl) });
+
+            Self::configure_pdm_filter(pdm);
+        }
+
+        /// The PCM/PDM converters are only present in I2S0
+        fn configure_pdm_filter(pdm: bool) {
+            if !matches!(Self::get_peripheral(), Peripheral::I2s0) {
+                return;
+            }
+
+            let i2s = unsafe { &*I2S0::PTR };
+
+            // PCM to PDM: OSR2 = 2 and FP / FS = 960 / 480 result in 128x
+            // oversampling
+            i2s.tx_pcm2pdm_conf()
+                .modify(|_, w| unsafe { w.pcm2pdm_conv_en().bit(pdm).tx_pdm_sinc_osr2().bits(2) });
+            i2s.tx_pcm2pdm_conf1()
+                .modify(|_, w| unsafe { w.tx_pdm_fp().bits(960).tx_pdm_fs().bits(480) });
+
+            // PDM to PCM with 128x downsampling
+            #[cfg(any(esp32c6, esp32s3))]
+            i2s.rx_conf()
+                .modify(|_, w| w.rx_pdm2pcm_en().bit(pdm).rx_pdm_sinc_dsr_16_en().set_bit());
         }
 
         fn set_master() {
@@ -1637,6 +2116,12 @@ mod private {
             i2s.rx_conf().modify(|_, w| w.rx_slave_mod().clear_bit());
         }
 
+        /// Let the receiver use BCLK and WS of the transmitter
+        fn set_shared_clock(enable: bool) {
+            let i2s = Self::register_block();
+            i2s.tx_conf().modify(|_, w| w.sig_loopback().bit(enable));
+        }
+
         fn update() {
             let i2s = Self::register_block();
             i2s.tx_conf().modify(|_, w| w.tx_update().clear_bit());
@@ -2010,8 +2495,13 @@ mod private {
         let rate = rate_hz.raw();
 
         let bclk = rate * channels as u32 * data_bits as u32;
-        let mclk = rate * mclk_multiple;
-        let bclk_divider = mclk / bclk;
+        let mut mclk = rate * mclk_multiple;
+        let mut bclk_divider = mclk / bclk;
+        // wide frames (TDM, PDM) need a higher MCLK
+        if bclk_divider <= 2 {
+            bclk_divider = 8;
+            mclk = bclk * bclk_divider;
+        }
         let mut mclk_divider = sclk / mclk;
 
         let mut ma: u32;
@@ -2064,7 +2554,7 @@ mod private {
 pub mod asynch {
     use embedded_dma::{ReadBuffer, WriteBuffer};
 
-    use super::{Error, I2sRx, I2sTx, RegisterAccess};
+    use super::{Error, I2sDuplexTransfer, I2sRx, I2sTx, RegisterAccess};
     use crate::{
         dma::{
             asynch::{DmaRxDoneChFuture, DmaRxFuture, DmaTxDoneChFuture, DmaTxFuture},
@@ -2336,4 +2826,51 @@ pub mod asynch {
             Ok(self.i2s_rx.rx_channel.pop(&mut data[..to_rcv])?)
         }
     }
+    impl<'t, 'd, T, CH> I2sDuplexTransfer<'t, 'd, T, CH, Async>
+    where
+        T: RegisterAccess,
+        CH: ChannelTypes,
+    {
+        /// How many bytes can be pushed into the TX buffer.
+        /// Will wait for more than 0 bytes available.
+        pub async fn tx_available_async(&mut self) -> Result<usize, Error> {
+            loop {
+                let res = self.tx_available();
+
+                if res != 0 {
+                    break Ok(res);
+                }
+
+                DmaTxDoneChFuture::new(&mut self.duplex.tx.tx_channel).await?
+            }
+        }
+
+        /// How many bytes can be popped from the RX buffer.
+        /// Will wait for more than 0 bytes available.
+        pub async fn rx_available_async(&mut self) -> Result<usize, Error> {
+            loop {
+                let res = self.rx_available();
+
+                if res != 0 {
+                    break Ok(res);
+                }
+
+                DmaRxDoneChFuture::new(&mut self.duplex.rx.rx_channel).await?;
+            }
+        }
+
+        /// Push bytes into the TX buffer.
+        pub async fn push_async(&mut self, data: &[u8]) -> Result<usize, Error> {
+            let avail = self.tx_available_async().await?;
+            let to_send = usize::min(avail, data.len());
+            self.push(&data[..to_send])
+        }
+
+        /// Pop bytes from the RX buffer.
+        pub async fn pop_async(&mut self, data: &mut [u8]) -> Result<usize, Error> {
+            let avail = self.rx_available_async().await?;
+            let to_rcv = usize::min(avail, data.len());
+            self.pop(&mut data[..to_rcv])
+        }
+    }
 }
@@ -8,6 +8,12 @@
 //! The module allows precise and flexible control over LED lighting and other
 //! `Pulse-Width Modulation (PWM)` applications by offering configurable duty
 //! cycles and frequencies.
+//!
+//! Fades are run by the hardware: a fade changes the duty by `duty_scale`
+//! every `duty_cycle` PWM cycles, `duty_num` times. [`FadeStep`]s can be
+//! chained into sequences, e.g. to approximate a gamma curve, and a running
+//! fade can be stopped at its current duty. The end of a fade raises an
+//! interrupt, which the async API waits for.
 
 use super::timer::{TimerIFace, TimerSpeed};
 use crate::{
@@ -44,6 +50,34 @@ pub enum Error {
     Fade(FadeError),
 }
 
+/// A single step of a hardware fade
+///
+/// The duty changes by `scale` every `cycles` PWM cycles, `num` times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FadeStep {
+    /// Increase the duty, decrease it otherwise
+    pub increase: bool,
+    /// Number of duty changes, 1..=1023
+    pub num: u16,
+    /// PWM cycles between the duty changes, 1..=1023
+    pub cycles: u16,
+    /// Change of the duty, 0..=1023
+    pub scale: u16,
+}
+
+impl FadeStep {
+    fn validate(&self) -> Result<(), Error> {
+        if self.num == 0 || self.num > 1023 || self.scale > 1023 {
+            return Err(Error::Fade(FadeError::DutyRange));
+        }
+        if self.cycles == 0 || self.cycles > 1023 {
+            return Err(Error::Fade(FadeError::Duration));
+        }
+        Ok(())
+    }
+}
+
 /// Channel number
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -101,6 +135,26 @@ where
 
     /// Check whether a duty-cycle fade is running
     fn is_duty_fade_running(&self) -> bool;
+
+    /// Start a fade step from the current duty
+    fn start_fade_step(&self, step: FadeStep) -> Result<(), Error>;
+
+    /// Stop a running fade, keeping the current duty
+    fn stop_duty_fade(&self);
+
+    /// Enable the fade end interrupt of the channel
+    ///
+    /// The interrupt stays raised until the next fade starts, a handler has
+    /// to acknowledge it with [`Self::acknowledge_fade_end`].
+    fn listen_fade_end(&self);
+
+    /// Disable the fade end interrupt of the channel
+    fn unlisten_fade_end(&self);
+
+    /// Acknowledge the fade end interrupt of the channel
+    ///
+    /// Returns whether the interrupt was pending, which disables it.
+    fn acknowledge_fade_end(&self) -> bool;
 }
 
 /// Channel HW interface
@@ -125,6 +179,15 @@ pub trait ChannelHW<O: OutputPin> {
 
     /// Check whether a duty-cycle fade is running HW
     fn is_duty_fade_running_hw(&self) -> bool;
+
+    /// Get the current duty HW, including a running fade
+    fn get_duty_hw(&self) -> u32;
+
+    /// Enable or disable the fade end interrupt HW
+    fn set_fade_end_interrupt_hw(&self, enable: bool);
+
+    /// Check whether the fade end interrupt is enabled and raised HW
+    fn is_fade_end_interrupt_pending_hw(&self) -> bool;
 }
 
 /// Channel struct
@@ -207,39 +270,105 @@ where
         end_duty_pct: u8,
         duration_ms: u16,
     ) -> Result<(), Error> {
-        let duty_exp;
-        let frequency;
         if start_duty_pct > 100u8 {
             return Err(Error::Fade(FadeError::StartDuty));
         }
         if end_duty_pct > 100u8 {
             return Err(Error::Fade(FadeError::EndDuty));
         }
-        if let Some(timer) = self.timer {
-            if let Some(timer_duty) = timer.get_duty() {
-                if timer.get_frequency() > 0 {
-                    duty_exp = timer_duty as u32;
-                    frequency = timer.get_frequency();
-                } else {
-                    return Err(Error::Timer);
-                }
-            } else {
-                return Err(Error::Timer);
-            }
-        } else {
-            return Err(Error::Channel);
-        }
 
-        let duty_range = (1u32 << duty_exp) - 1;
+        let duty_range = self.fade_duty_range()?;
         let start_duty_value = (duty_range * start_duty_pct as u32) / 100;
         let end_duty_value = (duty_range * end_duty_pct as u32) / 100;
 
+        self.start_duty_fade_value(start_duty_value, end_duty_value, duration_ms)
+    }
+
+    fn is_duty_fade_running(&self) -> bool {
+        self.is_duty_fade_running_hw()
+    }
+
+    /// Start a fade step from the current duty.
+    ///
+    /// The next step of a sequence can be started once this one ended, see
+    /// [`Self::is_duty_fade_running`] and [`Self::listen_fade_end`].
+    fn start_fade_step(&self, step: FadeStep) -> Result<(), Error> {
+        if self.timer.is_none() {
+            return Err(Error::Channel);
+        }
+        step.validate()?;
+
+        self.start_duty_fade_hw(
+            self.get_duty_hw(),
+            step.increase,
+            step.num,
+            step.cycles,
+            step.scale,
+        );
+
+        Ok(())
+    }
+
+    /// Stop a running fade, keeping the current duty.
+    ///
+    /// The fade ends with the next PWM cycle.
+    fn stop_duty_fade(&self) {
+        self.set_duty_hw(self.get_duty_hw());
+    }
+
+    fn listen_fade_end(&self) {
+        self.set_fade_end_interrupt_hw(true);
+    }
+
+    fn unlisten_fade_end(&self) {
+        self.set_fade_end_interrupt_hw(false);
+    }
+
+    /// Acknowledge the fade end interrupt of the channel.
+    ///
+    /// The raw interrupt is kept, it tells [`Self::is_duty_fade_running`]
+    /// that the fade ended. Listen again before starting the next fade.
+    fn acknowledge_fade_end(&self) -> bool {
+        let pending = self.is_fade_end_interrupt_pending_hw();
+        if pending {
+            self.set_fade_end_interrupt_hw(false);
+        }
+        pending
+    }
+}
+
+impl<'a, S: TimerSpeed, O: OutputPin> Channel<'a, S, O>
+where
+    Channel<'a, S, O>: ChannelHW<O>,
+{
+    /// Maximum duty value of a fade, the timer has to be configured
+    fn fade_duty_range(&self) -> Result<u32, Error> {
+        let timer = self.timer.ok_or(Error::Channel)?;
+        let duty_exp = timer.get_duty().ok_or(Error::Timer)? as u32;
+
+        Ok((1u32 << duty_exp) - 1)
+    }
+
+    /// Start a duty fade between two duty values.
+    fn start_duty_fade_value(
+        &self,
+        start_duty_value: u32,
+        end_duty_value: u32,
+        duration_ms: u16,
+    ) -> Result<(), Error> {
+        let timer = self.timer.ok_or(Error::Channel)?;
+        let frequency = timer.get_frequency();
+        if frequency == 0 {
+            return Err(Error::Timer);
+        }
+
         // NB: since we do the multiplication first here, there's no loss of
         // precision from using milliseconds instead of (e.g.) nanoseconds.
         let pwm_cycles = (duration_ms as u32) * frequency / 1000;
 
         let abs_duty_diff = end_duty_value.abs_diff(start_duty_value);
-        let duty_steps: u32 = u16::try_from(abs_duty_diff).unwrap_or(65535).into();
+        // a fade to the same duty still takes one step, ending the fade
+        let duty_steps: u32 = u16::try_from(abs_duty_diff).unwrap_or(65535).max(1).into();
         // This conversion may fail if duration_ms is too big, and if either
         // duty_steps gets truncated, or the fade is over a short range of duty
         // percentages, so it's too small.  Returning an Err in either case is
@@ -273,8 +402,105 @@ where
         Ok(())
     }
 
-    fn is_duty_fade_running(&self) -> bool {
-        self.is_duty_fade_running_hw()
+    /// Index of the channel among the fade end interrupts
+    #[cfg(feature = "async")]
+    fn fade_end_index(&self) -> usize {
+        #[cfg(esp32)]
+        let offset = if S::IS_HS { 0 } else { 8 };
+        #[cfg(not(esp32))]
+        let offset = 0;
+
+        offset + self.number as usize
+    }
+}
+
+#[cfg(feature = "async")]
+mod asynch {
+    use core::{
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use super::*;
+    use crate::ledc::asynch::FADE_END_WAKERS;
+
+    /// Resolves once the fade of the channel ended
+    struct FadeEndFuture<'c, 'a, S: TimerSpeed, O: OutputPin> {
+        channel: &'c Channel<'a, S, O>,
+    }
+
+    impl<'c, 'a, S: TimerSpeed, O: OutputPin> Future for FadeEndFuture<'c, 'a, S, O>
+    where
+        Channel<'a, S, O>: ChannelHW<O>,
+    {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            FADE_END_WAKERS[self.channel.fade_end_index()].register(cx.waker());
+
+            if !self.channel.is_duty_fade_running_hw() {
+                return Poll::Ready(());
+            }
+
+            // the interrupt handler disables the interrupt once it fired
+            self.channel.set_fade_end_interrupt_hw(true);
+            if self.channel.is_duty_fade_running_hw() {
+                Poll::Pending
+            } else {
+                Poll::Ready(())
+            }
+        }
+    }
+
+    impl<'a, S: TimerSpeed, O: OutputPin> Channel<'a, S, O>
+    where
+        Channel<'a, S, O>: ChannelHW<O>,
+    {
+        /// Wait for the end of the running fade
+        ///
+        /// The LEDC has to be created with
+        /// [`Ledc::new_async`](crate::ledc::Ledc::new_async).
+        pub async fn wait_for_fade_end_async(&self) {
+            FadeEndFuture { channel: self }.await
+        }
+
+        /// Fade from the current duty to `end_duty_pct` % in `duration_ms`
+        /// and wait for the end of the fade
+        ///
+        /// The constraints of
+        /// [`ChannelIFace::start_duty_fade`] apply.
+        pub async fn fade_to_async(&self, end_duty_pct: u8, duration_ms: u16) -> Result<(), Error> {
+            if end_duty_pct > 100u8 {
+                return Err(Error::Fade(FadeError::EndDuty));
+            }
+
+            let duty_range = self.fade_duty_range()?;
+            let end_duty_value = (duty_range * end_duty_pct as u32) / 100;
+            let start_duty_value = self.get_duty_hw().min(duty_range);
+
+            self.start_duty_fade_value(start_duty_value, end_duty_value, duration_ms)?;
+            self.wait_for_fade_end_async().await;
+
+            Ok(())
+        }
+
+        /// Run the fade steps one after another from the current duty and
+        /// wait for the end of the last one
+        ///
+        /// The steps are validated before the first one starts.
+        pub async fn fade_steps_async(&self, steps: &[FadeStep]) -> Result<(), Error> {
+            for step in steps {
+                step.validate()?;
+            }
+
+            for step in steps {
+                self.start_fade_step(*step)?;
+                self.wait_for_fade_end_async().await;
+            }
+
+            Ok(())
+        }
     }
 }
 
@@ -697,4 +923,76 @@ where
             .duty_chng_end_ch(self.number as u8)
             .bit_is_clear()
     }
+
+    #[cfg(esp32)]
+    fn get_duty_hw(&self) -> u32 {
+        let duty = if S::IS_HS {
+            self.ledc
+                .hsch(self.number as usize)
+                .duty_r()
+                .read()
+                .duty_r()
+                .bits()
+        } else {
+            self.ledc
+                .lsch(self.number as usize)
+                .duty_r()
+                .read()
+                .duty_r()
+                .bits()
+        };
+        duty >> 4
+    }
+
+    #[cfg(not(esp32))]
+    fn get_duty_hw(&self) -> u32 {
+        self.ledc
+            .ch(self.number as usize)
+            .duty_r()
+            .read()
+            .duty_r()
+            .bits()
+            >> 4
+    }
+
+    #[cfg(esp32)]
+    fn set_fade_end_interrupt_hw(&self, enable: bool) {
+        critical_section::with(|_| {
+            self.ledc.int_ena().modify(|_, w| {
+                if S::IS_HS {
+                    w.duty_chng_end_hsch(self.number as u8).bit(enable)
+                } else {
+                    w.duty_chng_end_lsch(self.number as u8).bit(enable)
+                }
+            });
+        });
+    }
+
+    #[cfg(not(esp32))]
+    fn set_fade_end_interrupt_hw(&self, enable: bool) {
+        critical_section::with(|_| {
+            self.ledc
+                .int_ena()
+                .modify(|_, w| w.duty_chng_end_ch(self.number as u8).bit(enable));
+        });
+    }
+
+    #[cfg(esp32)]
+    fn is_fade_end_interrupt_pending_hw(&self) -> bool {
+        let reg = self.ledc.int_st().read();
+        if S::IS_HS {
+            reg.duty_chng_end_hsch(self.number as u8).bit_is_set()
+        } else {
+            reg.duty_chng_end_lsch(self.number as u8).bit_is_set()
+        }
+    }
+
+    #[cfg(not(esp32))]
+    fn is_fade_end_interrupt_pending_hw(&self) -> bool {
+        self.ledc
+            .int_st()
+            .read()
+            .duty_chng_end_ch(self.number as u8)
+            .bit_is_set()
+    }
 }
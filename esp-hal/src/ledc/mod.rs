@@ -1,8 +1,13 @@
 //! # LEDC (LED PWM Controller) peripheral control
 //!
-//! Currently only supports fixed-frequency output. Interrupts are not currently
-//! implemented. High Speed channels are available for the ESP32 only, while Low
-//! Speed channels are available for all supported chips.
+//! Currently only supports fixed-frequency output. High Speed channels are
+//! available for the ESP32 only, while Low Speed channels are available for all
+//! supported chips.
+//!
+//! Channels fade their duty in hardware, see [`channel::FadeStep`]. An LEDC
+//! created with [`Ledc::new_async`] handles the fade end interrupts, so that
+//! `Channel::fade_to_async` and `Channel::fade_steps_async` can wait for the
+//! fades without blocking.
 //!
 //! # LowSpeed Example:
 //!
@@ -59,7 +64,7 @@
 //!
 //! # Unsupported
 //! - Source clock selection
-//! - Interrupts
+//! - Timer overflow interrupts
 
 use self::{
     channel::Channel,
@@ -68,7 +73,9 @@ use self::{
 use crate::{
     clock::Clocks,
     gpio::OutputPin,
+    interrupt::InterruptHandler,
     peripheral::{Peripheral, PeripheralRef},
+    peripherals::Interrupt,
     system::{Peripheral as PeripheralEnable, PeripheralClockControl},
 };
 
@@ -125,6 +132,29 @@ impl<'d> Ledc<'d> {
         }
     }
 
+    /// Return a new LEDC which handles the fade end interrupts needed by the
+    /// async API, like [`Channel::fade_to_async`].
+    #[cfg(feature = "async")]
+    pub fn new_async(
+        _instance: impl Peripheral<P = crate::peripherals::LEDC> + 'd,
+        clock_control_config: &'d Clocks,
+    ) -> Self {
+        let mut ledc = Self::new(_instance, clock_control_config);
+        ledc.set_interrupt_handler(asynch::ledc_interrupt_handler);
+        ledc
+    }
+
+    /// Sets the interrupt handler, enables it with the given priority
+    ///
+    /// Interrupts are not enabled at the peripheral level here, see
+    /// [`channel::ChannelIFace::listen_fade_end`].
+    pub fn set_interrupt_handler(&mut self, handler: InterruptHandler) {
+        unsafe {
+            crate::interrupt::bind_interrupt(Interrupt::LEDC, handler.handler());
+            crate::interrupt::enable(Interrupt::LEDC, handler.priority()).unwrap();
+        }
+    }
+
     /// Set global slow clock source
     #[cfg(esp32)]
     pub fn set_global_slow_clock(&mut self, _clock_source: LSGlobalClkSource) {
@@ -178,3 +208,48 @@ impl<'d> Ledc<'d> {
         Channel::new(number, output_pin)
     }
 }
+
+#[cfg(feature = "async")]
+mod asynch {
+    use embassy_sync::waitqueue::AtomicWaker;
+    use procmacros::handler;
+
+    /// Position of the first fade end interrupt in the interrupt registers
+    #[cfg(esp32)]
+    const FADE_END_INT_OFFSET: u32 = 8;
+    #[cfg(not(esp32))]
+    const FADE_END_INT_OFFSET: u32 = 4;
+
+    /// Number of fade end interrupts, the ones of the high speed channels come
+    /// first on the ESP32
+    #[cfg(esp32)]
+    const NUM_FADE_END_INTS: usize = 16;
+    #[cfg(any(esp32s2, esp32s3))]
+    const NUM_FADE_END_INTS: usize = 8;
+    #[cfg(any(esp32c2, esp32c3, esp32c6, esp32h2))]
+    const NUM_FADE_END_INTS: usize = 6;
+
+    const INIT: AtomicWaker = AtomicWaker::new();
+    pub(super) static FADE_END_WAKERS: [AtomicWaker; NUM_FADE_END_INTS] = [INIT; NUM_FADE_END_INTS];
+
+    #[handler]
+    pub(super) fn ledc_interrupt_handler() {
+        let ledc = unsafe { &*crate::peripherals::LEDC::ptr() };
+
+        // disable the pending fade end interrupts, the futures enable them
+        // again while a fade is running
+        let mask = ((1 << NUM_FADE_END_INTS) - 1) << FADE_END_INT_OFFSET;
+        let pending = critical_section::with(|_| {
+            let pending = ledc.int_st().read().bits() & mask;
+            ledc.int_ena()
+                .modify(|r, w| unsafe { w.bits(r.bits() & !pending) });
+            pending
+        });
+
+        for (index, waker) in FADE_END_WAKERS.iter().enumerate() {
+            if pending & (1 << (FADE_END_INT_OFFSET + index as u32)) != 0 {
+                waker.wake();
+            }
+        }
+    }
+}
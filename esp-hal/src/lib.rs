@@ -108,6 +108,7 @@ pub mod ecc;
 pub mod embassy;
 #[cfg(soc_etm)]
 pub mod etm;
+pub mod flash;
 #[cfg(gpio)]
 pub mod gpio;
 #[cfg(hmac)]
@@ -0,0 +1,401 @@
+//! # MCPWM peripheral - capture module
+//!
+//! ## Overview
+//! The `capture` module is part of the `MCPWM` peripheral driver for `ESP`
+//! chips. It timestamps edges of external signals, for example to measure
+//! hall-sensor or encoder pulse timings.
+//!
+//! Every MCPWM peripheral has a single 32-bit [`CaptureTimer`] shared by its
+//! three [`CaptureChannel`]s. When a selected edge is detected on the input of
+//! a channel, the current value of the capture timer is latched together with
+//! the polarity of the edge.
+//!
+//! ## Example
+//! ```no_run
+//! # use esp_hal::{mcpwm, prelude::*};
+//! use mcpwm::{capture::CaptureConfig, McPwm, PeripheralClockConfig};
+//!
+//! let clock_cfg = PeripheralClockConfig::with_frequency(&clocks, 40.MHz()).unwrap();
+//! let mut mcpwm = McPwm::new(peripherals.MCPWM0, clock_cfg);
+//!
+//! mcpwm.capture_timer.start();
+//! let mut hall = mcpwm.capture0.with_pin(pin, CaptureConfig::BOTH_EDGES);
+//!
+//! let first = hall.wait_for_capture();
+//! let second = hall.wait_for_capture();
+//! let ticks = second.value.wrapping_sub(first.value);
+//! ```
+
+use core::marker::PhantomData;
+
+use crate::{
+    gpio::InputPin,
+    mcpwm::{timer::SyncSource, PwmPeripheral},
+    peripheral::{Peripheral, PeripheralRef},
+    private,
+};
+
+/// Bit position of the CAP0 interrupt in the INT_* registers
+const CAPTURE_INT_OFFSET: u32 = 27;
+
+/// The capture timer of a MCPWM peripheral
+///
+/// The timer counts at the APB clock on ESP32 and ESP32-S3 and at the
+/// peripheral clock ([`PeripheralClockConfig::frequency`]) on ESP32-C6 and
+/// ESP32-H2.
+///
+/// [`PeripheralClockConfig::frequency`]: super::PeripheralClockConfig::frequency
+pub struct CaptureTimer<PWM> {
+    phantom: PhantomData<PWM>,
+}
+
+impl<PWM: PwmPeripheral> CaptureTimer<PWM> {
+    pub(super) fn new() -> Self {
+        CaptureTimer {
+            phantom: PhantomData,
+        }
+    }
+
+    /// Start the capture timer
+    pub fn start(&mut self) {
+        // SAFETY:
+        // We only write to our CAP_TIMER_CFG register
+        let block = unsafe { &*PWM::block() };
+        block
+            .cap_timer_cfg()
+            .modify(|_, w| w.cap_timer_en().set_bit());
+    }
+
+    /// Stop the capture timer
+    pub fn stop(&mut self) {
+        // SAFETY:
+        // We only write to our CAP_TIMER_CFG register
+        let block = unsafe { &*PWM::block() };
+        block
+            .cap_timer_cfg()
+            .modify(|_, w| w.cap_timer_en().clear_bit());
+    }
+
+    /// Select the sync input which reloads the capture timer with the
+    /// value set by [`CaptureTimer::set_sync_phase`]
+    ///
+    /// `None` disables the hardware sync.
+    pub fn set_sync_source(&mut self, source: Option<SyncSource>) {
+        // SAFETY:
+        // We only write to our CAP_TIMER_CFG register
+        let block = unsafe { &*PWM::block() };
+        block.cap_timer_cfg().modify(|_, w| match source {
+            Some(source) => unsafe {
+                w.cap_synci_sel().bits(source as u8);
+                w.cap_synci_en().set_bit()
+            },
+            None => w.cap_synci_en().clear_bit(),
+        });
+    }
+
+    /// Set the value loaded into the capture timer on a sync event
+    pub fn set_sync_phase(&mut self, phase: u32) {
+        // SAFETY:
+        // We only write to our CAP_TIMER_PHASE register
+        let block = unsafe { &*PWM::block() };
+        block.cap_timer_phase().write(|w| unsafe { w.bits(phase) });
+    }
+
+    /// Reload the capture timer with the sync phase now
+    pub fn software_sync(&mut self) {
+        // SAFETY:
+        // We only write to our CAP_TIMER_CFG register
+        let block = unsafe { &*PWM::block() };
+        block
+            .cap_timer_cfg()
+            .modify(|_, w| w.cap_sync_sw().set_bit());
+    }
+}
+
+/// Edges which trigger a capture
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum CaptureEdgeSelect {
+    /// Capture on falling edges
+    Falling = 0b01,
+    /// Capture on rising edges
+    Rising  = 0b10,
+    /// Capture on both edges
+    Both    = 0b11,
+}
+
+/// Polarity of the edge which triggered a capture
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CaptureEdge {
+    /// Rising edge
+    Rising,
+    /// Falling edge
+    Falling,
+}
+
+/// A captured timer value
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Capture {
+    /// Value of the capture timer at the time of the edge
+    pub value: u32,
+    /// Polarity of the edge
+    pub edge: CaptureEdge,
+}
+
+/// Configuration of a capture channel
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CaptureConfig {
+    /// Edges which trigger a capture
+    pub edge: CaptureEdgeSelect,
+    /// Only capture every `prescaler + 1`th rising edge
+    pub prescaler: u8,
+    /// Invert the input signal before edge detection
+    pub invert: bool,
+}
+
+impl CaptureConfig {
+    /// Capture on every rising edge
+    pub const RISING_EDGE: Self = Self::new(CaptureEdgeSelect::Rising);
+    /// Capture on every falling edge
+    pub const FALLING_EDGE: Self = Self::new(CaptureEdgeSelect::Falling);
+    /// Capture on every edge
+    pub const BOTH_EDGES: Self = Self::new(CaptureEdgeSelect::Both);
+
+    /// Get a configuration capturing on the given edges, without prescaler
+    /// and inversion
+    pub const fn new(edge: CaptureEdgeSelect) -> Self {
+        CaptureConfig {
+            edge,
+            prescaler: 0,
+            invert: false,
+        }
+    }
+
+    /// Set the prescaler
+    pub const fn with_prescaler(mut self, prescaler: u8) -> Self {
+        self.prescaler = prescaler;
+        self
+    }
+
+    /// Invert the input signal
+    pub const fn with_invert(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+}
+
+/// A MCPWM capture channel
+pub struct CaptureChannel<const CH: u8, PWM> {
+    phantom: PhantomData<PWM>,
+}
+
+impl<const CH: u8, PWM: PwmPeripheral> CaptureChannel<CH, PWM> {
+    pub(super) fn new() -> Self {
+        CaptureChannel {
+            phantom: PhantomData,
+        }
+    }
+
+    /// Use the channel with the given pin and configuration
+    pub fn with_pin<'d, Pin: InputPin>(
+        self,
+        pin: impl Peripheral<P = Pin> + 'd,
+        config: CaptureConfig,
+    ) -> CapturePin<'d, Pin, PWM, CH> {
+        CapturePin::new(pin, config)
+    }
+}
+
+/// A pin connected to a MCPWM capture channel
+pub struct CapturePin<'d, Pin, PWM, const CH: u8> {
+    _pin: PeripheralRef<'d, Pin>,
+    phantom: PhantomData<PWM>,
+}
+
+impl<'d, Pin: InputPin, PWM: PwmPeripheral, const CH: u8> CapturePin<'d, Pin, PWM, CH> {
+    fn new(pin: impl Peripheral<P = Pin> + 'd, config: CaptureConfig) -> Self {
+        crate::into_ref!(pin);
+        pin.set_to_input(private::Internal);
+        pin.connect_input_to_peripheral(PWM::capture_signal::<CH>(), private::Internal);
+
+        let mut capture = CapturePin {
+            _pin: pin,
+            phantom: PhantomData,
+        };
+        capture.set_config(config);
+        capture.clear();
+        capture
+    }
+
+    /// Change the configuration and enable the channel
+    pub fn set_config(&mut self, config: CaptureConfig) {
+        // SAFETY:
+        // We only write to our CAP_CHx_CFG register
+        let block = unsafe { &*PWM::block() };
+        block.cap_ch_cfg(CH as usize).write(|w| unsafe {
+            w.mode().bits(config.edge as u8);
+            w.prescale().bits(config.prescaler);
+            w.in_invert().bit(config.invert);
+            w.en().set_bit()
+        });
+    }
+
+    /// Capture the current timer value now
+    ///
+    /// The capture is reported as a rising edge.
+    pub fn trigger_software_capture(&mut self) {
+        // SAFETY:
+        // We only write to our CAP_CHx_CFG register
+        let block = unsafe { &*PWM::block() };
+        block
+            .cap_ch_cfg(CH as usize)
+            .modify(|_, w| w.sw().set_bit());
+    }
+
+    /// Returns the latest capture if there has been one since the last call
+    pub fn capture(&mut self) -> Option<Capture> {
+        // SAFETY:
+        // We only read the bit of our channel
+        let block = unsafe { &*PWM::block() };
+        if block.int_raw().read().bits() & Self::int_bit() == 0 {
+            return None;
+        }
+
+        let capture = Self::read_capture();
+        self.clear();
+        Some(capture)
+    }
+
+    /// Wait for the next capture
+    pub fn wait_for_capture(&mut self) -> Capture {
+        loop {
+            if let Some(capture) = self.capture() {
+                return capture;
+            }
+        }
+    }
+
+    fn read_capture() -> Capture {
+        // SAFETY:
+        // We only read from our CAP_CHx and the CAP_STATUS register
+        let block = unsafe { &*PWM::block() };
+        let value = block.cap_ch(CH as usize).read().bits();
+        let edge = if block.cap_status().read().bits() & (1 << CH) == 0 {
+            CaptureEdge::Rising
+        } else {
+            CaptureEdge::Falling
+        };
+
+        Capture { value, edge }
+    }
+
+    fn clear(&mut self) {
+        // SAFETY:
+        // We only clear the bit of our channel
+        let block = unsafe { &*PWM::block() };
+        block
+            .int_clr()
+            .write(|w| unsafe { w.bits(Self::int_bit()) });
+    }
+
+    const fn int_bit() -> u32 {
+        1 << (CAPTURE_INT_OFFSET + CH as u32)
+    }
+}
+
+#[cfg(feature = "async")]
+mod asynch {
+    use core::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use embassy_sync::waitqueue::AtomicWaker;
+
+    use super::*;
+
+    const INIT: AtomicWaker = AtomicWaker::new();
+    static WAKERS: [[AtomicWaker; 3]; super::super::NUM_MCPWM] =
+        [[INIT; 3]; super::super::NUM_MCPWM];
+
+    /// Wake the tasks waiting for the given capture interrupts
+    pub(in crate::mcpwm) fn on_interrupt(number: usize, pending: u32) {
+        for (ch, waker) in WAKERS[number].iter().enumerate() {
+            if pending & (1 << (CAPTURE_INT_OFFSET + ch as u32)) != 0 {
+                waker.wake();
+            }
+        }
+    }
+
+    struct CaptureFuture<PWM, const CH: u8> {
+        phantom: PhantomData<PWM>,
+    }
+
+    impl<PWM: PwmPeripheral, const CH: u8> CaptureFuture<PWM, CH> {
+        fn new() -> Self {
+            // SAFETY:
+            // We only set the bit of our channel
+            let block = unsafe { &*PWM::block() };
+            critical_section::with(|_| {
+                block.int_ena().modify(|r, w| unsafe {
+                    w.bits(r.bits() | 1 << (CAPTURE_INT_OFFSET + CH as u32))
+                });
+            });
+
+            CaptureFuture {
+                phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<PWM: PwmPeripheral, const CH: u8> core::future::Future for CaptureFuture<PWM, CH> {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            WAKERS[PWM::number()][CH as usize].register(cx.waker());
+
+            // the interrupt handler disables the interrupt once it fired
+            let block = unsafe { &*PWM::block() };
+            if block.int_ena().read().bits() & 1 << (CAPTURE_INT_OFFSET + CH as u32) == 0 {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        }
+    }
+
+    impl<PWM: PwmPeripheral, const CH: u8> Drop for CaptureFuture<PWM, CH> {
+        fn drop(&mut self) {
+            // SAFETY:
+            // We only clear the bit of our channel
+            let block = unsafe { &*PWM::block() };
+            critical_section::with(|_| {
+                block.int_ena().modify(|r, w| unsafe {
+                    w.bits(r.bits() & !(1 << (CAPTURE_INT_OFFSET + CH as u32)))
+                });
+            });
+        }
+    }
+
+    impl<'d, Pin: InputPin, PWM: PwmPeripheral, const CH: u8> CapturePin<'d, Pin, PWM, CH> {
+        /// Wait for the next capture
+        ///
+        /// The MCPWM peripheral has to be created with
+        /// [`McPwm::new_async`](super::super::McPwm::new_async).
+        pub async fn wait_for_capture_async(&mut self) -> Capture {
+            loop {
+                if let Some(capture) = self.capture() {
+                    return capture;
+                }
+                CaptureFuture::<PWM, CH>::new().await;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+pub(super) use asynch::on_interrupt;
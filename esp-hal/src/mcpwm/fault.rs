@@ -0,0 +1,478 @@
+//! # MCPWM peripheral - fault module
+//!
+//! ## Overview
+//! The `fault` module is part of the `MCPWM` peripheral driver for `ESP`
+//! chips. It detects fault conditions signalled by external circuitry (for
+//! example an over-current comparator) and lets every
+//! [`Operator`](super::operator::Operator) force its outputs into a safe state.
+//!
+//! Every MCPWM peripheral has three fault inputs (F0 to F2) which can be
+//! connected to GPIOs with [`FaultInput::with_pin`]. The fault handler of each
+//! operator reacts to these inputs or to a software forced fault with
+//! * a cycle-by-cycle trip, which is cleared automatically at the next timer
+//!   zero and/or period event once the fault is gone, or
+//! * a one-shot trip, which stays active until it is cleared by software.
+//!
+//! The reaction is configured with [`FaultConfig`] on the [`FaultHandler`] of
+//! the operator, which is available as a field of
+//! [`McPwm`](super::McPwm). Since both outputs of an operator share the fault
+//! handler, one configuration can shut down both legs of a half-bridge at once.
+//!
+//! ## Example
+//! ```no_run
+//! # use esp_hal::{mcpwm, prelude::*};
+//! use mcpwm::{
+//!     fault::{FaultConfig, FaultPolarity, FaultSource, TripActions},
+//!     operator::{DeadTimeCfg, PwmPinConfig},
+//! };
+//!
+//! let over_current = mcpwm.fault0.with_pin(fault_pin, FaultPolarity::ActiveHigh);
+//!
+//! let pins = mcpwm.operator0.with_linked_pins(
+//!     pin_a,
+//!     PwmPinConfig::UP_DOWN_ACTIVE_HIGH,
+//!     pin_b,
+//!     PwmPinConfig::EMPTY,
+//!     DeadTimeCfg::new_ahc(),
+//! );
+//!
+//! // shut down both legs of the half-bridge on over-current
+//! let mut fault_handler = mcpwm.fault_handler0;
+//! fault_handler.set_config(
+//!     FaultConfig::new()
+//!         .with_one_shot(FaultSource::Fault0 | FaultSource::Software)
+//!         .with_actions_a(TripActions::FORCE_LOW)
+//!         .with_actions_b(TripActions::FORCE_LOW),
+//! );
+//!
+//! // ... after the fault has been handled
+//! if !over_current.is_active() {
+//!     fault_handler.clear_one_shot_trip();
+//! }
+//! ```
+
+use core::marker::PhantomData;
+
+use enumset::{EnumSet, EnumSetType};
+
+use crate::{
+    gpio::InputPin,
+    mcpwm::PwmPeripheral,
+    peripheral::{Peripheral, PeripheralRef},
+    private,
+};
+
+/// Bit position of the FAULT0 interrupt in the INT_* registers
+const FAULT_INT_OFFSET: u32 = 9;
+
+/// Level of a fault input which signals a fault
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FaultPolarity {
+    /// A low level signals a fault
+    ActiveLow,
+    /// A high level signals a fault
+    ActiveHigh,
+}
+
+/// A MCPWM fault input
+pub struct FaultInput<const F: u8, PWM> {
+    phantom: PhantomData<PWM>,
+}
+
+impl<const F: u8, PWM: PwmPeripheral> FaultInput<F, PWM> {
+    pub(super) fn new() -> Self {
+        FaultInput {
+            phantom: PhantomData,
+        }
+    }
+
+    /// Use the fault input with the given pin and polarity
+    pub fn with_pin<'d, Pin: InputPin>(
+        self,
+        pin: impl Peripheral<P = Pin> + 'd,
+        polarity: FaultPolarity,
+    ) -> FaultPin<'d, Pin, PWM, F> {
+        FaultPin::new(pin, polarity)
+    }
+}
+
+/// A pin connected to a MCPWM fault input
+pub struct FaultPin<'d, Pin, PWM, const F: u8> {
+    _pin: PeripheralRef<'d, Pin>,
+    phantom: PhantomData<PWM>,
+}
+
+impl<'d, Pin: InputPin, PWM: PwmPeripheral, const F: u8> FaultPin<'d, Pin, PWM, F> {
+    fn new(pin: impl Peripheral<P = Pin> + 'd, polarity: FaultPolarity) -> Self {
+        crate::into_ref!(pin);
+        pin.set_to_input(private::Internal);
+        pin.connect_input_to_peripheral(PWM::fault_signal::<F>(), private::Internal);
+
+        // SAFETY:
+        // We only modify the bits of our fault input
+        let block = unsafe { &*PWM::block() };
+        critical_section::with(|_| {
+            block.fault_detect().modify(|r, w| unsafe {
+                let mut bits = r.bits() | 1 << F;
+                match polarity {
+                    FaultPolarity::ActiveLow => bits &= !(1 << (3 + F)),
+                    FaultPolarity::ActiveHigh => bits |= 1 << (3 + F),
+                }
+                w.bits(bits)
+            });
+        });
+
+        FaultPin {
+            _pin: pin,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns `true` while the fault input signals a fault
+    pub fn is_active(&self) -> bool {
+        // SAFETY:
+        // We only read from the FAULT_DETECT register
+        let block = unsafe { &*PWM::block() };
+        block.fault_detect().read().bits() & 1 << (6 + F) != 0
+    }
+}
+
+/// A source of fault events for the fault handler of an operator
+#[derive(Debug, EnumSetType)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FaultSource {
+    /// Fault input F0
+    Fault0,
+    /// Fault input F1
+    Fault1,
+    /// Fault input F2
+    Fault2,
+    /// Faults forced by software, see [`FaultHandler::force_trip`]
+    Software,
+}
+
+/// Kind of a trip of the fault handler
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TripKind {
+    /// Cycle-by-cycle trip, cleared automatically once the fault is gone
+    CycleByCycle,
+    /// One-shot trip, stays active until cleared by software
+    OneShot,
+}
+
+/// Action applied to a PWM output while a trip is active
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum FaultAction {
+    /// Keep the output as generated
+    Nothing = 0,
+    /// Force the output low
+    Low     = 1,
+    /// Force the output high
+    High    = 2,
+    /// Toggle the output
+    Toggle  = 3,
+}
+
+/// Actions applied to one PWM output while a trip is active, depending on the
+/// trip kind and the counting direction of the timer
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TripActions {
+    /// Cycle-by-cycle trip while the timer is counting up
+    pub cbc_up: FaultAction,
+    /// Cycle-by-cycle trip while the timer is counting down
+    pub cbc_down: FaultAction,
+    /// One-shot trip while the timer is counting up
+    pub ost_up: FaultAction,
+    /// One-shot trip while the timer is counting down
+    pub ost_down: FaultAction,
+}
+
+impl TripActions {
+    /// Keep the output as generated on all trips
+    pub const NOTHING: Self = Self::all(FaultAction::Nothing);
+    /// Force the output low on all trips
+    pub const FORCE_LOW: Self = Self::all(FaultAction::Low);
+    /// Force the output high on all trips
+    pub const FORCE_HIGH: Self = Self::all(FaultAction::High);
+
+    /// Apply the same action on all trips
+    pub const fn all(action: FaultAction) -> Self {
+        TripActions {
+            cbc_up: action,
+            cbc_down: action,
+            ost_up: action,
+            ost_down: action,
+        }
+    }
+
+    const fn bits(&self) -> u32 {
+        (self.cbc_down as u32)
+            | (self.cbc_up as u32) << 2
+            | (self.ost_down as u32) << 4
+            | (self.ost_up as u32) << 6
+    }
+}
+
+/// Fault handling configuration of an operator
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FaultConfig {
+    /// Sources which cause a cycle-by-cycle trip
+    pub cycle_by_cycle: EnumSet<FaultSource>,
+    /// Sources which cause a one-shot trip
+    pub one_shot: EnumSet<FaultSource>,
+    /// Actions on the A output
+    pub actions_a: TripActions,
+    /// Actions on the B output
+    pub actions_b: TripActions,
+    /// Clear cycle-by-cycle trips when the timer equals zero
+    pub cbc_clear_on_zero: bool,
+    /// Clear cycle-by-cycle trips when the timer equals the period
+    pub cbc_clear_on_period: bool,
+}
+
+impl Default for FaultConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FaultConfig {
+    /// Get a configuration without any trip sources
+    ///
+    /// Cycle-by-cycle trips are cleared when the timer equals zero.
+    pub fn new() -> Self {
+        FaultConfig {
+            cycle_by_cycle: EnumSet::empty(),
+            one_shot: EnumSet::empty(),
+            actions_a: TripActions::NOTHING,
+            actions_b: TripActions::NOTHING,
+            cbc_clear_on_zero: true,
+            cbc_clear_on_period: false,
+        }
+    }
+
+    /// Set the sources which cause a cycle-by-cycle trip
+    pub fn with_cycle_by_cycle(mut self, sources: impl Into<EnumSet<FaultSource>>) -> Self {
+        self.cycle_by_cycle = sources.into();
+        self
+    }
+
+    /// Set the sources which cause a one-shot trip
+    pub fn with_one_shot(mut self, sources: impl Into<EnumSet<FaultSource>>) -> Self {
+        self.one_shot = sources.into();
+        self
+    }
+
+    /// Set the actions on the A output
+    pub fn with_actions_a(mut self, actions: TripActions) -> Self {
+        self.actions_a = actions;
+        self
+    }
+
+    /// Set the actions on the B output
+    pub fn with_actions_b(mut self, actions: TripActions) -> Self {
+        self.actions_b = actions;
+        self
+    }
+
+    /// Set the timer events which clear cycle-by-cycle trips
+    pub fn with_cbc_clear(mut self, on_zero: bool, on_period: bool) -> Self {
+        self.cbc_clear_on_zero = on_zero;
+        self.cbc_clear_on_period = on_period;
+        self
+    }
+}
+
+/// Trips currently active in the fault handler of an operator
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TripStatus {
+    /// A cycle-by-cycle trip is active
+    pub cycle_by_cycle: bool,
+    /// A one-shot trip is active
+    pub one_shot: bool,
+}
+
+fn source_bits(sources: EnumSet<FaultSource>) -> u32 {
+    // FHx_SW, FHx_F2, FHx_F1 and FHx_F0 are in reversed order
+    sources.iter().fold(0, |bits, source| {
+        bits | match source {
+            FaultSource::Software => 0b0001,
+            FaultSource::Fault2 => 0b0010,
+            FaultSource::Fault1 => 0b0100,
+            FaultSource::Fault0 => 0b1000,
+        }
+    })
+}
+
+/// The fault handler of a MCPWM operator
+///
+/// It decides which fault sources trip the operator and how both of its
+/// outputs react, so a single configuration covers both legs of a half-bridge
+/// regardless of how the outputs are connected to pins.
+pub struct FaultHandler<const OP: u8, PWM> {
+    phantom: PhantomData<PWM>,
+}
+
+impl<const OP: u8, PWM: PwmPeripheral> FaultHandler<OP, PWM> {
+    pub(super) fn new() -> Self {
+        FaultHandler {
+            phantom: PhantomData,
+        }
+    }
+
+    /// Configure how the operator reacts to faults
+    pub fn set_config(&mut self, config: FaultConfig) {
+        let ch = Self::ch();
+
+        let bits = source_bits(config.cycle_by_cycle)
+            | source_bits(config.one_shot) << 4
+            | config.actions_a.bits() << 8
+            | config.actions_b.bits() << 16;
+        ch.fh_cfg0().write(|w| unsafe { w.bits(bits) });
+
+        ch.fh_cfg1().modify(|_, w| unsafe {
+            w.cbcpulse()
+                .bits(config.cbc_clear_on_zero as u8 | (config.cbc_clear_on_period as u8) << 1)
+        });
+    }
+
+    /// Force a trip of the given kind by software
+    ///
+    /// Only has an effect if [`FaultSource::Software`] is configured as a
+    /// source for this kind of trip.
+    pub fn force_trip(&mut self, kind: TripKind) {
+        // a toggle of the bit forces the event
+        Self::ch().fh_cfg1().modify(|r, w| match kind {
+            TripKind::CycleByCycle => w.force_cbc().bit(!r.force_cbc().bit()),
+            TripKind::OneShot => w.force_ost().bit(!r.force_ost().bit()),
+        });
+    }
+
+    /// Clear an active one-shot trip
+    pub fn clear_one_shot_trip(&mut self) {
+        // a rising edge clears the one-shot trip
+        let ch = Self::ch();
+        ch.fh_cfg1().modify(|_, w| w.clr_ost().set_bit());
+        ch.fh_cfg1().modify(|_, w| w.clr_ost().clear_bit());
+    }
+
+    /// Get the trips currently active on the operator
+    pub fn trip_status(&self) -> TripStatus {
+        let status = Self::ch().fh_status().read();
+
+        TripStatus {
+            cycle_by_cycle: status.cbc_on().bit_is_set(),
+            one_shot: status.ost_on().bit_is_set(),
+        }
+    }
+
+    fn ch() -> &'static crate::peripherals::mcpwm0::CH {
+        // SAFETY:
+        // We only access our FHx_CFG0, FHx_CFG1 and FHx_STATUS registers
+        let block = unsafe { &*PWM::block() };
+        block.ch(OP as usize)
+    }
+}
+
+#[cfg(feature = "async")]
+mod asynch {
+    use core::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use embassy_sync::waitqueue::AtomicWaker;
+
+    use super::*;
+
+    const INIT: AtomicWaker = AtomicWaker::new();
+    static WAKERS: [[AtomicWaker; 3]; super::super::NUM_MCPWM] =
+        [[INIT; 3]; super::super::NUM_MCPWM];
+
+    /// Wake the tasks waiting for the given fault interrupts
+    pub(in crate::mcpwm) fn on_interrupt(number: usize, pending: u32) {
+        for (f, waker) in WAKERS[number].iter().enumerate() {
+            if pending & (1 << (FAULT_INT_OFFSET + f as u32)) != 0 {
+                waker.wake();
+            }
+        }
+    }
+
+    struct FaultFuture<PWM, const F: u8> {
+        phantom: PhantomData<PWM>,
+    }
+
+    impl<PWM: PwmPeripheral, const F: u8> FaultFuture<PWM, F> {
+        fn new() -> Self {
+            // SAFETY:
+            // We only set the bit of our fault input
+            let block = unsafe { &*PWM::block() };
+            critical_section::with(|_| {
+                block
+                    .int_clr()
+                    .write(|w| unsafe { w.bits(1 << (FAULT_INT_OFFSET + F as u32)) });
+                block.int_ena().modify(|r, w| unsafe {
+                    w.bits(r.bits() | 1 << (FAULT_INT_OFFSET + F as u32))
+                });
+            });
+
+            FaultFuture {
+                phantom: PhantomData,
+            }
+        }
+    }
+
+    impl<PWM: PwmPeripheral, const F: u8> core::future::Future for FaultFuture<PWM, F> {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            WAKERS[PWM::number()][F as usize].register(cx.waker());
+
+            // the interrupt handler disables the interrupt once it fired
+            let block = unsafe { &*PWM::block() };
+            if block.int_ena().read().bits() & 1 << (FAULT_INT_OFFSET + F as u32) == 0 {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        }
+    }
+
+    impl<PWM: PwmPeripheral, const F: u8> Drop for FaultFuture<PWM, F> {
+        fn drop(&mut self) {
+            // SAFETY:
+            // We only clear the bit of our fault input
+            let block = unsafe { &*PWM::block() };
+            critical_section::with(|_| {
+                block.int_ena().modify(|r, w| unsafe {
+                    w.bits(r.bits() & !(1 << (FAULT_INT_OFFSET + F as u32)))
+                });
+            });
+        }
+    }
+
+    impl<'d, Pin: InputPin, PWM: PwmPeripheral, const F: u8> FaultPin<'d, Pin, PWM, F> {
+        /// Wait until the fault input signals a fault
+        ///
+        /// Returns immediately if the fault is already active. The MCPWM
+        /// peripheral has to be created with
+        /// [`McPwm::new_async`](super::super::McPwm::new_async).
+        pub async fn wait_for_fault_async(&mut self) {
+            let future = FaultFuture::<PWM, F>::new();
+            if self.is_active() {
+                return;
+            }
+            future.await;
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+pub(super) use asynch::on_interrupt;
@@ -7,7 +7,7 @@
 //!     * The 16-bit counter in the PWM timer can work in count-up mode,
 //!       count-down mode or count-up-down mode.
 //!     * A hardware sync or software sync can trigger a reload on the PWM timer
-//!       with a phase register (Not yet implemented)
+//!       with a phase register
 //! * PWM Operators 0, 1 and 2
 //!     * Every PWM operator has two PWM outputs: PWMxA and PWMxB. They can work
 //!       independently, in symmetric and asymmetric configuration.
@@ -20,8 +20,16 @@
 //!       implemented)
 //!     * Period, time stamps and important control registers have shadow
 //!       registers with flexible updating methods.
-//! * Fault Detection Module (Not yet implemented)
-//! * Capture Module (Not yet implemented)
+//! * Fault Detection Module
+//!     * Three fault inputs with configurable polarity and a software forced
+//!       fault.
+//!     * Cycle-by-cycle and one-shot trips with configurable output actions per
+//!       operator.
+//! * Capture Module
+//!     * Three capture channels sharing a 32-bit capture timer, each
+//!       timestamping selectable edges of an input signal.
+//!     * Prescaler and inversion of the input signal, software triggered
+//!       captures and sync of the capture timer.
 #![doc = ""]
 #![cfg_attr(esp32, doc = "Clock source is PWM_CLOCK")]
 #![cfg_attr(esp32s3, doc = "Clock source is CRYPTO_PWM_CLOCK")]
@@ -61,17 +69,25 @@
 
 use core::{marker::PhantomData, ops::Deref};
 
+use capture::{CaptureChannel, CaptureTimer};
+use fault::{FaultHandler, FaultInput};
 use fugit::HertzU32;
 use operator::Operator;
-use timer::Timer;
+use timer::{SyncInput, Timer};
 
 use crate::{
     clock::Clocks,
-    gpio::OutputSignal,
+    gpio::{InputSignal, OutputSignal},
+    interrupt::InterruptHandler,
     peripheral::{Peripheral, PeripheralRef},
+    peripherals::Interrupt,
     system::{Peripheral as PeripheralEnable, PeripheralClockControl},
 };
 
+/// MCPWM capture channels
+pub mod capture;
+/// MCPWM fault detection
+pub mod fault;
 /// MCPWM operators
 pub mod operator;
 /// MCPWM timers
@@ -79,6 +95,13 @@ pub mod timer;
 
 type RegisterBlock = crate::peripherals::mcpwm0::RegisterBlock;
 
+#[cfg(feature = "async")]
+#[cfg(mcpwm1)]
+const NUM_MCPWM: usize = 2;
+#[cfg(feature = "async")]
+#[cfg(not(mcpwm1))]
+const NUM_MCPWM: usize = 1;
+
 /// The MCPWM peripheral
 #[non_exhaustive]
 pub struct McPwm<'d, PWM> {
@@ -95,6 +118,32 @@ pub struct McPwm<'d, PWM> {
     pub operator1: Operator<1, PWM>,
     /// Operator2
     pub operator2: Operator<2, PWM>,
+    /// Capture timer
+    pub capture_timer: CaptureTimer<PWM>,
+    /// Capture channel 0
+    pub capture0: CaptureChannel<0, PWM>,
+    /// Capture channel 1
+    pub capture1: CaptureChannel<1, PWM>,
+    /// Capture channel 2
+    pub capture2: CaptureChannel<2, PWM>,
+    /// Fault input 0
+    pub fault0: FaultInput<0, PWM>,
+    /// Fault input 1
+    pub fault1: FaultInput<1, PWM>,
+    /// Fault input 2
+    pub fault2: FaultInput<2, PWM>,
+    /// Fault handler of operator 0
+    pub fault_handler0: FaultHandler<0, PWM>,
+    /// Fault handler of operator 1
+    pub fault_handler1: FaultHandler<1, PWM>,
+    /// Fault handler of operator 2
+    pub fault_handler2: FaultHandler<2, PWM>,
+    /// GPIO sync input 0
+    pub sync0: SyncInput<0, PWM>,
+    /// GPIO sync input 1
+    pub sync1: SyncInput<1, PWM>,
+    /// GPIO sync input 2
+    pub sync2: SyncInput<2, PWM>,
 }
 
 impl<'d, PWM: PwmPeripheral> McPwm<'d, PWM> {
@@ -155,10 +204,84 @@ impl<'d, PWM: PwmPeripheral> McPwm<'d, PWM> {
             operator0: Operator::new(),
             operator1: Operator::new(),
             operator2: Operator::new(),
+            capture_timer: CaptureTimer::new(),
+            capture0: CaptureChannel::new(),
+            capture1: CaptureChannel::new(),
+            capture2: CaptureChannel::new(),
+            fault0: FaultInput::new(),
+            fault1: FaultInput::new(),
+            fault2: FaultInput::new(),
+            fault_handler0: FaultHandler::new(),
+            fault_handler1: FaultHandler::new(),
+            fault_handler2: FaultHandler::new(),
+            sync0: SyncInput::new(),
+            sync1: SyncInput::new(),
+            sync2: SyncInput::new(),
+        }
+    }
+
+    /// Create a new instance which handles the interrupts needed by the async
+    /// API, like [`CapturePin::wait_for_capture_async`] or
+    /// [`FaultPin::wait_for_fault_async`].
+    ///
+    /// [`CapturePin::wait_for_capture_async`]: capture::CapturePin::wait_for_capture_async
+    /// [`FaultPin::wait_for_fault_async`]: fault::FaultPin::wait_for_fault_async
+    #[cfg(feature = "async")]
+    pub fn new_async(
+        peripheral: impl Peripheral<P = PWM> + 'd,
+        peripheral_clock: PeripheralClockConfig,
+    ) -> Self {
+        let mut mcpwm = Self::new(peripheral, peripheral_clock);
+        let handler = match PWM::number() {
+            0 => asynch::mcpwm0_handler,
+            #[cfg(mcpwm1)]
+            1 => asynch::mcpwm1_handler,
+            _ => unreachable!(),
+        };
+        mcpwm.set_interrupt_handler(handler);
+        mcpwm
+    }
+
+    /// Sets the interrupt handler, enables it with the given priority
+    ///
+    /// Interrupts are not enabled at the peripheral level here.
+    pub fn set_interrupt_handler(&mut self, handler: InterruptHandler) {
+        unsafe {
+            crate::interrupt::bind_interrupt(PWM::interrupt(), handler.handler());
+            crate::interrupt::enable(PWM::interrupt(), handler.priority()).unwrap();
         }
     }
 }
 
+#[cfg(feature = "async")]
+mod asynch {
+    use procmacros::handler;
+
+    use super::*;
+
+    fn handle_interrupt(block: &RegisterBlock, number: usize) {
+        // disable the pending interrupts, the futures check `int_ena`
+        let pending = block.int_st().read().bits();
+        block
+            .int_ena()
+            .modify(|r, w| unsafe { w.bits(r.bits() & !pending) });
+
+        capture::on_interrupt(number, pending);
+        fault::on_interrupt(number, pending);
+    }
+
+    #[handler]
+    pub(super) fn mcpwm0_handler() {
+        handle_interrupt(unsafe { &*crate::peripherals::MCPWM0::PTR }, 0);
+    }
+
+    #[cfg(mcpwm1)]
+    #[handler]
+    pub(super) fn mcpwm1_handler() {
+        handle_interrupt(unsafe { &*crate::peripherals::MCPWM1::PTR }, 1);
+    }
+}
+
 /// Clock configuration of the MCPWM peripheral
 #[derive(Copy, Clone)]
 pub struct PeripheralClockConfig<'a> {
@@ -290,6 +413,16 @@ pub trait PwmPeripheral: Deref<Target = RegisterBlock> + crate::private::Sealed
     fn block() -> *const RegisterBlock;
     /// Get operator GPIO mux output signal
     fn output_signal<const OP: u8, const IS_A: bool>() -> OutputSignal;
+    /// Get capture channel GPIO mux input signal
+    fn capture_signal<const CH: u8>() -> InputSignal;
+    /// Get fault input GPIO mux input signal
+    fn fault_signal<const F: u8>() -> InputSignal;
+    /// Get sync input GPIO mux input signal
+    fn sync_signal<const S: u8>() -> InputSignal;
+    /// Get the interrupt of the peripheral
+    fn interrupt() -> Interrupt;
+    #[doc(hidden)]
+    fn number() -> usize;
 }
 
 #[cfg(mcpwm0)]
@@ -313,6 +446,44 @@ impl PwmPeripheral for crate::peripherals::MCPWM0 {
             _ => unreachable!(),
         }
     }
+
+    fn capture_signal<const CH: u8>() -> InputSignal {
+        match CH {
+            0 => InputSignal::PWM0_CAP0,
+            1 => InputSignal::PWM0_CAP1,
+            2 => InputSignal::PWM0_CAP2,
+            _ => unreachable!(),
+        }
+    }
+
+    fn fault_signal<const F: u8>() -> InputSignal {
+        match F {
+            0 => InputSignal::PWM0_F0,
+            1 => InputSignal::PWM0_F1,
+            2 => InputSignal::PWM0_F2,
+            _ => unreachable!(),
+        }
+    }
+
+    fn sync_signal<const S: u8>() -> InputSignal {
+        match S {
+            0 => InputSignal::PWM0_SYNC0,
+            1 => InputSignal::PWM0_SYNC1,
+            2 => InputSignal::PWM0_SYNC2,
+            _ => unreachable!(),
+        }
+    }
+
+    fn interrupt() -> Interrupt {
+        #[cfg(esp32)]
+        return Interrupt::PWM0;
+        #[cfg(not(esp32))]
+        return Interrupt::MCPWM0;
+    }
+
+    fn number() -> usize {
+        0
+    }
 }
 
 #[cfg(mcpwm1)]
@@ -336,4 +507,42 @@ impl PwmPeripheral for crate::peripherals::MCPWM1 {
             _ => unreachable!(),
         }
     }
+
+    fn capture_signal<const CH: u8>() -> InputSignal {
+        match CH {
+            0 => InputSignal::PWM1_CAP0,
+            1 => InputSignal::PWM1_CAP1,
+            2 => InputSignal::PWM1_CAP2,
+            _ => unreachable!(),
+        }
+    }
+
+    fn fault_signal<const F: u8>() -> InputSignal {
+        match F {
+            0 => InputSignal::PWM1_F0,
+            1 => InputSignal::PWM1_F1,
+            2 => InputSignal::PWM1_F2,
+            _ => unreachable!(),
+        }
+    }
+
+    fn sync_signal<const S: u8>() -> InputSignal {
+        match S {
+            0 => InputSignal::PWM1_SYNC0,
+            1 => InputSignal::PWM1_SYNC1,
+            2 => InputSignal::PWM1_SYNC2,
+            _ => unreachable!(),
+        }
+    }
+
+    fn interrupt() -> Interrupt {
+        #[cfg(esp32)]
+        return Interrupt::PWM1;
+        #[cfg(not(esp32))]
+        return Interrupt::MCPWM1;
+    }
+
+    fn number() -> usize {
+        1
+    }
 }
@@ -167,7 +167,8 @@ impl DeadTimeCfg {
 ///   time. (Not yet implemented)
 /// * Superimposes a carrier on the PWM signal, if configured to do so. (Not yet
 ///   implemented)
-/// * Handles response under fault conditions. (Not yet implemented)
+/// * Handles response under fault conditions, see
+///   [`FaultHandler`](super::fault::FaultHandler).
 pub struct Operator<const OP: u8, PWM> {
     phantom: PhantomData<PWM>,
 }
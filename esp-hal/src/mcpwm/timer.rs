@@ -5,6 +5,33 @@
 //! Modulator)` driver for ESP chips. It provides an interface to configure and
 //! use timers for generating `PWM` signals used in motor control and other
 //! applications.
+//!
+//! Timers can be synchronized to each other or to external sync signals
+//! connected with [`SyncInput::with_pin`]. On a sync event the counter of a
+//! timer is reloaded with the phase and direction of its [`TimerSyncConfig`],
+//! which allows e.g. phase locking all three timers for a three-phase inverter:
+//!
+//! ```no_run
+//! # use esp_hal::{mcpwm, prelude::*};
+//! use mcpwm::timer::{CounterDirection, SyncOutput, SyncSource, TimerSyncConfig};
+//!
+//! // timer0 is the master, its sync output fires whenever it equals zero
+//! mcpwm
+//!     .timer0
+//!     .set_sync_config(TimerSyncConfig::new().with_output(SyncOutput::Zero));
+//!
+//! // timer1 and timer2 follow with a phase offset of 1/3 and 2/3 of the period
+//! mcpwm.timer1.set_sync_config(
+//!     TimerSyncConfig::new()
+//!         .with_input(SyncSource::Timer0)
+//!         .with_phase(period / 3, CounterDirection::Increasing),
+//! );
+//! mcpwm.timer2.set_sync_config(
+//!     TimerSyncConfig::new()
+//!         .with_input(SyncSource::Timer0)
+//!         .with_phase(2 * period / 3, CounterDirection::Increasing),
+//! );
+//! ```
 
 use core::marker::PhantomData;
 
@@ -12,7 +39,10 @@ use fugit::HertzU32;
 
 use crate::{
     clock::Clocks,
+    gpio::InputPin,
     mcpwm::{FrequencyError, PeripheralClockConfig, PwmPeripheral},
+    peripheral::{Peripheral, PeripheralRef},
+    private,
 };
 
 /// A MCPWM timer
@@ -65,20 +95,79 @@ impl<const TIM: u8, PWM: PwmPeripheral> Timer<TIM, PWM> {
     }
 
     /// Set the timer counter to the provided value
+    ///
+    /// This triggers a software sync event, so a sync output is generated if
+    /// [`SyncOutput::Software`] is selected. The sync input, sync output and
+    /// the phase of the [`TimerSyncConfig`] are kept.
     pub fn set_counter(&mut self, phase: u16, direction: CounterDirection) {
         // SAFETY:
         // We only write to our TIMERx_SYNC register
         let tmr = unsafe { Self::tmr() };
-        let sw = tmr.sync().read().sw().bit_is_set();
-        tmr.sync().write(|w| {
+        let sync = tmr.sync().read();
+        let configured_phase = sync.phase().bits();
+        let configured_direction = sync.phase_direction().bit();
+
+        tmr.sync().modify(|r, w| {
             w.phase_direction().bit(direction as u8 != 0);
             unsafe {
                 w.phase().bits(phase);
             }
-            w.sw().bit(!sw)
+            w.sw().bit(!r.sw().bit())
+        });
+
+        // The counter is loaded on the next tick of the timer clock. A register
+        // read lasts at least two PWM clock cycles, so this waits for two ticks
+        // before the configured phase is restored.
+        let prescale = self.cfg0().read().prescale().bits();
+        for _ in 0..=prescale {
+            tmr.sync().read();
+        }
+
+        tmr.sync().modify(|_, w| {
+            w.phase_direction().bit(configured_direction);
+            unsafe { w.phase().bits(configured_phase) }
         });
     }
 
+    /// Configure the sync input, the sync output and the phase loaded on a
+    /// sync event
+    pub fn set_sync_config(&mut self, config: TimerSyncConfig) {
+        // SAFETY:
+        // We only write to our TIMER_SYNCI_CFG bits
+        let block = unsafe { &*PWM::block() };
+        let shift = 3 * TIM as u32;
+        critical_section::with(|_| {
+            block.timer_synci_cfg().modify(|r, w| unsafe {
+                let source = config.input.map_or(0, |source| source as u32);
+                w.bits(r.bits() & !(0b111 << shift) | source << shift)
+            });
+        });
+
+        // SAFETY:
+        // We only write to our TIMERx_SYNC register
+        let tmr = unsafe { Self::tmr() };
+        tmr.sync().modify(|_, w| {
+            w.synci_en().bit(config.input.is_some());
+            w.phase_direction().bit(config.direction as u8 != 0);
+            unsafe {
+                w.synco_sel().bits(config.output as u8);
+                w.phase().bits(config.phase)
+            }
+        });
+    }
+
+    /// Trigger a software sync event
+    ///
+    /// The counter is reloaded with the configured phase and a sync output
+    /// is generated if [`SyncOutput::Software`] is selected.
+    pub fn software_sync(&mut self) {
+        // SAFETY:
+        // We only write to our TIMERx_SYNC register
+        let tmr = unsafe { Self::tmr() };
+        // a toggle of the bit triggers the sync
+        tmr.sync().modify(|r, w| w.sw().bit(!r.sw().bit()));
+    }
+
     /// Read the counter value and counter direction of the timer
     pub fn status(&self) -> (u16, CounterDirection) {
         // SAFETY:
@@ -205,7 +294,7 @@ pub enum PwmWorkingMode {
 }
 
 /// The direction the timer counter is changing
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 #[repr(u8)]
 pub enum CounterDirection {
     /// The timer counter is increasing
@@ -222,3 +311,142 @@ impl From<bool> for CounterDirection {
         }
     }
 }
+
+/// A sync event source for a PWM timer or the capture timer
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum SyncSource {
+    /// Sync output of PWM timer 0
+    Timer0 = 1,
+    /// Sync output of PWM timer 1
+    Timer1 = 2,
+    /// Sync output of PWM timer 2
+    Timer2 = 3,
+    /// GPIO sync input 0
+    Sync0  = 4,
+    /// GPIO sync input 1
+    Sync1  = 5,
+    /// GPIO sync input 2
+    Sync2  = 6,
+}
+
+/// The event which generates the sync output of a PWM timer
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum SyncOutput {
+    /// Pass the sync input of the timer through
+    SyncInput = 0,
+    /// The timer equals zero
+    Zero      = 1,
+    /// The timer equals the period
+    Period    = 2,
+    /// Only [`Timer::software_sync`]
+    Software  = 3,
+}
+
+/// Sync configuration of a PWM timer
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimerSyncConfig {
+    /// The sync input of the timer, `None` disables the hardware sync
+    pub input: Option<SyncSource>,
+    /// The event which generates the sync output
+    pub output: SyncOutput,
+    /// The value loaded into the counter on a sync event
+    pub phase: u16,
+    /// The direction of the counter after a sync event in
+    /// [`PwmWorkingMode::UpDown`]
+    pub direction: CounterDirection,
+}
+
+impl Default for TimerSyncConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimerSyncConfig {
+    /// Get a configuration without sync input, with a sync output on software
+    /// syncs only and a phase of zero
+    pub const fn new() -> Self {
+        TimerSyncConfig {
+            input: None,
+            output: SyncOutput::Software,
+            phase: 0,
+            direction: CounterDirection::Increasing,
+        }
+    }
+
+    /// Set the sync input
+    pub const fn with_input(mut self, input: SyncSource) -> Self {
+        self.input = Some(input);
+        self
+    }
+
+    /// Set the event which generates the sync output
+    pub const fn with_output(mut self, output: SyncOutput) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Set the phase and direction loaded into the counter on a sync event
+    pub const fn with_phase(mut self, phase: u16, direction: CounterDirection) -> Self {
+        self.phase = phase;
+        self.direction = direction;
+        self
+    }
+}
+
+/// A MCPWM GPIO sync input
+pub struct SyncInput<const S: u8, PWM> {
+    phantom: PhantomData<PWM>,
+}
+
+impl<const S: u8, PWM: PwmPeripheral> SyncInput<S, PWM> {
+    pub(super) fn new() -> Self {
+        SyncInput {
+            phantom: PhantomData,
+        }
+    }
+
+    /// Use the sync input with the given pin
+    ///
+    /// Sync events are generated on rising edges, or on falling edges if
+    /// `invert` is set.
+    pub fn with_pin<'d, Pin: InputPin>(
+        self,
+        pin: impl Peripheral<P = Pin> + 'd,
+        invert: bool,
+    ) -> SyncPin<'d, Pin, PWM, S> {
+        crate::into_ref!(pin);
+        pin.set_to_input(private::Internal);
+        pin.connect_input_to_peripheral(PWM::sync_signal::<S>(), private::Internal);
+
+        // SAFETY:
+        // We only modify the invert bit of our sync input
+        let block = unsafe { &*PWM::block() };
+        critical_section::with(|_| {
+            block.timer_synci_cfg().modify(|r, w| unsafe {
+                let bit = 1 << (9 + S);
+                w.bits(if invert {
+                    r.bits() | bit
+                } else {
+                    r.bits() & !bit
+                })
+            });
+        });
+
+        SyncPin {
+            _pin: pin,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// A pin connected to a MCPWM GPIO sync input
+pub struct SyncPin<'d, Pin, PWM, const S: u8> {
+    _pin: PeripheralRef<'d, Pin>,
+    phantom: PhantomData<PWM>,
+}
@@ -127,9 +127,24 @@
 //! }
 //! ```
 //!
+//! ## Overflow accumulation and async events
+//! The counter of a unit is 16 bits wide and restarts at zero when it reaches
+//! its low or high limit. A PCNT created with
+//! [`Pcnt::new_with_driver_handler`] services its interrupt itself: it adds the
+//! limit to a software count of the units with
+//! [`unit::Unit::enable_overflow_accumulation`], read with
+//! [`unit::Unit::get_count`], and wakes the tasks waiting in
+//! `Unit::wait_for_event_async`.
+//!
+//! [`unit::Unit::configure_quadrature`] sets up both channels of a unit to
+//! decode the A and B signals of a quadrature encoder.
+//!
 //! [channel]: channel/index.html
 //! [unit]: unit/index.html
 
+use portable_atomic::Ordering;
+use procmacros::handler;
+
 use self::unit::Unit;
 use crate::{
     interrupt::{self, InterruptHandler},
@@ -161,12 +176,29 @@ impl<'d> Pcnt<'d> {
                 interrupt::enable(Interrupt::PCNT, interrupt.priority()).unwrap();
             }
         }
+        unit::DRIVER_HANDLER.store(false, Ordering::Relaxed);
 
         Pcnt { _instance }
     }
 
+    /// Return a new PCNT whose interrupt is handled by the driver
+    ///
+    /// This is required to accumulate the overflows of the units reliably
+    /// and for the async API.
+    pub fn new_with_driver_handler(_instance: impl Peripheral<P = peripherals::PCNT> + 'd) -> Self {
+        let pcnt = Self::new(_instance, Some(pcnt_interrupt_handler));
+        unit::DRIVER_HANDLER.store(true, Ordering::Relaxed);
+
+        pcnt
+    }
+
     /// Return a unit
     pub fn get_unit(&self, number: unit::Number) -> Unit {
         Unit::new(number)
     }
 }
+
+#[handler]
+fn pcnt_interrupt_handler() {
+    unit::on_interrupt();
+}
@@ -9,10 +9,35 @@
 //! and so on. This module provides methods to configure a unit with specific
 //! settings, like low and high limits, thresholds, and optional filtering.
 //! Users can easily configure these units based on their requirements.
+//!
+//! A unit can accumulate the overflows of its 16-bit counter into a 64-bit
+//! software count and decode the signals of a quadrature encoder.
+
+use core::cell::Cell;
+
+use critical_section::{CriticalSection, Mutex};
+use portable_atomic::{AtomicBool, AtomicU8, Ordering};
+
+use super::channel::{self, CtrlMode, EdgeMode, PcntSource};
+
+#[cfg(esp32)]
+const NUM_UNITS: usize = 8;
+#[cfg(not(esp32))]
+const NUM_UNITS: usize = 4;
+
+/// Units accumulating their overflows, one bit per unit
+static ACCUMULATING: AtomicU8 = AtomicU8::new(0);
 
-use critical_section::CriticalSection;
+/// Whether the interrupt is handled by the driver, see
+/// [`Pcnt::new_with_driver_handler`](super::Pcnt::new_with_driver_handler)
+pub(super) static DRIVER_HANDLER: AtomicBool = AtomicBool::new(false);
 
-use super::channel;
+/// Accumulated overflows of the units
+static OVERFLOW: Mutex<Cell<[i64; NUM_UNITS]>> = Mutex::new(Cell::new([0; NUM_UNITS]));
+
+/// Events recorded by the interrupt handler, see [`Events::bits`]
+const EVENTS_INIT: AtomicU8 = AtomicU8::new(0);
+static PENDING_EVENTS: [AtomicU8; NUM_UNITS] = [EVENTS_INIT; NUM_UNITS];
 
 /// Unit number
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
@@ -81,6 +106,59 @@ pub struct Events {
     pub zero: bool,
 }
 
+impl Events {
+    const LOW_LIMIT: u8 = 1 << 0;
+    const HIGH_LIMIT: u8 = 1 << 1;
+    const THRESH0: u8 = 1 << 2;
+    const THRESH1: u8 = 1 << 3;
+    const ZERO: u8 = 1 << 4;
+
+    fn bits(&self) -> u8 {
+        let mut bits = 0;
+        if self.low_limit {
+            bits |= Self::LOW_LIMIT;
+        }
+        if self.high_limit {
+            bits |= Self::HIGH_LIMIT;
+        }
+        if self.thresh0 {
+            bits |= Self::THRESH0;
+        }
+        if self.thresh1 {
+            bits |= Self::THRESH1;
+        }
+        if self.zero {
+            bits |= Self::ZERO;
+        }
+        bits
+    }
+
+    #[cfg(feature = "async")]
+    fn from_bits(bits: u8) -> Self {
+        Self {
+            low_limit: bits & Self::LOW_LIMIT != 0,
+            high_limit: bits & Self::HIGH_LIMIT != 0,
+            thresh0: bits & Self::THRESH0 != 0,
+            thresh1: bits & Self::THRESH1 != 0,
+            zero: bits & Self::ZERO != 0,
+        }
+    }
+}
+
+/// Decoding of the A and B signals of a quadrature encoder, see
+/// [`Unit::configure_quadrature`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum QuadratureMode {
+    /// Count the rising edges of A
+    X1,
+    /// Count the rising and falling edges of A
+    X2,
+    /// Count the rising and falling edges of A and B
+    #[default]
+    X4,
+}
+
 /// Unit configuration
 #[derive(Copy, Clone, Default)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -157,14 +235,21 @@ impl Unit {
         super::channel::Channel::new(self.number, number)
     }
 
+    /// Clear the counter and the accumulated overflows
     pub fn clear(&self) {
         let pcnt = unsafe { &*crate::peripherals::PCNT::ptr() };
-        critical_section::with(|_cs| {
+        critical_section::with(|cs| {
+            // account for a limit reached before the reset, so the handler
+            // doesn't add it afterwards
+            service_if_raw(cs, self.number as usize);
+
             pcnt.ctrl()
                 .modify(|_, w| w.cnt_rst_u(self.number as u8).set_bit());
             // TODO: does this need a delay? (liebman / Jan 2 2023)
             pcnt.ctrl()
                 .modify(|_, w| w.cnt_rst_u(self.number as u8).clear_bit());
+
+            set_overflow(cs, self.number as usize, 0);
         });
     }
 
@@ -187,11 +272,15 @@ impl Unit {
     }
 
     /// Enable which events generate interrupts on this unit.
+    ///
+    /// The limit events stay enabled while the unit accumulates its
+    /// overflows.
     pub fn events(&self, events: Events) {
         let pcnt = unsafe { &*crate::peripherals::PCNT::ptr() };
+        let accumulating = self.is_accumulating_overflow();
         pcnt.unit(self.number as usize).conf0().modify(|_, w| {
-            w.thr_l_lim_en().bit(events.low_limit);
-            w.thr_h_lim_en().bit(events.high_limit);
+            w.thr_l_lim_en().bit(events.low_limit || accumulating);
+            w.thr_h_lim_en().bit(events.high_limit || accumulating);
             w.thr_thres0_en().bit(events.thresh0);
             w.thr_thres1_en().bit(events.thresh1);
             w.thr_zero_en().bit(events.zero)
@@ -263,4 +352,252 @@ impl Unit {
         let pcnt = unsafe { &*crate::peripherals::PCNT::ptr() };
         pcnt.u_cnt(self.number as usize).read().cnt().bits() as i16
     }
+
+    /// Accumulate the overflows of the counter into a software count
+    ///
+    /// The counter restarts at zero when it reaches its low or high limit,
+    /// the limit is then added to the count returned by
+    /// [`Unit::get_count`]. This enables the limit events and the interrupt
+    /// of the unit, the PCNT has to be created with
+    /// [`Pcnt::new_with_driver_handler`](super::Pcnt::new_with_driver_handler).
+    pub fn enable_overflow_accumulation(&self) {
+        let pcnt = unsafe { &*crate::peripherals::PCNT::ptr() };
+        critical_section::with(|cs| {
+            ACCUMULATING.fetch_or(1 << self.number as u8, Ordering::Relaxed);
+            set_overflow(cs, self.number as usize, 0);
+
+            pcnt.unit(self.number as usize).conf0().modify(|_, w| {
+                w.thr_l_lim_en().set_bit();
+                w.thr_h_lim_en().set_bit()
+            });
+            pcnt.int_ena()
+                .modify(|_, w| w.cnt_thr_event_u(self.number as u8).set_bit());
+        });
+    }
+
+    /// Stop accumulating the overflows of the counter
+    ///
+    /// The limit events stay enabled, disable them with [`Unit::events`].
+    pub fn disable_overflow_accumulation(&self) {
+        critical_section::with(|cs| {
+            ACCUMULATING.fetch_and(!(1 << self.number as u8), Ordering::Relaxed);
+            set_overflow(cs, self.number as usize, 0);
+        });
+    }
+
+    /// Returns true if the unit accumulates its overflows
+    pub fn is_accumulating_overflow(&self) -> bool {
+        ACCUMULATING.load(Ordering::Relaxed) & (1 << self.number as u8) != 0
+    }
+
+    /// Get the counter value including the accumulated overflows
+    pub fn get_count(&self) -> i64 {
+        critical_section::with(|cs| {
+            // the counter already restarted if a limit was reached but the
+            // interrupt is not handled yet
+            service_if_raw(cs, self.number as usize);
+
+            OVERFLOW.borrow(cs).get()[self.number as usize] + self.get_value() as i64
+        })
+    }
+
+    /// Configure both channels to decode the A and B signals of a quadrature
+    /// encoder
+    ///
+    /// The count increases while A leads B. With [`QuadratureMode::X1`] and
+    /// [`QuadratureMode::X2`] only channel 0 is used, channel 1 is disabled.
+    pub fn configure_quadrature(&mut self, a: PcntSource, b: PcntSource, mode: QuadratureMode) {
+        let (pos_edge, neg_edge) = match mode {
+            QuadratureMode::X1 => (EdgeMode::Decrement, EdgeMode::Hold),
+            QuadratureMode::X2 | QuadratureMode::X4 => (EdgeMode::Decrement, EdgeMode::Increment),
+        };
+
+        self.get_channel(channel::Number::Channel0).configure(
+            b,
+            a,
+            channel::Config {
+                lctrl_mode: CtrlMode::Reverse,
+                hctrl_mode: CtrlMode::Keep,
+                pos_edge,
+                neg_edge,
+                invert_ctrl: false,
+                invert_sig: false,
+            },
+        );
+
+        let mut ch1 = self.get_channel(channel::Number::Channel1);
+        match mode {
+            QuadratureMode::X4 => ch1.configure(
+                a,
+                b,
+                channel::Config {
+                    lctrl_mode: CtrlMode::Reverse,
+                    hctrl_mode: CtrlMode::Keep,
+                    pos_edge: EdgeMode::Increment,
+                    neg_edge: EdgeMode::Decrement,
+                    invert_ctrl: false,
+                    invert_sig: false,
+                },
+            ),
+            QuadratureMode::X1 | QuadratureMode::X2 => ch1.configure(
+                PcntSource::always_low(),
+                PcntSource::always_low(),
+                channel::Config {
+                    lctrl_mode: CtrlMode::Keep,
+                    hctrl_mode: CtrlMode::Keep,
+                    pos_edge: EdgeMode::Hold,
+                    neg_edge: EdgeMode::Hold,
+                    invert_ctrl: false,
+                    invert_sig: false,
+                },
+            ),
+        }
+    }
+}
+
+fn set_overflow(cs: CriticalSection, unit: usize, value: i64) {
+    let overflow = OVERFLOW.borrow(cs);
+    let mut values = overflow.get();
+    values[unit] = value;
+    overflow.set(values);
+}
+
+/// Handle the interrupts of all units
+pub(super) fn on_interrupt() {
+    let pcnt = unsafe { &*crate::peripherals::PCNT::ptr() };
+
+    critical_section::with(|cs| {
+        // read inside the critical section, `Unit::get_count` may have
+        // handled the interrupt in the meantime
+        let pending = pcnt.int_st().read().bits();
+        for unit in 0..NUM_UNITS {
+            if pending & (1 << unit) != 0 {
+                service(cs, unit);
+            }
+        }
+    });
+}
+
+/// Handle the raised interrupt of an accumulating unit before the handler
+/// does
+///
+/// The interrupt is left alone unless the driver handler is installed, a user
+/// handler still sees it and the events of the unit.
+fn service_if_raw(cs: CriticalSection, unit: usize) {
+    if !DRIVER_HANDLER.load(Ordering::Relaxed)
+        || ACCUMULATING.load(Ordering::Relaxed) & (1 << unit) == 0
+    {
+        return;
+    }
+
+    let pcnt = unsafe { &*crate::peripherals::PCNT::ptr() };
+    if pcnt.int_raw().read().cnt_thr_event_u(unit as u8).bit() {
+        service(cs, unit);
+    }
+}
+
+/// Record the events of a unit, accumulate its overflow and clear its
+/// interrupt
+fn service(cs: CriticalSection, unit: usize) {
+    let pcnt = unsafe { &*crate::peripherals::PCNT::ptr() };
+    let status = pcnt.u_status(unit).read();
+
+    if ACCUMULATING.load(Ordering::Relaxed) & (1 << unit) != 0 {
+        let limits = pcnt.unit(unit).conf2().read();
+        let mut overflow = OVERFLOW.borrow(cs).get()[unit];
+        if status.h_lim().bit() {
+            overflow += limits.cnt_h_lim().bits() as i16 as i64;
+        }
+        if status.l_lim().bit() {
+            overflow += limits.cnt_l_lim().bits() as i16 as i64;
+        }
+        set_overflow(cs, unit, overflow);
+    }
+
+    let events = Events {
+        low_limit: status.l_lim().bit(),
+        high_limit: status.h_lim().bit(),
+        thresh0: status.thres0().bit(),
+        thresh1: status.thres1().bit(),
+        zero: status.zero().bit(),
+    };
+    PENDING_EVENTS[unit].fetch_or(events.bits(), Ordering::Relaxed);
+
+    pcnt.int_clr()
+        .write(|w| w.cnt_thr_event_u(unit as u8).set_bit());
+
+    #[cfg(feature = "async")]
+    asynch::WAKERS[unit].wake();
+}
+
+#[cfg(feature = "async")]
+mod asynch {
+    use core::{
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use embassy_sync::waitqueue::AtomicWaker;
+
+    use super::*;
+
+    const INIT: AtomicWaker = AtomicWaker::new();
+    pub(super) static WAKERS: [AtomicWaker; NUM_UNITS] = [INIT; NUM_UNITS];
+
+    /// Resolves once the handler recorded one of the events
+    struct EventFuture {
+        unit: usize,
+        events: u8,
+    }
+
+    impl Future for EventFuture {
+        type Output = Events;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            WAKERS[self.unit].register(cx.waker());
+
+            let pending = PENDING_EVENTS[self.unit].fetch_and(!self.events, Ordering::Relaxed);
+            if pending & self.events != 0 {
+                Poll::Ready(Events::from_bits(pending & self.events))
+            } else {
+                Poll::Pending
+            }
+        }
+    }
+
+    impl Unit {
+        /// Wait for one of the given events, e.g. a threshold, the zero
+        /// crossing or a limit, and return the ones that occurred
+        ///
+        /// The events and the interrupt of the unit are enabled and stay
+        /// enabled. Events that occurred before the call are ignored. The PCNT
+        /// has to be created with
+        /// [`Pcnt::new_with_driver_handler`](super::super::Pcnt::new_with_driver_handler).
+        pub async fn wait_for_event_async(&mut self, events: Events) -> Events {
+            let unit = self.number as usize;
+            let bits = events.bits();
+
+            PENDING_EVENTS[unit].fetch_and(!bits, Ordering::Relaxed);
+
+            let pcnt = unsafe { &*crate::peripherals::PCNT::ptr() };
+            critical_section::with(|_cs| {
+                pcnt.unit(unit).conf0().modify(|r, w| {
+                    w.thr_l_lim_en()
+                        .bit(events.low_limit || r.thr_l_lim_en().bit());
+                    w.thr_h_lim_en()
+                        .bit(events.high_limit || r.thr_h_lim_en().bit());
+                    w.thr_thres0_en()
+                        .bit(events.thresh0 || r.thr_thres0_en().bit());
+                    w.thr_thres1_en()
+                        .bit(events.thresh1 || r.thr_thres1_en().bit());
+                    w.thr_zero_en().bit(events.zero || r.thr_zero_en().bit())
+                });
+                pcnt.int_ena()
+                    .modify(|_, w| w.cnt_thr_event_u(unit as u8).set_bit());
+            });
+
+            EventFuture { unit, events: bits }.await
+        }
+    }
 }
@@ -0,0 +1,245 @@
+//! # RMT encoders and decoders
+//!
+//! An [Encoder] produces the pulse codes of a higher level message, which
+//! [TxChannel::transmit_encoded](super::TxChannel::transmit_encoded) streams
+//! into the channel RAM while transmitting. A [Decoder] turns the pulse codes
+//! received by an RX channel back into a message.
+//!
+//! Codecs don't access the hardware, they only need to know the resolution of
+//! the channel, i.e. the frequency of its ticks after the clock divider.
+//! Creating a codec fails with [Error::InvalidArgument] if the durations of
+//! its protocol are shorter than a tick or don't fit into the 15-bit lengths
+//! of a pulse code at that resolution.
+//!
+//! Infrared protocols are implemented in [ir](super::ir).
+//!
+//! ## Example
+//!
+//! ```no_run
+//! let mut data = [0u8; 3 * 8];
+//! // fill `data` with GRB values
+//! let encoder = BytesEncoder::ws2812(&data, 80.MHz()).unwrap();
+//! channel = channel.transmit_encoded(encoder).wait().unwrap();
+//! ```
+
+use fugit::HertzU32;
+
+use super::{Error, PulseCode};
+
+/// Produces the pulse codes of a message
+pub trait Encoder {
+    /// Returns the next pulse code of the message or `None` once the message
+    /// is complete
+    ///
+    /// The end marker is added by the channel and must not be returned.
+    fn encode(&mut self) -> Option<PulseCode>;
+}
+
+/// Decodes received pulse codes into a message
+pub trait Decoder {
+    /// The decoded message
+    type Message;
+
+    /// Decode `codes`, as received by an RX channel
+    ///
+    /// Decoding stops at the end marker, i.e. the first zero-length pulse.
+    /// Only the lengths of the pulses are considered, the first pulse is
+    /// expected to be a mark regardless of its level.
+    fn decode(&self, codes: &[PulseCode]) -> Result<Self::Message, DecodeError>;
+}
+
+/// Decoding errors
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DecodeError {
+    /// The sequence doesn't start with the leader of the protocol
+    InvalidLeader,
+    /// A pulse doesn't match the timing of the protocol
+    InvalidTiming,
+    /// The sequence ended before the message was complete
+    Incomplete,
+    /// Redundant data of the message doesn't match
+    Checksum,
+    /// The message uses a variant of the protocol which isn't supported
+    Unsupported,
+}
+
+/// Encodes each bit of a byte buffer as a single pulse code, MSB first
+pub struct BytesEncoder<'a> {
+    data: &'a [u8],
+    bit0: PulseCode,
+    bit1: PulseCode,
+    index: usize,
+}
+
+impl<'a> BytesEncoder<'a> {
+    /// Create an encoder sending `bit0` for each cleared and `bit1` for each
+    /// set bit of `data`
+    pub fn new(data: &'a [u8], bit0: PulseCode, bit1: PulseCode) -> Self {
+        Self {
+            data,
+            bit0,
+            bit1,
+            index: 0,
+        }
+    }
+
+    /// Create an encoder using the bit timings of WS2812 LEDs
+    ///
+    /// The reset time after the data is provided by the idle level of the
+    /// channel, which has to be low.
+    pub fn ws2812(data: &'a [u8], resolution: HertzU32) -> Result<Self, Error> {
+        Ok(Self::new(
+            data,
+            PulseCode {
+                level1: true,
+                length1: ns_to_ticks(resolution, 400)?,
+                level2: false,
+                length2: ns_to_ticks(resolution, 850)?,
+            },
+            PulseCode {
+                level1: true,
+                length1: ns_to_ticks(resolution, 800)?,
+                level2: false,
+                length2: ns_to_ticks(resolution, 450)?,
+            },
+        ))
+    }
+}
+
+impl<'a> Encoder for BytesEncoder<'a> {
+    fn encode(&mut self) -> Option<PulseCode> {
+        let byte = self.data.get(self.index / 8)?;
+        let bit = byte & (0x80 >> (self.index % 8)) != 0;
+        self.index += 1;
+
+        Some(if bit { self.bit1 } else { self.bit0 })
+    }
+}
+
+/// Longest length of a pulse code interval in ticks
+const MAX_LENGTH: u16 = 0b111_1111_1111_1111;
+
+/// Convert a duration in nanoseconds into ticks at the given resolution
+///
+/// Fails if the duration is shorter than a tick, which would end the
+/// sequence, or longer than [MAX_LENGTH].
+pub(crate) fn ns_to_ticks(resolution: HertzU32, ns: u32) -> Result<u16, Error> {
+    let ticks = resolution.raw() as u64 * ns as u64 / 1_000_000_000;
+    if ticks == 0 || ticks > MAX_LENGTH as u64 {
+        return Err(Error::InvalidArgument);
+    }
+
+    Ok(ticks as u16)
+}
+
+/// Convert a duration in microseconds into ticks at the given resolution
+pub(crate) fn us_to_ticks(resolution: HertzU32, us: u32) -> Result<u16, Error> {
+    ns_to_ticks(resolution, us * 1000)
+}
+
+/// Length of two merged pulses, which must not exceed [MAX_LENGTH]
+fn merge(ticks: u16, other: u16) -> Result<u16, Error> {
+    match ticks.checked_add(other) {
+        Some(merged) if merged <= MAX_LENGTH => Ok(merged),
+        _ => Err(Error::InvalidArgument),
+    }
+}
+
+/// Check if `ticks` is within 25% of `expected`
+pub(crate) fn matches(ticks: u16, expected: u16) -> bool {
+    let ticks = ticks as u32 * 4;
+    ticks >= expected as u32 * 3 && ticks <= expected as u32 * 5
+}
+
+/// Lengths of the pulses of a received sequence up to the end marker
+pub(crate) fn durations(codes: &[PulseCode]) -> impl Iterator<Item = u16> + '_ {
+    codes
+        .iter()
+        .flat_map(|code| [code.length1, code.length2])
+        .take_while(|length| *length != 0)
+}
+
+/// Fixed capacity pulse code sequence, consecutive pulses of the same level
+/// are merged
+pub(crate) struct Sequence<const N: usize> {
+    codes: [PulseCode; N],
+    len: usize,
+    index: usize,
+    pending: Option<(bool, u16)>,
+}
+
+impl<const N: usize> Sequence<N> {
+    pub(crate) fn new() -> Self {
+        Self {
+            codes: [PulseCode::default(); N],
+            len: 0,
+            index: 0,
+            pending: None,
+        }
+    }
+
+    /// Append a pulse of `level` lasting `ticks`
+    ///
+    /// Fails if the pulse merged with the previous one of the same level
+    /// exceeds [MAX_LENGTH].
+    pub(crate) fn push(&mut self, level: bool, ticks: u16) -> Result<(), Error> {
+        match self.pending {
+            None if self.len > 0 && self.codes[self.len - 1].level2 == level => {
+                let code = &mut self.codes[self.len - 1];
+                code.length2 = merge(code.length2, ticks)?;
+            }
+            None => self.pending = Some((level, ticks)),
+            Some((pending_level, pending_ticks)) if pending_level == level => {
+                self.pending = Some((level, merge(pending_ticks, ticks)?));
+            }
+            Some((pending_level, pending_ticks)) => {
+                let code = &mut self.codes[self.len];
+                code.level1 = pending_level;
+                code.length1 = pending_ticks;
+                code.level2 = level;
+                code.length2 = ticks;
+                self.len += 1;
+                self.pending = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Append a pulse which can't be merged with the previous one
+    fn push_code(&mut self, code: PulseCode) {
+        self.codes[self.len] = code;
+        self.len += 1;
+    }
+
+    /// Complete the sequence
+    ///
+    /// Marks are high, a trailing space is dropped as it merges with the idle
+    /// level.
+    pub(crate) fn finish(mut self) -> Self {
+        match self.pending.take() {
+            Some((true, ticks)) => self.push_code(PulseCode {
+                level1: true,
+                length1: ticks,
+                level2: false,
+                length2: 0,
+            }),
+            Some((false, _)) => (),
+            None if self.len > 0 && !self.codes[self.len - 1].level2 => {
+                self.codes[self.len - 1].length2 = 0;
+            }
+            None => (),
+        }
+
+        self
+    }
+}
+
+impl<const N: usize> Encoder for Sequence<N> {
+    fn encode(&mut self) -> Option<PulseCode> {
+        let code = self.codes[..self.len].get(self.index).copied()?;
+        self.index += 1;
+        Some(code)
+    }
+}
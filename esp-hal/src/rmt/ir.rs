@@ -0,0 +1,629 @@
+//! # Infrared remote control protocols
+//!
+//! Encoders and decoders for the NEC, RC5, RC6 (mode 0) and Sony SIRC
+//! protocols.
+//!
+//! Encoders send marks as high level, the TX channel should be configured to
+//! modulate them with the carrier of the protocol (38 kHz for NEC, 36 kHz for
+//! RC5 and RC6 and 40 kHz for SIRC). Decoders only look at the lengths of the
+//! received pulses, so they work with both active-low and active-high IR
+//! receivers.
+//!
+//! Creating a codec fails with [Error::InvalidArgument] if the pulses of the
+//! protocol don't fit into pulse codes at the given resolution, e.g. the 9 ms
+//! NEC leader exceeds the longest pulse at resolutions above 3.6 MHz.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! let encoder = NecEncoder::new(
+//!     NecFrame::Command {
+//!         address: 0x04,
+//!         command: 0x08,
+//!     },
+//!     1.MHz(),
+//! )
+//! .unwrap();
+//! tx_channel = tx_channel.transmit_encoded(encoder).wait().unwrap();
+//!
+//! let mut data = [PulseCode::default(); 48];
+//! rx_channel = rx_channel.receive(&mut data).unwrap().wait().unwrap();
+//! let frame = NecDecoder::new(1.MHz()).unwrap().decode(&data).unwrap();
+//! ```
+
+use fugit::HertzU32;
+
+use super::{
+    codec::{
+        durations,
+        matches,
+        ns_to_ticks,
+        us_to_ticks,
+        DecodeError,
+        Decoder,
+        Encoder,
+        Sequence,
+    },
+    Error,
+    PulseCode,
+};
+
+/// A NEC frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NecFrame {
+    /// A command
+    ///
+    /// Addresses above `0xff` use the extended protocol, which sends the high
+    /// byte instead of the inverted low byte. Extended addresses whose high
+    /// byte is the inverted low byte are decoded as 8-bit addresses.
+    Command {
+        /// Address of the device
+        address: u16,
+        /// The command
+        command: u8,
+    },
+    /// The repeat code sent while a button is held
+    Repeat,
+}
+
+struct NecTiming {
+    leader_mark: u16,
+    leader_space: u16,
+    repeat_space: u16,
+    mark: u16,
+    space0: u16,
+    space1: u16,
+}
+
+impl NecTiming {
+    fn new(resolution: HertzU32) -> Result<Self, Error> {
+        Ok(Self {
+            leader_mark: us_to_ticks(resolution, 9000)?,
+            leader_space: us_to_ticks(resolution, 4500)?,
+            repeat_space: us_to_ticks(resolution, 2250)?,
+            mark: us_to_ticks(resolution, 560)?,
+            space0: us_to_ticks(resolution, 560)?,
+            space1: us_to_ticks(resolution, 1690)?,
+        })
+    }
+}
+
+/// Encoder for [NecFrame]s
+pub struct NecEncoder {
+    sequence: Sequence<34>,
+}
+
+impl NecEncoder {
+    /// Create an encoder for `frame` at the given channel resolution
+    pub fn new(frame: NecFrame, resolution: HertzU32) -> Result<Self, Error> {
+        let timing = NecTiming::new(resolution)?;
+        let mut sequence = Sequence::new();
+
+        sequence.push(true, timing.leader_mark)?;
+        match frame {
+            NecFrame::Command { address, command } => {
+                sequence.push(false, timing.leader_space)?;
+
+                let address_high = if address > 0xff {
+                    (address >> 8) as u8
+                } else {
+                    !(address as u8)
+                };
+                let data = u32::from_le_bytes([address as u8, address_high, command, !command]);
+                for bit in 0..32 {
+                    sequence.push(true, timing.mark)?;
+                    if data & (1 << bit) != 0 {
+                        sequence.push(false, timing.space1)?;
+                    } else {
+                        sequence.push(false, timing.space0)?;
+                    }
+                }
+            }
+            NecFrame::Repeat => sequence.push(false, timing.repeat_space)?,
+        }
+        sequence.push(true, timing.mark)?;
+
+        Ok(Self {
+            sequence: sequence.finish(),
+        })
+    }
+}
+
+impl Encoder for NecEncoder {
+    fn encode(&mut self) -> Option<PulseCode> {
+        self.sequence.encode()
+    }
+}
+
+/// Decoder for [NecFrame]s
+pub struct NecDecoder {
+    timing: NecTiming,
+}
+
+impl NecDecoder {
+    /// Create a decoder at the given channel resolution
+    pub fn new(resolution: HertzU32) -> Result<Self, Error> {
+        Ok(Self {
+            timing: NecTiming::new(resolution)?,
+        })
+    }
+}
+
+impl Decoder for NecDecoder {
+    type Message = NecFrame;
+
+    fn decode(&self, codes: &[PulseCode]) -> Result<NecFrame, DecodeError> {
+        let timing = &self.timing;
+        let mut durations = durations(codes);
+
+        let leader_mark = durations.next().ok_or(DecodeError::Incomplete)?;
+        if !matches(leader_mark, timing.leader_mark) {
+            return Err(DecodeError::InvalidLeader);
+        }
+
+        let leader_space = durations.next().ok_or(DecodeError::Incomplete)?;
+        if matches(leader_space, timing.repeat_space) {
+            let mark = durations.next().ok_or(DecodeError::Incomplete)?;
+            if !matches(mark, timing.mark) {
+                return Err(DecodeError::InvalidTiming);
+            }
+            return Ok(NecFrame::Repeat);
+        } else if !matches(leader_space, timing.leader_space) {
+            return Err(DecodeError::InvalidLeader);
+        }
+
+        let mut data = 0u32;
+        for bit in 0..32 {
+            let mark = durations.next().ok_or(DecodeError::Incomplete)?;
+            let space = durations.next().ok_or(DecodeError::Incomplete)?;
+            if !matches(mark, timing.mark) {
+                return Err(DecodeError::InvalidTiming);
+            }
+
+            if matches(space, timing.space1) {
+                data |= 1 << bit;
+            } else if !matches(space, timing.space0) {
+                return Err(DecodeError::InvalidTiming);
+            }
+        }
+
+        let [address_low, address_high, command, command_inverted] = data.to_le_bytes();
+        if command_inverted != !command {
+            return Err(DecodeError::Checksum);
+        }
+
+        let address = if address_high == !address_low {
+            address_low as u16
+        } else {
+            u16::from_le_bytes([address_low, address_high])
+        };
+
+        Ok(NecFrame::Command { address, command })
+    }
+}
+
+/// An RC5 frame
+///
+/// Commands above `0x3f` use the extended protocol (RC5X), which sends the
+/// inverted bit 6 of the command as the second start bit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Rc5Frame {
+    /// Address of the device (5 bits)
+    pub address: u8,
+    /// The command (7 bits)
+    pub command: u8,
+    /// Toggled each time a button is pressed again
+    pub toggle: bool,
+}
+
+const RC5_BITS: usize = 14;
+
+/// Encoder for [Rc5Frame]s
+pub struct Rc5Encoder {
+    sequence: Sequence<RC5_BITS>,
+}
+
+impl Rc5Encoder {
+    /// Create an encoder for `frame` at the given channel resolution
+    pub fn new(frame: Rc5Frame, resolution: HertzU32) -> Result<Self, Error> {
+        let half = us_to_ticks(resolution, 889)?;
+        let data = 1 << 13
+            | ((frame.command & 0x40 == 0) as u16) << 12
+            | (frame.toggle as u16) << 11
+            | ((frame.address & 0x1f) as u16) << 6
+            | (frame.command & 0x3f) as u16;
+
+        let mut sequence = Sequence::new();
+        for bit in (0..RC5_BITS).rev() {
+            // a one is a rising edge, a zero a falling edge
+            let first = data & (1 << bit) == 0;
+            // the transmission starts with the edge of the first start bit
+            if bit != RC5_BITS - 1 {
+                sequence.push(first, half)?;
+            }
+            sequence.push(!first, half)?;
+        }
+
+        Ok(Self {
+            sequence: sequence.finish(),
+        })
+    }
+}
+
+impl Encoder for Rc5Encoder {
+    fn encode(&mut self) -> Option<PulseCode> {
+        self.sequence.encode()
+    }
+}
+
+/// Decoder for [Rc5Frame]s
+pub struct Rc5Decoder {
+    half: u16,
+    full: u16,
+}
+
+impl Rc5Decoder {
+    /// Create a decoder at the given channel resolution
+    pub fn new(resolution: HertzU32) -> Result<Self, Error> {
+        Ok(Self {
+            half: us_to_ticks(resolution, 889)?,
+            full: us_to_ticks(resolution, 2 * 889)?,
+        })
+    }
+}
+
+impl Decoder for Rc5Decoder {
+    type Message = Rc5Frame;
+
+    fn decode(&self, codes: &[PulseCode]) -> Result<Rc5Frame, DecodeError> {
+        // the space of the first start bit isn't received
+        let mut halves = HalfBits::<{ RC5_BITS * 2 }>::new();
+        halves.push(false, 1)?;
+
+        for (idx, duration) in durations(codes).enumerate() {
+            let count = if matches(duration, self.half) {
+                1
+            } else if matches(duration, self.full) {
+                2
+            } else if idx == 0 {
+                return Err(DecodeError::InvalidLeader);
+            } else {
+                return Err(DecodeError::InvalidTiming);
+            };
+            halves.push(idx % 2 == 0, count)?;
+        }
+
+        // the space of a trailing zero merges with the idle level
+        halves.pad(false);
+        if halves.len < RC5_BITS * 2 {
+            return Err(DecodeError::Incomplete);
+        }
+
+        let mut data = 0u16;
+        for half in halves.levels.chunks(2) {
+            let bit = match half {
+                [false, true] => true,
+                [true, false] => false,
+                _ => return Err(DecodeError::InvalidTiming),
+            };
+            data = data << 1 | bit as u16;
+        }
+
+        Ok(Rc5Frame {
+            address: ((data >> 6) & 0x1f) as u8,
+            command: (data & 0x3f) as u8 | if data & (1 << 12) == 0 { 0x40 } else { 0 },
+            toggle: data & (1 << 11) != 0,
+        })
+    }
+}
+
+/// An RC6 mode 0 frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Rc6Frame {
+    /// Address of the device
+    pub address: u8,
+    /// The command
+    pub command: u8,
+    /// Toggled each time a button is pressed again
+    pub toggle: bool,
+}
+
+// start bit, 3 mode bits, the double length trailer bit and 16 data bits, in
+// units of half bits
+const RC6_HALVES: usize = 2 + 3 * 2 + 4 + 16 * 2;
+
+/// Length of an RC6 half bit in nanoseconds, 16 periods of the 36 kHz carrier
+const RC6_UNIT_NS: u32 = 444_444;
+
+/// Encoder for [Rc6Frame]s
+pub struct Rc6Encoder {
+    sequence: Sequence<{ RC6_HALVES / 2 + 1 }>,
+}
+
+impl Rc6Encoder {
+    /// Create an encoder for `frame` at the given channel resolution
+    pub fn new(frame: Rc6Frame, resolution: HertzU32) -> Result<Self, Error> {
+        let unit = ns_to_ticks(resolution, RC6_UNIT_NS)?;
+        let double = ns_to_ticks(resolution, 2 * RC6_UNIT_NS)?;
+        let mut sequence = Sequence::new();
+
+        sequence.push(true, ns_to_ticks(resolution, 6 * RC6_UNIT_NS)?)?;
+        sequence.push(false, double)?;
+
+        // a one is a falling edge, a zero a rising edge
+        let mut push_bit = |bit: bool, length: u16| {
+            sequence.push(bit, length)?;
+            sequence.push(!bit, length)
+        };
+
+        // start bit and mode 0
+        push_bit(true, unit)?;
+        for _ in 0..3 {
+            push_bit(false, unit)?;
+        }
+        push_bit(frame.toggle, double)?;
+
+        let data = (frame.address as u16) << 8 | frame.command as u16;
+        for bit in (0..16).rev() {
+            push_bit(data & (1 << bit) != 0, unit)?;
+        }
+
+        Ok(Self {
+            sequence: sequence.finish(),
+        })
+    }
+}
+
+impl Encoder for Rc6Encoder {
+    fn encode(&mut self) -> Option<PulseCode> {
+        self.sequence.encode()
+    }
+}
+
+/// Decoder for [Rc6Frame]s
+///
+/// Only mode 0 is supported.
+pub struct Rc6Decoder {
+    unit: u16,
+    leader_mark: u16,
+    leader_space: u16,
+}
+
+impl Rc6Decoder {
+    /// Create a decoder at the given channel resolution
+    pub fn new(resolution: HertzU32) -> Result<Self, Error> {
+        Ok(Self {
+            unit: ns_to_ticks(resolution, RC6_UNIT_NS)?,
+            leader_mark: ns_to_ticks(resolution, 6 * RC6_UNIT_NS)?,
+            leader_space: ns_to_ticks(resolution, 2 * RC6_UNIT_NS)?,
+        })
+    }
+}
+
+impl Decoder for Rc6Decoder {
+    type Message = Rc6Frame;
+
+    fn decode(&self, codes: &[PulseCode]) -> Result<Rc6Frame, DecodeError> {
+        let mut durations = durations(codes);
+
+        let leader_mark = durations.next().ok_or(DecodeError::Incomplete)?;
+        let leader_space = durations.next().ok_or(DecodeError::Incomplete)?;
+        if !matches(leader_mark, self.leader_mark) || !matches(leader_space, self.leader_space) {
+            return Err(DecodeError::InvalidLeader);
+        }
+
+        let mut halves = HalfBits::<RC6_HALVES>::new();
+        for (idx, duration) in durations.enumerate() {
+            // the trailer bit merges with its neighbours into up to 3 units
+            let count = (duration + self.unit / 2) / self.unit;
+            if !(1..=3).contains(&count) || !matches(duration, count * self.unit) {
+                return Err(DecodeError::InvalidTiming);
+            }
+            halves.push(idx % 2 == 0, count as usize)?;
+        }
+
+        // the space of a trailing one merges with the idle level
+        halves.pad(false);
+        if halves.len < RC6_HALVES {
+            return Err(DecodeError::Incomplete);
+        }
+
+        let levels = &halves.levels;
+        let bit = |idx: usize| match (levels[idx], levels[idx + 1]) {
+            (true, false) => Ok(true),
+            (false, true) => Ok(false),
+            _ => Err(DecodeError::InvalidTiming),
+        };
+
+        if !bit(0)? {
+            return Err(DecodeError::InvalidTiming);
+        }
+        if bit(2)? || bit(4)? || bit(6)? {
+            return Err(DecodeError::Unsupported);
+        }
+        if levels[8] != levels[9] || levels[10] != levels[11] || levels[8] == levels[10] {
+            return Err(DecodeError::InvalidTiming);
+        }
+
+        let mut data = 0u16;
+        for idx in (12..RC6_HALVES).step_by(2) {
+            data = data << 1 | bit(idx)? as u16;
+        }
+
+        Ok(Rc6Frame {
+            address: (data >> 8) as u8,
+            command: data as u8,
+            toggle: levels[8],
+        })
+    }
+}
+
+/// Length of a Sony SIRC frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SircLength {
+    /// 7 command bits and 5 address bits
+    Bits12,
+    /// 7 command bits and 8 address bits
+    Bits15,
+    /// 7 command bits, 5 address bits and 8 extended bits
+    Bits20,
+}
+
+impl SircLength {
+    fn address_bits(self) -> usize {
+        match self {
+            SircLength::Bits12 => 5,
+            SircLength::Bits15 => 8,
+            SircLength::Bits20 => 13,
+        }
+    }
+}
+
+/// A Sony SIRC frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SircFrame {
+    /// The command (7 bits)
+    pub command: u8,
+    /// Address of the device, the extended bits of 20-bit frames are bits 5
+    /// to 12
+    pub address: u16,
+    /// Length of the frame
+    pub length: SircLength,
+}
+
+/// Encoder for [SircFrame]s
+pub struct SircEncoder {
+    sequence: Sequence<21>,
+}
+
+impl SircEncoder {
+    /// Create an encoder for `frame` at the given channel resolution
+    pub fn new(frame: SircFrame, resolution: HertzU32) -> Result<Self, Error> {
+        let unit = us_to_ticks(resolution, 600)?;
+        let long = us_to_ticks(resolution, 2 * 600)?;
+        let mut sequence = Sequence::new();
+
+        sequence.push(true, us_to_ticks(resolution, 4 * 600)?)?;
+
+        let bits = 7 + frame.length.address_bits();
+        let data = (frame.command & 0x7f) as u32 | (frame.address as u32) << 7;
+        for bit in 0..bits {
+            sequence.push(false, unit)?;
+            let mark = if data & (1 << bit) != 0 { long } else { unit };
+            sequence.push(true, mark)?;
+        }
+
+        Ok(Self {
+            sequence: sequence.finish(),
+        })
+    }
+}
+
+impl Encoder for SircEncoder {
+    fn encode(&mut self) -> Option<PulseCode> {
+        self.sequence.encode()
+    }
+}
+
+/// Decoder for [SircFrame]s
+pub struct SircDecoder {
+    unit: u16,
+    long: u16,
+    leader: u16,
+}
+
+impl SircDecoder {
+    /// Create a decoder at the given channel resolution
+    pub fn new(resolution: HertzU32) -> Result<Self, Error> {
+        Ok(Self {
+            unit: us_to_ticks(resolution, 600)?,
+            long: us_to_ticks(resolution, 2 * 600)?,
+            leader: us_to_ticks(resolution, 4 * 600)?,
+        })
+    }
+}
+
+impl Decoder for SircDecoder {
+    type Message = SircFrame;
+
+    fn decode(&self, codes: &[PulseCode]) -> Result<SircFrame, DecodeError> {
+        let mut durations = durations(codes);
+
+        let leader = durations.next().ok_or(DecodeError::Incomplete)?;
+        if !matches(leader, self.leader) {
+            return Err(DecodeError::InvalidLeader);
+        }
+
+        let mut data = 0u32;
+        let mut bits = 0;
+        while let Some(space) = durations.next() {
+            let mark = durations.next().ok_or(DecodeError::Incomplete)?;
+            if !matches(space, self.unit) {
+                return Err(DecodeError::InvalidTiming);
+            }
+
+            if matches(mark, self.long) {
+                data |= 1 << bits;
+            } else if !matches(mark, self.unit) {
+                return Err(DecodeError::InvalidTiming);
+            }
+
+            bits += 1;
+            if bits > 20 {
+                return Err(DecodeError::Unsupported);
+            }
+        }
+
+        let length = match bits {
+            0..=11 => return Err(DecodeError::Incomplete),
+            12 => SircLength::Bits12,
+            15 => SircLength::Bits15,
+            20 => SircLength::Bits20,
+            _ => return Err(DecodeError::Unsupported),
+        };
+
+        Ok(SircFrame {
+            command: (data & 0x7f) as u8,
+            address: (data >> 7) as u16,
+            length,
+        })
+    }
+}
+
+/// Levels of the half bits of a Manchester coded frame
+struct HalfBits<const N: usize> {
+    levels: [bool; N],
+    len: usize,
+}
+
+impl<const N: usize> HalfBits<N> {
+    fn new() -> Self {
+        Self {
+            levels: [false; N],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, level: bool, count: usize) -> Result<(), DecodeError> {
+        if self.len + count > N {
+            return Err(DecodeError::InvalidTiming);
+        }
+
+        self.levels[self.len..][..count].fill(level);
+        self.len += count;
+        Ok(())
+    }
+
+    /// Add the half bit of a final edge which merged with the idle level
+    fn pad(&mut self, idle: bool) {
+        if self.len == N - 1 {
+            self.levels[self.len] = idle;
+            self.len += 1;
+        }
+    }
+}
@@ -82,10 +82,37 @@
 //! let transaction = channel.transmit(&data);
 //! channel = transaction.wait().unwrap();
 //! ```
+//!
+//! ### Long sequences
+//!
+//! A channel uses one memory block by default, `memsize` in
+//! [TxChannelConfig] and [RxChannelConfig] lets it borrow the blocks of the
+//! following channels. Sequences exceeding the memory blocks of a channel are
+//! refilled while transmitting and, except on ESP32 and ESP32-S2, copied out
+//! while receiving.
+//!
+//! ### Encoders and decoders
+//!
+//! Instead of building pulse code arrays by hand, messages can be sent with
+//! an [Encoder](codec::Encoder) via [TxChannel::transmit_encoded] and
+//! received pulse codes turned back into messages with a
+//! [Decoder](codec::Decoder). See [codec] and [ir] for the built-in ones.
+//!
+//! ### Synchronized transmission
+//!
+//! Except on ESP32 and ESP32-S2, configured TX channels can be gathered into
+//! a `TxSyncGroup`, whose channels start transmitting on the same clock edge.
+//!
+//! ```no_run
+//! let group = TxSyncGroup::new((channel0, channel1));
+//! let transaction = group.transmit(&[&data0, &data1]).unwrap();
+//! let (channel0, channel1) = transaction.wait().unwrap().release();
+//! ```
 #![warn(missing_docs)]
 
-use core::marker::PhantomData;
+use core::{cell::Cell, marker::PhantomData};
 
+use critical_section::Mutex;
 use fugit::HertzU32;
 
 use crate::{
@@ -93,11 +120,13 @@ use crate::{
     gpio::{InputPin, OutputPin},
     interrupt::InterruptHandler,
     peripheral::Peripheral,
-    rmt::private::CreateInstance,
-    soc::constants,
+    rmt::{codec::Encoder, private::CreateInstance},
     system::PeripheralClockControl,
 };
 
+pub mod codec;
+pub mod ir;
+
 /// Errors
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -110,6 +139,8 @@ pub enum Error {
     InvalidArgument,
     /// An error occurred during transmission
     TransmissionError,
+    /// A memory block of the channel is used by another channel
+    MemoryBlockNotAvailable,
 }
 
 /// Convenience representation of a pulse code entry.
@@ -169,7 +200,7 @@ impl From<PulseCode> for u32 {
 }
 
 /// Channel configuration for TX channels
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct TxChannelConfig {
     /// Channel's clock divider
@@ -186,10 +217,32 @@ pub struct TxChannelConfig {
     pub carrier_low: u16,
     /// Level of the carrier
     pub carrier_level: bool,
+    /// Number of memory blocks used by the channel
+    ///
+    /// A channel can borrow the blocks of the following channels, which
+    /// cannot be configured while they are borrowed. Configuring a channel
+    /// fails with [Error::MemoryBlockNotAvailable] if one of its blocks is
+    /// used by another channel.
+    pub memsize: u8,
+}
+
+impl Default for TxChannelConfig {
+    fn default() -> Self {
+        Self {
+            clk_divider: 0,
+            idle_output_level: false,
+            idle_output: false,
+            carrier_modulation: false,
+            carrier_high: 0,
+            carrier_low: 0,
+            carrier_level: false,
+            memsize: 1,
+        }
+    }
 }
 
 /// Channel configuration for RX channels
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct RxChannelConfig {
     /// Channel's clock divider
@@ -206,10 +259,78 @@ pub struct RxChannelConfig {
     pub filter_threshold: u8,
     /// Idle threshold in ticks
     pub idle_threshold: u16,
+    /// Number of memory blocks used by the channel
+    ///
+    /// A channel can borrow the blocks of the following channels, which
+    /// cannot be configured while they are borrowed. Configuring a channel
+    /// fails with [Error::MemoryBlockNotAvailable] if one of its blocks is
+    /// used by another channel.
+    pub memsize: u8,
 }
 
+impl Default for RxChannelConfig {
+    fn default() -> Self {
+        Self {
+            clk_divider: 0,
+            carrier_modulation: false,
+            carrier_high: 0,
+            carrier_low: 0,
+            carrier_level: false,
+            filter_threshold: 0,
+            idle_threshold: 0,
+            memsize: 1,
+        }
+    }
+}
+
+#[cfg(any(esp32, esp32s3))]
+const NUM_CHANNELS: usize = 8;
+#[cfg(not(any(esp32, esp32s3)))]
+const NUM_CHANNELS: usize = 4;
+
 pub use impl_for_chip::Rmt;
 
+/// Number of memory blocks used by each configured channel, zero if the
+/// channel is not configured
+static MEMORY_BLOCKS: Mutex<Cell<[u8; NUM_CHANNELS]>> = Mutex::new(Cell::new([0; NUM_CHANNELS]));
+
+/// Bit mask of the memory blocks `channel` uses with the given `memsize`
+fn memory_block_mask(channel: usize, memsize: u8) -> u32 {
+    ((1 << memsize) - 1) << channel
+}
+
+/// Claim the memory blocks of `channel`, none of them may be used by another
+/// configured channel
+fn claim_memory_blocks(channel: u8, memsize: u8) -> Result<(), Error> {
+    if memsize == 0 || channel as usize + memsize as usize > NUM_CHANNELS {
+        return Err(Error::InvalidArgument);
+    }
+
+    critical_section::with(|cs| {
+        let mut claimed = MEMORY_BLOCKS.borrow(cs).get();
+        let used = claimed
+            .iter()
+            .enumerate()
+            .fold(0, |used, (ch, &size)| used | memory_block_mask(ch, size));
+
+        if used & memory_block_mask(channel as usize, memsize) != 0 {
+            return Err(Error::MemoryBlockNotAvailable);
+        }
+
+        claimed[channel as usize] = memsize;
+        MEMORY_BLOCKS.borrow(cs).set(claimed);
+        Ok(())
+    })
+}
+
+fn release_memory_blocks(channel: u8) {
+    critical_section::with(|cs| {
+        let mut claimed = MEMORY_BLOCKS.borrow(cs).get();
+        claimed[channel as usize] = 0;
+        MEMORY_BLOCKS.borrow(cs).set(claimed);
+    });
+}
+
 #[cfg(feature = "async")]
 use self::asynch::{RxChannelAsync, TxChannelAsync};
 
@@ -316,10 +437,13 @@ where
     where
         Self: Sized,
     {
+        claim_memory_blocks(T::CHANNEL, config.memsize)?;
+
         crate::into_ref!(pin);
         pin.set_to_push_pull_output(crate::private::Internal);
         pin.connect_peripheral_to_output(T::output_signal(), crate::private::Internal);
         T::set_divider(config.clk_divider);
+        T::set_memsize(config.memsize);
         T::set_carrier(
             config.carrier_modulation,
             config.carrier_high,
@@ -348,10 +472,13 @@ where
     where
         Self: Sized,
     {
+        claim_memory_blocks(T::CHANNEL, config.memsize)?;
+
         crate::into_ref!(pin);
         pin.set_to_push_pull_output(crate::private::Internal);
         pin.connect_peripheral_to_output(T::output_signal(), crate::private::Internal);
         T::set_divider(config.clk_divider);
+        T::set_memsize(config.memsize);
         T::set_carrier(
             config.carrier_modulation,
             config.carrier_high,
@@ -393,6 +520,8 @@ where
             return Err(Error::InvalidArgument);
         }
 
+        claim_memory_blocks(T::CHANNEL, config.memsize)?;
+
         crate::into_ref!(pin);
         pin.set_to_input(crate::private::Internal);
         pin.connect_input_to_peripheral(T::input_signal(), crate::private::Internal);
@@ -405,6 +534,7 @@ where
         );
         T::set_filter_threshold(config.filter_threshold);
         T::set_idle_threshold(config.idle_threshold);
+        T::set_memsize(config.memsize);
 
         Ok(T::new())
     }
@@ -440,6 +570,8 @@ where
             return Err(Error::InvalidArgument);
         }
 
+        claim_memory_blocks(T::CHANNEL, config.memsize)?;
+
         crate::into_ref!(pin);
         pin.set_to_input(crate::private::Internal);
         pin.connect_input_to_peripheral(T::input_signal(), crate::private::Internal);
@@ -452,6 +584,7 @@ where
         );
         T::set_filter_threshold(config.filter_threshold);
         T::set_idle_threshold(config.idle_threshold);
+        T::set_memsize(config.memsize);
 
         Ok(T::new())
     }
@@ -488,25 +621,7 @@ where
                 <C as private::TxChannelInternal<crate::Blocking>>::reset_threshold_set();
 
                 // re-fill TX RAM
-                let ram_index = (((self.index - constants::RMT_CHANNEL_RAM_SIZE)
-                    / (constants::RMT_CHANNEL_RAM_SIZE / 2))
-                    % 2)
-                    * (constants::RMT_CHANNEL_RAM_SIZE / 2);
-
-                let ptr = (constants::RMT_RAM_START
-                    + C::CHANNEL as usize * constants::RMT_CHANNEL_RAM_SIZE * 4
-                    + ram_index * 4) as *mut u32;
-                for (idx, entry) in self.data[self.index..]
-                    .iter()
-                    .take(constants::RMT_CHANNEL_RAM_SIZE / 2)
-                    .enumerate()
-                {
-                    unsafe {
-                        ptr.add(idx).write_volatile((*entry).into());
-                    }
-                }
-
-                self.index += constants::RMT_CHANNEL_RAM_SIZE / 2;
+                self.index = C::refill_raw(self.data, self.index);
             } else {
                 break;
             }
@@ -526,6 +641,59 @@ where
     }
 }
 
+/// An in-progress TX transaction streaming the pulse codes of an [Encoder]
+pub struct EncodedTxTransaction<C, E>
+where
+    C: TxChannel,
+    E: Encoder,
+{
+    channel: C,
+    encoder: E,
+    ram_index: usize,
+    pending: bool,
+}
+
+impl<C, E> EncodedTxTransaction<C, E>
+where
+    C: TxChannel,
+    E: Encoder,
+{
+    /// Wait for the transaction to complete
+    pub fn wait(mut self) -> Result<C, (Error, C)> {
+        let half = C::ram_size() / 2;
+
+        while self.pending {
+            // wait for TX-THR
+            loop {
+                if <C as private::TxChannelInternal<crate::Blocking>>::is_error() {
+                    return Err((Error::TransmissionError, self.channel));
+                }
+
+                if <C as private::TxChannelInternal<crate::Blocking>>::is_threshold_set() {
+                    break;
+                }
+            }
+            <C as private::TxChannelInternal<crate::Blocking>>::reset_threshold_set();
+
+            // re-fill the half of the TX RAM which has been sent
+            self.pending = C::write_encoded(&mut self.encoder, self.ram_index, half);
+            self.ram_index = (self.ram_index + half) % (half * 2);
+        }
+
+        loop {
+            if <C as private::TxChannelInternal<crate::Blocking>>::is_error() {
+                return Err((Error::TransmissionError, self.channel));
+            }
+
+            if <C as private::TxChannelInternal<crate::Blocking>>::is_done() {
+                break;
+            }
+        }
+
+        Ok(self.channel)
+    }
+}
+
 /// An in-progress continuous TX transaction
 pub struct ContinuousTxTransaction<C>
 where
@@ -561,10 +729,8 @@ where
         <C as private::TxChannelInternal<crate::Blocking>>::set_continuous(false);
         <C as private::TxChannelInternal<crate::Blocking>>::update();
 
-        let ptr = (constants::RMT_RAM_START
-            + C::CHANNEL as usize * constants::RMT_CHANNEL_RAM_SIZE * 4)
-            as *mut u32;
-        for idx in 0..constants::RMT_CHANNEL_RAM_SIZE {
+        let ptr = C::ram_ptr();
+        for idx in 0..C::ram_size() {
             unsafe {
                 ptr.add(idx).write_volatile(0);
             }
@@ -589,6 +755,254 @@ where
     }
 }
 
+/// Maximum number of channels in a [TxSyncGroup]
+#[cfg(not(any(esp32, esp32s2)))]
+const MAX_TX_SYNC_CHANNELS: usize = 4;
+
+/// TX channels which can be gathered into a [TxSyncGroup]
+///
+/// Implemented for tuples of two to four TX channels.
+#[cfg(not(any(esp32, esp32s2)))]
+pub trait TxSyncChannels<M>: private::TxSyncChannelsInternal<M>
+where
+    M: crate::Mode,
+{
+}
+
+#[cfg(not(any(esp32, esp32s2)))]
+impl<M, C> TxSyncChannels<M> for C
+where
+    M: crate::Mode,
+    C: private::TxSyncChannelsInternal<M>,
+{
+}
+
+#[cfg(not(any(esp32, esp32s2)))]
+macro_rules! tx_sync_dispatch {
+    ($idx:expr, [$($ch:ident $n:tt),+], $method:ident $args:tt) => {
+        match $idx {
+            $($n => <$ch as private::TxChannelInternal<M>>::$method $args,)+
+            _ => unreachable!(),
+        }
+    };
+}
+
+#[cfg(not(any(esp32, esp32s2)))]
+macro_rules! impl_tx_sync_channels {
+    ($len:literal: $($ch:ident $n:tt),+) => {
+        impl<M, $($ch),+> private::TxSyncChannelsInternal<M> for ($($ch,)+)
+        where
+            M: crate::Mode,
+            $($ch: private::TxChannelInternal<M>,)+
+        {
+            const LEN: usize = $len;
+
+            fn mask() -> u8 {
+                0 $(| 1 << <$ch as private::TxChannelInternal<M>>::CHANNEL)+
+            }
+
+            #[cfg(feature = "async")]
+            fn channel(idx: usize) -> u8 {
+                match idx {
+                    $($n => <$ch as private::TxChannelInternal<M>>::CHANNEL,)+
+                    _ => unreachable!(),
+                }
+            }
+
+            fn prepare_raw<T: Into<u32> + Copy>(idx: usize, data: &[T]) -> usize {
+                tx_sync_dispatch!(idx, [$($ch $n),+], prepare_raw(data, false, 0))
+            }
+
+            fn refill_raw<T: Into<u32> + Copy>(idx: usize, data: &[T], index: usize) -> usize {
+                tx_sync_dispatch!(idx, [$($ch $n),+], refill_raw(data, index))
+            }
+
+            fn update(idx: usize) {
+                tx_sync_dispatch!(idx, [$($ch $n),+], update())
+            }
+
+            fn start_tx_synced(idx: usize) {
+                tx_sync_dispatch!(idx, [$($ch $n),+], start_tx_synced())
+            }
+
+            fn is_done(idx: usize) -> bool {
+                tx_sync_dispatch!(idx, [$($ch $n),+], is_done())
+            }
+
+            fn is_error(idx: usize) -> bool {
+                tx_sync_dispatch!(idx, [$($ch $n),+], is_error())
+            }
+
+            fn is_threshold_set(idx: usize) -> bool {
+                tx_sync_dispatch!(idx, [$($ch $n),+], is_threshold_set())
+            }
+
+            fn reset_threshold_set(idx: usize) {
+                tx_sync_dispatch!(idx, [$($ch $n),+], reset_threshold_set())
+            }
+
+            #[cfg(feature = "async")]
+            fn listen_interrupt(idx: usize, event: private::Event) {
+                tx_sync_dispatch!(idx, [$($ch $n),+], listen_interrupt(event))
+            }
+
+            #[cfg(feature = "async")]
+            fn unlisten_interrupt(idx: usize, event: private::Event) {
+                tx_sync_dispatch!(idx, [$($ch $n),+], unlisten_interrupt(event))
+            }
+        }
+    };
+}
+
+#[cfg(not(any(esp32, esp32s2)))]
+impl_tx_sync_channels!(2: A 0, B 1);
+#[cfg(not(any(esp32, esp32s2)))]
+impl_tx_sync_channels!(3: A 0, B 1, C 2);
+#[cfg(not(any(esp32, esp32s2)))]
+impl_tx_sync_channels!(4: A 0, B 1, C 2, D 3);
+
+/// TX channels starting their transmissions on the same clock edge
+///
+/// The peripheral has a single sync group, only one [TxSyncGroup] should
+/// exist at a time.
+#[cfg(not(any(esp32, esp32s2)))]
+#[derive(Debug)]
+pub struct TxSyncGroup<M, C>
+where
+    M: crate::Mode,
+    C: TxSyncChannels<M>,
+{
+    channels: C,
+    phantom: PhantomData<M>,
+}
+
+#[cfg(not(any(esp32, esp32s2)))]
+impl<M, C> TxSyncGroup<M, C>
+where
+    M: crate::Mode,
+    C: TxSyncChannels<M>,
+{
+    /// Gather the configured TX `channels` into the sync group
+    pub fn new(channels: C) -> Self {
+        chip_specific::set_tx_sync(C::mask());
+
+        Self {
+            channels,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Disable synchronization and get back the channels
+    pub fn release(self) -> C {
+        chip_specific::set_tx_sync(0);
+        self.channels
+    }
+
+    /// Fill the RAM of every channel with the beginning of its sequence,
+    /// returns the indices of the next pulse codes to send
+    fn prepare<T: Into<u32> + Copy>(data: &[&[T]]) -> Result<[usize; MAX_TX_SYNC_CHANNELS], Error> {
+        if data.len() != C::LEN {
+            return Err(Error::InvalidArgument);
+        }
+
+        let mut index = [0; MAX_TX_SYNC_CHANNELS];
+        for (idx, data) in data.iter().enumerate() {
+            index[idx] = C::prepare_raw(idx, data);
+        }
+
+        Ok(index)
+    }
+
+    fn start() {
+        chip_specific::reset_clock_dividers(C::mask());
+        for idx in 0..C::LEN {
+            C::update(idx);
+        }
+
+        // the channels only start once all of them have been started
+        for idx in 0..C::LEN {
+            C::start_tx_synced(idx);
+        }
+    }
+}
+
+#[cfg(not(any(esp32, esp32s2)))]
+impl<C> TxSyncGroup<crate::Blocking, C>
+where
+    C: TxSyncChannels<crate::Blocking>,
+{
+    /// Start transmitting `data[n]` on the `n`th channel of the group.
+    /// This returns a [`SyncTxTransaction`] which can be used to wait for
+    /// the transaction to complete and get back the group for further use.
+    pub fn transmit<'a, T: Into<u32> + Copy>(
+        self,
+        data: &[&'a [T]],
+    ) -> Result<SyncTxTransaction<'a, C, T>, Error> {
+        let index = Self::prepare(data)?;
+        Self::start();
+
+        let mut sequences: [&'a [T]; MAX_TX_SYNC_CHANNELS] = [&[]; MAX_TX_SYNC_CHANNELS];
+        sequences[..data.len()].copy_from_slice(data);
+
+        Ok(SyncTxTransaction {
+            group: self,
+            data: sequences,
+            index,
+        })
+    }
+}
+
+/// An in-progress transaction of a [TxSyncGroup]
+#[cfg(not(any(esp32, esp32s2)))]
+pub struct SyncTxTransaction<'a, C, T: Into<u32> + Copy>
+where
+    C: TxSyncChannels<crate::Blocking>,
+{
+    group: TxSyncGroup<crate::Blocking, C>,
+    data: [&'a [T]; MAX_TX_SYNC_CHANNELS],
+    index: [usize; MAX_TX_SYNC_CHANNELS],
+}
+
+#[cfg(not(any(esp32, esp32s2)))]
+impl<'a, C, T: Into<u32> + Copy> SyncTxTransaction<'a, C, T>
+where
+    C: TxSyncChannels<crate::Blocking>,
+{
+    /// Wait for the transactions of all channels to complete
+    #[allow(clippy::type_complexity)]
+    pub fn wait(
+        mut self,
+    ) -> Result<TxSyncGroup<crate::Blocking, C>, (Error, TxSyncGroup<crate::Blocking, C>)> {
+        loop {
+            let mut complete = true;
+
+            for idx in 0..C::LEN {
+                if C::is_error(idx) {
+                    return Err((Error::TransmissionError, self.group));
+                }
+
+                if self.index[idx] < self.data[idx].len() {
+                    complete = false;
+
+                    // re-fill TX RAM
+                    if C::is_threshold_set(idx) {
+                        C::reset_threshold_set(idx);
+                        self.index[idx] = C::refill_raw(idx, self.data[idx], self.index[idx]);
+                    }
+                } else if !C::is_done(idx) {
+                    complete = false;
+                }
+            }
+
+            if complete {
+                break;
+            }
+        }
+
+        Ok(self.group)
+    }
+}
+
 macro_rules! impl_tx_channel_creator {
     ($channel:literal) => {
         impl<'d, P> $crate::rmt::TxChannelCreator<'d, $crate::rmt::Channel<$crate::Blocking, $channel>, P>
@@ -987,6 +1401,15 @@ where
     phantom: PhantomData<M>,
 }
 
+impl<M, const CHANNEL: u8> Drop for Channel<M, CHANNEL>
+where
+    M: crate::Mode,
+{
+    fn drop(&mut self) {
+        release_memory_blocks(CHANNEL);
+    }
+}
+
 /// Channel in TX mode
 pub trait TxChannel: private::TxChannelInternal<crate::Blocking> {
     /// Start transmitting the given pulse code sequence.
@@ -1005,10 +1428,27 @@ pub trait TxChannel: private::TxChannelInternal<crate::Blocking> {
         }
     }
 
+    /// Start transmitting the pulse codes produced by `encoder`.
+    /// The pulse codes are streamed into the memory blocks of the channel
+    /// while transmitting, the end marker is added after the last one.
+    fn transmit_encoded<E: Encoder>(self, mut encoder: E) -> EncodedTxTransaction<Self, E>
+    where
+        Self: Sized,
+    {
+        let pending = Self::send_encoded(&mut encoder);
+        EncodedTxTransaction {
+            channel: self,
+            encoder,
+            ram_index: 0,
+            pending,
+        }
+    }
+
     /// Start transmitting the given pulse code continuously.
     /// This returns a [`ContinuousTxTransaction`] which can be used to stop the
     /// ongoing transmission and get back the channel for further use.
-    /// The length of sequence cannot exceed the size of the allocated RMT RAM.
+    /// The length of sequence cannot exceed the size of the memory blocks used
+    /// by the channel.
     fn transmit_continuously<T: Into<u32> + Copy>(
         self,
         data: &[T],
@@ -1030,7 +1470,7 @@ pub trait TxChannel: private::TxChannelInternal<crate::Blocking> {
     where
         Self: Sized,
     {
-        if data.len() > constants::RMT_CHANNEL_RAM_SIZE {
+        if data.len() > Self::ram_size() {
             return Err(Error::Overflow);
         }
 
@@ -1046,6 +1486,10 @@ where
 {
     channel: C,
     data: &'a mut [T],
+    wrap: bool,
+    index: usize,
+    ram_index: usize,
+    complete: bool,
 }
 
 impl<'a, C, T: From<u32> + Copy> RxTransaction<'a, C, T>
@@ -1053,12 +1497,33 @@ where
     C: RxChannel,
 {
     /// Wait for the transaction to complete
-    pub fn wait(self) -> Result<C, (Error, C)> {
+    pub fn wait(mut self) -> Result<C, (Error, C)> {
         loop {
             if <C as private::RxChannelInternal<crate::Blocking>>::is_error() {
                 return Err((Error::TransmissionError, self.channel));
             }
 
+            // empty the half of the RAM which has been filled
+            #[cfg(not(any(esp32, esp32s2)))]
+            if self.wrap && <C as private::RxChannelInternal<crate::Blocking>>::is_threshold_set() {
+                <C as private::RxChannelInternal<crate::Blocking>>::reset_threshold_set();
+
+                let half = C::ram_size() / 2;
+                let (len, end) = C::read_raw(&mut self.data[self.index..], self.ram_index, half);
+                self.index += len;
+                self.ram_index = (self.ram_index + half) % C::ram_size();
+                self.complete = end;
+
+                if !end && len < half {
+                    <C as private::RxChannelInternal<crate::Blocking>>::stop();
+                    <C as private::RxChannelInternal<crate::Blocking>>::clear_interrupts();
+                    <C as private::RxChannelInternal<crate::Blocking>>::update();
+                    return Err((Error::Overflow, self.channel));
+                }
+
+                continue;
+            }
+
             if <C as private::RxChannelInternal<crate::Blocking>>::is_done() {
                 break;
             }
@@ -1068,12 +1533,12 @@ where
         <C as private::RxChannelInternal<crate::Blocking>>::clear_interrupts();
         <C as private::RxChannelInternal<crate::Blocking>>::update();
 
-        let ptr = (constants::RMT_RAM_START
-            + C::CHANNEL as usize * constants::RMT_CHANNEL_RAM_SIZE * 4)
-            as *mut u32;
-        let len = self.data.len();
-        for (idx, entry) in self.data.iter_mut().take(len).enumerate() {
-            *entry = unsafe { ptr.add(idx).read_volatile().into() };
+        if !self.complete {
+            let (_, end) = C::read_raw(&mut self.data[self.index..], self.ram_index, C::ram_size());
+
+            if self.wrap && !end {
+                return Err((Error::Overflow, self.channel));
+            }
         }
 
         Ok(self.channel)
@@ -1085,20 +1550,34 @@ pub trait RxChannel: private::RxChannelInternal<crate::Blocking> {
     /// Start receiving pulse codes into the given buffer.
     /// This returns a [RxTransaction] which can be used to wait for receive to
     /// complete and get back the channel for further use.
-    /// The length of the received data cannot exceed the allocated RMT RAM.
+    #[cfg_attr(
+        not(any(esp32, esp32s2)),
+        doc = "The received data is copied out of the channel RAM while receiving if it exceeds the memory blocks used by the channel, [Error::Overflow] is returned if it exceeds the given buffer."
+    )]
+    #[cfg_attr(
+        any(esp32, esp32s2),
+        doc = "The length of the received data cannot exceed the memory blocks used by the channel."
+    )]
     fn receive<T: From<u32> + Copy>(self, data: &mut [T]) -> Result<RxTransaction<Self, T>, Error>
     where
         Self: Sized,
     {
-        if data.len() > constants::RMT_CHANNEL_RAM_SIZE {
+        let wrap = data.len() > Self::ram_size();
+
+        #[cfg(any(esp32, esp32s2))]
+        if wrap {
             return Err(Error::InvalidArgument);
         }
 
-        Self::start_receive_raw();
+        Self::start_receive_raw(wrap);
 
         Ok(RxTransaction {
             channel: self,
             data,
+            wrap,
+            index: 0,
+            ram_index: 0,
+            complete: false,
         })
     }
 }
@@ -1116,11 +1595,6 @@ pub mod asynch {
 
     use super::{private::Event, *};
 
-    #[cfg(any(esp32, esp32s3))]
-    const NUM_CHANNELS: usize = 8;
-    #[cfg(not(any(esp32, esp32s3)))]
-    const NUM_CHANNELS: usize = 4;
-
     const INIT: AtomicWaker = AtomicWaker::new();
     static WAKER: [AtomicWaker; NUM_CHANNELS] = [INIT; NUM_CHANNELS];
 
@@ -1151,7 +1625,7 @@ pub mod asynch {
         fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
             WAKER[T::CHANNEL as usize].register(ctx.waker());
 
-            if T::is_error() || T::is_done() {
+            if T::is_error() || T::is_done() || T::is_threshold_set() {
                 Poll::Ready(())
             } else {
                 Poll::Pending
@@ -1162,28 +1636,205 @@ pub mod asynch {
     /// TX channel in async mode
     pub trait TxChannelAsync: private::TxChannelInternal<crate::Async> {
         /// Start transmitting the given pulse code sequence.
-        /// The length of sequence cannot exceed the size of the allocated RMT
-        /// RAM.
+        /// Sequences exceeding the memory blocks used by the channel are
+        /// refilled while transmitting.
         async fn transmit<'a, T: Into<u32> + Copy>(&mut self, data: &'a [T]) -> Result<(), Error>
         where
             Self: Sized,
         {
-            if data.len() > constants::RMT_CHANNEL_RAM_SIZE {
-                return Err(Error::InvalidArgument);
+            Self::clear_interrupts();
+            Self::listen_interrupt(super::private::Event::End);
+            Self::listen_interrupt(super::private::Event::Error);
+            let mut index = Self::send_raw(data, false, 0);
+
+            loop {
+                if index < data.len() {
+                    Self::listen_interrupt(super::private::Event::Threshold);
+                }
+
+                RmtTxFuture::new(self).await;
+
+                if Self::is_error() {
+                    return Err(Error::TransmissionError);
+                }
+
+                if Self::is_threshold_set() {
+                    Self::reset_threshold_set();
+
+                    if index < data.len() {
+                        index = Self::refill_raw(data, index);
+                    }
+
+                    Self::listen_interrupt(super::private::Event::End);
+                    Self::listen_interrupt(super::private::Event::Error);
+                } else if Self::is_done() {
+                    break;
+                }
             }
 
+            Self::unlisten_interrupt(super::private::Event::Threshold);
+
+            Ok(())
+        }
+
+        /// Start transmitting the pulse codes produced by `encoder`.
+        /// The pulse codes are streamed into the memory blocks of the channel
+        /// while transmitting, the end marker is added after the last one.
+        async fn transmit_encoded<E: Encoder>(&mut self, mut encoder: E) -> Result<(), Error>
+        where
+            Self: Sized,
+        {
+            let half = Self::ram_size() / 2;
+            let mut ram_index = 0;
+
             Self::clear_interrupts();
             Self::listen_interrupt(super::private::Event::End);
             Self::listen_interrupt(super::private::Event::Error);
-            Self::send_raw(data, false, 0);
+            let mut pending = Self::send_encoded(&mut encoder);
+
+            loop {
+                if pending {
+                    Self::listen_interrupt(super::private::Event::Threshold);
+                }
+
+                RmtTxFuture::new(self).await;
+
+                if Self::is_error() {
+                    return Err(Error::TransmissionError);
+                }
+
+                if Self::is_threshold_set() {
+                    Self::reset_threshold_set();
+
+                    if pending {
+                        pending = Self::write_encoded(&mut encoder, ram_index, half);
+                        ram_index = (ram_index + half) % (half * 2);
+                    }
+
+                    Self::listen_interrupt(super::private::Event::End);
+                    Self::listen_interrupt(super::private::Event::Error);
+                } else if Self::is_done() {
+                    break;
+                }
+            }
 
-            RmtTxFuture::new(self).await;
+            Self::unlisten_interrupt(super::private::Event::Threshold);
 
-            if Self::is_error() {
-                Err(Error::TransmissionError)
+            Ok(())
+        }
+    }
+
+    #[cfg(not(any(esp32, esp32s2)))]
+    pub(crate) struct RmtSyncTxFuture<C>
+    where
+        C: TxSyncChannels<crate::Async>,
+    {
+        active: u8,
+        _phantom: PhantomData<C>,
+    }
+
+    #[cfg(not(any(esp32, esp32s2)))]
+    impl<C> RmtSyncTxFuture<C>
+    where
+        C: TxSyncChannels<crate::Async>,
+    {
+        /// Wait for an event of the channels of the group whose bit is set in
+        /// `active`
+        pub fn new(active: u8) -> Self {
+            Self {
+                active,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    #[cfg(not(any(esp32, esp32s2)))]
+    impl<C> core::future::Future for RmtSyncTxFuture<C>
+    where
+        C: TxSyncChannels<crate::Async>,
+    {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+            let mut ready = false;
+            for idx in (0..C::LEN).filter(|idx| self.active & (1 << idx) != 0) {
+                WAKER[C::channel(idx) as usize].register(ctx.waker());
+                ready |= C::is_error(idx) || C::is_done(idx) || C::is_threshold_set(idx);
+            }
+
+            if ready {
+                Poll::Ready(())
             } else {
-                Ok(())
+                Poll::Pending
+            }
+        }
+    }
+
+    #[cfg(not(any(esp32, esp32s2)))]
+    impl<C> TxSyncGroup<crate::Async, C>
+    where
+        C: TxSyncChannels<crate::Async>,
+    {
+        /// Start transmitting `data[n]` on the `n`th channel of the group and
+        /// wait for all transactions to complete
+        pub async fn transmit<T: Into<u32> + Copy>(&mut self, data: &[&[T]]) -> Result<(), Error> {
+            let mut index = Self::prepare(data)?;
+
+            let mut active = 0u8;
+            for idx in 0..C::LEN {
+                C::listen_interrupt(idx, Event::End);
+                C::listen_interrupt(idx, Event::Error);
+                active |= 1 << idx;
+            }
+
+            Self::start();
+
+            let result = loop {
+                for idx in 0..C::LEN {
+                    if index[idx] < data[idx].len() {
+                        C::listen_interrupt(idx, Event::Threshold);
+                    }
+                }
+
+                RmtSyncTxFuture::<C>::new(active).await;
+
+                if (0..C::LEN).any(C::is_error) {
+                    break Err(Error::TransmissionError);
+                }
+
+                for idx in 0..C::LEN {
+                    if active & (1 << idx) == 0 {
+                        continue;
+                    }
+
+                    if C::is_threshold_set(idx) {
+                        C::reset_threshold_set(idx);
+
+                        if index[idx] < data[idx].len() {
+                            index[idx] = C::refill_raw(idx, data[idx], index[idx]);
+                        }
+                    } else if C::is_done(idx) {
+                        active &= !(1 << idx);
+                    }
+
+                    if active & (1 << idx) != 0 {
+                        C::listen_interrupt(idx, Event::End);
+                        C::listen_interrupt(idx, Event::Error);
+                    }
+                }
+
+                if active == 0 {
+                    break Ok(());
+                }
+            };
+
+            for idx in 0..C::LEN {
+                C::unlisten_interrupt(idx, Event::End);
+                C::unlisten_interrupt(idx, Event::Error);
+                C::unlisten_interrupt(idx, Event::Threshold);
             }
+
+            result
         }
     }
 
@@ -1213,6 +1864,12 @@ pub mod asynch {
 
         fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
             WAKER[T::CHANNEL as usize].register(ctx.waker());
+
+            #[cfg(not(any(esp32, esp32s2)))]
+            if T::is_threshold_set() {
+                return Poll::Ready(());
+            }
+
             if T::is_error() || T::is_done() {
                 Poll::Ready(())
             } else {
@@ -1224,40 +1881,89 @@ pub mod asynch {
     /// RX channel in async mode
     pub trait RxChannelAsync: private::RxChannelInternal<crate::Async> {
         /// Start receiving a pulse code sequence.
-        /// The length of sequence cannot exceed the size of the allocated RMT
-        /// RAM.
+        #[cfg_attr(
+            not(any(esp32, esp32s2)),
+            doc = "The received data is copied out of the channel RAM while receiving if it exceeds the memory blocks used by the channel, [Error::Overflow] is returned if it exceeds the given buffer."
+        )]
+        #[cfg_attr(
+            any(esp32, esp32s2),
+            doc = "The length of sequence cannot exceed the memory blocks used by the channel."
+        )]
         async fn receive<'a, T: From<u32> + Copy>(&mut self, data: &'a mut [T]) -> Result<(), Error>
         where
             Self: Sized,
         {
-            if data.len() > constants::RMT_CHANNEL_RAM_SIZE {
+            let wrap = data.len() > Self::ram_size();
+
+            #[cfg(any(esp32, esp32s2))]
+            if wrap {
                 return Err(Error::InvalidArgument);
             }
 
             Self::clear_interrupts();
             Self::listen_interrupt(super::private::Event::End);
             Self::listen_interrupt(super::private::Event::Error);
-            Self::start_receive_raw();
+            Self::start_receive_raw(wrap);
 
-            RmtRxFuture::new(self).await;
+            #[cfg_attr(any(esp32, esp32s2), allow(unused_mut))]
+            let (mut index, mut ram_index, mut complete) = (0, 0, false);
 
-            if Self::is_error() {
-                Err(Error::TransmissionError)
-            } else {
-                Self::stop();
-                Self::clear_interrupts();
-                Self::update();
-
-                let ptr = (constants::RMT_RAM_START
-                    + Self::CHANNEL as usize * constants::RMT_CHANNEL_RAM_SIZE * 4)
-                    as *mut u32;
-                let len = data.len();
-                for (idx, entry) in data.iter_mut().take(len).enumerate() {
-                    *entry = unsafe { ptr.add(idx).read_volatile().into() };
+            loop {
+                #[cfg(not(any(esp32, esp32s2)))]
+                if wrap {
+                    Self::listen_interrupt(super::private::Event::Threshold);
+                }
+
+                RmtRxFuture::new(self).await;
+
+                if Self::is_error() {
+                    return Err(Error::TransmissionError);
                 }
 
-                Ok(())
+                // empty the half of the RAM which has been filled
+                #[cfg(not(any(esp32, esp32s2)))]
+                if wrap && Self::is_threshold_set() {
+                    Self::reset_threshold_set();
+
+                    let half = Self::ram_size() / 2;
+                    let (len, end) = Self::read_raw(&mut data[index..], ram_index, half);
+                    index += len;
+                    ram_index = (ram_index + half) % Self::ram_size();
+                    complete = end;
+
+                    if !end && len < half {
+                        Self::unlisten_interrupt(super::private::Event::Threshold);
+                        Self::stop();
+                        Self::clear_interrupts();
+                        Self::update();
+                        return Err(Error::Overflow);
+                    }
+
+                    Self::listen_interrupt(super::private::Event::End);
+                    Self::listen_interrupt(super::private::Event::Error);
+                    continue;
+                }
+
+                if Self::is_done() {
+                    break;
+                }
             }
+
+            #[cfg(not(any(esp32, esp32s2)))]
+            Self::unlisten_interrupt(super::private::Event::Threshold);
+            Self::stop();
+            Self::clear_interrupts();
+            Self::update();
+
+            if !complete {
+                let (_, end) = Self::read_raw(&mut data[index..], ram_index, Self::ram_size());
+
+                if wrap && !end {
+                    return Err(Error::Overflow);
+                }
+            }
+
+            Ok(())
         }
     }
 
@@ -1271,39 +1977,47 @@ pub mod asynch {
                 0 => {
                     super::Channel::<crate::Async, 0>::unlisten_interrupt(Event::End);
                     super::Channel::<crate::Async, 0>::unlisten_interrupt(Event::Error);
+                    super::Channel::<crate::Async, 0>::unlisten_interrupt(Event::Threshold);
                 }
                 1 => {
                     super::Channel::<crate::Async, 1>::unlisten_interrupt(Event::End);
                     super::Channel::<crate::Async, 1>::unlisten_interrupt(Event::Error);
+                    super::Channel::<crate::Async, 1>::unlisten_interrupt(Event::Threshold);
                 }
                 2 => {
                     super::Channel::<crate::Async, 2>::unlisten_interrupt(Event::End);
                     super::Channel::<crate::Async, 2>::unlisten_interrupt(Event::Error);
+                    super::Channel::<crate::Async, 2>::unlisten_interrupt(Event::Threshold);
                 }
                 3 => {
                     super::Channel::<crate::Async, 3>::unlisten_interrupt(Event::End);
                     super::Channel::<crate::Async, 3>::unlisten_interrupt(Event::Error);
+                    super::Channel::<crate::Async, 3>::unlisten_interrupt(Event::Threshold);
                 }
 
                 #[cfg(any(esp32, esp32s3))]
                 4 => {
                     super::Channel::<crate::Async, 4>::unlisten_interrupt(Event::End);
                     super::Channel::<crate::Async, 4>::unlisten_interrupt(Event::Error);
+                    super::Channel::<crate::Async, 4>::unlisten_interrupt(Event::Threshold);
                 }
                 #[cfg(any(esp32, esp32s3))]
                 5 => {
                     super::Channel::<crate::Async, 5>::unlisten_interrupt(Event::End);
                     super::Channel::<crate::Async, 5>::unlisten_interrupt(Event::Error);
+                    super::Channel::<crate::Async, 5>::unlisten_interrupt(Event::Threshold);
                 }
                 #[cfg(any(esp32, esp32s3))]
                 6 => {
                     super::Channel::<crate::Async, 6>::unlisten_interrupt(Event::End);
                     super::Channel::<crate::Async, 6>::unlisten_interrupt(Event::Error);
+                    super::Channel::<crate::Async, 6>::unlisten_interrupt(Event::Threshold);
                 }
                 #[cfg(any(esp32, esp32s3))]
                 7 => {
                     super::Channel::<crate::Async, 7>::unlisten_interrupt(Event::End);
                     super::Channel::<crate::Async, 7>::unlisten_interrupt(Event::Error);
+                    super::Channel::<crate::Async, 7>::unlisten_interrupt(Event::Threshold);
                 }
 
                 _ => unreachable!(),
@@ -1325,6 +2039,9 @@ pub mod asynch {
                     <Channel<crate::Async,0> as super::private::TxChannelInternal<crate::Async>>::unlisten_interrupt(
                         Event::Error,
                     );
+                    <Channel<crate::Async,0> as super::private::TxChannelInternal<crate::Async>>::unlisten_interrupt(
+                        Event::Threshold,
+                    );
                     <Channel<crate::Async,0> as super::private::RxChannelInternal<crate::Async>>::unlisten_interrupt(
                         Event::End,
                     );
@@ -1339,6 +2056,9 @@ pub mod asynch {
                     <Channel<crate::Async,1> as super::private::TxChannelInternal<crate::Async>>::unlisten_interrupt(
                         Event::Error,
                     );
+                    <Channel<crate::Async,1> as super::private::TxChannelInternal<crate::Async>>::unlisten_interrupt(
+                        Event::Threshold,
+                    );
                     <Channel<crate::Async,1> as super::private::RxChannelInternal<crate::Async>>::unlisten_interrupt(
                         Event::End,
                     );
@@ -1353,6 +2073,9 @@ pub mod asynch {
                     <Channel<crate::Async,2> as super::private::TxChannelInternal<crate::Async>>::unlisten_interrupt(
                         Event::Error,
                     );
+                    <Channel<crate::Async,2> as super::private::TxChannelInternal<crate::Async>>::unlisten_interrupt(
+                        Event::Threshold,
+                    );
                     <Channel<crate::Async,2> as super::private::RxChannelInternal<crate::Async>>::unlisten_interrupt(
                         Event::End,
                     );
@@ -1367,6 +2090,9 @@ pub mod asynch {
                     <Channel<crate::Async,3> as super::private::TxChannelInternal<crate::Async>>::unlisten_interrupt(
                         Event::Error,
                     );
+                    <Channel<crate::Async,3> as super::private::TxChannelInternal<crate::Async>>::unlisten_interrupt(
+                        Event::Threshold,
+                    );
                     <Channel<crate::Async,3> as super::private::RxChannelInternal<crate::Async>>::unlisten_interrupt(
                         Event::End,
                     );
@@ -1382,6 +2108,9 @@ pub mod asynch {
                     <Channel<crate::Async,4> as super::private::TxChannelInternal<crate::Async>>::unlisten_interrupt(
                         Event::Error,
                     );
+                    <Channel<crate::Async,4> as super::private::TxChannelInternal<crate::Async>>::unlisten_interrupt(
+                        Event::Threshold,
+                    );
                     <Channel<crate::Async,4> as super::private::RxChannelInternal<crate::Async>>::unlisten_interrupt(
                         Event::End,
                     );
@@ -1397,6 +2126,9 @@ pub mod asynch {
                     <Channel<crate::Async,5> as super::private::TxChannelInternal<crate::Async>>::unlisten_interrupt(
                         Event::Error,
                     );
+                    <Channel<crate::Async,5> as super::private::TxChannelInternal<crate::Async>>::unlisten_interrupt(
+                        Event::Threshold,
+                    );
                     <Channel<crate::Async,5> as super::private::RxChannelInternal<crate::Async>>::unlisten_interrupt(
                         Event::End,
                     );
@@ -1412,6 +2144,9 @@ pub mod asynch {
                     <Channel<crate::Async,6> as super::private::TxChannelInternal<crate::Async>>::unlisten_interrupt(
                         Event::Error,
                     );
+                    <Channel<crate::Async,6> as super::private::TxChannelInternal<crate::Async>>::unlisten_interrupt(
+                        Event::Threshold,
+                    );
                     <Channel<crate::Async,6> as super::private::RxChannelInternal<crate::Async>>::unlisten_interrupt(
                         Event::End,
                     );
@@ -1427,6 +2162,9 @@ pub mod asynch {
                     <Channel<crate::Async,7> as super::private::TxChannelInternal<crate::Async>>::unlisten_interrupt(
                         Event::Error,
                     );
+                    <Channel<crate::Async,7> as super::private::TxChannelInternal<crate::Async>>::unlisten_interrupt(
+                        Event::Threshold,
+                    );
                     <Channel<crate::Async,7> as super::private::RxChannelInternal<crate::Async>>::unlisten_interrupt(
                         Event::End,
                     );
@@ -1444,6 +2182,7 @@ pub mod asynch {
 }
 
 mod private {
+    use super::codec::Encoder;
     use crate::{peripheral::Peripheral, soc::constants};
 
     pub enum Event {
@@ -1484,8 +2223,26 @@ mod private {
 
         fn set_memsize(memsize: u8);
 
+        fn memsize() -> u8;
+
+        /// Number of pulse codes fitting into the memory blocks of the channel
+        fn ram_size() -> usize {
+            Self::memsize() as usize * constants::RMT_CHANNEL_RAM_SIZE
+        }
+
+        fn ram_ptr() -> *mut u32 {
+            (constants::RMT_RAM_START
+                + Self::CHANNEL as usize * constants::RMT_CHANNEL_RAM_SIZE * 4)
+                as *mut u32
+        }
+
         fn start_tx();
 
+        /// Start transmitting without resetting the clock divider, which is
+        /// reset for all channels of a sync group at once
+        #[cfg(not(any(esp32, esp32s2)))]
+        fn start_tx_synced();
+
         fn is_done() -> bool;
 
         fn is_error() -> bool;
@@ -1494,40 +2251,92 @@ mod private {
 
         fn reset_threshold_set();
 
-        fn set_threshold(threshold: u8);
+        fn set_threshold(threshold: u16);
 
         fn is_loopcount_interrupt_set() -> bool;
 
         fn send_raw<T: Into<u32> + Copy>(data: &[T], continuous: bool, repeat: u16) -> usize {
+            let index = Self::prepare_raw(data, continuous, repeat);
+            Self::start_tx();
+            Self::update();
+
+            index
+        }
+
+        /// Fill the channel RAM and configure the channel for sending `data`
+        /// without starting it, returns the index of the next pulse code to
+        /// send
+        fn prepare_raw<T: Into<u32> + Copy>(data: &[T], continuous: bool, repeat: u16) -> usize {
             Self::clear_interrupts();
 
-            let ptr = (constants::RMT_RAM_START
-                + Self::CHANNEL as usize * constants::RMT_CHANNEL_RAM_SIZE * 4)
-                as *mut u32;
-            for (idx, entry) in data
-                .iter()
-                .take(constants::RMT_CHANNEL_RAM_SIZE)
-                .enumerate()
-            {
+            let ram_size = Self::ram_size();
+            let ptr = Self::ram_ptr();
+            for (idx, entry) in data.iter().take(ram_size).enumerate() {
                 unsafe {
                     ptr.add(idx).write_volatile((*entry).into());
                 }
             }
 
-            Self::set_threshold((constants::RMT_CHANNEL_RAM_SIZE / 2) as u8);
+            Self::configure_send(continuous, repeat);
+
+            usize::min(data.len(), ram_size)
+        }
+
+        fn configure_send(continuous: bool, repeat: u16) {
+            Self::set_threshold((Self::ram_size() / 2) as u16);
             Self::set_continuous(continuous);
             Self::set_generate_repeat_interrupt(repeat);
             Self::set_wrap_mode(true);
-            Self::set_memsize(1);
             Self::update();
+        }
+
+        /// Write the pulse codes of `encoder` to `len` entries of the channel
+        /// RAM starting at `ram_index`, returns `false` once the end marker
+        /// has been written
+        fn write_encoded<E: Encoder>(encoder: &mut E, ram_index: usize, len: usize) -> bool {
+            let ptr = unsafe { Self::ram_ptr().add(ram_index) };
+            for idx in 0..len {
+                let (entry, pending) = match encoder.encode() {
+                    Some(code) => (code.into(), true),
+                    None => (0, false),
+                };
+
+                unsafe {
+                    ptr.add(idx).write_volatile(entry);
+                }
+
+                if !pending {
+                    return false;
+                }
+            }
+
+            true
+        }
+
+        fn send_encoded<E: Encoder>(encoder: &mut E) -> bool {
+            Self::clear_interrupts();
+            let pending = Self::write_encoded(encoder, 0, Self::ram_size());
+            Self::configure_send(false, 0);
             Self::start_tx();
             Self::update();
+            pending
+        }
 
-            if data.len() >= constants::RMT_CHANNEL_RAM_SIZE {
-                constants::RMT_CHANNEL_RAM_SIZE
-            } else {
-                data.len()
+        /// Refill the half of the channel RAM which has been sent with the
+        /// pulse codes starting at `index`, returns the index of the next pulse
+        /// code to send
+        fn refill_raw<T: Into<u32> + Copy>(data: &[T], index: usize) -> usize {
+            let ram_size = Self::ram_size();
+            let ram_index = (((index - ram_size) / (ram_size / 2)) % 2) * (ram_size / 2);
+
+            let ptr = unsafe { Self::ram_ptr().add(ram_index) };
+            for (idx, entry) in data[index..].iter().take(ram_size / 2).enumerate() {
+                unsafe {
+                    ptr.add(idx).write_volatile((*entry).into());
+                }
             }
+
+            index + ram_size / 2
         }
 
         fn stop();
@@ -1537,6 +2346,41 @@ mod private {
         fn unlisten_interrupt(event: Event);
     }
 
+    #[cfg(not(any(esp32, esp32s2)))]
+    pub trait TxSyncChannelsInternal<M>
+    where
+        M: crate::Mode,
+    {
+        const LEN: usize;
+
+        fn mask() -> u8;
+
+        #[cfg(feature = "async")]
+        fn channel(idx: usize) -> u8;
+
+        fn prepare_raw<T: Into<u32> + Copy>(idx: usize, data: &[T]) -> usize;
+
+        fn refill_raw<T: Into<u32> + Copy>(idx: usize, data: &[T], index: usize) -> usize;
+
+        fn update(idx: usize);
+
+        fn start_tx_synced(idx: usize);
+
+        fn is_done(idx: usize) -> bool;
+
+        fn is_error(idx: usize) -> bool;
+
+        fn is_threshold_set(idx: usize) -> bool;
+
+        fn reset_threshold_set(idx: usize);
+
+        #[cfg(feature = "async")]
+        fn listen_interrupt(idx: usize, event: Event);
+
+        #[cfg(feature = "async")]
+        fn unlisten_interrupt(idx: usize, event: Event);
+    }
+
     pub trait RxChannelInternal<M>
     where
         M: crate::Mode,
@@ -1559,20 +2403,69 @@ mod private {
 
         fn set_memsize(memsize: u8);
 
+        fn memsize() -> u8;
+
+        /// Number of pulse codes fitting into the memory blocks of the channel
+        fn ram_size() -> usize {
+            Self::memsize() as usize * constants::RMT_CHANNEL_RAM_SIZE
+        }
+
+        fn ram_ptr() -> *mut u32 {
+            (constants::RMT_RAM_START
+                + Self::CHANNEL as usize * constants::RMT_CHANNEL_RAM_SIZE * 4)
+                as *mut u32
+        }
+
         fn start_rx();
 
         fn is_done() -> bool;
 
         fn is_error() -> bool;
 
-        fn start_receive_raw() {
+        #[cfg(not(any(esp32, esp32s2)))]
+        fn is_threshold_set() -> bool;
+
+        #[cfg(not(any(esp32, esp32s2)))]
+        fn reset_threshold_set();
+
+        #[cfg(not(any(esp32, esp32s2)))]
+        fn set_threshold(threshold: u16);
+
+        /// Start receiving, in wrap mode the threshold event is raised each
+        /// time half of the channel RAM has been filled
+        fn start_receive_raw(wrap: bool) {
             Self::clear_interrupts();
-            Self::set_wrap_mode(false);
-            Self::set_memsize(1);
+            #[cfg(not(any(esp32, esp32s2)))]
+            Self::set_threshold((Self::ram_size() / 2) as u16);
+            Self::set_wrap_mode(wrap);
             Self::start_rx();
             Self::update();
         }
 
+        /// Copy up to `len` pulse codes from the channel RAM, starting at
+        /// `ram_index`, into `data`, stopping after the end marker. Returns the
+        /// number of pulse codes copied and if the end marker has been found.
+        fn read_raw<T: From<u32> + Copy>(
+            data: &mut [T],
+            ram_index: usize,
+            len: usize,
+        ) -> (usize, bool) {
+            let ram_size = Self::ram_size();
+            let ptr = Self::ram_ptr();
+
+            for (idx, entry) in data.iter_mut().take(len).enumerate() {
+                let code = unsafe { ptr.add((ram_index + idx) % ram_size).read_volatile() };
+                *entry = code.into();
+
+                // a zero length marks the end of the received sequence
+                if code & 0x7fff == 0 || (code >> 16) & 0x7fff == 0 {
+                    return (idx + 1, true);
+                }
+            }
+
+            (usize::min(data.len(), len), false)
+        }
+
         fn stop();
 
         fn set_filter_threshold(value: u8);
@@ -1637,13 +2530,13 @@ mod chip_specific {
         let rmt = unsafe { &*crate::peripherals::RMT::PTR };
         let st = rmt.int_st().read();
 
-        if st.ch0_tx_end().bit() || st.ch0_tx_err().bit() {
+        if st.ch0_tx_end().bit() || st.ch0_tx_err().bit() || st.ch0_tx_thr_event().bit() {
             Some(0)
-        } else if st.ch1_tx_end().bit() || st.ch1_tx_err().bit() {
+        } else if st.ch1_tx_end().bit() || st.ch1_tx_err().bit() || st.ch1_tx_thr_event().bit() {
             Some(1)
-        } else if st.ch2_rx_end().bit() || st.ch2_rx_err().bit() {
+        } else if st.ch2_rx_end().bit() || st.ch2_rx_err().bit() || st.ch2_rx_thr_event().bit() {
             Some(2)
-        } else if st.ch3_rx_end().bit() || st.ch3_rx_err().bit() {
+        } else if st.ch3_rx_end().bit() || st.ch3_rx_err().bit() || st.ch3_rx_thr_event().bit() {
             Some(3)
         } else {
             None
@@ -1656,27 +2549,63 @@ mod chip_specific {
         let rmt = unsafe { &*crate::peripherals::RMT::PTR };
         let st = rmt.int_st().read();
 
-        if st.ch0_tx_end().bit() || st.ch0_tx_err().bit() {
+        if st.ch0_tx_end().bit() || st.ch0_tx_err().bit() || st.ch0_tx_thr_event().bit() {
             Some(0)
-        } else if st.ch1_tx_end().bit() || st.ch1_tx_err().bit() {
+        } else if st.ch1_tx_end().bit() || st.ch1_tx_err().bit() || st.ch1_tx_thr_event().bit() {
             Some(1)
-        } else if st.ch2_tx_end().bit() || st.ch2_tx_err().bit() {
+        } else if st.ch2_tx_end().bit() || st.ch2_tx_err().bit() || st.ch2_tx_thr_event().bit() {
             Some(2)
-        } else if st.ch3_tx_end().bit() || st.ch3_tx_err().bit() {
+        } else if st.ch3_tx_end().bit() || st.ch3_tx_err().bit() || st.ch3_tx_thr_event().bit() {
             Some(3)
-        } else if st.ch4_rx_end().bit() || st.ch4_rx_err().bit() {
+        } else if st.ch4_rx_end().bit() || st.ch4_rx_err().bit() || st.ch4_rx_thr_event().bit() {
             Some(4)
-        } else if st.ch5_rx_end().bit() || st.ch5_rx_err().bit() {
+        } else if st.ch5_rx_end().bit() || st.ch5_rx_err().bit() || st.ch5_rx_thr_event().bit() {
             Some(5)
-        } else if st.ch6_rx_end().bit() || st.ch6_rx_err().bit() {
+        } else if st.ch6_rx_end().bit() || st.ch6_rx_err().bit() || st.ch6_rx_thr_event().bit() {
             Some(6)
-        } else if st.ch7_rx_end().bit() || st.ch7_rx_err().bit() {
+        } else if st.ch7_rx_end().bit() || st.ch7_rx_err().bit() || st.ch7_rx_thr_event().bit() {
             Some(7)
         } else {
             None
         }
     }
 
+    /// Add the TX channels in `mask` to the sync group, an empty mask
+    /// disables synchronization
+    pub fn set_tx_sync(mask: u8) {
+        let rmt = unsafe { &*crate::peripherals::RMT::PTR };
+
+        #[cfg(not(esp32s3))]
+        rmt.tx_sim().write(|w| {
+            w.tx_sim_ch0()
+                .bit(mask & 0b01 != 0)
+                .tx_sim_ch1()
+                .bit(mask & 0b10 != 0)
+                .tx_sim_en()
+                .bit(mask != 0)
+        });
+
+        #[cfg(esp32s3)]
+        rmt.tx_sim().write(|w| {
+            w.tx_sim_ch0()
+                .bit(mask & 0b0001 != 0)
+                .tx_sim_ch1()
+                .bit(mask & 0b0010 != 0)
+                .tx_sim_ch2()
+                .bit(mask & 0b0100 != 0)
+                .tx_sim_ch3()
+                .bit(mask & 0b1000 != 0)
+                .tx_sim_en()
+                .bit(mask != 0)
+        });
+    }
+
+    /// Reset the clock dividers of the channels in `mask` at once
+    pub fn reset_clock_dividers(mask: u8) {
+        let rmt = unsafe { &*crate::peripherals::RMT::PTR };
+        rmt.ref_cnt_rst().write(|w| unsafe { w.bits(mask as u32) });
+    }
+
     macro_rules! impl_tx_channel {
         ($channel:ident, $signal:ident, $ch_num:literal) => {
             paste::paste! {
@@ -1782,12 +2711,23 @@ mod chip_specific {
                         rmt.ch_tx_conf0($ch_num).modify(|_, w| unsafe { w.mem_size().bits(memsize) });
                     }
 
+                    fn memsize() -> u8 {
+                        let rmt = unsafe { &*crate::peripherals::RMT::PTR };
+                        rmt.ch_tx_conf0($ch_num).read().mem_size().bits()
+                    }
+
                     fn start_tx() {
                         let rmt = unsafe { &*crate::peripherals::RMT::PTR };
 
                         rmt.ref_cnt_rst().write(|w| unsafe { w.bits(1 << $ch_num) });
                         Self::update();
 
+                        Self::start_tx_synced();
+                    }
+
+                    fn start_tx_synced() {
+                        let rmt = unsafe { &*crate::peripherals::RMT::PTR };
+
                         rmt.ch_tx_conf0($ch_num).modify(|_, w| {
                             w.mem_rd_rst()
                                 .set_bit()
@@ -1820,9 +2760,9 @@ mod chip_specific {
                             .write(|w| w.[< ch $ch_num _tx_thr_event >]().set_bit());
                     }
 
-                    fn set_threshold(threshold: u8) {
+                    fn set_threshold(threshold: u16) {
                         let rmt = unsafe { &*crate::peripherals::RMT::PTR };
-                        rmt.ch_tx_lim($ch_num).modify(|_, w| unsafe { w.tx_lim().bits(threshold as u16) });
+                        rmt.ch_tx_lim($ch_num).modify(|_, w| unsafe { w.tx_lim().bits(threshold) });
                     }
 
                     fn is_loopcount_interrupt_set() -> bool {
@@ -1933,6 +2873,11 @@ mod chip_specific {
                         rmt.[< ch $ch_num _rx_conf0 >]().modify(|_, w| unsafe { w.mem_size().bits(memsize) });
                     }
 
+                    fn memsize() -> u8 {
+                        let rmt = unsafe { &*crate::peripherals::RMT::PTR };
+                        rmt.[< ch $ch_num _rx_conf0 >]().read().mem_size().bits()
+                    }
+
                     fn start_rx() {
                         let rmt = unsafe { &*crate::peripherals::RMT::PTR };
                         rmt.[< ch $ch_num _rx_conf1 >]().modify(|_, w| {
@@ -1957,6 +2902,22 @@ mod chip_specific {
                         rmt.int_raw().read().[< ch $ch_num _rx_err >]().bit()
                     }
 
+                    fn is_threshold_set() -> bool {
+                        let rmt = unsafe { &*crate::peripherals::RMT::PTR };
+                        rmt.int_raw().read().[< ch $ch_num _rx_thr_event >]().bit()
+                    }
+
+                    fn reset_threshold_set() {
+                        let rmt = unsafe { &*crate::peripherals::RMT::PTR };
+                        rmt.int_clr()
+                            .write(|w| w.[< ch $ch_num _rx_thr_event >]().set_bit());
+                    }
+
+                    fn set_threshold(threshold: u16) {
+                        let rmt = unsafe { &*crate::peripherals::RMT::PTR };
+                        rmt.ch_rx_lim($ch_index).modify(|_, w| unsafe { w.rx_lim().bits(threshold) });
+                    }
+
                     fn stop() {
                         let rmt = unsafe { &*crate::peripherals::RMT::PTR };
                         rmt.[< ch $ch_num _rx_conf1 >]().modify(|_, w| w.rx_en().clear_bit());
@@ -2046,21 +3007,53 @@ mod chip_specific {
         let rmt = unsafe { &*crate::peripherals::RMT::PTR };
         let st = rmt.int_st().read();
 
-        if st.ch0_rx_end().bit() || st.ch0_tx_end().bit() || st.ch0_err().bit() {
+        if st.ch0_rx_end().bit()
+            || st.ch0_tx_end().bit()
+            || st.ch0_err().bit()
+            || st.ch0_tx_thr_event().bit()
+        {
             Some(0)
-        } else if st.ch1_rx_end().bit() || st.ch1_tx_end().bit() || st.ch1_err().bit() {
+        } else if st.ch1_rx_end().bit()
+            || st.ch1_tx_end().bit()
+            || st.ch1_err().bit()
+            || st.ch1_tx_thr_event().bit()
+        {
             Some(1)
-        } else if st.ch2_rx_end().bit() || st.ch2_tx_end().bit() || st.ch2_err().bit() {
+        } else if st.ch2_rx_end().bit()
+            || st.ch2_tx_end().bit()
+            || st.ch2_err().bit()
+            || st.ch2_tx_thr_event().bit()
+        {
             Some(2)
-        } else if st.ch3_rx_end().bit() || st.ch3_tx_end().bit() || st.ch3_err().bit() {
+        } else if st.ch3_rx_end().bit()
+            || st.ch3_tx_end().bit()
+            || st.ch3_err().bit()
+            || st.ch3_tx_thr_event().bit()
+        {
             Some(3)
-        } else if st.ch4_rx_end().bit() || st.ch4_tx_end().bit() || st.ch4_err().bit() {
+        } else if st.ch4_rx_end().bit()
+            || st.ch4_tx_end().bit()
+            || st.ch4_err().bit()
+            || st.ch4_tx_thr_event().bit()
+        {
             Some(4)
-        } else if st.ch5_rx_end().bit() || st.ch5_tx_end().bit() || st.ch5_err().bit() {
+        } else if st.ch5_rx_end().bit()
+            || st.ch5_tx_end().bit()
+            || st.ch5_err().bit()
+            || st.ch5_tx_thr_event().bit()
+        {
             Some(5)
-        } else if st.ch6_rx_end().bit() || st.ch6_tx_end().bit() || st.ch6_err().bit() {
+        } else if st.ch6_rx_end().bit()
+            || st.ch6_tx_end().bit()
+            || st.ch6_err().bit()
+            || st.ch6_tx_thr_event().bit()
+        {
             Some(6)
-        } else if st.ch7_rx_end().bit() || st.ch7_tx_end().bit() || st.ch7_err().bit() {
+        } else if st.ch7_rx_end().bit()
+            || st.ch7_tx_end().bit()
+            || st.ch7_err().bit()
+            || st.ch7_tx_thr_event().bit()
+        {
             Some(7)
         } else {
             None
@@ -2073,13 +3066,29 @@ mod chip_specific {
         let rmt = unsafe { &*crate::peripherals::RMT::PTR };
         let st = rmt.int_st().read();
 
-        if st.ch0_rx_end().bit() || st.ch0_tx_end().bit() || st.ch0_err().bit() {
+        if st.ch0_rx_end().bit()
+            || st.ch0_tx_end().bit()
+            || st.ch0_err().bit()
+            || st.ch0_tx_thr_event().bit()
+        {
             Some(0)
-        } else if st.ch1_rx_end().bit() || st.ch1_tx_end().bit() || st.ch1_err().bit() {
+        } else if st.ch1_rx_end().bit()
+            || st.ch1_tx_end().bit()
+            || st.ch1_err().bit()
+            || st.ch1_tx_thr_event().bit()
+        {
             Some(1)
-        } else if st.ch2_rx_end().bit() || st.ch2_tx_end().bit() || st.ch2_err().bit() {
+        } else if st.ch2_rx_end().bit()
+            || st.ch2_tx_end().bit()
+            || st.ch2_err().bit()
+            || st.ch2_tx_thr_event().bit()
+        {
             Some(2)
-        } else if st.ch3_rx_end().bit() || st.ch3_tx_end().bit() || st.ch3_err().bit() {
+        } else if st.ch3_rx_end().bit()
+            || st.ch3_tx_end().bit()
+            || st.ch3_err().bit()
+            || st.ch3_tx_thr_event().bit()
+        {
             Some(3)
         } else {
             None
@@ -2173,6 +3182,11 @@ mod chip_specific {
                         rmt.[< ch $ch_num conf0 >]().modify(|_, w| unsafe { w.mem_size().bits(memsize) });
                     }
 
+                    fn memsize() -> u8 {
+                        let rmt = unsafe { &*crate::peripherals::RMT::PTR };
+                        rmt.[< ch $ch_num conf0 >]().read().mem_size().bits()
+                    }
+
                     fn start_tx() {
                         let rmt = unsafe { &*crate::peripherals::RMT::PTR };
 
@@ -2207,9 +3221,9 @@ mod chip_specific {
                             .write(|w| w.[< ch $ch_num _tx_thr_event >]().set_bit());
                     }
 
-                    fn set_threshold(threshold: u8) {
+                    fn set_threshold(threshold: u16) {
                         let rmt = unsafe { &*crate::peripherals::RMT::PTR };
-                        rmt.ch_tx_lim($ch_num).modify(|_, w| unsafe { w.tx_lim().bits(threshold as u16) });
+                        rmt.ch_tx_lim($ch_num).modify(|_, w| unsafe { w.tx_lim().bits(threshold) });
                     }
 
                     fn is_loopcount_interrupt_set() -> bool {
@@ -2329,6 +3343,11 @@ mod chip_specific {
                         rmt.[< ch $ch_num conf0 >]().modify(|_, w| unsafe { w.mem_size().bits(memsize) });
                     }
 
+                    fn memsize() -> u8 {
+                        let rmt = unsafe { &*crate::peripherals::RMT::PTR };
+                        rmt.[< ch $ch_num conf0 >]().read().mem_size().bits()
+                    }
+
                     fn start_rx() {
                         let rmt = unsafe { &*crate::peripherals::RMT::PTR };
 
@@ -111,6 +111,37 @@ impl Efuse {
     pub fn get_flash_encryption() -> bool {
         (Self::read_field_le::<u8>(FLASH_CRYPT_CNT).count_ones() % 2) != 0
     }
+
+    /// Get the raw eFuse value of the measured ADC reference voltage, if it
+    /// was burned during production
+    ///
+    /// see <https://github.com/espressif/esp-idf/blob/903af13e8/components/esp_adc/deprecated/esp32/esp_adc_cal_legacy.c#L186>
+    pub fn get_adc_vref() -> Option<u8> {
+        let vref = Self::read_field_le::<u8>(ADC_VREF);
+        (vref != 0).then_some(vref)
+    }
+
+    /// Get the raw eFuse values of the ADC readings at 150 mV and 850 mV for
+    /// the given unit, if they were burned during production
+    ///
+    /// see <https://github.com/espressif/esp-idf/blob/903af13e8/components/esp_adc/deprecated/esp32/esp_adc_cal_legacy.c#L158>
+    pub fn get_adc_two_point(unit: u8) -> Option<(u8, u16)> {
+        if !Self::read_field_le::<bool>(BLK3_PART_RESERVE) {
+            return None;
+        }
+
+        Some(if unit == 1 {
+            (
+                Self::read_field_le(ADC1_TP_LOW),
+                Self::read_field_le(ADC1_TP_HIGH),
+            )
+        } else {
+            (
+                Self::read_field_le(ADC2_TP_LOW),
+                Self::read_field_le(ADC2_TP_HIGH),
+            )
+        })
+    }
 }
 
 #[allow(unused)]
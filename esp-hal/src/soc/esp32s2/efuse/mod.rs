@@ -34,7 +34,7 @@
 //! ```
 
 pub use self::fields::*;
-use crate::peripherals::EFUSE;
+use crate::{analog::adc::Attenuation, peripherals::EFUSE};
 
 mod fields;
 
@@ -71,6 +71,37 @@ impl Efuse {
     pub fn get_rwdt_multiplier() -> u8 {
         Self::read_field_le::<u8>(WDT_DELAY_SEL)
     }
+
+    /// Get version of RTC calibration block
+    ///
+    /// see <https://github.com/espressif/esp-idf/blob/903af13e8/components/efuse/esp32s2/esp_efuse_rtc_table.c#L148>
+    pub fn get_rtc_calib_version() -> u8 {
+        Self::read_field_le::<u8>(BLK_VERSION_MINOR)
+    }
+
+    /// Get the raw eFuse values of the ADC readings at the low and high
+    /// reference voltages for the given unit and attenuation, if the version 1
+    /// two point calibration was burned during production
+    ///
+    /// see <https://github.com/espressif/esp-idf/blob/903af13e8/components/efuse/esp32s2/esp_efuse_rtc_table.c#L30-L48>
+    pub fn get_rtc_calib_two_point(unit: u8, atten: Attenuation) -> Option<(u8, u8)> {
+        if Self::get_rtc_calib_version() != 1 {
+            return None;
+        }
+
+        let (low, high) = match (unit, atten) {
+            (1, Attenuation::Attenuation0dB) => (RTCCALIB_V1IDX_A10L, RTCCALIB_V1IDX_A10H),
+            (1, Attenuation::Attenuation2p5dB) => (RTCCALIB_V1IDX_A11L, RTCCALIB_V1IDX_A11H),
+            (1, Attenuation::Attenuation6dB) => (RTCCALIB_V1IDX_A12L, RTCCALIB_V1IDX_A12H),
+            (1, Attenuation::Attenuation11dB) => (RTCCALIB_V1IDX_A13L, RTCCALIB_V1IDX_A13H),
+            (_, Attenuation::Attenuation0dB) => (RTCCALIB_V1IDX_A20L, RTCCALIB_V1IDX_A20H),
+            (_, Attenuation::Attenuation2p5dB) => (RTCCALIB_V1IDX_A21L, RTCCALIB_V1IDX_A21H),
+            (_, Attenuation::Attenuation6dB) => (RTCCALIB_V1IDX_A22L, RTCCALIB_V1IDX_A22H),
+            (_, Attenuation::Attenuation11dB) => (RTCCALIB_V1IDX_A23L, RTCCALIB_V1IDX_A23H),
+        };
+
+        Some((Self::read_field_le(low), Self::read_field_le(high)))
+    }
 }
 
 #[derive(Copy, Clone)]
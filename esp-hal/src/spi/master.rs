@@ -38,11 +38,23 @@
 //!   from [`embassy-embedded-hal`].
 //!
 //!
+//! ## Octal SPI (ESP32-S3)
+//!
+//! In half-duplex mode, SPI2 of the ESP32-S3 can use eight data lines. Connect
+//! SIO4 to SIO7 with `Spi::with_sio4` to `Spi::with_sio7` in addition to
+//! the quad pins, and use `SpiDataMode::Octal` for the command, address
+//! and data phases of [`HalfDuplexReadWrite`] transfers, with or without DMA.
+//! Double transfer rate (DTR) is only implemented by the SPI0/1 memory
+//! controller and is therefore not available for SPI2.
+//!
 //! ## Shared SPI access
 //!
 //! If you have multiple devices on the same SPI bus that each have their own CS
-//! line, you may want to have a look at the implementations provided by
-//! [`embedded-hal-bus`] and [`embassy-embedded-hal`].
+//! line, the [`shared`](super::shared) module provides `SpiDevice`
+//! implementations which also switch mode, frequency and bit order per device
+//! and can use the hardware CS lines. Alternatively, have a look at the
+//! implementations provided by [`embedded-hal-bus`] and
+//! [`embassy-embedded-hal`].
 //!
 //! [`embedded-hal-bus`]: https://docs.rs/embedded-hal-bus/latest/embedded_hal_bus/spi/index.html
 //! [`embassy-embedded-hal`]: https://docs.embassy.dev/embassy-embedded-hal/git/default/shared_bus/index.html
@@ -483,6 +495,32 @@ where
         self
     }
 
+    /// Connect the second hardware CS line, used by devices on a
+    /// [shared bus](super::shared) selecting [`HardwareCs::Cs1`].
+    ///
+    /// [`HardwareCs::Cs1`]: super::shared::HardwareCs::Cs1
+    #[cfg(feature = "embedded-hal")]
+    pub fn with_cs1<CS: OutputPin>(self, cs: impl Peripheral<P = CS> + 'd) -> Self {
+        crate::into_ref!(cs);
+        cs.set_to_push_pull_output(private::Internal);
+        cs.connect_peripheral_to_output(self.spi.cs1_signal(), private::Internal);
+
+        self
+    }
+
+    /// Connect the third hardware CS line, used by devices on a
+    /// [shared bus](super::shared) selecting [`HardwareCs::Cs2`].
+    ///
+    /// [`HardwareCs::Cs2`]: super::shared::HardwareCs::Cs2
+    #[cfg(feature = "embedded-hal")]
+    pub fn with_cs2<CS: OutputPin>(self, cs: impl Peripheral<P = CS> + 'd) -> Self {
+        crate::into_ref!(cs);
+        cs.set_to_push_pull_output(private::Internal);
+        cs.connect_peripheral_to_output(self.spi.cs2_signal(), private::Internal);
+
+        self
+    }
+
     /// Set the bit order for the SPI instance.
     ///
     /// The default is MSB first for both read and write.
@@ -553,6 +591,27 @@ where
     }
 }
 
+#[cfg(feature = "embedded-hal")]
+impl<'d, T> private::Sealed for Spi<'d, T, FullDuplexMode> where T: Instance {}
+
+#[cfg(feature = "embedded-hal")]
+impl<'d, T> crate::spi::shared::ConfigurableBus for Spi<'d, T, FullDuplexMode>
+where
+    T: Instance,
+{
+    fn apply_device_config(&mut self, config: &crate::spi::shared::DeviceConfig, clock_reg: u32) {
+        self.spi.set_data_mode(config.mode);
+        self.spi.set_clock_register(clock_reg);
+        self.spi
+            .set_bit_order(config.read_bit_order, config.write_bit_order);
+    }
+
+    fn set_hardware_cs(&mut self, line: Option<u8>) {
+        self.spi.select_cs_line(line);
+        self.spi.set_cs_keep_active(line.is_some());
+    }
+}
+
 impl<'d, T> Spi<'d, T, HalfDuplexMode>
 where
     T: ExtendedInstance,
@@ -738,6 +797,64 @@ where
     }
 }
 
+#[cfg(esp32s3)]
+impl<'d, T> Spi<'d, T, HalfDuplexMode>
+where
+    T: OctalInstance,
+{
+    pub fn with_sio4<SIO4: OutputPin + InputPin>(
+        self,
+        sio4: impl Peripheral<P = SIO4> + 'd,
+    ) -> Self {
+        crate::into_ref!(sio4);
+        sio4.enable_output(true, private::Internal);
+        sio4.connect_peripheral_to_output(self.spi.sio4_output_signal(), private::Internal);
+        sio4.enable_input(true, private::Internal);
+        sio4.connect_input_to_peripheral(self.spi.sio4_input_signal(), private::Internal);
+
+        self
+    }
+
+    pub fn with_sio5<SIO5: OutputPin + InputPin>(
+        self,
+        sio5: impl Peripheral<P = SIO5> + 'd,
+    ) -> Self {
+        crate::into_ref!(sio5);
+        sio5.enable_output(true, private::Internal);
+        sio5.connect_peripheral_to_output(self.spi.sio5_output_signal(), private::Internal);
+        sio5.enable_input(true, private::Internal);
+        sio5.connect_input_to_peripheral(self.spi.sio5_input_signal(), private::Internal);
+
+        self
+    }
+
+    pub fn with_sio6<SIO6: OutputPin + InputPin>(
+        self,
+        sio6: impl Peripheral<P = SIO6> + 'd,
+    ) -> Self {
+        crate::into_ref!(sio6);
+        sio6.enable_output(true, private::Internal);
+        sio6.connect_peripheral_to_output(self.spi.sio6_output_signal(), private::Internal);
+        sio6.enable_input(true, private::Internal);
+        sio6.connect_input_to_peripheral(self.spi.sio6_input_signal(), private::Internal);
+
+        self
+    }
+
+    pub fn with_sio7<SIO7: OutputPin + InputPin>(
+        self,
+        sio7: impl Peripheral<P = SIO7> + 'd,
+    ) -> Self {
+        crate::into_ref!(sio7);
+        sio7.enable_output(true, private::Internal);
+        sio7.connect_peripheral_to_output(self.spi.sio7_output_signal(), private::Internal);
+        sio7.enable_input(true, private::Internal);
+        sio7.connect_input_to_peripheral(self.spi.sio7_input_signal(), private::Internal);
+
+        self
+    }
+}
+
 impl<T, M> HalfDuplexReadWrite for Spi<'_, T, M>
 where
     T: Instance,
@@ -999,6 +1116,42 @@ pub mod dma {
         }
     }
 
+    #[cfg(feature = "embedded-hal")]
+    impl<'d, T, C, DmaMode> crate::private::Sealed for SpiDma<'d, T, C, FullDuplexMode, DmaMode>
+    where
+        T: InstanceDma<C::Tx<'d>, C::Rx<'d>>,
+        C: ChannelTypes,
+        C::P: SpiPeripheral,
+        DmaMode: Mode,
+    {
+    }
+
+    #[cfg(feature = "embedded-hal")]
+    impl<'d, T, C, DmaMode> crate::spi::shared::ConfigurableBus
+        for SpiDma<'d, T, C, FullDuplexMode, DmaMode>
+    where
+        T: InstanceDma<C::Tx<'d>, C::Rx<'d>>,
+        C: ChannelTypes,
+        C::P: SpiPeripheral,
+        DmaMode: Mode,
+    {
+        fn apply_device_config(
+            &mut self,
+            config: &crate::spi::shared::DeviceConfig,
+            clock_reg: u32,
+        ) {
+            self.spi.set_data_mode(config.mode);
+            self.spi.set_clock_register(clock_reg);
+            self.spi
+                .set_bit_order(config.read_bit_order, config.write_bit_order);
+        }
+
+        fn set_hardware_cs(&mut self, line: Option<u8>) {
+            self.spi.select_cs_line(line);
+            self.spi.set_cs_keep_active(line.is_some());
+        }
+    }
+
     impl<'d, T, C, M, DmaMode> DmaSupport for SpiDma<'d, T, C, M, DmaMode>
     where
         T: InstanceDma<C::Tx<'d>, C::Rx<'d>>,
@@ -2075,6 +2228,108 @@ pub trait ExtendedInstance: Instance {
     fn sio3_input_signal(&self) -> InputSignal;
 }
 
+#[doc(hidden)]
+#[cfg(esp32s3)]
+pub trait OctalInstance: ExtendedInstance {
+    fn sio4_output_signal(&self) -> OutputSignal;
+
+    fn sio4_input_signal(&self) -> InputSignal;
+
+    fn sio5_output_signal(&self) -> OutputSignal;
+
+    fn sio5_input_signal(&self) -> InputSignal;
+
+    fn sio6_output_signal(&self) -> OutputSignal;
+
+    fn sio6_input_signal(&self) -> InputSignal;
+
+    fn sio7_output_signal(&self) -> OutputSignal;
+
+    fn sio7_input_signal(&self) -> InputSignal;
+}
+
+/// Calculates the value of the clock register for the given bus frequency
+pub(super) fn clock_register_value(frequency: HertzU32, clocks: &Clocks) -> u32 {
+    #[cfg(not(esp32h2))]
+    let apb_clk_freq: HertzU32 = HertzU32::Hz(clocks.apb_clock.to_Hz());
+    // ESP32-H2 is using PLL_48M_CLK source instead of APB_CLK
+    #[cfg(esp32h2)]
+    let apb_clk_freq: HertzU32 = HertzU32::Hz(clocks.pll_48m_clock.to_Hz());
+
+    let reg_val: u32;
+    let duty_cycle = 128;
+
+    // In HW, n, h and l fields range from 1 to 64, pre ranges from 1 to 8K.
+    // The value written to register is one lower than the used value.
+
+    if frequency > ((apb_clk_freq / 4) * 3) {
+        // Using APB frequency directly will give us the best result here.
+        reg_val = 1 << 31;
+    } else {
+        /* For best duty cycle resolution, we want n to be as close to 32 as
+         * possible, but we also need a pre/n combo that gets us as close as
+         * possible to the intended frequency. To do this, we bruteforce n and
+         * calculate the best pre to go along with that. If there's a choice
+         * between pre/n combos that give the same result, use the one with the
+         * higher n.
+         */
+
+        let mut pre: i32;
+        let mut bestn: i32 = -1;
+        let mut bestpre: i32 = -1;
+        let mut besterr: i32 = 0;
+        let mut errval: i32;
+
+        /* Start at n = 2. We need to be able to set h/l so we have at least
+         * one high and one low pulse.
+         */
+
+        for n in 2..64 {
+            /* Effectively, this does:
+             *   pre = round((APB_CLK_FREQ / n) / frequency)
+             */
+
+            pre = ((apb_clk_freq.raw() as i32 / n) + (frequency.raw() as i32 / 2))
+                / frequency.raw() as i32;
+
+            if pre <= 0 {
+                pre = 1;
+            }
+
+            if pre > 16 {
+                pre = 16;
+            }
+
+            errval = (apb_clk_freq.raw() as i32 / (pre * n) - frequency.raw() as i32).abs();
+            if bestn == -1 || errval <= besterr {
+                besterr = errval;
+                bestn = n;
+                bestpre = pre;
+            }
+        }
+
+        let n: i32 = bestn;
+        pre = bestpre;
+        let l: i32 = n;
+
+        /* Effectively, this does:
+         *   h = round((duty_cycle * n) / 256)
+         */
+
+        let mut h: i32 = (duty_cycle * n + 127) / 256;
+        if h <= 0 {
+            h = 1;
+        }
+
+        reg_val = (l as u32 - 1)
+            | ((h as u32 - 1) << 6)
+            | ((n as u32 - 1) << 12)
+            | ((pre as u32 - 1) << 18);
+    }
+
+    reg_val
+}
+
 #[doc(hidden)]
 pub trait Instance: private::Sealed {
     fn register_block(&self) -> &RegisterBlock;
@@ -2087,6 +2342,10 @@ pub trait Instance: private::Sealed {
 
     fn cs_signal(&self) -> OutputSignal;
 
+    fn cs1_signal(&self) -> OutputSignal;
+
+    fn cs2_signal(&self) -> OutputSignal;
+
     fn enable_peripheral(&self);
 
     fn spi_num(&self) -> u8;
@@ -2176,7 +2435,15 @@ pub trait Instance: private::Sealed {
             SpiDataMode::Quad => reg_block
                 .ctrl()
                 .modify(|_, w| w.fcmd_dual().clear_bit().fcmd_quad().set_bit()),
+            #[cfg(esp32s3)]
+            SpiDataMode::Octal => reg_block
+                .ctrl()
+                .modify(|_, w| w.fcmd_dual().clear_bit().fcmd_quad().clear_bit()),
         }
+        #[cfg(esp32s3)]
+        reg_block
+            .ctrl()
+            .modify(|_, w| w.fcmd_oct().bit(cmd_mode == SpiDataMode::Octal));
 
         match address_mode {
             SpiDataMode::Single => reg_block
@@ -2188,7 +2455,15 @@ pub trait Instance: private::Sealed {
             SpiDataMode::Quad => reg_block
                 .ctrl()
                 .modify(|_, w| w.faddr_dual().clear_bit().faddr_quad().set_bit()),
+            #[cfg(esp32s3)]
+            SpiDataMode::Octal => reg_block
+                .ctrl()
+                .modify(|_, w| w.faddr_dual().clear_bit().faddr_quad().clear_bit()),
         }
+        #[cfg(esp32s3)]
+        reg_block
+            .ctrl()
+            .modify(|_, w| w.faddr_oct().bit(address_mode == SpiDataMode::Octal));
 
         match data_mode {
             SpiDataMode::Single => {
@@ -2215,6 +2490,21 @@ pub trait Instance: private::Sealed {
                     .user()
                     .modify(|_, w| w.fwrite_quad().set_bit().fwrite_dual().clear_bit());
             }
+            #[cfg(esp32s3)]
+            SpiDataMode::Octal => {
+                reg_block
+                    .ctrl()
+                    .modify(|_, w| w.fread_quad().clear_bit().fread_dual().clear_bit());
+                reg_block
+                    .user()
+                    .modify(|_, w| w.fwrite_quad().clear_bit().fwrite_dual().clear_bit());
+            }
+        }
+        #[cfg(esp32s3)]
+        {
+            let octal = data_mode == SpiDataMode::Octal;
+            reg_block.ctrl().modify(|_, w| w.fread_oct().bit(octal));
+            reg_block.user().modify(|_, w| w.fwrite_oct().bit(octal));
         }
     }
 
@@ -2364,82 +2654,7 @@ pub trait Instance: private::Sealed {
 
     // taken from https://github.com/apache/incubator-nuttx/blob/8267a7618629838231256edfa666e44b5313348e/arch/risc-v/src/esp32c3/esp32c3_spi.c#L496
     fn setup(&mut self, frequency: HertzU32, clocks: &Clocks) {
-        #[cfg(not(esp32h2))]
-        let apb_clk_freq: HertzU32 = HertzU32::Hz(clocks.apb_clock.to_Hz());
-        // ESP32-H2 is using PLL_48M_CLK source instead of APB_CLK
-        #[cfg(esp32h2)]
-        let apb_clk_freq: HertzU32 = HertzU32::Hz(clocks.pll_48m_clock.to_Hz());
-
-        let reg_val: u32;
-        let duty_cycle = 128;
-
-        // In HW, n, h and l fields range from 1 to 64, pre ranges from 1 to 8K.
-        // The value written to register is one lower than the used value.
-
-        if frequency > ((apb_clk_freq / 4) * 3) {
-            // Using APB frequency directly will give us the best result here.
-            reg_val = 1 << 31;
-        } else {
-            /* For best duty cycle resolution, we want n to be as close to 32 as
-             * possible, but we also need a pre/n combo that gets us as close as
-             * possible to the intended frequency. To do this, we bruteforce n and
-             * calculate the best pre to go along with that. If there's a choice
-             * between pre/n combos that give the same result, use the one with the
-             * higher n.
-             */
-
-            let mut pre: i32;
-            let mut bestn: i32 = -1;
-            let mut bestpre: i32 = -1;
-            let mut besterr: i32 = 0;
-            let mut errval: i32;
-
-            /* Start at n = 2. We need to be able to set h/l so we have at least
-             * one high and one low pulse.
-             */
-
-            for n in 2..64 {
-                /* Effectively, this does:
-                 *   pre = round((APB_CLK_FREQ / n) / frequency)
-                 */
-
-                pre = ((apb_clk_freq.raw() as i32 / n) + (frequency.raw() as i32 / 2))
-                    / frequency.raw() as i32;
-
-                if pre <= 0 {
-                    pre = 1;
-                }
-
-                if pre > 16 {
-                    pre = 16;
-                }
-
-                errval = (apb_clk_freq.raw() as i32 / (pre * n) - frequency.raw() as i32).abs();
-                if bestn == -1 || errval <= besterr {
-                    besterr = errval;
-                    bestn = n;
-                    bestpre = pre;
-                }
-            }
-
-            let n: i32 = bestn;
-            pre = bestpre;
-            let l: i32 = n;
-
-            /* Effectively, this does:
-             *   h = round((duty_cycle * n) / 256)
-             */
-
-            let mut h: i32 = (duty_cycle * n + 127) / 256;
-            if h <= 0 {
-                h = 1;
-            }
-
-            reg_val = (l as u32 - 1)
-                | ((h as u32 - 1) << 6)
-                | ((n as u32 - 1) << 12)
-                | ((pre as u32 - 1) << 18);
-        }
+        let reg_val = clock_register_value(frequency, clocks);
 
         self.register_block()
             .clock()
@@ -2589,6 +2804,65 @@ pub trait Instance: private::Sealed {
         });
     }
 
+    /// Changes the clock register to a value calculated by
+    /// `clock_register_value`
+    fn set_clock_register(&mut self, reg_val: u32) {
+        // Disable clock source
+        #[cfg(not(any(esp32, esp32s2)))]
+        self.register_block().clk_gate().modify(|_, w| {
+            w.clk_en()
+                .clear_bit()
+                .mst_clk_active()
+                .clear_bit()
+                .mst_clk_sel()
+                .clear_bit()
+        });
+
+        self.register_block()
+            .clock()
+            .write(|w| unsafe { w.bits(reg_val) });
+
+        // Enable clock source
+        #[cfg(not(any(esp32, esp32s2)))]
+        self.register_block().clk_gate().modify(|_, w| {
+            w.clk_en()
+                .set_bit()
+                .mst_clk_active()
+                .set_bit()
+                .mst_clk_sel()
+                .set_bit()
+        });
+    }
+
+    /// Enables the given hardware CS line (0 to 2) and disables the others.
+    /// `None` disables all of them.
+    fn select_cs_line(&mut self, line: Option<u8>) {
+        #[cfg(esp32)]
+        let reg = self.register_block().pin();
+        #[cfg(not(esp32))]
+        let reg = self.register_block().misc();
+
+        reg.modify(|_, w| {
+            w.cs0_dis()
+                .bit(line != Some(0))
+                .cs1_dis()
+                .bit(line != Some(1))
+                .cs2_dis()
+                .bit(line != Some(2))
+        });
+    }
+
+    /// Keeps the enabled hardware CS line asserted between transfers
+    fn set_cs_keep_active(&mut self, active: bool) {
+        #[cfg(esp32)]
+        let reg = self.register_block().pin();
+        #[cfg(not(esp32))]
+        let reg = self.register_block().misc();
+
+        reg.modify(|_, w| w.cs_keep_active().bit(active));
+        self.update();
+    }
+
     #[cfg(not(any(esp32, esp32c3, esp32s2)))]
     fn set_bit_order(&mut self, read_order: SpiBitOrder, write_order: SpiBitOrder) {
         let reg_block = self.register_block();
@@ -3044,6 +3318,16 @@ impl Instance for crate::peripherals::SPI2 {
         OutputSignal::FSPICS0
     }
 
+    #[inline(always)]
+    fn cs1_signal(&self) -> OutputSignal {
+        OutputSignal::FSPICS1
+    }
+
+    #[inline(always)]
+    fn cs2_signal(&self) -> OutputSignal {
+        OutputSignal::FSPICS2
+    }
+
     #[inline(always)]
     fn enable_peripheral(&self) {
         PeripheralClockControl::enable(crate::system::Peripheral::Spi2);
@@ -3121,6 +3405,16 @@ impl Instance for crate::peripherals::SPI2 {
         OutputSignal::HSPICS0
     }
 
+    #[inline(always)]
+    fn cs1_signal(&self) -> OutputSignal {
+        OutputSignal::HSPICS1
+    }
+
+    #[inline(always)]
+    fn cs2_signal(&self) -> OutputSignal {
+        OutputSignal::HSPICS2
+    }
+
     #[inline(always)]
     fn enable_peripheral(&self) {
         PeripheralClockControl::enable(crate::system::Peripheral::Spi2);
@@ -3192,6 +3486,16 @@ impl Instance for crate::peripherals::SPI3 {
         OutputSignal::VSPICS0
     }
 
+    #[inline(always)]
+    fn cs1_signal(&self) -> OutputSignal {
+        OutputSignal::VSPICS1
+    }
+
+    #[inline(always)]
+    fn cs2_signal(&self) -> OutputSignal {
+        OutputSignal::VSPICS2
+    }
+
     #[inline(always)]
     fn enable_peripheral(&self) {
         PeripheralClockControl::enable(crate::system::Peripheral::Spi3)
@@ -3236,6 +3540,16 @@ impl Instance for crate::peripherals::SPI2 {
         OutputSignal::FSPICS0
     }
 
+    #[inline(always)]
+    fn cs1_signal(&self) -> OutputSignal {
+        OutputSignal::FSPICS1
+    }
+
+    #[inline(always)]
+    fn cs2_signal(&self) -> OutputSignal {
+        OutputSignal::FSPICS2
+    }
+
     #[inline(always)]
     fn enable_peripheral(&self) {
         PeripheralClockControl::enable(crate::system::Peripheral::Spi2)
@@ -3280,6 +3594,49 @@ impl ExtendedInstance for crate::peripherals::SPI2 {
     }
 }
 
+#[cfg(esp32s3)]
+impl OctalInstance for crate::peripherals::SPI2 {
+    #[inline(always)]
+    fn sio4_output_signal(&self) -> OutputSignal {
+        OutputSignal::FSPIIO4
+    }
+
+    #[inline(always)]
+    fn sio4_input_signal(&self) -> InputSignal {
+        InputSignal::FSPIIO4
+    }
+
+    #[inline(always)]
+    fn sio5_output_signal(&self) -> OutputSignal {
+        OutputSignal::FSPIIO5
+    }
+
+    #[inline(always)]
+    fn sio5_input_signal(&self) -> InputSignal {
+        InputSignal::FSPIIO5
+    }
+
+    #[inline(always)]
+    fn sio6_output_signal(&self) -> OutputSignal {
+        OutputSignal::FSPIIO6
+    }
+
+    #[inline(always)]
+    fn sio6_input_signal(&self) -> InputSignal {
+        InputSignal::FSPIIO6
+    }
+
+    #[inline(always)]
+    fn sio7_output_signal(&self) -> OutputSignal {
+        OutputSignal::FSPIIO7
+    }
+
+    #[inline(always)]
+    fn sio7_input_signal(&self) -> InputSignal {
+        InputSignal::FSPIIO7
+    }
+}
+
 #[cfg(any(esp32s2, esp32s3))]
 impl Instance for crate::peripherals::SPI3 {
     #[inline(always)]
@@ -3313,6 +3670,16 @@ impl Instance for crate::peripherals::SPI3 {
         OutputSignal::SPI3_CS0
     }
 
+    #[inline(always)]
+    fn cs1_signal(&self) -> OutputSignal {
+        OutputSignal::SPI3_CS1
+    }
+
+    #[inline(always)]
+    fn cs2_signal(&self) -> OutputSignal {
+        OutputSignal::SPI3_CS2
+    }
+
     #[inline(always)]
     fn enable_peripheral(&self) {
         PeripheralClockControl::enable(crate::system::Peripheral::Spi3)
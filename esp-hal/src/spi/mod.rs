@@ -2,11 +2,14 @@
 //!
 //! This peripheral is capable of operating in either master or slave mode. For
 //! more information on these modes, please refer to the documentation in their
-//! respective modules.
+//! respective modules. Devices sharing a bus in master mode are covered by the
+//! `shared` module.
 
 use crate::dma::DmaError;
 
 pub mod master;
+#[cfg(feature = "embedded-hal")]
+pub mod shared;
 #[cfg(not(esp32))]
 pub mod slave;
 
@@ -61,12 +64,17 @@ pub trait IsHalfDuplex: DuplexMode {}
 /// Single = 1 bit, 2 wires
 /// Dual = 2 bit, 2 wires
 /// Quad = 4 bit, 4 wires
+/// Octal = 8 bit, 8 wires (ESP32-S3 SPI2 only)
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SpiDataMode {
     Single,
     Dual,
     Quad,
+    /// Eight data lines at single transfer rate. Double transfer rate (DTR)
+    /// is only supported by the SPI0/1 memory controller, not by SPI2.
+    #[cfg(esp32s3)]
+    Octal,
 }
 
 /// Full-duplex operation
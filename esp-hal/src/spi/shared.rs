@@ -0,0 +1,359 @@
+//! # Shared SPI bus
+//!
+//! ## Overview
+//!
+//! This module provides [`SpiDevice`] implementations which allow multiple
+//! devices to share a single [`Spi`] or [`SpiDma`] bus. Every device carries
+//! its own [`DeviceConfig`] (frequency, mode and bit order) which is applied to
+//! the bus at the beginning of each transaction, so devices with different
+//! requirements can live on the same bus.
+//!
+//! A device is selected either with one of the hardware CS lines of the SPI
+//! peripheral (see [`HardwareCs`]) or with any GPIO configured as an output.
+//!
+//! - [`CriticalSectionDevice`] shares the bus through a
+//!   [`critical_section::Mutex`] and implements the blocking [`SpiDevice`]
+//!   trait.
+//! - [`MutexDevice`] shares the bus through an [`embassy_sync::mutex::Mutex`]
+//!   and implements the async [`SpiDevice`](embedded_hal_async::spi::SpiDevice)
+//!   trait (requires the `async` feature).
+//!
+//! ## Example
+//! ```no_run
+//! let spi = Spi::new(peripherals.SPI2, 1.MHz(), SpiMode::Mode0, &clocks)
+//!     .with_sck(sclk)
+//!     .with_mosi(mosi)
+//!     .with_miso(miso)
+//!     .with_cs(cs0)
+//!     .with_cs1(cs1);
+//!
+//! let bus = critical_section::Mutex::new(RefCell::new(spi));
+//!
+//! let mut display = CriticalSectionDevice::new(
+//!     &bus,
+//!     HardwareCs::Cs0,
+//!     DeviceConfig::new(20.MHz(), SpiMode::Mode0),
+//!     &clocks,
+//! );
+//! let mut sensor = CriticalSectionDevice::new(
+//!     &bus,
+//!     HardwareCs::Cs1,
+//!     DeviceConfig::new(1.MHz(), SpiMode::Mode3),
+//!     &clocks,
+//! );
+//! let mut flash = CriticalSectionDevice::new(
+//!     &bus,
+//!     Output::new(io.pins.gpio4, Level::High),
+//!     DeviceConfig::new(40.MHz(), SpiMode::Mode0),
+//!     &clocks,
+//! );
+//! ```
+//!
+//! [`Spi`]: super::master::Spi
+//! [`SpiDma`]: super::master::dma::SpiDma
+
+use core::{cell::RefCell, convert::Infallible};
+
+use embedded_hal::spi::{ErrorType, Operation, SpiBus, SpiDevice};
+use fugit::HertzU32;
+
+use super::{master::clock_register_value, Error, SpiBitOrder, SpiMode};
+use crate::{clock::Clocks, delay::Delay};
+
+/// Bus settings of a single device on a shared bus
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DeviceConfig {
+    /// SPI clock frequency
+    pub frequency: HertzU32,
+    /// SPI mode
+    pub mode: SpiMode,
+    /// Bit order used when receiving
+    pub read_bit_order: SpiBitOrder,
+    /// Bit order used when transmitting
+    pub write_bit_order: SpiBitOrder,
+}
+
+impl DeviceConfig {
+    /// Create a new configuration, using MSB first for both directions
+    pub fn new(frequency: HertzU32, mode: SpiMode) -> Self {
+        Self {
+            frequency,
+            mode,
+            read_bit_order: SpiBitOrder::MSBFirst,
+            write_bit_order: SpiBitOrder::MSBFirst,
+        }
+    }
+
+    /// Set the bit order for both directions
+    pub fn with_bit_order(mut self, read_order: SpiBitOrder, write_order: SpiBitOrder) -> Self {
+        self.read_bit_order = read_order;
+        self.write_bit_order = write_order;
+        self
+    }
+}
+
+/// Hardware CS lines of the SPI peripheral
+///
+/// The lines have to be connected to pins with [`Spi::with_cs`],
+/// [`Spi::with_cs1`] and [`Spi::with_cs2`].
+///
+/// [`Spi::with_cs`]: super::master::Spi::with_cs
+/// [`Spi::with_cs1`]: super::master::Spi::with_cs1
+/// [`Spi::with_cs2`]: super::master::Spi::with_cs2
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HardwareCs {
+    /// The line connected with [`Spi::with_cs`]
+    ///
+    /// [`Spi::with_cs`]: super::master::Spi::with_cs
+    Cs0,
+    /// The line connected with [`Spi::with_cs1`]
+    ///
+    /// [`Spi::with_cs1`]: super::master::Spi::with_cs1
+    Cs1,
+    /// The line connected with [`Spi::with_cs2`]
+    ///
+    /// [`Spi::with_cs2`]: super::master::Spi::with_cs2
+    Cs2,
+}
+
+/// A way to select a device on a shared bus
+///
+/// Implemented for [`HardwareCs`] and for output pins.
+pub trait ChipSelect {
+    #[doc(hidden)]
+    fn hardware_line(&self) -> Option<u8>;
+
+    #[doc(hidden)]
+    fn set_asserted(&mut self, asserted: bool);
+}
+
+impl ChipSelect for HardwareCs {
+    fn hardware_line(&self) -> Option<u8> {
+        Some(*self as u8)
+    }
+
+    fn set_asserted(&mut self, _asserted: bool) {
+        // driven by the peripheral
+    }
+}
+
+impl<P> ChipSelect for P
+where
+    P: embedded_hal::digital::OutputPin<Error = Infallible>,
+{
+    fn hardware_line(&self) -> Option<u8> {
+        None
+    }
+
+    fn set_asserted(&mut self, asserted: bool) {
+        if asserted {
+            self.set_low().ok();
+        } else {
+            self.set_high().ok();
+        }
+    }
+}
+
+/// An SPI bus which can be reconfigured for each device
+///
+/// Implemented for full-duplex [`Spi`](super::master::Spi) and
+/// [`SpiDma`](super::master::dma::SpiDma).
+pub trait ConfigurableBus: crate::private::Sealed {
+    #[doc(hidden)]
+    fn apply_device_config(&mut self, config: &DeviceConfig, clock_reg: u32);
+
+    #[doc(hidden)]
+    fn set_hardware_cs(&mut self, line: Option<u8>);
+}
+
+fn run_operations<BUS>(
+    bus: &mut BUS,
+    operations: &mut [Operation<'_, u8>],
+    delay: &Delay,
+) -> Result<(), Error>
+where
+    BUS: SpiBus<Error = Error>,
+{
+    for operation in operations {
+        match operation {
+            Operation::Read(buffer) => bus.read(buffer)?,
+            Operation::Write(buffer) => bus.write(buffer)?,
+            Operation::Transfer(read, write) => bus.transfer(read, write)?,
+            Operation::TransferInPlace(buffer) => bus.transfer_in_place(buffer)?,
+            Operation::DelayNs(ns) => {
+                bus.flush()?;
+                delay.delay_nanos(*ns);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A device on an SPI bus shared through a [`critical_section::Mutex`]
+///
+/// Each transaction runs inside a critical section.
+pub struct CriticalSectionDevice<'a, BUS, CS> {
+    bus: &'a critical_section::Mutex<RefCell<BUS>>,
+    cs: CS,
+    config: DeviceConfig,
+    clock_reg: u32,
+    delay: Delay,
+}
+
+impl<'a, BUS, CS> CriticalSectionDevice<'a, BUS, CS>
+where
+    BUS: ConfigurableBus,
+    CS: ChipSelect,
+{
+    /// Create a new device on the given bus
+    pub fn new(
+        bus: &'a critical_section::Mutex<RefCell<BUS>>,
+        mut cs: CS,
+        config: DeviceConfig,
+        clocks: &Clocks,
+    ) -> Self {
+        cs.set_asserted(false);
+
+        Self {
+            bus,
+            cs,
+            config,
+            clock_reg: clock_register_value(config.frequency, clocks),
+            delay: Delay::new(clocks),
+        }
+    }
+}
+
+impl<BUS, CS> ErrorType for CriticalSectionDevice<'_, BUS, CS> {
+    type Error = Error;
+}
+
+impl<BUS, CS> SpiDevice for CriticalSectionDevice<'_, BUS, CS>
+where
+    BUS: ConfigurableBus + SpiBus<Error = Error>,
+    CS: ChipSelect,
+{
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        critical_section::with(|token| {
+            let bus = &mut *self.bus.borrow_ref_mut(token);
+
+            bus.apply_device_config(&self.config, self.clock_reg);
+            bus.set_hardware_cs(self.cs.hardware_line());
+            self.cs.set_asserted(true);
+
+            let result = run_operations(bus, operations, &self.delay);
+            let flush_result = bus.flush();
+
+            self.cs.set_asserted(false);
+            bus.set_hardware_cs(None);
+
+            result.and(flush_result)
+        })
+    }
+}
+
+#[cfg(feature = "async")]
+pub use asynch::MutexDevice;
+
+#[cfg(feature = "async")]
+mod asynch {
+    use embassy_sync::{blocking_mutex::raw::RawMutex, mutex::Mutex};
+    use embedded_hal_async::spi::SpiBus;
+
+    use super::*;
+
+    async fn run_operations<BUS>(
+        bus: &mut BUS,
+        operations: &mut [Operation<'_, u8>],
+        delay: &Delay,
+    ) -> Result<(), Error>
+    where
+        BUS: SpiBus<Error = Error>,
+    {
+        for operation in operations {
+            match operation {
+                Operation::Read(buffer) => bus.read(buffer).await?,
+                Operation::Write(buffer) => bus.write(buffer).await?,
+                Operation::Transfer(read, write) => bus.transfer(read, write).await?,
+                Operation::TransferInPlace(buffer) => bus.transfer_in_place(buffer).await?,
+                Operation::DelayNs(ns) => {
+                    bus.flush().await?;
+                    delay.delay_nanos(*ns);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// A device on an SPI bus shared through an
+    /// [`embassy_sync::mutex::Mutex`]
+    ///
+    /// The bus is locked for the whole transaction, other tasks keep running
+    /// while waiting for it. Delays inside a transaction are blocking.
+    pub struct MutexDevice<'a, M: RawMutex, BUS, CS> {
+        bus: &'a Mutex<M, BUS>,
+        cs: CS,
+        config: DeviceConfig,
+        clock_reg: u32,
+        delay: Delay,
+    }
+
+    impl<'a, M, BUS, CS> MutexDevice<'a, M, BUS, CS>
+    where
+        M: RawMutex,
+        BUS: ConfigurableBus,
+        CS: ChipSelect,
+    {
+        /// Create a new device on the given bus
+        pub fn new(
+            bus: &'a Mutex<M, BUS>,
+            mut cs: CS,
+            config: DeviceConfig,
+            clocks: &Clocks,
+        ) -> Self {
+            cs.set_asserted(false);
+
+            Self {
+                bus,
+                cs,
+                config,
+                clock_reg: clock_register_value(config.frequency, clocks),
+                delay: Delay::new(clocks),
+            }
+        }
+    }
+
+    impl<M: RawMutex, BUS, CS> ErrorType for MutexDevice<'_, M, BUS, CS> {
+        type Error = Error;
+    }
+
+    impl<M, BUS, CS> embedded_hal_async::spi::SpiDevice for MutexDevice<'_, M, BUS, CS>
+    where
+        M: RawMutex,
+        BUS: ConfigurableBus + SpiBus<Error = Error>,
+        CS: ChipSelect,
+    {
+        async fn transaction(
+            &mut self,
+            operations: &mut [Operation<'_, u8>],
+        ) -> Result<(), Self::Error> {
+            let mut bus = self.bus.lock().await;
+
+            bus.apply_device_config(&self.config, self.clock_reg);
+            bus.set_hardware_cs(self.cs.hardware_line());
+            self.cs.set_asserted(true);
+
+            let result = run_operations(&mut *bus, operations, &self.delay).await;
+            let flush_result = bus.flush().await;
+
+            self.cs.set_asserted(false);
+            bus.set_hardware_cs(None);
+
+            result.and(flush_result)
+        }
+    }
+}
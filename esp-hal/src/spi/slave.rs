@@ -26,6 +26,13 @@
 //! then the DmaTransfer trait instance can be wait()ed on or polled for
 //! is_done().
 //!
+//! A transfer completes when the master de-asserts CS, so transactions may be
+//! shorter than the provided buffers; `received_len()` returns the number of
+//! bytes actually received. With a DMA channel configured for async operation,
+//! `read`, `write` and `transfer` can be awaited instead. For continuous
+//! streaming, `dma_read_circular` keeps receiving into a circular buffer as
+//! long as the master keeps CS asserted.
+//!
 //! ```rust
 //! let dma = Gdma::new(peripherals.DMA);
 //! const N: usize = (buffer_size + 4091) / 4092;
@@ -138,6 +145,7 @@ pub mod dma {
             Channel,
             ChannelTypes,
             DmaTransferRx,
+            DmaTransferRxCircular,
             DmaTransferTx,
             DmaTransferTxRx,
             RxPrivate,
@@ -253,7 +261,7 @@ pub mod dma {
         }
 
         fn peripheral_dma_stop(&mut self) {
-            unreachable!("unsupported")
+            self.spi.disable_dma();
         }
     }
 
@@ -339,11 +347,43 @@ pub mod dma {
 
             unsafe {
                 self.spi
-                    .start_read_bytes_dma(ptr, len, &mut self.channel.rx)
+                    .start_read_bytes_dma(ptr, len, false, &mut self.channel.rx)
                     .map(move |_| DmaTransferRx::new(self))
             }
         }
 
+        /// Continuously receive data into a circular buffer using DMA.
+        ///
+        /// This will return a [DmaTransferRxCircular]. Received data becomes
+        /// available whenever a descriptor has been filled or the master
+        /// ends the transaction. The master has to keep the CS line asserted
+        /// for the whole stream, since the slave is not re-armed after CS
+        /// has been de-asserted.
+        pub fn dma_read_circular<'t, RXBUF>(
+            &'t mut self,
+            words: &'t mut RXBUF,
+        ) -> Result<DmaTransferRxCircular<Self>, Error>
+        where
+            RXBUF: WriteBuffer<Word = u8>,
+        {
+            let (ptr, len) = unsafe { words.write_buffer() };
+
+            unsafe {
+                self.spi
+                    .start_read_bytes_dma(ptr, len, true, &mut self.channel.rx)
+                    .map(move |_| DmaTransferRxCircular::new(self))
+            }
+        }
+
+        /// Number of bytes received by the last (non-circular) read or
+        /// transfer.
+        ///
+        /// A transfer completes when the master de-asserts CS, which may
+        /// happen before the buffer has been filled.
+        pub fn received_len(&self) -> usize {
+            self.channel.rx.received_len()
+        }
+
         /// Register buffers for a DMA transfer.
         ///
         /// This will return a [SpiDmaTransferRxTx] owning the buffer(s) and the
@@ -382,6 +422,114 @@ pub mod dma {
             }
         }
     }
+
+    #[cfg(feature = "async")]
+    mod asynch {
+        use super::*;
+        use crate::dma::{
+            asynch::{DmaRxFuture, DmaTxFuture},
+            DmaError,
+        };
+
+        impl<'d, T, C> SpiDma<'d, T, C, crate::Async>
+        where
+            T: InstanceDma<C::Tx<'d>, C::Rx<'d>>,
+            C: ChannelTypes,
+            C::P: SpiPeripheral,
+        {
+            /// Receive data until the master de-asserts CS or the buffer is
+            /// full.
+            ///
+            /// Returns the number of bytes received.
+            pub async fn read(&mut self, words: &mut [u8]) -> Result<usize, Error> {
+                if words.len() > MAX_DMA_SIZE {
+                    return Err(Error::MaxDmaTransferSizeExceeded);
+                }
+
+                let mut future = DmaRxFuture::new(&mut self.channel.rx);
+                unsafe {
+                    self.spi.start_read_bytes_dma(
+                        words.as_mut_ptr(),
+                        words.len(),
+                        false,
+                        future.rx(),
+                    )?;
+                }
+                let result = future.await;
+
+                Self::completed_len(self.channel.rx.received_len(), words.len(), result)
+            }
+
+            /// Send data to the master.
+            ///
+            /// Completes once the master has ended the transaction. The master
+            /// is expected to clock out the complete buffer, use
+            /// [`transfer`](Self::transfer) for transactions of variable
+            /// length.
+            pub async fn write(&mut self, words: &[u8]) -> Result<(), Error> {
+                if words.len() > MAX_DMA_SIZE {
+                    return Err(Error::MaxDmaTransferSizeExceeded);
+                }
+
+                let mut future = DmaTxFuture::new(&mut self.channel.tx);
+                self.spi
+                    .start_write_bytes_dma(words.as_ptr(), words.len(), future.tx())?;
+                future.await?;
+
+                // at most a FIFO worth of data is left to be clocked out
+                self.spi.flush()
+            }
+
+            /// Send and receive data in the same transaction.
+            ///
+            /// Completes when the master de-asserts CS, which may happen
+            /// before all data has been sent or the receive buffer is full.
+            ///
+            /// Returns the number of bytes received.
+            pub async fn transfer(
+                &mut self,
+                read: &mut [u8],
+                write: &[u8],
+            ) -> Result<usize, Error> {
+                if read.len() > MAX_DMA_SIZE || write.len() > MAX_DMA_SIZE {
+                    return Err(Error::MaxDmaTransferSizeExceeded);
+                }
+
+                let mut future = DmaRxFuture::new(&mut self.channel.rx);
+                unsafe {
+                    self.spi.start_transfer_dma(
+                        write.as_ptr(),
+                        write.len(),
+                        read.as_mut_ptr(),
+                        read.len(),
+                        &mut self.channel.tx,
+                        future.rx(),
+                    )?;
+                }
+                let result = future.await;
+
+                if self.channel.tx.has_error() {
+                    self.channel.tx.clear_interrupts();
+                    return Err(Error::DmaError(DmaError::DescriptorError));
+                }
+
+                Self::completed_len(self.channel.rx.received_len(), read.len(), result)
+            }
+
+            fn completed_len(
+                len: usize,
+                buffer_len: usize,
+                result: Result<(), DmaError>,
+            ) -> Result<usize, Error> {
+                match result {
+                    Ok(()) => Ok(len),
+                    // The master kept clocking after the buffer has been filled
+                    Err(_) if len == buffer_len => Ok(len),
+                    Err(error) => Err(error.into()),
+                }
+            }
+        }
+    }
 }
 
 #[doc(hidden)]
@@ -424,9 +572,7 @@ where
 
         reset_dma_before_usr_cmd(reg_block);
 
-        reg_block
-            .dma_conf()
-            .modify(|_, w| w.dma_slv_seg_trans_en().clear_bit());
+        set_eof_on_cs_deassert(reg_block);
 
         self.clear_dma_interrupts();
         self.setup_for_flush();
@@ -456,9 +602,7 @@ where
 
         reset_dma_before_usr_cmd(reg_block);
 
-        reg_block
-            .dma_conf()
-            .modify(|_, w| w.dma_slv_seg_trans_en().clear_bit());
+        set_eof_on_cs_deassert(reg_block);
 
         self.clear_dma_interrupts();
         self.setup_for_flush();
@@ -473,6 +617,7 @@ where
         &mut self,
         ptr: *mut u8,
         len: usize,
+        circular: bool,
         rx: &mut RX,
     ) -> Result<(), Error> {
         let reg_block = self.register_block();
@@ -482,13 +627,11 @@ where
         self.enable_dma();
 
         reset_dma_before_load_dma_dscr(reg_block);
-        rx.prepare_transfer_without_start(false, self.dma_peripheral(), ptr, len)?;
+        rx.prepare_transfer_without_start(circular, self.dma_peripheral(), ptr, len)?;
 
         reset_dma_before_usr_cmd(reg_block);
 
-        reg_block
-            .dma_conf()
-            .modify(|_, w| w.dma_slv_seg_trans_en().clear_bit());
+        set_eof_on_cs_deassert(reg_block);
 
         self.clear_dma_interrupts();
         self.setup_for_flush();
@@ -511,14 +654,9 @@ where
     #[cfg(any(esp32c2, esp32c3, esp32c6, esp32h2, esp32s3))]
     fn enable_dma(&self) {
         let reg_block = self.register_block();
-        reg_block.dma_conf().modify(|_, w| {
-            w.dma_tx_ena()
-                .set_bit()
-                .dma_rx_ena()
-                .set_bit()
-                .rx_eof_en()
-                .clear_bit()
-        });
+        reg_block
+            .dma_conf()
+            .modify(|_, w| w.dma_tx_ena().set_bit().dma_rx_ena().set_bit());
     }
 
     #[cfg(esp32s2)]
@@ -526,6 +664,18 @@ where
         // for non GDMA this is done in `assign_tx_device` / `assign_rx_device`
     }
 
+    #[cfg(any(esp32c2, esp32c3, esp32c6, esp32h2, esp32s3))]
+    fn disable_dma(&self) {
+        self.register_block()
+            .dma_conf()
+            .modify(|_, w| w.dma_tx_ena().clear_bit().dma_rx_ena().clear_bit());
+    }
+
+    #[cfg(esp32s2)]
+    fn disable_dma(&self) {
+        reset_dma_before_load_dma_dscr(self.register_block());
+    }
+
     #[cfg(any(esp32c2, esp32c3, esp32c6, esp32h2, esp32s3))]
     fn clear_dma_interrupts(&self) {
         let reg_block = self.register_block();
@@ -569,6 +719,15 @@ where
     }
 }
 
+/// Ends a DMA read with an EOF when the master de-asserts CS, instead of after
+/// a fixed number of received bits, and disables segmented transfers. This is
+/// what makes `received_len` report the length of the last transaction.
+fn set_eof_on_cs_deassert(reg_block: &RegisterBlock) {
+    reg_block
+        .dma_conf()
+        .modify(|_, w| w.rx_eof_en().clear_bit().dma_slv_seg_trans_en().clear_bit());
+}
+
 #[cfg(not(esp32s2))]
 fn reset_dma_before_usr_cmd(reg_block: &RegisterBlock) {
     reg_block.dma_conf().modify(|_, w| {
@@ -97,6 +97,8 @@ pub enum Peripheral {
     Trace0,
     #[cfg(lcd_cam)]
     LcdCam,
+    #[cfg(all(gdma, uhci0))]
+    Uhci0,
 }
 
 /// The `DPORT`/`PCR`/`SYSTEM` peripheral split into its different logical
@@ -478,6 +480,11 @@ impl PeripheralClockControl {
                 perip_clk_en1.modify(|_, w| w.lcd_cam_clk_en().set_bit());
                 perip_rst_en1.modify(|_, w| w.lcd_cam_rst().clear_bit());
             }
+            #[cfg(all(gdma, uhci0))]
+            Peripheral::Uhci0 => {
+                perip_clk_en0.modify(|_, w| w.uhci0_clk_en().set_bit());
+                perip_rst_en0.modify(|_, w| w.uhci0_rst().clear_bit());
+            }
         });
     }
 
@@ -682,6 +689,11 @@ impl PeripheralClockControl {
                 perip_rst_en1.modify(|_, w| w.lcd_cam_rst().set_bit());
                 perip_rst_en1.modify(|_, w| w.lcd_cam_rst().clear_bit());
             }
+            #[cfg(all(gdma, uhci0))]
+            Peripheral::Uhci0 => {
+                perip_rst_en0.modify(|_, w| w.uhci0_rst().set_bit());
+                perip_rst_en0.modify(|_, w| w.uhci0_rst().clear_bit());
+            }
         });
     }
 }
@@ -900,6 +912,13 @@ impl PeripheralClockControl {
                     .trace_conf()
                     .modify(|_, w| w.trace_rst_en().clear_bit());
             }
+            #[cfg(uhci0)]
+            Peripheral::Uhci0 => {
+                system.uhci_conf().modify(|_, w| w.uhci_clk_en().set_bit());
+                system
+                    .uhci_conf()
+                    .modify(|_, w| w.uhci_rst_en().clear_bit());
+            }
         }
     }
 
@@ -1092,6 +1111,13 @@ impl PeripheralClockControl {
                     .trace_conf()
                     .modify(|_, w| w.trace_rst_en().clear_bit());
             }
+            #[cfg(uhci0)]
+            Peripheral::Uhci0 => {
+                system.uhci_conf().modify(|_, w| w.uhci_rst_en().set_bit());
+                system
+                    .uhci_conf()
+                    .modify(|_, w| w.uhci_rst_en().clear_bit());
+            }
         }
     }
 }
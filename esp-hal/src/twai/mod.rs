@@ -16,6 +16,28 @@
 //! controllers. It supports Standard Frame Format (11-bit) and Extended Frame
 //! Format (29-bit) frame identifiers.
 //!
+//! Besides the normal mode, the controller can be configured with
+//! [TwaiConfiguration::into_mode] for a self-test without other nodes on the
+//! bus ([SelfTest]) or for monitoring a bus without affecting it
+//! ([ListenOnly]). The operating mode is part of the driver types, so
+//! transmitting in listen-only mode is rejected at compile time. With
+//! [TwaiConfiguration::set_single_shot] frames are not retransmitted after a
+//! lost arbitration or an error.
+//!
+//! ## Error handling
+//! The controller reports changes of its fault confinement state
+//! ([ErrorState]), lost arbitrations and captured bus errors as [TwaiEvent]s.
+//! In blocking mode they are polled with [Twai::poll_event], in async mode
+//! the interrupt handler queues them for the stream returned by
+//! `Twai::events`. The
+//! async interrupt handler also moves all received frames into a receive
+//! queue, so no frames are lost while tasks are busy.
+//!
+//! When the transmit error counter exceeds 255 the controller goes bus off
+//! and stops taking part in bus activities. It is brought back with
+//! [Twai::recover_from_bus_off] (or `Twai::recover_from_bus_off_async`),
+//! which follows the recovery sequence of the CAN specification.
+//!
 //! ## Example
 //! ```no_run
 //! // Use GPIO pins 2 and 3 to connect to the respective pins on the CAN
@@ -628,16 +650,79 @@ impl BaudRate {
     }
 }
 
+/// Operating mode of the TWAI controller
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TwaiMode {
+    /// The controller transmits, receives and acknowledges frames.
+    Normal,
+    /// The controller transmits frames without requiring an acknowledgement
+    /// and receives its own frames, e.g. for a self-test without other
+    /// nodes on the bus.
+    SelfTest,
+    /// The controller only receives frames. It doesn't acknowledge frames or
+    /// send error flags, e.g. for a bus monitor.
+    ListenOnly,
+}
+
+/// Operating mode of a [TwaiConfiguration] and [Twai] on type level
+pub trait OperatingMode: crate::private::Sealed {
+    /// The operating mode of the controller
+    const MODE: TwaiMode;
+}
+
+/// An [OperatingMode] in which the controller can transmit frames
+pub trait Transmitting: OperatingMode {}
+
+/// The operating mode [TwaiMode::Normal]
+pub struct Normal;
+
+/// The operating mode [TwaiMode::SelfTest]
+pub struct SelfTest;
+
+/// The operating mode [TwaiMode::ListenOnly]
+pub struct ListenOnly;
+
+impl crate::private::Sealed for Normal {}
+impl crate::private::Sealed for SelfTest {}
+impl crate::private::Sealed for ListenOnly {}
+
+impl OperatingMode for Normal {
+    const MODE: TwaiMode = TwaiMode::Normal;
+}
+
+impl OperatingMode for SelfTest {
+    const MODE: TwaiMode = TwaiMode::SelfTest;
+}
+
+impl OperatingMode for ListenOnly {
+    const MODE: TwaiMode = TwaiMode::ListenOnly;
+}
+
+impl Transmitting for Normal {}
+impl Transmitting for SelfTest {}
+
+fn set_mode<T: Instance>(mode: TwaiMode) {
+    T::register_block().mode().modify(|_, w| {
+        w.listen_only_mode()
+            .bit(mode == TwaiMode::ListenOnly)
+            .self_test_mode()
+            .bit(mode == TwaiMode::SelfTest)
+    });
+}
+
 /// An inactive TWAI peripheral in the "Reset"/configuration state.
-pub struct TwaiConfiguration<'d, T, DM: crate::Mode> {
+pub struct TwaiConfiguration<'d, T, DM: crate::Mode, M: OperatingMode = Normal> {
     peripheral: PhantomData<&'d PeripheralRef<'d, T>>,
+    mode: PhantomData<M>,
+    single_shot: bool,
     phantom: PhantomData<DM>,
 }
 
-impl<'d, T, DM> TwaiConfiguration<'d, T, DM>
+impl<'d, T, DM, M> TwaiConfiguration<'d, T, DM, M>
 where
     T: Instance,
     DM: crate::Mode,
+    M: OperatingMode,
 {
     fn new_internal<TX: OutputPin, RX: InputPin>(
         _peripheral: impl Peripheral<P = T> + 'd,
@@ -661,10 +746,13 @@ where
 
         let mut cfg = TwaiConfiguration {
             peripheral: PhantomData,
+            mode: PhantomData,
+            single_shot: false,
             phantom: PhantomData,
         };
 
         cfg.set_baud_rate(baud_rate, clocks);
+        set_mode::<T>(M::MODE);
 
         if let Some(interrupt) = interrupt {
             unsafe {
@@ -753,9 +841,32 @@ where
             .write(|w| unsafe { w.err_warning_limit().bits(limit) });
     }
 
+    /// Change the operating mode of the controller.
+    ///
+    /// The default is [Normal].
+    pub fn into_mode<M2: OperatingMode>(self, _mode: M2) -> TwaiConfiguration<'d, T, DM, M2> {
+        set_mode::<T>(M2::MODE);
+
+        TwaiConfiguration {
+            peripheral: PhantomData,
+            mode: PhantomData,
+            single_shot: self.single_shot,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Enable or disable single-shot transmission.
+    ///
+    /// In single-shot mode a frame is transmitted only once, it is not
+    /// retransmitted automatically when arbitration is lost or an error
+    /// occurs. Use [TwaiTx::last_transmission_succeeded] to check the result.
+    pub fn set_single_shot(&mut self, single_shot: bool) {
+        self.single_shot = single_shot;
+    }
+
     /// Put the peripheral into Operation Mode, allowing the transmission and
     /// reception of packets using the new object.
-    pub fn start(self) -> Twai<'d, T, DM> {
+    pub fn start(self) -> Twai<'d, T, DM, M> {
         // Put the peripheral into operation mode by clearing the reset mode bit.
         T::register_block()
             .mode()
@@ -764,12 +875,15 @@ where
         Twai {
             tx: TwaiTx {
                 _peripheral: PhantomData,
+                single_shot: self.single_shot,
+                mode: PhantomData,
                 phantom: PhantomData,
             },
             rx: TwaiRx {
                 _peripheral: PhantomData,
                 phantom: PhantomData,
             },
+            pending_events: 0,
             phantom: PhantomData,
         }
     }
@@ -866,24 +980,26 @@ where
     }
 }
 
-/// An active TWAI peripheral in Normal Mode.
+/// An active TWAI peripheral in the [OperatingMode] `M`.
 ///
 /// In this mode, the TWAI controller can transmit and receive messages
 /// including error signals (such as error and overload frames).
-pub struct Twai<'d, T, DM: crate::Mode> {
-    tx: TwaiTx<'d, T, DM>,
+pub struct Twai<'d, T, DM: crate::Mode, M: OperatingMode = Normal> {
+    tx: TwaiTx<'d, T, DM, M>,
     rx: TwaiRx<'d, T, DM>,
+    pending_events: u32,
     phantom: PhantomData<DM>,
 }
 
-impl<'d, T, DM> Twai<'d, T, DM>
+impl<'d, T, DM, M> Twai<'d, T, DM, M>
 where
     T: OperationInstance,
     DM: crate::Mode,
+    M: OperatingMode,
 {
     /// Stop the peripheral, putting it into reset mode and enabling
     /// reconfiguration.
-    pub fn stop(self) -> TwaiConfiguration<'d, T, DM> {
+    pub fn stop(self) -> TwaiConfiguration<'d, T, DM, M> {
         // Put the peripheral into reset/configuration mode by setting the reset mode
         // bit.
         T::register_block()
@@ -892,10 +1008,17 @@ where
 
         TwaiConfiguration {
             peripheral: PhantomData,
+            mode: PhantomData,
+            single_shot: self.tx.single_shot,
             phantom: PhantomData,
         }
     }
 
+    /// Get the operating mode the controller was started in.
+    pub fn mode(&self) -> TwaiMode {
+        M::MODE
+    }
+
     pub fn receive_error_count(&self) -> u8 {
         T::register_block().rx_err_cnt().read().rx_err_cnt().bits()
     }
@@ -913,6 +1036,37 @@ where
             .bit_is_set()
     }
 
+    /// Get the current fault confinement state of the controller.
+    pub fn error_state(&self) -> ErrorState {
+        error_state::<T>()
+    }
+
+    /// Recover the controller from the bus off state.
+    ///
+    /// When entering bus off the controller stops all bus activities. The
+    /// first call starts the recovery sequence, after which the controller
+    /// waits for 128 occurrences of 11 consecutive recessive bits before it
+    /// becomes error active again. Returns [nb::Error::WouldBlock] until then.
+    ///
+    /// Returns `Ok(())` right away if the controller is not bus off.
+    pub fn recover_from_bus_off(&mut self) -> nb::Result<(), EspTwaiError> {
+        let register_block = T::register_block();
+
+        if !register_block.status().read().bus_off_st().bit_is_set() {
+            return Ok(());
+        }
+
+        // The controller enters reset mode when it goes bus off, leaving reset
+        // mode starts the recovery sequence.
+        if register_block.mode().read().reset_mode().bit_is_set() {
+            register_block
+                .mode()
+                .modify(|_, w| w.reset_mode().clear_bit());
+        }
+
+        Err(nb::Error::WouldBlock)
+    }
+
     /// Get the number of messages that the peripheral has available in the
     /// receive FIFO.
     ///
@@ -939,31 +1093,68 @@ where
         }
     }
 
-    pub fn transmit(&mut self, frame: &EspTwaiFrame) -> nb::Result<(), EspTwaiError> {
-        self.tx.transmit(frame)
-    }
-
     pub fn receive(&mut self) -> nb::Result<EspTwaiFrame, EspTwaiError> {
         self.rx.receive()
     }
 
     /// Consumes this `Twai` instance and splits it into transmitting and
     /// receiving halves.
-    pub fn split(self) -> (TwaiTx<'d, T, DM>, TwaiRx<'d, T, DM>) {
+    pub fn split(self) -> (TwaiTx<'d, T, DM, M>, TwaiRx<'d, T, DM>) {
         (self.tx, self.rx)
     }
 }
 
+impl<'d, T, DM, M> Twai<'d, T, DM, M>
+where
+    T: OperationInstance,
+    DM: crate::Mode,
+    M: Transmitting,
+{
+    pub fn transmit(&mut self, frame: &EspTwaiFrame) -> nb::Result<(), EspTwaiError> {
+        self.tx.transmit(frame)
+    }
+
+    /// Abort the pending transmission, see [TwaiTx::abort_transmission].
+    pub fn abort_transmission(&mut self) {
+        self.tx.abort_transmission()
+    }
+}
+
+impl<'d, T, M> Twai<'d, T, crate::Blocking, M>
+where
+    T: OperationInstance,
+    M: OperatingMode,
+{
+    /// Get the next event reported by the controller, if any.
+    ///
+    /// The controller only records events while their interrupt flags are
+    /// enabled, which the first call does. Events which occurred before are
+    /// not reported.
+    ///
+    /// This reads and clears the interrupt flags of the peripheral, so it
+    /// can't be combined with a custom interrupt handler that does the same.
+    pub fn poll_event(&mut self) -> Option<TwaiEvent> {
+        enable_interrupt_bits::<T>(EVENT_INTERRUPTS);
+        self.pending_events |= T::take_interrupts() & EVENT_INTERRUPTS;
+        take_event::<T>(&mut self.pending_events)
+    }
+}
+
 /// Interface to the CAN transmitter part.
-pub struct TwaiTx<'d, T, DM: crate::Mode> {
+///
+/// Frames can only be transmitted in the [Transmitting] operating modes.
+pub struct TwaiTx<'d, T, DM: crate::Mode, M: OperatingMode = Normal> {
     _peripheral: PhantomData<&'d T>,
+    single_shot: bool,
+    mode: PhantomData<M>,
     phantom: PhantomData<DM>,
 }
 
-impl<'d, T, DM> TwaiTx<'d, T, DM>
+impl<'d, T, DM, M> TwaiTx<'d, T, DM, M>
 where
     T: OperationInstance,
     DM: crate::Mode,
+    M: Transmitting,
 {
     /// Transmit a frame.
     ///
@@ -974,9 +1165,8 @@ where
     ///
     /// [ESP32C3 Reference Manual](https://www.espressif.com/sites/default/files/documentation/esp32-c3_technical_reference_manual_en.pdf#subsubsection.29.4.4.2)
     ///
-    /// NOTE: TODO: This may not work if using the self reception/self test
-    /// functionality. See notes 1 and 2 in the "Frame Identifier" section
-    /// of the reference manual.
+    /// In [SelfTest] mode the frame is also received by the controller
+    /// itself.
     pub fn transmit(&mut self, frame: &EspTwaiFrame) -> nb::Result<(), EspTwaiError> {
         let register_block = T::register_block();
         let status = register_block.status().read();
@@ -990,10 +1180,31 @@ where
             return nb::Result::Err(nb::Error::WouldBlock);
         }
 
-        T::write_frame(frame);
+        T::write_frame(frame, self.single_shot);
 
         Ok(())
     }
+
+    /// Abort the pending transmission.
+    ///
+    /// A transmission which is already in progress is completed, a frame
+    /// waiting for the bus is not transmitted. The transmit buffer is
+    /// available again once the controller processed the request.
+    pub fn abort_transmission(&mut self) {
+        T::register_block().cmd().write(|w| w.abort_tx().set_bit());
+    }
+
+    /// Check if the last requested transmission completed successfully.
+    ///
+    /// This is useful in single-shot mode, in which failed transmissions are
+    /// not retried.
+    pub fn last_transmission_succeeded(&self) -> bool {
+        T::register_block()
+            .status()
+            .read()
+            .tx_complete()
+            .bit_is_set()
+    }
 }
 
 /// Interface to the CAN receiver part.
@@ -1064,6 +1275,146 @@ impl embedded_can::Error for EspTwaiError {
     }
 }
 
+/// Fault confinement state of the TWAI controller
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ErrorState {
+    /// Both error counters are below the error warning limit
+    Active,
+    /// One of the error counters reached the error warning limit, see
+    /// [TwaiConfiguration::set_error_warning_limit]
+    Warning,
+    /// One of the error counters reached 128. The controller only sends
+    /// passive error flags.
+    Passive,
+    /// The transmit error counter exceeded 255. The controller does not take
+    /// part in bus activities until it is recovered with
+    /// [Twai::recover_from_bus_off].
+    BusOff,
+}
+
+/// Details of a bus error captured by the controller
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BusError {
+    /// The kind of the error
+    pub kind: ErrorKind,
+    /// The segment of the frame in which the error occurred, encoded as in
+    /// the `ERR_CODE_CAP` register
+    pub segment: u8,
+    /// `true` if the error occurred while receiving, `false` while
+    /// transmitting
+    pub during_reception: bool,
+}
+
+impl BusError {
+    fn from_capture(capture: u32) -> Self {
+        let segment = (capture & 0b1_1111) as u8;
+        let kind = match (capture >> 6) & 0b11 {
+            0 => ErrorKind::Bit,
+            1 => ErrorKind::Form,
+            2 => ErrorKind::Stuff,
+            _ => match segment {
+                // CRC sequence
+                0b0_1000 => ErrorKind::Crc,
+                // ACK slot, ACK delimiter
+                0b1_1001 | 0b1_1011 => ErrorKind::Acknowledge,
+                _ => ErrorKind::Other,
+            },
+        };
+
+        Self {
+            kind,
+            segment,
+            during_reception: capture & (1 << 5) != 0,
+        }
+    }
+}
+
+/// An event reported by the TWAI controller
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TwaiEvent {
+    /// The fault confinement state changed
+    ErrorStateChanged(ErrorState),
+    /// The controller lost arbitration while transmitting
+    ArbitrationLost {
+        /// Position of the bit in the frame at which arbitration was lost
+        bit: u8,
+    },
+    /// A bus error was detected
+    BusError(BusError),
+    /// A received frame was lost because the receive FIFO was full
+    RxOverrun,
+}
+
+const RX_INTERRUPT: u32 = 1 << 0;
+const TX_INTERRUPT: u32 = 1 << 1;
+const ERR_WARN_INTERRUPT: u32 = 1 << 2;
+const OVERRUN_INTERRUPT: u32 = 1 << 3;
+const ERR_PASSIVE_INTERRUPT: u32 = 1 << 5;
+const ARB_LOST_INTERRUPT: u32 = 1 << 6;
+const BUS_ERR_INTERRUPT: u32 = 1 << 7;
+
+const EVENT_INTERRUPTS: u32 = ERR_WARN_INTERRUPT
+    | OVERRUN_INTERRUPT
+    | ERR_PASSIVE_INTERRUPT
+    | ARB_LOST_INTERRUPT
+    | BUS_ERR_INTERRUPT;
+
+/// Enable the given interrupts at the peripheral level
+fn enable_interrupt_bits<T: Instance>(bits: u32) {
+    #[cfg(not(esp32c6))]
+    let int_ena = T::register_block().int_ena();
+    #[cfg(esp32c6)]
+    let int_ena = T::register_block().interrupt_enable();
+    int_ena.modify(|r, w| unsafe { w.bits(r.bits() | bits) });
+}
+
+/// Get the current fault confinement state of the controller
+fn error_state<T: Instance>() -> ErrorState {
+    let register_block = T::register_block();
+    let status = register_block.status().read();
+
+    if status.bus_off_st().bit_is_set() {
+        ErrorState::BusOff
+    } else if register_block.tx_err_cnt().read().tx_err_cnt().bits() >= 128
+        || register_block.rx_err_cnt().read().rx_err_cnt().bits() >= 128
+    {
+        ErrorState::Passive
+    } else if status.err_st().bit_is_set() {
+        ErrorState::Warning
+    } else {
+        ErrorState::Active
+    }
+}
+
+/// Take the next event out of the given pending interrupt flags
+fn take_event<T: OperationInstance>(pending: &mut u32) -> Option<TwaiEvent> {
+    let register_block = T::register_block();
+
+    let event = if *pending & (ERR_WARN_INTERRUPT | ERR_PASSIVE_INTERRUPT) != 0 {
+        *pending &= !(ERR_WARN_INTERRUPT | ERR_PASSIVE_INTERRUPT);
+        TwaiEvent::ErrorStateChanged(error_state::<T>())
+    } else if *pending & ARB_LOST_INTERRUPT != 0 {
+        *pending &= !ARB_LOST_INTERRUPT;
+        // reading the capture register re-arms it
+        let bit = register_block.arb_lost_cap().read().bits() & 0b1_1111;
+        TwaiEvent::ArbitrationLost { bit: bit as u8 }
+    } else if *pending & BUS_ERR_INTERRUPT != 0 {
+        *pending &= !BUS_ERR_INTERRUPT;
+        // reading the capture register re-arms it
+        TwaiEvent::BusError(BusError::from_capture(
+            register_block.err_code_cap().read().bits(),
+        ))
+    } else if *pending & OVERRUN_INTERRUPT != 0 {
+        *pending &= !OVERRUN_INTERRUPT;
+        T::clear_overrun();
+        TwaiEvent::RxOverrun
+    } else {
+        return None;
+    };
+
+    Some(event)
+}
+
 /// Copy data from multiple TWAI_DATA_x_REG registers, packing the source into
 /// the destination.
 ///
@@ -1097,10 +1448,11 @@ unsafe fn copy_to_data_register(dest: *mut u32, src: &[u8]) {
 }
 
 #[cfg(feature = "embedded-hal-02")]
-impl<'d, T, DM> embedded_hal_02::can::Can for Twai<'d, T, DM>
+impl<'d, T, DM, M> embedded_hal_02::can::Can for Twai<'d, T, DM, M>
 where
     T: OperationInstance,
     DM: crate::Mode,
+    M: Transmitting,
 {
     type Frame = EspTwaiFrame;
     type Error = EspTwaiError;
@@ -1122,10 +1474,11 @@ where
 }
 
 #[cfg(feature = "embedded-hal")]
-impl<'d, T, DM> embedded_can::nb::Can for Twai<'d, T, DM>
+impl<'d, T, DM, M> embedded_can::nb::Can for Twai<'d, T, DM, M>
 where
     T: OperationInstance,
     DM: crate::Mode,
+    M: Transmitting,
 {
     type Frame = EspTwaiFrame;
     type Error = EspTwaiError;
@@ -1170,6 +1523,23 @@ pub trait OperationInstance: Instance {
         &asynch::TWAI_STATE[Self::NUMBER]
     }
 
+    /// Read the interrupt flags, clearing all of them but the receive
+    /// interrupt.
+    fn take_interrupts() -> u32 {
+        #[cfg(not(esp32c6))]
+        let bits = Self::register_block().int_raw().read().bits();
+        #[cfg(esp32c6)]
+        let bits = Self::register_block().interrupt().read().bits();
+        bits
+    }
+
+    /// Clear the data overrun status.
+    fn clear_overrun() {
+        Self::register_block()
+            .cmd()
+            .write(|w| unsafe { w.bits(1 << 3) });
+    }
+
     /// Release the message in the buffer. This will decrement the received
     /// message counter and prepare the next message in the FIFO for
     /// reading.
@@ -1179,8 +1549,8 @@ pub trait OperationInstance: Instance {
             .write(|w| w.release_buf().set_bit());
     }
 
-    /// Write a frame to the peripheral.
-    fn write_frame(frame: &EspTwaiFrame) {
+    /// Write a frame to the peripheral and request its transmission.
+    fn write_frame(frame: &EspTwaiFrame, single_shot: bool) {
         // Assemble the frame information into the data_0 byte.
         let frame_format: u8 = matches!(frame.id, Id::Extended(_)) as u8;
         let rtr_bit: u8 = frame.is_remote as u8;
@@ -1252,8 +1622,19 @@ pub trait OperationInstance: Instance {
         }
 
         // Set the transmit request command, this will lock the transmit buffer until
-        // the transmission is complete or aborted.
-        register_block.cmd().write(|w| w.tx_req().set_bit());
+        // the transmission is complete or aborted. In self test mode the
+        // self reception request makes the controller receive its own frame.
+        // Requesting and aborting at the same time results in a single-shot
+        // transmission.
+        let self_test = register_block.mode().read().self_test_mode().bit_is_set();
+        register_block.cmd().write(|w| {
+            if self_test {
+                w.self_rx_req().set_bit();
+            } else {
+                w.tx_req().set_bit();
+            }
+            w.abort_tx().bit(single_shot)
+        });
     }
 
     /// Read a frame from the peripheral.
@@ -1347,17 +1728,8 @@ impl Instance for crate::peripherals::TWAI0 {
 
     fn enable_interrupts() {
         let register_block = Self::register_block();
-        register_block.int_ena().modify(|_, w| {
-            w.rx_int_ena()
-                .set_bit()
-                .tx_int_ena()
-                .set_bit()
-                .bus_err_int_ena()
-                .set_bit()
-                .arb_lost_int_ena()
-                .set_bit()
-                .err_passive_int_ena()
-                .set_bit()
+        register_block.int_ena().modify(|r, w| unsafe {
+            w.bits(r.bits() | RX_INTERRUPT | TX_INTERRUPT | EVENT_INTERRUPTS)
         });
     }
 }
@@ -1391,17 +1763,8 @@ impl Instance for crate::peripherals::TWAI0 {
 
     fn enable_interrupts() {
         let register_block = Self::register_block();
-        register_block.interrupt_enable().modify(|_, w| {
-            w.ext_receive_int_ena()
-                .set_bit()
-                .ext_transmit_int_ena()
-                .set_bit()
-                .bus_err_int_ena()
-                .set_bit()
-                .arbitration_lost_int_ena()
-                .set_bit()
-                .err_passive_int_ena()
-                .set_bit()
+        register_block.interrupt_enable().modify(|r, w| unsafe {
+            w.bits(r.bits() | RX_INTERRUPT | TX_INTERRUPT | EVENT_INTERRUPTS)
         });
     }
 }
@@ -1435,17 +1798,8 @@ impl Instance for crate::peripherals::TWAI1 {
 
     fn enable_interrupts() {
         let register_block = Self::register_block();
-        register_block.interrupt_enable().modify(|_, w| {
-            w.ext_receive_int_ena()
-                .set_bit()
-                .ext_transmit_int_ena()
-                .set_bit()
-                .bus_err_int_ena()
-                .set_bit()
-                .arbitration_lost_int_ena()
-                .set_bit()
-                .err_passive_int_ena()
-                .set_bit()
+        register_block.interrupt_enable().modify(|r, w| unsafe {
+            w.bits(r.bits() | RX_INTERRUPT | TX_INTERRUPT | EVENT_INTERRUPTS)
         });
     }
 }
@@ -1453,9 +1807,16 @@ impl Instance for crate::peripherals::TWAI1 {
 #[cfg(esp32c6)]
 impl OperationInstance for crate::peripherals::TWAI1 {}
 
+#[cfg(feature = "async")]
+pub use asynch::TwaiEvents;
+
 #[cfg(feature = "async")]
 mod asynch {
-    use core::{future::poll_fn, task::Poll};
+    use core::{
+        future::poll_fn,
+        pin::Pin,
+        task::{Context, Poll},
+    };
 
     use embassy_sync::{
         blocking_mutex::raw::CriticalSectionRawMutex,
@@ -1473,6 +1834,7 @@ mod asynch {
         pub tx_waker: AtomicWaker,
         pub err_waker: AtomicWaker,
         pub rx_queue: Channel<CriticalSectionRawMutex, Result<EspTwaiFrame, EspTwaiError>, 32>,
+        pub events: Channel<CriticalSectionRawMutex, TwaiEvent, 16>,
     }
 
     impl TwaiAsyncState {
@@ -1481,6 +1843,7 @@ mod asynch {
                 tx_waker: AtomicWaker::new(),
                 err_waker: AtomicWaker::new(),
                 rx_queue: Channel::new(),
+                events: Channel::new(),
             }
         }
     }
@@ -1489,22 +1852,51 @@ mod asynch {
     const NEW_STATE: TwaiAsyncState = TwaiAsyncState::new();
     pub(crate) static TWAI_STATE: [TwaiAsyncState; NUM_TWAI] = [NEW_STATE; NUM_TWAI];
 
-    impl<T> Twai<'_, T, crate::Async>
+    impl<T, M> Twai<'_, T, crate::Async, M>
     where
         T: OperationInstance,
+        M: Transmitting,
     {
         pub async fn transmit_async(&mut self, frame: &EspTwaiFrame) -> Result<(), EspTwaiError> {
             self.tx.transmit_async(frame).await
         }
+    }
 
+    impl<T, M> Twai<'_, T, crate::Async, M>
+    where
+        T: OperationInstance,
+        M: OperatingMode,
+    {
         pub async fn receive_async(&mut self) -> Result<EspTwaiFrame, EspTwaiError> {
             self.rx.receive_async().await
         }
+
+        /// Get a stream of the events reported by the controller, see
+        /// [TwaiRx::events].
+        pub fn events(&mut self) -> TwaiEvents<'_, T> {
+            self.rx.events()
+        }
+
+        /// Recover the controller from the bus off state, see
+        /// [Twai::recover_from_bus_off].
+        pub async fn recover_from_bus_off_async(&mut self) {
+            T::enable_interrupts();
+            poll_fn(|cx| {
+                T::async_state().err_waker.register(cx.waker());
+
+                match self.recover_from_bus_off() {
+                    Err(nb::Error::WouldBlock) => Poll::Pending,
+                    _ => Poll::Ready(()),
+                }
+            })
+            .await
+        }
     }
 
-    impl<'d, T> TwaiTx<'d, T, crate::Async>
+    impl<'d, T, M> TwaiTx<'d, T, crate::Async, M>
     where
         T: OperationInstance,
+        M: Transmitting,
     {
         pub async fn transmit_async(&mut self, frame: &EspTwaiFrame) -> Result<(), EspTwaiError> {
             T::enable_interrupts();
@@ -1523,7 +1915,7 @@ mod asynch {
                     return Poll::Pending;
                 }
 
-                T::write_frame(frame);
+                T::write_frame(frame, self.single_shot);
 
                 return Poll::Ready(Ok(()));
             })
@@ -1535,6 +1927,21 @@ mod asynch {
     where
         T: OperationInstance,
     {
+        /// Get a stream of the events reported by the controller.
+        ///
+        /// Events are queued by the interrupt handler from this call on, up
+        /// to 16 events are kept while no task is waiting.
+        pub fn events(&mut self) -> TwaiEvents<'_, T> {
+            T::enable_interrupts();
+            TwaiEvents { _rx: PhantomData }
+        }
+
+        /// Receive a frame from the receive queue.
+        ///
+        /// The interrupt handler moves all frames from the hardware FIFO into
+        /// a queue of 32 frames, so no frames are lost while the task is
+        /// busy. When the queue is full the frames are kept in the hardware
+        /// FIFO until there is room again.
         pub async fn receive_async(&mut self) -> Result<EspTwaiFrame, EspTwaiError> {
             T::enable_interrupts();
             poll_fn(|cx| {
@@ -1550,11 +1957,6 @@ mod asynch {
                     if status.bus_off_st().bit_is_set() {
                         return Poll::Ready(Err(EspTwaiError::BusOff));
                     }
-
-                    // Check if the packet in the receive buffer is valid or overrun.
-                    if status.miss_st().bit_is_set() {
-                        return Poll::Ready(Err(EspTwaiError::EmbeddedHAL(ErrorKind::Overrun)));
-                    }
                 }
 
                 Poll::Pending
@@ -1563,138 +1965,108 @@ mod asynch {
         }
     }
 
-    #[cfg(any(esp32c3, esp32, esp32s2, esp32s3))]
-    #[handler]
-    pub(super) fn twai0() {
-        let register_block = TWAI0::register_block();
-
-        let intr_enable = register_block.int_ena().read();
-        let intr_status = register_block.int_raw().read();
+    /// A stream of the [TwaiEvent]s reported by the controller, created with
+    /// [TwaiRx::events].
+    ///
+    /// [TwaiEvents::poll_next] has the signature of `Stream::poll_next` from
+    /// the `futures` crate, so the stream can be adapted to it. The stream
+    /// never ends.
+    pub struct TwaiEvents<'a, T> {
+        _rx: PhantomData<&'a mut T>,
+    }
 
-        let async_state = TWAI0::async_state();
+    impl<T> TwaiEvents<'_, T>
+    where
+        T: OperationInstance,
+    {
+        /// Poll for the next event.
+        pub fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<TwaiEvent>> {
+            T::async_state().events.poll_receive(cx).map(Some)
+        }
 
-        if intr_status.tx_int_st().bit_is_set() {
-            async_state.tx_waker.wake();
+        /// Wait for the next event.
+        pub async fn next(&mut self) -> TwaiEvent {
+            T::async_state().events.receive().await
         }
+    }
 
-        if intr_status.rx_int_st().bit_is_set() {
-            let status = register_block.status().read();
+    /// Handle the given interrupt flags.
+    ///
+    /// Returns `false` if the receive queue is full and the receive interrupt
+    /// needs to be disabled until a task took frames out of it.
+    fn handle_interrupt<T: OperationInstance>(intr_status: u32) -> bool {
+        let register_block = T::register_block();
+        let async_state = T::async_state();
 
-            let rx_queue = &async_state.rx_queue;
+        if intr_status & TX_INTERRUPT != 0 {
+            async_state.tx_waker.wake();
+        }
 
-            if status.bus_off_st().bit_is_set() {
-                let _ = rx_queue.try_send(Err(EspTwaiError::BusOff));
-            }
+        let mut rx_queue_full = false;
+        if intr_status & RX_INTERRUPT != 0 {
+            // Drain the hardware FIFO into the queue.
+            while register_block.status().read().rx_buf_st().bit_is_set() {
+                if async_state.rx_queue.is_full() {
+                    rx_queue_full = true;
+                    break;
+                }
 
-            if status.miss_st().bit_is_set() {
-                let _ = rx_queue.try_send(Err(EspTwaiError::EmbeddedHAL(ErrorKind::Overrun)));
+                let frame = T::read_frame();
+                let _ = async_state.rx_queue.try_send(Ok(frame));
             }
+        }
 
-            let frame = TWAI0::read_frame();
-
-            let _ = rx_queue.try_send(Ok(frame));
+        if intr_status & OVERRUN_INTERRUPT != 0 {
+            let _ = async_state
+                .rx_queue
+                .try_send(Err(EspTwaiError::EmbeddedHAL(ErrorKind::Overrun)));
+        }
 
-            register_block.cmd().write(|w| w.release_buf().set_bit());
+        let mut pending = intr_status & EVENT_INTERRUPTS;
+        while let Some(event) = take_event::<T>(&mut pending) {
+            let _ = async_state.events.try_send(event);
         }
 
-        if intr_status.bits() & 0b11111100 > 0 {
+        if intr_status & EVENT_INTERRUPTS != 0 {
             async_state.err_waker.wake();
         }
 
-        unsafe {
-            register_block
-                .int_ena()
-                .modify(|_, w| w.bits(intr_enable.bits() & (!intr_status.bits() | 1)));
-        }
+        !rx_queue_full
     }
 
-    #[cfg(esp32c6)]
+    #[cfg(any(esp32c3, esp32, esp32s2, esp32s3))]
     #[handler]
     pub(super) fn twai0() {
-        let register_block = TWAI0::register_block();
+        let intr_status = TWAI0::take_interrupts();
 
-        let intr_enable = register_block.interrupt_enable().read();
-        let intr_status = register_block.interrupt().read();
-
-        let async_state = TWAI0::async_state();
-
-        if intr_status.transmit_int_st().bit_is_set() {
-            async_state.tx_waker.wake();
-        }
-
-        if intr_status.receive_int_st().bit_is_set() {
-            let status = register_block.status().read();
-
-            let rx_queue = &async_state.rx_queue;
-
-            if status.bus_off_st().bit_is_set() {
-                let _ = rx_queue.try_send(Err(EspTwaiError::BusOff));
-            }
-
-            if status.miss_st().bit_is_set() {
-                let _ = rx_queue.try_send(Err(EspTwaiError::EmbeddedHAL(ErrorKind::Overrun)));
-            }
-
-            let frame = TWAI0::read_frame();
-
-            let _ = rx_queue.try_send(Ok(frame));
-
-            register_block.cmd().write(|w| w.release_buf().set_bit());
+        if !handle_interrupt::<TWAI0>(intr_status) {
+            TWAI0::register_block()
+                .int_ena()
+                .modify(|r, w| unsafe { w.bits(r.bits() & !RX_INTERRUPT) });
         }
+    }
 
-        if intr_status.bits() & 0b11111100 > 0 {
-            async_state.err_waker.wake();
-        }
+    #[cfg(esp32c6)]
+    #[handler]
+    pub(super) fn twai0() {
+        let intr_status = TWAI0::take_interrupts();
 
-        unsafe {
-            register_block
+        if !handle_interrupt::<TWAI0>(intr_status) {
+            TWAI0::register_block()
                 .interrupt_enable()
-                .modify(|_, w| w.bits(intr_enable.bits() & (!intr_status.bits() | 1)));
+                .modify(|r, w| unsafe { w.bits(r.bits() & !RX_INTERRUPT) });
         }
     }
 
     #[cfg(esp32c6)]
     #[handler]
     pub(super) fn twai1() {
-        let register_block = TWAI1::register_block();
-
-        let intr_enable = register_block.interrupt_enable().read();
-        let intr_status = register_block.interrupt().read();
-
-        let async_state = TWAI1::async_state();
-
-        if intr_status.transmit_int_st().bit_is_set() {
-            async_state.tx_waker.wake();
-        }
-
-        if intr_status.receive_int_st().bit_is_set() {
-            let status = register_block.status().read();
-
-            let rx_queue = &async_state.rx_queue;
+        let intr_status = TWAI1::take_interrupts();
 
-            if status.bus_off_st().bit_is_set() {
-                let _ = rx_queue.try_send(Err(EspTwaiError::BusOff));
-            }
-
-            if status.miss_st().bit_is_set() {
-                let _ = rx_queue.try_send(Err(EspTwaiError::EmbeddedHAL(ErrorKind::Overrun)));
-            }
-
-            let frame = TWAI1::read_frame();
-
-            let _ = rx_queue.try_send(Ok(frame));
-
-            register_block.cmd().write(|w| w.release_buf().set_bit());
-        }
-
-        if intr_status.bits() & 0b11111100 > 0 {
-            async_state.err_waker.wake();
-        }
-
-        unsafe {
-            register_block
+        if !handle_interrupt::<TWAI1>(intr_status) {
+            TWAI1::register_block()
                 .interrupt_enable()
-                .modify(|_, w| w.bits(intr_enable.bits() & (!intr_status.bits() | 1)));
+                .modify(|r, w| unsafe { w.bits(r.bits() & !RX_INTERRUPT) });
         }
     }
 }
@@ -26,6 +26,18 @@
 //!     Uart::new_with_config(peripherals.UART1, Config::default(), Some(pins), &clocks);
 //! ```
 //!
+//! Hardware (RTS/CTS) and software (XON/XOFF) flow control, RS-485
+//! half-duplex mode and signal inversion are configured through [`Config`]
+//! as well. In RS-485 mode the RTS pin drives the driver-enable input of the
+//! transceiver:
+//!
+//! ```no_run
+//! let pins = AllPins::new(io.pins.gpio1, io.pins.gpio2, io.pins.gpio3, io.pins.gpio4);
+//! let config = Config::default().rs485(Rs485Config::default());
+//!
+//! let mut uart1 = Uart::new_with_config(peripherals.UART1, config, Some(pins), &clocks);
+//! ```
+//!
 //! ## Usage
 //!
 //! The UART driver implements a number of third-party traits, with the
@@ -58,6 +70,31 @@
 //! let byte = rx.read_byte()?;
 //! ```
 //!
+//! #### Using DMA
+//!
+//! On chips with GDMA, the UART can be combined with the UHCI peripheral and a
+//! DMA channel to transfer data without CPU involvement. This isn't available
+//! on the ESP32 and ESP32-S2:
+//!
+//! ```no_run
+//! let dma = Dma::new(peripherals.DMA);
+//! let (tx_buffer, mut tx_descriptors, mut rx_buffer, mut rx_descriptors) = dma_buffers!(4096);
+//!
+//! let mut uart1 = uart1.with_dma(
+//!     peripherals.UHCI0,
+//!     dma.channel0.configure(
+//!         false,
+//!         &mut tx_descriptors,
+//!         &mut rx_descriptors,
+//!         DmaPriority::Priority0,
+//!     ),
+//! );
+//!
+//! let transfer = uart1.dma_read(&mut rx_buffer).unwrap();
+//! transfer.wait().unwrap();
+//! let received = &rx_buffer[..uart1.received_len()];
+//! ```
+//!
 //! [embedded-hal]: https://docs.rs/embedded-hal/latest/embedded_hal/
 //! [embedded-io]: https://docs.rs/embedded-io/latest/embedded_io/
 //! [embedded-hal-async]: https://docs.rs/embedded-hal-async/latest/embedded_hal_async/
@@ -83,6 +120,16 @@ use crate::{
 const CONSOLE_UART_NUM: usize = 0;
 const UART_FIFO_SIZE: u16 = 128;
 
+/// Largest RX FIFO threshold accepted by the hardware
+#[cfg(esp32)]
+const RX_FIFO_MAX_THRHD: u16 = 0x7F;
+#[cfg(any(esp32c6, esp32h2))]
+const RX_FIFO_MAX_THRHD: u16 = 0xFF;
+#[cfg(any(esp32c3, esp32c2, esp32s2))]
+const RX_FIFO_MAX_THRHD: u16 = 0x1FF;
+#[cfg(esp32s3)]
+const RX_FIFO_MAX_THRHD: u16 = 0x3FF;
+
 #[cfg(not(any(esp32, esp32s2)))]
 use crate::soc::constants::RC_FAST_CLK;
 #[cfg(any(esp32, esp32s2))]
@@ -103,6 +150,16 @@ pub enum Error {
     RxFrameError,
     #[cfg(feature = "async")]
     RxParityError,
+    /// An error occurred in the DMA transfer
+    #[cfg(all(gdma, uhci0))]
+    DmaError(crate::dma::DmaError),
+}
+
+#[cfg(all(gdma, uhci0))]
+impl From<crate::dma::DmaError> for Error {
+    fn from(value: crate::dma::DmaError) -> Self {
+        Error::DmaError(value)
+    }
 }
 
 #[cfg(feature = "embedded-hal")]
@@ -172,6 +229,87 @@ pub mod config {
         STOP2   = 3,
     }
 
+    /// Hardware flow control using the RTS and CTS signals
+    #[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub struct HwFlowControl {
+        /// Deassert RTS once the RX FIFO holds more than this many bytes.
+        /// `None` leaves RTS under software control. Values above the size
+        /// of the RX FIFO are clamped.
+        pub rts_threshold: Option<u16>,
+        /// Only transmit while CTS is asserted
+        pub cts: bool,
+    }
+
+    /// Software flow control using XON/XOFF characters
+    #[derive(PartialEq, Eq, Copy, Clone, Debug)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub struct SwFlowControl {
+        /// Character sent to resume the remote transmitter
+        pub xon_char: u8,
+        /// Character sent to pause the remote transmitter
+        pub xoff_char: u8,
+        /// Send XON once the RX FIFO holds fewer than this many bytes
+        pub xon_threshold: u16,
+        /// Send XOFF once the RX FIFO holds more than this many bytes
+        pub xoff_threshold: u16,
+    }
+
+    impl Default for SwFlowControl {
+        fn default() -> Self {
+            Self {
+                xon_char: 0x11,
+                xoff_char: 0x13,
+                xon_threshold: 16,
+                xoff_threshold: 100,
+            }
+        }
+    }
+
+    /// RS-485 half-duplex configuration
+    ///
+    /// Like ESP-IDF's half-duplex mode, the driver sets RTS high before
+    /// writing and low again once the data has been transmitted, so RTS can
+    /// be connected to the driver-enable input of the transceiver. Writes
+    /// only complete after that. With `collision_detection` RTS stays high.
+    /// Writes of the DMA driver don't switch RTS.
+    #[derive(PartialEq, Eq, Copy, Clone, Debug)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub struct Rs485Config {
+        /// Discard the data received while transmitting
+        pub echo_suppression: bool,
+        /// Compare the transmitted data with the data seen on the bus and
+        /// flag a collision on mismatch, see
+        /// [`Uart::rs485_collision_detected`](super::Uart::rs485_collision_detected).
+        ///
+        /// The receiver has to see the transmitted data, so this overrides
+        /// `echo_suppression`.
+        pub collision_detection: bool,
+    }
+
+    impl Default for Rs485Config {
+        fn default() -> Self {
+            Self {
+                echo_suppression: true,
+                collision_detection: false,
+            }
+        }
+    }
+
+    /// Signals to invert
+    #[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub struct InvertedSignals {
+        /// Invert the transmitted data
+        pub tx: bool,
+        /// Invert the received data
+        pub rx: bool,
+        /// Invert the RTS output, e.g. for an active-low driver enable
+        pub rts: bool,
+        /// Invert the CTS input
+        pub cts: bool,
+    }
+
     /// UART Configuration
     #[derive(Debug, Copy, Clone)]
     #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -181,6 +319,10 @@ pub mod config {
         pub parity: Parity,
         pub stop_bits: StopBits,
         pub clock_source: super::ClockSource,
+        pub hw_flow_control: HwFlowControl,
+        pub sw_flow_control: Option<SwFlowControl>,
+        pub rs485: Option<Rs485Config>,
+        pub inverted: InvertedSignals,
     }
 
     impl Config {
@@ -219,6 +361,26 @@ pub mod config {
             self
         }
 
+        pub fn hw_flow_control(mut self, flow_control: HwFlowControl) -> Self {
+            self.hw_flow_control = flow_control;
+            self
+        }
+
+        pub fn sw_flow_control(mut self, flow_control: SwFlowControl) -> Self {
+            self.sw_flow_control = Some(flow_control);
+            self
+        }
+
+        pub fn rs485(mut self, rs485: Rs485Config) -> Self {
+            self.rs485 = Some(rs485);
+            self
+        }
+
+        pub fn inverted(mut self, inverted: InvertedSignals) -> Self {
+            self.inverted = inverted;
+            self
+        }
+
         pub fn symbol_length(&self) -> u8 {
             let mut length: u8 = 1; // start bit
             length += match self.data_bits {
@@ -250,6 +412,10 @@ pub mod config {
                 clock_source: super::ClockSource::Xtal,
                 #[cfg(not(any(esp32c6, esp32h2, lp_uart)))]
                 clock_source: super::ClockSource::Apb,
+                hw_flow_control: HwFlowControl::default(),
+                sw_flow_control: None,
+                rs485: None,
+                inverted: InvertedSignals::default(),
             }
         }
     }
@@ -422,17 +588,32 @@ where
     }
 
     /// Writes bytes
+    ///
+    /// In RS-485 half-duplex mode this returns once the data has been
+    /// transmitted and the transceiver's driver is disabled again.
     pub fn write_bytes(&mut self, data: &[u8]) -> Result<usize, Error> {
         let count = data.len();
 
         data.iter()
             .try_for_each(|c| nb::block!(self.write_byte(*c)))?;
 
+        if Self::is_rs485_half_duplex() {
+            nb::block!(self.flush_tx())?;
+        }
+
         Ok(count)
     }
 
     fn write_byte(&mut self, word: u8) -> nb::Result<(), Error> {
         if T::get_tx_fifo_count() < UART_FIFO_SIZE {
+            if Self::is_rs485_half_duplex() {
+                // TX_DONE is raised again once this byte has been sent
+                T::register_block()
+                    .int_clr()
+                    .write(|w| w.tx_done().clear_bit_by_one());
+                Self::set_rs485_driver_enabled(true);
+            }
+
             T::register_block()
                 .fifo()
                 .write(|w| unsafe { w.rxfifo_rd_byte().bits(word) });
@@ -444,12 +625,43 @@ where
     }
 
     fn flush_tx(&self) -> nb::Result<(), Error> {
+        if Self::is_rs485_half_duplex() && Self::is_rs485_driver_enabled() {
+            let tx_done = T::register_block().int_raw().read().tx_done().bit_is_set();
+            if T::get_tx_fifo_count() > 0 || !tx_done {
+                return Err(nb::Error::WouldBlock);
+            }
+
+            Self::set_rs485_driver_enabled(false);
+            return Ok(());
+        }
+
         if T::is_tx_idle() {
             Ok(())
         } else {
             Err(nb::Error::WouldBlock)
         }
     }
+
+    /// Whether RTS drives the driver-enable input of an RS-485 transceiver,
+    /// see [config::Rs485Config]
+    fn is_rs485_half_duplex() -> bool {
+        let rs485_conf = T::register_block().rs485_conf().read();
+        rs485_conf.rs485_en().bit_is_set() && rs485_conf.rs485rxby_tx_en().bit_is_clear()
+    }
+
+    fn is_rs485_driver_enabled() -> bool {
+        T::register_block().conf0().read().sw_rts().bit_is_clear()
+    }
+
+    /// Assert RTS before transmitting and release it once the data has been
+    /// sent, like ESP-IDF's RS-485 half-duplex mode
+    fn set_rs485_driver_enabled(enabled: bool) {
+        // a cleared SW_RTS drives RTS high
+        T::register_block()
+            .conf0()
+            .modify(|_, w| w.sw_rts().bit(!enabled));
+        T::sync_regs();
+    }
 }
 
 impl<'d, T, M> UartRx<'d, T, M>
@@ -572,6 +784,11 @@ where
         serial.change_data_bits(config.data_bits);
         serial.change_parity(config.parity);
         serial.change_stop_bits(config.stop_bits);
+        serial.change_hw_flow_control(config.hw_flow_control);
+        serial.change_sw_flow_control(config.sw_flow_control);
+        serial.change_rs485(config.rs485);
+        serial.change_inverted_signals(config.inverted);
+        serial.sync_regs();
 
         if let Some(interrupt) = interrupt {
             unsafe {
@@ -732,16 +949,7 @@ where
     /// - `esp32c3`, `esp32c2`, `esp32s2` **0x1FF**
     /// - `esp32s3` **0x3FF**
     pub fn set_rx_fifo_full_threshold(&mut self, threshold: u16) -> Result<(), Error> {
-        #[cfg(esp32)]
-        const MAX_THRHD: u16 = 0x7F;
-        #[cfg(any(esp32c6, esp32h2))]
-        const MAX_THRHD: u16 = 0xFF;
-        #[cfg(any(esp32c3, esp32c2, esp32s2))]
-        const MAX_THRHD: u16 = 0x1FF;
-        #[cfg(esp32s3)]
-        const MAX_THRHD: u16 = 0x3FF;
-
-        if threshold > MAX_THRHD {
+        if threshold > RX_FIFO_MAX_THRHD {
             return Err(Error::InvalidArgument);
         }
 
@@ -755,6 +963,25 @@ where
         Ok(())
     }
 
+    /// Returns `true` if a collision was detected on the RS-485 bus since
+    /// the last call to [`Self::reset_rs485_collision`]
+    ///
+    /// Requires [`config::Rs485Config::collision_detection`] to be enabled.
+    pub fn rs485_collision_detected(&self) -> bool {
+        T::register_block()
+            .int_raw()
+            .read()
+            .rs485_clash()
+            .bit_is_set()
+    }
+
+    /// Reset the RS-485 collision flag
+    pub fn reset_rs485_collision(&mut self) {
+        T::register_block()
+            .int_clr()
+            .write(|w| w.rs485_clash().clear_bit_by_one());
+    }
+
     /// Listen for AT-CMD interrupts
     pub fn listen_at_cmd(&mut self) {
         T::register_block()
@@ -904,6 +1131,158 @@ where
         self
     }
 
+    fn change_hw_flow_control(&mut self, flow_control: config::HwFlowControl) -> &mut Self {
+        let threshold = flow_control
+            .rts_threshold
+            .unwrap_or(0)
+            .min(RX_FIFO_MAX_THRHD);
+        #[cfg(any(esp32, esp32c6, esp32h2))]
+        let threshold: u8 = threshold as u8;
+        let rx_flow_en = flow_control.rts_threshold.is_some();
+
+        #[cfg(esp32)]
+        T::register_block().conf1().modify(|_, w| unsafe {
+            w.rx_flow_thrhd()
+                .bits(threshold)
+                .rx_flow_en()
+                .bit(rx_flow_en)
+        });
+
+        #[cfg(any(esp32c2, esp32c3, esp32s2, esp32s3))]
+        {
+            T::register_block()
+                .mem_conf()
+                .modify(|_, w| unsafe { w.rx_flow_thrhd().bits(threshold) });
+            T::register_block()
+                .conf1()
+                .modify(|_, w| w.rx_flow_en().bit(rx_flow_en));
+        }
+
+        #[cfg(any(esp32c6, esp32h2))]
+        T::register_block().hwfc_conf().modify(|_, w| unsafe {
+            w.rx_flow_thrhd()
+                .bits(threshold)
+                .rx_flow_en()
+                .bit(rx_flow_en)
+        });
+
+        T::register_block()
+            .conf0()
+            .modify(|_, w| w.tx_flow_en().bit(flow_control.cts));
+
+        self
+    }
+
+    fn change_sw_flow_control(&mut self, flow_control: Option<config::SwFlowControl>) -> &mut Self {
+        let enable = flow_control.is_some();
+        let flow_control = flow_control.unwrap_or_default();
+        let xon_threshold = flow_control.xon_threshold.min(RX_FIFO_MAX_THRHD);
+        let xoff_threshold = flow_control.xoff_threshold.min(RX_FIFO_MAX_THRHD);
+        #[cfg(any(esp32, esp32c6, esp32h2))]
+        let (xon_threshold, xoff_threshold) = (xon_threshold as u8, xoff_threshold as u8);
+
+        #[cfg(esp32)]
+        T::register_block().swfc_conf().modify(|_, w| unsafe {
+            w.xon_threshold()
+                .bits(xon_threshold)
+                .xoff_threshold()
+                .bits(xoff_threshold)
+                .xon_char()
+                .bits(flow_control.xon_char)
+                .xoff_char()
+                .bits(flow_control.xoff_char)
+        });
+
+        #[cfg(any(esp32c2, esp32c3, esp32s2, esp32s3))]
+        {
+            T::register_block().swfc_conf0().modify(|_, w| unsafe {
+                w.xoff_threshold()
+                    .bits(xoff_threshold)
+                    .xoff_char()
+                    .bits(flow_control.xoff_char)
+            });
+            T::register_block().swfc_conf1().modify(|_, w| unsafe {
+                w.xon_threshold()
+                    .bits(xon_threshold)
+                    .xon_char()
+                    .bits(flow_control.xon_char)
+            });
+        }
+
+        #[cfg(not(any(esp32c6, esp32h2)))]
+        T::register_block()
+            .flow_conf()
+            .modify(|_, w| w.sw_flow_con_en().bit(enable).xonoff_del().bit(enable));
+
+        #[cfg(any(esp32c6, esp32h2))]
+        {
+            T::register_block().swfc_conf1().modify(|_, w| unsafe {
+                w.xon_threshold()
+                    .bits(xon_threshold)
+                    .xoff_threshold()
+                    .bits(xoff_threshold)
+            });
+            T::register_block().swfc_conf0().modify(|_, w| unsafe {
+                w.xon_char()
+                    .bits(flow_control.xon_char)
+                    .xoff_char()
+                    .bits(flow_control.xoff_char)
+                    .sw_flow_con_en()
+                    .bit(enable)
+                    .xonoff_del()
+                    .bit(enable)
+            });
+        }
+
+        self
+    }
+
+    fn change_rs485(&mut self, rs485: Option<config::Rs485Config>) -> &mut Self {
+        match rs485 {
+            Some(rs485) => {
+                // in half-duplex mode the driver asserts RTS while transmitting, in
+                // collision detection mode the transceiver's driver stays enabled and
+                // the receiver must see the transmitted data
+                let receive_while_transmitting =
+                    rs485.collision_detection || !rs485.echo_suppression;
+
+                T::register_block()
+                    .conf0()
+                    .modify(|_, w| w.sw_rts().bit(!rs485.collision_detection));
+                T::register_block().rs485_conf().modify(|_, w| {
+                    w.rs485tx_rx_en()
+                        .bit(receive_while_transmitting)
+                        .rs485rxby_tx_en()
+                        .bit(rs485.collision_detection)
+                        .rs485_en()
+                        .set_bit()
+                });
+            }
+            None => {
+                T::register_block()
+                    .rs485_conf()
+                    .modify(|_, w| w.rs485_en().clear_bit());
+            }
+        }
+
+        self
+    }
+
+    fn change_inverted_signals(&mut self, inverted: config::InvertedSignals) -> &mut Self {
+        T::register_block().conf0().modify(|_, w| {
+            w.txd_inv()
+                .bit(inverted.tx)
+                .rxd_inv()
+                .bit(inverted.rx)
+                .rts_inv()
+                .bit(inverted.rts)
+                .cts_inv()
+                .bit(inverted.cts)
+        });
+
+        self
+    }
+
     #[cfg(any(esp32c2, esp32c3, esp32s3))]
     fn change_baud_internal(&self, baudrate: u32, clock_source: ClockSource, clocks: &Clocks) {
         let clk = match clock_source {
@@ -1109,26 +1488,11 @@ where
         }
     }
 
-    #[cfg(any(esp32c3, esp32c6, esp32h2, esp32s3))] // TODO introduce a cfg symbol for this
     #[inline(always)]
     fn sync_regs(&self) {
-        #[cfg(any(esp32c6, esp32h2))]
-        let update_reg = T::register_block().reg_update();
-
-        #[cfg(any(esp32c3, esp32s3))]
-        let update_reg = T::register_block().id();
-
-        update_reg.modify(|_, w| w.reg_update().set_bit());
-
-        while update_reg.read().reg_update().bit_is_set() {
-            // wait
-        }
+        T::sync_regs();
     }
 
-    #[cfg(not(any(esp32c3, esp32c6, esp32h2, esp32s3)))]
-    #[inline(always)]
-    fn sync_regs(&mut self) {}
-
     fn rxfifo_reset(&mut self) {
         T::register_block()
             .conf0()
@@ -1286,6 +1650,26 @@ pub trait Instance: crate::private::Sealed {
         idle
     }
 
+    #[cfg(any(esp32c3, esp32c6, esp32h2, esp32s3))] // TODO introduce a cfg symbol for this
+    #[inline(always)]
+    fn sync_regs() {
+        #[cfg(any(esp32c6, esp32h2))]
+        let update_reg = Self::register_block().reg_update();
+
+        #[cfg(any(esp32c3, esp32s3))]
+        let update_reg = Self::register_block().id();
+
+        update_reg.modify(|_, w| w.reg_update().set_bit());
+
+        while update_reg.read().reg_update().bit_is_set() {
+            // wait
+        }
+    }
+
+    #[cfg(not(any(esp32c3, esp32c6, esp32h2, esp32s3)))]
+    #[inline(always)]
+    fn sync_regs() {}
+
     fn tx_signal() -> OutputSignal;
     fn rx_signal() -> InputSignal;
     fn cts_signal() -> InputSignal;
@@ -1912,10 +2296,21 @@ mod asynch {
                 UartTxFuture::<T>::new(TxEvent::TxFiFoEmpty.into()).await;
             }
 
+            if Self::is_rs485_half_duplex() {
+                self.flush_async().await?;
+            }
+
             Ok(count)
         }
 
         pub async fn flush_async(&mut self) -> Result<(), Error> {
+            if Self::is_rs485_half_duplex() && Self::is_rs485_driver_enabled() {
+                // the bytes have been handed to the FIFO after clearing TX_DONE
+                UartTxFuture::<T>::new(TxEvent::TxDone.into()).await;
+                Self::set_rs485_driver_enabled(false);
+                return Ok(());
+            }
+
             let count = T::get_tx_fifo_count();
             if count > 0 {
                 UartTxFuture::<T>::new(TxEvent::TxDone.into()).await;
@@ -2113,6 +2508,391 @@ mod asynch {
     }
 }
 
+/// UART with DMA support
+///
+/// On chips with GDMA the UHCI peripheral moves data between a UART's FIFOs
+/// and memory, so the CPU doesn't need to service the FIFOs. This allows for
+/// reliable communication at high baud rates.
+///
+/// The ESP32 and ESP32-S2 are not supported. Their UHCI has a DMA engine of
+/// its own instead of using the shared PDMA channels.
+///
+/// A receive transfer completes when the RX line has been idle for the
+/// configured number of bit times, see [UartDma::set_rx_idle_threshold], or
+/// when the buffer is full.
+#[cfg(all(gdma, uhci0))]
+pub mod dma {
+    use embedded_dma::{ReadBuffer, WriteBuffer};
+
+    use super::*;
+    use crate::{
+        dma::{
+            dma_private::{DmaSupport, DmaSupportRx, DmaSupportTx},
+            Channel,
+            ChannelTypes,
+            DmaPeripheral,
+            DmaTransferRx,
+            DmaTransferRxCircular,
+            DmaTransferTx,
+            RxPrivate,
+            TxPrivate,
+            UhciPeripheral,
+        },
+        peripherals::{uhci0::RegisterBlock as UhciRegisterBlock, UHCI0},
+    };
+
+    const MAX_RX_IDLE_THRHD: u16 = 0x3FF;
+
+    fn uhci() -> &'static UhciRegisterBlock {
+        unsafe { &*UHCI0::PTR }
+    }
+
+    fn reset_uhci_rx() {
+        uhci().conf0().modify(|_, w| w.rx_rst().set_bit());
+        uhci().conf0().modify(|_, w| w.rx_rst().clear_bit());
+    }
+
+    fn reset_uhci_tx() {
+        uhci().conf0().modify(|_, w| w.tx_rst().set_bit());
+        uhci().conf0().modify(|_, w| w.tx_rst().clear_bit());
+    }
+
+    fn start_tx_transfer<TX: TxPrivate>(
+        tx: &mut TX,
+        ptr: *const u8,
+        len: usize,
+    ) -> Result<(), Error> {
+        reset_uhci_tx();
+
+        tx.prepare_transfer_without_start(DmaPeripheral::Uhci0, false, ptr, len)?;
+        tx.start_transfer()?;
+
+        Ok(())
+    }
+
+    unsafe fn start_rx_transfer<RX: RxPrivate>(
+        rx: &mut RX,
+        ptr: *mut u8,
+        len: usize,
+        circular: bool,
+    ) -> Result<(), Error> {
+        reset_uhci_rx();
+
+        rx.prepare_transfer_without_start(circular, DmaPeripheral::Uhci0, ptr, len)?;
+        rx.start_transfer()?;
+
+        Ok(())
+    }
+
+    impl<'d, T> Uart<'d, T, Blocking>
+    where
+        T: Instance + 'd,
+    {
+        /// Route the UART's data through the UHCI peripheral and the given DMA
+        /// channel
+        pub fn with_dma<C, DmaMode>(
+            self,
+            uhci: impl Peripheral<P = UHCI0> + 'd,
+            mut channel: Channel<'d, C, DmaMode>,
+        ) -> UartDma<'d, T, C, DmaMode>
+        where
+            C: ChannelTypes,
+            C::P: UhciPeripheral,
+            DmaMode: Mode,
+        {
+            crate::into_ref!(uhci);
+
+            PeripheralClockControl::enable(crate::system::Peripheral::Uhci0);
+            channel.tx.init_channel();
+
+            reset_uhci_rx();
+            reset_uhci_tx();
+
+            // Transfer raw bytes: no SLIP framing, no packet headers, no CRC
+            // and no escaping. Incoming data is closed by an EOF once the
+            // line goes idle.
+            uhci().conf0().write(|w| {
+                w.clk_en().set_bit();
+                w.uart_idle_eof_en().set_bit();
+
+                #[cfg(any(esp32c6, esp32h2))]
+                unsafe {
+                    w.uart_sel().bits(T::uart_number() as u8);
+                }
+
+                #[cfg(any(esp32c3, esp32s3))]
+                match T::uart_number() {
+                    0 => w.uart0_ce().set_bit(),
+                    1 => w.uart1_ce().set_bit(),
+                    #[cfg(esp32s3)]
+                    2 => w.uart2_ce().set_bit(),
+                    _ => unreachable!(),
+                };
+
+                w
+            });
+            uhci().conf1().write(|w| unsafe { w.bits(0) });
+            uhci().escape_conf().write(|w| unsafe { w.bits(0) });
+
+            UartDma {
+                uart: self,
+                _uhci: uhci,
+                channel,
+                rx_len: 0,
+            }
+        }
+    }
+
+    /// UART driver using DMA
+    pub struct UartDma<'d, T, C, DmaMode>
+    where
+        T: Instance,
+        C: ChannelTypes,
+        C::P: UhciPeripheral,
+        DmaMode: Mode,
+    {
+        uart: Uart<'d, T, Blocking>,
+        _uhci: PeripheralRef<'d, UHCI0>,
+        channel: Channel<'d, C, DmaMode>,
+        rx_len: usize,
+    }
+
+    impl<'d, T, C, DmaMode> core::fmt::Debug for UartDma<'d, T, C, DmaMode>
+    where
+        T: Instance,
+        C: ChannelTypes,
+        C::P: UhciPeripheral,
+        DmaMode: Mode,
+    {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_struct("UartDma").finish()
+        }
+    }
+
+    impl<'d, T, C, DmaMode> DmaSupport for UartDma<'d, T, C, DmaMode>
+    where
+        T: Instance,
+        C: ChannelTypes,
+        C::P: UhciPeripheral,
+        DmaMode: Mode,
+    {
+        fn peripheral_wait_dma(&mut self, is_tx: bool, is_rx: bool) {
+            if is_tx {
+                while !self.channel.tx.is_done() {}
+
+                // The last bytes are still in the FIFO when the DMA is done
+                while T::get_tx_fifo_count() > 0 || !T::is_tx_idle() {}
+            }
+
+            if is_rx {
+                while !self.channel.rx.is_done()
+                    && !self.channel.rx.has_dscr_empty_error()
+                    && self.channel.rx.received_len() < self.rx_len
+                {}
+            }
+        }
+
+        fn peripheral_dma_stop(&mut self) {
+            reset_uhci_rx();
+        }
+    }
+
+    impl<'d, T, C, DmaMode> DmaSupportTx for UartDma<'d, T, C, DmaMode>
+    where
+        T: Instance,
+        C: ChannelTypes,
+        C::P: UhciPeripheral,
+        DmaMode: Mode,
+    {
+        type TX = C::Tx<'d>;
+
+        fn tx(&mut self) -> &mut Self::TX {
+            &mut self.channel.tx
+        }
+    }
+
+    impl<'d, T, C, DmaMode> DmaSupportRx for UartDma<'d, T, C, DmaMode>
+    where
+        T: Instance,
+        C: ChannelTypes,
+        C::P: UhciPeripheral,
+        DmaMode: Mode,
+    {
+        type RX = C::Rx<'d>;
+
+        fn rx(&mut self) -> &mut Self::RX {
+            &mut self.channel.rx
+        }
+    }
+
+    impl<'d, T, C, DmaMode> UartDma<'d, T, C, DmaMode>
+    where
+        T: Instance,
+        C: ChannelTypes,
+        C::P: UhciPeripheral,
+        DmaMode: Mode,
+    {
+        /// Configures the number of bit times the RX line has to be idle
+        /// before a receive transfer is completed
+        ///
+        /// # Errors
+        /// `Err(Error::InvalidArgument)` if the value exceeds **0x3FF**
+        pub fn set_rx_idle_threshold(&mut self, bit_times: u16) -> Result<(), Error> {
+            if bit_times > MAX_RX_IDLE_THRHD {
+                return Err(Error::InvalidArgument);
+            }
+
+            T::register_block()
+                .idle_conf()
+                .modify(|_, w| unsafe { w.rx_idle_thrhd().bits(bit_times) });
+            self.uart.sync_regs();
+
+            Ok(())
+        }
+
+        /// Write bytes out over the UART using DMA
+        ///
+        /// This will return a [DmaTransferTx]. Waiting for it returns once
+        /// all bytes have been sent.
+        pub fn dma_write<'t, TXBUF>(
+            &'t mut self,
+            words: &'t TXBUF,
+        ) -> Result<DmaTransferTx<Self>, Error>
+        where
+            TXBUF: ReadBuffer<Word = u8>,
+        {
+            let (ptr, len) = unsafe { words.read_buffer() };
+
+            start_tx_transfer(&mut self.channel.tx, ptr, len)?;
+
+            Ok(DmaTransferTx::new(self))
+        }
+
+        /// Receive bytes into the buffer using DMA
+        ///
+        /// This will return a [DmaTransferRx]. The transfer completes when the
+        /// RX line goes idle or when the buffer is full. Use
+        /// [UartDma::received_len] afterwards to get the number of bytes
+        /// received.
+        pub fn dma_read<'t, RXBUF>(
+            &'t mut self,
+            words: &'t mut RXBUF,
+        ) -> Result<DmaTransferRx<Self>, Error>
+        where
+            RXBUF: WriteBuffer<Word = u8>,
+        {
+            let (ptr, len) = unsafe { words.write_buffer() };
+
+            unsafe { start_rx_transfer(&mut self.channel.rx, ptr, len, false)? };
+            self.rx_len = len;
+
+            Ok(DmaTransferRx::new(self))
+        }
+
+        /// Continuously receive bytes into the buffer using DMA
+        ///
+        /// This will return a [DmaTransferRxCircular]. Received data becomes
+        /// available whenever a descriptor is full or the RX line goes idle.
+        pub fn dma_read_circular<'t, RXBUF>(
+            &'t mut self,
+            words: &'t mut RXBUF,
+        ) -> Result<DmaTransferRxCircular<Self>, Error>
+        where
+            RXBUF: WriteBuffer<Word = u8>,
+        {
+            let (ptr, len) = unsafe { words.write_buffer() };
+
+            unsafe { start_rx_transfer(&mut self.channel.rx, ptr, len, true)? };
+
+            Ok(DmaTransferRxCircular::new(self))
+        }
+
+        /// Number of bytes received by the last (non-circular) receive
+        /// transfer
+        pub fn received_len(&self) -> usize {
+            self.channel.rx.received_len()
+        }
+    }
+
+    #[cfg(feature = "async")]
+    mod asynch {
+        use super::*;
+        use crate::dma::asynch::{DmaRxFuture, DmaTxFuture};
+
+        impl<'d, T, C> UartDma<'d, T, C, crate::Async>
+        where
+            T: Instance,
+            C: ChannelTypes,
+            C::P: UhciPeripheral,
+        {
+            /// Receive bytes into `buf`, completing when the RX line goes idle
+            /// or the buffer is full
+            ///
+            /// Returns the number of bytes received.
+            pub async fn read_async(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+                if buf.is_empty() {
+                    return Err(Error::InvalidArgument);
+                }
+
+                let mut future = DmaRxFuture::new(&mut self.channel.rx);
+                unsafe { start_rx_transfer(future.rx(), buf.as_mut_ptr(), buf.len(), false)? };
+                let result = future.await;
+
+                let len = self.channel.rx.received_len();
+                match result {
+                    Ok(()) => Ok(len),
+                    // More data arrived after the buffer has been filled
+                    Err(_) if len == buf.len() => Ok(len),
+                    Err(error) => Err(error.into()),
+                }
+            }
+
+            /// Write `words` using DMA
+            ///
+            /// Completes once all bytes have been handed to the UART, the last
+            /// bytes may still be in the TX FIFO at that point.
+            pub async fn write_async(&mut self, words: &[u8]) -> Result<usize, Error> {
+                let mut future = DmaTxFuture::new(&mut self.channel.tx);
+                start_tx_transfer(future.tx(), words.as_ptr(), words.len())?;
+                future.await?;
+
+                Ok(words.len())
+            }
+        }
+
+        impl<'d, T, C> embedded_io::ErrorType for UartDma<'d, T, C, crate::Async>
+        where
+            T: Instance,
+            C: ChannelTypes,
+            C::P: UhciPeripheral,
+        {
+            type Error = Error;
+        }
+
+        impl<'d, T, C> embedded_io_async::Read for UartDma<'d, T, C, crate::Async>
+        where
+            T: Instance,
+            C: ChannelTypes,
+            C::P: UhciPeripheral,
+        {
+            async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+                self.read_async(buf).await
+            }
+        }
+
+        impl<'d, T, C> embedded_io_async::Write for UartDma<'d, T, C, crate::Async>
+        where
+            T: Instance,
+            C: ChannelTypes,
+            C::P: UhciPeripheral,
+        {
+            async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+                self.write_async(buf).await
+            }
+        }
+    }
+}
+
 /// Low-power UART
 #[cfg(lp_uart)]
 pub mod lp_uart {
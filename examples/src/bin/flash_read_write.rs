@@ -0,0 +1,50 @@
+//! Writes and reads flash memory.
+//!
+//! Uses the sector at 0x9000, which is part of the `nvs` partition in the
+//! default partition table. Make sure nothing else stores data there.
+
+//% CHIPS: esp32 esp32c2 esp32c3 esp32c6 esp32h2 esp32s2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+use esp_backtrace as _;
+use esp_hal::{
+    flash::{FlashStorage, FLASH_SECTOR_SIZE},
+    peripherals::Peripherals,
+    prelude::*,
+};
+use esp_println::println;
+
+#[entry]
+fn main() -> ! {
+    let peripherals = Peripherals::take();
+
+    let mut flash = FlashStorage::new(peripherals.SPI1);
+    println!("Flash size = {}", flash.capacity());
+
+    let flash_addr = 0x9000;
+    let mut bytes = [0u8; 32];
+
+    flash.read(flash_addr, &mut bytes).unwrap();
+    println!("Read from {:x}:  {:02x?}", flash_addr, &bytes[..]);
+
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+
+    flash
+        .erase(flash_addr, flash_addr + FLASH_SECTOR_SIZE)
+        .unwrap();
+    flash.write(flash_addr, &bytes).unwrap();
+    println!("Written to {:x}: {:02x?}", flash_addr, &bytes[..]);
+
+    let mut reread_bytes = [0u8; 32];
+    flash.read(flash_addr, &mut reread_bytes).unwrap();
+    println!("Read from {:x}:  {:02x?}", flash_addr, &reread_bytes[..]);
+
+    assert_eq!(bytes, reread_bytes);
+
+    loop {}
+}
@@ -0,0 +1,69 @@
+//! Act as an I2C slave device
+//!
+//! This example exposes 16 byte-wide registers at address 0x42. The master
+//! writes a register index followed by the values to store, or writes a
+//! register index and reads back its contents after a repeated START.
+//!
+//! The following wiring is assumed:
+//! - SDA => GPIO4
+//! - SCL => GPIO5
+
+//% CHIPS: esp32 esp32c2 esp32c3 esp32c6 esp32h2 esp32s2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use esp_backtrace as _;
+use esp_hal::{
+    clock::ClockControl,
+    gpio::Io,
+    i2c::slave::{I2cSlave, SlaveAddress},
+    peripherals::Peripherals,
+    prelude::*,
+    system::SystemControl,
+};
+use esp_println::println;
+
+#[entry]
+fn main() -> ! {
+    let peripherals = Peripherals::take();
+    let system = SystemControl::new(peripherals.SYSTEM);
+    let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+
+    let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+    let mut i2c = I2cSlave::new(
+        peripherals.I2C0,
+        io.pins.gpio4,
+        io.pins.gpio5,
+        SlaveAddress::SevenBit(0x42),
+        &clocks,
+        None,
+    );
+
+    let mut registers = [0u8; 16];
+
+    loop {
+        let mut request = [0u8; 17];
+        let len = match i2c.read(&mut request) {
+            Ok(len) => len,
+            Err(err) => {
+                println!("Error: {:?}", err);
+                continue;
+            }
+        };
+        if len == 0 {
+            continue;
+        }
+
+        let start = request[0] as usize % registers.len();
+        if len > 1 {
+            for (i, value) in request[1..len].iter().enumerate() {
+                registers[(start + i) % registers.len()] = *value;
+            }
+            println!("Registers: {:02x?}", registers);
+        } else if let Err(err) = i2c.write(&registers[start..]) {
+            println!("Error: {:?}", err);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! Measures the high and low times of a signal with capture channel 0 of the
+//! MCPWM0 peripheral.
+//!
+//! The signal is expected on the pin assigned to `pin`. (GPIO2)
+
+//% CHIPS: esp32 esp32c6 esp32h2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use esp_backtrace as _;
+use esp_hal::{
+    clock::ClockControl,
+    gpio::Io,
+    mcpwm::{
+        capture::{CaptureConfig, CaptureEdge},
+        McPwm,
+        PeripheralClockConfig,
+    },
+    peripherals::Peripherals,
+    prelude::*,
+    system::SystemControl,
+};
+use esp_println::println;
+
+#[entry]
+fn main() -> ! {
+    let peripherals = Peripherals::take();
+    let system = SystemControl::new(peripherals.SYSTEM);
+    let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+
+    let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+    let pin = io.pins.gpio2;
+
+    let clock_cfg = PeripheralClockConfig::with_frequency(&clocks, 32.MHz()).unwrap();
+    let mut mcpwm = McPwm::new(peripherals.MCPWM0, clock_cfg);
+
+    mcpwm.capture_timer.start();
+    let mut input = mcpwm.capture0.with_pin(pin, CaptureConfig::BOTH_EDGES);
+
+    let mut last = input.wait_for_capture();
+    loop {
+        let capture = input.wait_for_capture();
+        let ticks = capture.value.wrapping_sub(last.value);
+        match capture.edge {
+            CaptureEdge::Rising => println!("low for {} ticks", ticks),
+            CaptureEdge::Falling => println!("high for {} ticks", ticks),
+        }
+        last = capture;
+    }
+}
@@ -0,0 +1,87 @@
+//! Uses the three timers and operators of the MCPWM0 peripheral to output three
+//! phase-locked 50% duty signals at 20 kHz, shifted by 120° each.
+//!
+//! Timer1 and timer2 are synced to timer0 and reload a phase offset whenever
+//! timer0 equals zero.
+//!
+//! The signals will be output to GPIO0, GPIO1 and GPIO2.
+
+//% CHIPS: esp32 esp32c6 esp32h2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use esp_backtrace as _;
+use esp_hal::{
+    clock::ClockControl,
+    gpio::Io,
+    mcpwm::{
+        operator::PwmPinConfig,
+        timer::{CounterDirection, PwmWorkingMode, SyncOutput, SyncSource, TimerSyncConfig},
+        McPwm,
+        PeripheralClockConfig,
+    },
+    peripherals::Peripherals,
+    prelude::*,
+    system::SystemControl,
+};
+
+const PERIOD: u16 = 99;
+
+#[entry]
+fn main() -> ! {
+    let peripherals = Peripherals::take();
+    let system = SystemControl::new(peripherals.SYSTEM);
+    let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+
+    let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+    #[cfg(feature = "esp32h2")]
+    let clock_cfg = PeripheralClockConfig::with_frequency(&clocks, 40.MHz()).unwrap();
+    #[cfg(not(feature = "esp32h2"))]
+    let clock_cfg = PeripheralClockConfig::with_frequency(&clocks, 32.MHz()).unwrap();
+
+    let mut mcpwm = McPwm::new(peripherals.MCPWM0, clock_cfg);
+
+    mcpwm.operator0.set_timer(&mcpwm.timer0);
+    mcpwm.operator1.set_timer(&mcpwm.timer1);
+    mcpwm.operator2.set_timer(&mcpwm.timer2);
+
+    let mut pwm_u = mcpwm
+        .operator0
+        .with_pin_a(io.pins.gpio0, PwmPinConfig::UP_ACTIVE_HIGH);
+    let mut pwm_v = mcpwm
+        .operator1
+        .with_pin_a(io.pins.gpio1, PwmPinConfig::UP_ACTIVE_HIGH);
+    let mut pwm_w = mcpwm
+        .operator2
+        .with_pin_a(io.pins.gpio2, PwmPinConfig::UP_ACTIVE_HIGH);
+
+    // timer0 is the master, timer1 and timer2 follow with a phase offset
+    mcpwm
+        .timer0
+        .set_sync_config(TimerSyncConfig::new().with_output(SyncOutput::Zero));
+    mcpwm.timer1.set_sync_config(
+        TimerSyncConfig::new()
+            .with_input(SyncSource::Timer0)
+            .with_phase(PERIOD / 3, CounterDirection::Increasing),
+    );
+    mcpwm.timer2.set_sync_config(
+        TimerSyncConfig::new()
+            .with_input(SyncSource::Timer0)
+            .with_phase(2 * PERIOD / 3, CounterDirection::Increasing),
+    );
+
+    let timer_clock_cfg = clock_cfg
+        .timer_clock_with_frequency(PERIOD, PwmWorkingMode::Increase, 20.kHz())
+        .unwrap();
+    mcpwm.timer0.start(timer_clock_cfg);
+    mcpwm.timer1.start(timer_clock_cfg);
+    mcpwm.timer2.start(timer_clock_cfg);
+
+    pwm_u.set_timestamp(50);
+    pwm_v.set_timestamp(50);
+    pwm_w.set_timestamp(50);
+
+    loop {}
+}
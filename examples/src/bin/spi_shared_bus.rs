@@ -0,0 +1,86 @@
+//! SPI shared bus loopback test
+//!
+//! Folowing pins are used:
+//! SCLK    GPIO0
+//! MISO    GPIO2
+//! MOSI    GPIO4
+//! CS 0    GPIO5 (driven by the peripheral)
+//! CS 1    GPIO6 (driven as GPIO)
+//!
+//! Depending on your target and the board you are using you have to change the
+//! pins.
+//!
+//! Two devices with different frequency, mode and bit order share one bus.
+//! Connect MISO and MOSI pins to see the outgoing data is read as incoming
+//! data.
+
+//% CHIPS: esp32c2 esp32c3 esp32c6 esp32h2 esp32s2 esp32s3
+//% FEATURES: embedded-hal
+
+#![no_std]
+#![no_main]
+
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+use embedded_hal::spi::SpiDevice;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::ClockControl,
+    delay::Delay,
+    gpio::{Io, Level, Output},
+    peripherals::Peripherals,
+    prelude::*,
+    spi::{
+        master::Spi,
+        shared::{CriticalSectionDevice, DeviceConfig, HardwareCs},
+        SpiBitOrder,
+        SpiMode,
+    },
+    system::SystemControl,
+};
+use esp_println::println;
+
+#[entry]
+fn main() -> ! {
+    let peripherals = Peripherals::take();
+    let system = SystemControl::new(peripherals.SYSTEM);
+    let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+
+    let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+    let spi = Spi::new(peripherals.SPI2, 1.MHz(), SpiMode::Mode0, &clocks)
+        .with_sck(io.pins.gpio0)
+        .with_mosi(io.pins.gpio4)
+        .with_miso(io.pins.gpio2)
+        .with_cs(io.pins.gpio5);
+    let bus = Mutex::new(RefCell::new(spi));
+
+    let mut fast_device = CriticalSectionDevice::new(
+        &bus,
+        HardwareCs::Cs0,
+        DeviceConfig::new(8.MHz(), SpiMode::Mode0),
+        &clocks,
+    );
+    let mut slow_device = CriticalSectionDevice::new(
+        &bus,
+        Output::new(io.pins.gpio6, Level::High),
+        DeviceConfig::new(100.kHz(), SpiMode::Mode3)
+            .with_bit_order(SpiBitOrder::LSBFirst, SpiBitOrder::LSBFirst),
+        &clocks,
+    );
+
+    let delay = Delay::new(&clocks);
+
+    loop {
+        let mut data = [0xde, 0xca, 0xfb, 0xad];
+        fast_device.transfer_in_place(&mut data).unwrap();
+        println!("fast: {:x?}", data);
+
+        let mut data = [0xde, 0xca, 0xfb, 0xad];
+        slow_device.transfer_in_place(&mut data).unwrap();
+        println!("slow: {:x?}", data);
+
+        delay.delay_millis(250);
+    }
+}
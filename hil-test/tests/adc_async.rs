@@ -0,0 +1,63 @@
+//! Async ADC oneshot test
+//!
+//! Following pins are used:
+//! GPIO2
+//!
+//! The pin is sampled without anything connected, only the completion of the
+//! conversion is checked.
+
+//% CHIPS: esp32c2 esp32c3 esp32c6 esp32h2
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::{
+    analog::adc::{Adc, AdcConfig, AdcPin, Attenuation},
+    clock::ClockControl,
+    gpio::{GpioPin, Io},
+    peripherals::{Peripherals, ADC1},
+    system::SystemControl,
+    Async,
+};
+
+struct Context {
+    adc: Adc<'static, ADC1, Async>,
+    pin: AdcPin<GpioPin<2>, ADC1>,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let _clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+        let mut config = AdcConfig::new();
+        let pin = config.enable_pin(io.pins.gpio2, Attenuation::Attenuation11dB);
+        let adc = Adc::new_async(peripherals.ADC1, config);
+
+        Context { adc, pin }
+    }
+}
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal::embassy::executor::Executor::new())]
+mod tests {
+    use super::*;
+
+    #[init]
+    async fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_read_oneshot_async(mut ctx: Context) {
+        for _ in 0..8 {
+            let value = ctx.adc.read_oneshot_async(&mut ctx.pin).await;
+            defmt::assert!(value <= 0xfff);
+        }
+    }
+}
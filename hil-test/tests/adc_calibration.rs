@@ -0,0 +1,95 @@
+//! ADC calibration tests
+//!
+//! Converts readings with characteristics built from recorded eFuse values, no
+//! pins are used. The characteristics don't depend on the chip, so all of them
+//! are checked on every chip.
+
+//% CHIPS: esp32 esp32c2 esp32c3 esp32c6 esp32h2 esp32s2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::analog::adc::{AdcCharacteristics, Attenuation};
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use defmt::assert_eq;
+
+    use super::*;
+
+    #[init]
+    fn init() {}
+
+    #[test]
+    fn test_line() {
+        let characteristics = AdcCharacteristics::line(2000, 1000);
+
+        assert_eq!(characteristics.raw_to_millivolts(0), 0);
+        assert_eq!(characteristics.raw_to_millivolts(1000), 500);
+        assert_eq!(characteristics.raw_to_millivolts(2000), 1000);
+        // truncated like line fitting, not rounded
+        assert_eq!(characteristics.raw_to_millivolts(1999), 999);
+    }
+
+    #[test]
+    fn test_two_point() {
+        let characteristics = AdcCharacteristics::two_point(1000, 250, 3000, 750);
+
+        assert_eq!(characteristics.raw_to_millivolts(1000), 250);
+        assert_eq!(characteristics.raw_to_millivolts(2000), 500);
+        assert_eq!(characteristics.raw_to_millivolts(3000), 750);
+        assert_eq!(characteristics.raw_to_millivolts(0), 0);
+        // rounded like ESP-IDF
+        assert_eq!(characteristics.raw_to_millivolts(1002), 251);
+    }
+
+    #[test]
+    fn test_esp32_vref() {
+        // ADC_VREF of 1121 mV
+        let characteristics =
+            AdcCharacteristics::from_esp32_efuse_vref(1, Attenuation::Attenuation11dB, 0b00011);
+        assert_eq!(characteristics.raw_to_millivolts(2048), 1823);
+
+        // ADC_VREF of 1079 mV, the sign bit is set
+        let characteristics =
+            AdcCharacteristics::from_esp32_efuse_vref(1, Attenuation::Attenuation11dB, 0b10011);
+        assert_eq!(characteristics.raw_to_millivolts(2048), 1760);
+
+        // no eFuse value, 1100 mV
+        let characteristics =
+            AdcCharacteristics::from_esp32_efuse_vref(2, Attenuation::Attenuation0dB, 0);
+        assert_eq!(characteristics.raw_to_millivolts(0), 63);
+        assert_eq!(characteristics.raw_to_millivolts(4095), 1023);
+    }
+
+    #[test]
+    fn test_esp32_two_point() {
+        // ADC1_TP_LOW of 270 and ADC1_TP_HIGH of 3285, i.e. readings at 150 mV
+        // and 850 mV without attenuation
+        let characteristics = AdcCharacteristics::from_esp32_efuse_two_point(
+            1,
+            Attenuation::Attenuation0dB,
+            0x7e,
+            0x005,
+        );
+        assert_eq!(characteristics.raw_to_millivolts(270), 150);
+        assert_eq!(characteristics.raw_to_millivolts(3285), 849);
+    }
+
+    #[test]
+    fn test_esp32s2_two_point() {
+        // RTCCALIB_V1IDX_A13L of 689 and RTCCALIB_V1IDX_A13H of 6229, i.e.
+        // readings at 250 mV and 2000 mV with 11 dB attenuation
+        let characteristics = AdcCharacteristics::from_esp32s2_efuse_two_point(
+            1,
+            Attenuation::Attenuation11dB,
+            0x23,
+            0x05,
+        );
+        assert_eq!(characteristics.raw_to_millivolts(689), 250);
+        assert_eq!(characteristics.raw_to_millivolts(6229), 2000);
+    }
+}
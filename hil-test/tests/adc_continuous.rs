@@ -0,0 +1,138 @@
+//! ADC continuous mode test
+//!
+//! Following pins are used:
+//! ESP32:             GPIO32, GPIO33
+//! ESP32-C3/C6/H2/S3: GPIO2, GPIO3
+//!
+//! The pins are sampled without anything connected, only the order of the
+//! channels is checked.
+
+//% CHIPS: esp32 esp32c3 esp32c6 esp32h2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::{
+    analog::adc::{
+        continuous::{AdcContinuous, AdcContinuousConfig, AdcSample, Error},
+        AdcChannel,
+        AdcPin,
+        Attenuation,
+        Resolution,
+    },
+    clock::ClockControl,
+    dma::{Dma, DmaPriority},
+    dma_buffers,
+    gpio::Io,
+    peripherals::{Peripherals, ADC1},
+    prelude::*,
+    system::SystemControl,
+};
+
+fn channel<PIN: AdcChannel, CS>(_pin: &AdcPin<PIN, ADC1, CS>) -> u8 {
+    PIN::CHANNEL
+}
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use defmt::assert_eq;
+
+    use super::*;
+
+    #[test]
+    #[timeout(3)]
+    fn test_pattern_order() {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let _clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+        #[cfg(feature = "esp32")]
+        let (pin_a, pin_b) = (io.pins.gpio32, io.pins.gpio33);
+        #[cfg(not(feature = "esp32"))]
+        let (pin_a, pin_b) = (io.pins.gpio2, io.pins.gpio3);
+
+        let mut config = AdcContinuousConfig::new(20.kHz());
+        let pin_a = config.enable_pin(pin_a, Attenuation::Attenuation11dB, Resolution::default());
+        let pin_b = config.enable_pin(pin_b, Attenuation::Attenuation11dB, Resolution::default());
+        let channels = [channel(&pin_a), channel(&pin_b)];
+
+        let dma = Dma::new(peripherals.DMA);
+        #[cfg(feature = "esp32")]
+        let dma_channel = dma.i2s0channel;
+        #[cfg(not(feature = "esp32"))]
+        let dma_channel = dma.channel0;
+
+        let (_, mut tx_descriptors, mut rx_buffer, mut rx_descriptors) = dma_buffers!(0, 4 * 256);
+
+        let mut adc = AdcContinuous::new(
+            peripherals.ADC1,
+            #[cfg(feature = "esp32")]
+            peripherals.I2S0,
+            config,
+            dma_channel.configure(
+                false,
+                &mut tx_descriptors,
+                &mut rx_descriptors,
+                DmaPriority::Priority0,
+            ),
+        )
+        .unwrap();
+
+        let mut transfer = adc.read_circular(&mut rx_buffer).unwrap();
+
+        let mut samples = [AdcSample::default(); 64];
+        let mut received = 0;
+        while received < samples.len() {
+            received += transfer.pop(&mut samples[received..]).unwrap();
+        }
+
+        for pair in samples.windows(2) {
+            assert!(channels.contains(&pair[0].channel));
+            assert!(pair[0].channel != pair[1].channel);
+            assert!(pair[0].value <= 0xfff);
+        }
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_invalid_config() {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let _clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+        #[cfg(feature = "esp32")]
+        let pin = io.pins.gpio32;
+        #[cfg(not(feature = "esp32"))]
+        let pin = io.pins.gpio2;
+
+        let dma = Dma::new(peripherals.DMA);
+        #[cfg(feature = "esp32")]
+        let dma_channel = dma.i2s0channel;
+        #[cfg(not(feature = "esp32"))]
+        let dma_channel = dma.channel0;
+
+        let (_, mut tx_descriptors, _, mut rx_descriptors) = dma_buffers!(0, 4 * 256);
+
+        let mut config = AdcContinuousConfig::new(10.MHz());
+        let _pin = config.enable_pin(pin, Attenuation::Attenuation11dB, Resolution::default());
+
+        let result = AdcContinuous::new(
+            peripherals.ADC1,
+            #[cfg(feature = "esp32")]
+            peripherals.I2S0,
+            config,
+            dma_channel.configure(
+                false,
+                &mut tx_descriptors,
+                &mut rx_descriptors,
+                DmaPriority::Priority0,
+            ),
+        );
+        assert_eq!(result.err(), Some(Error::InvalidSampleRate));
+    }
+}
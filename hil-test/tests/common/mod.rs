@@ -0,0 +1,62 @@
+//! Helpers shared by several HIL tests
+
+use embedded_storage::nor_flash::{
+    ErrorType,
+    MultiwriteNorFlash,
+    NorFlash,
+    NorFlashErrorKind,
+    ReadNorFlash,
+};
+
+/// RAM-backed NOR flash of `SIZE` bytes, used to test flash data structures
+/// without modifying the flash chip of the device
+pub struct RamFlash<const SIZE: usize> {
+    pub data: [u8; SIZE],
+}
+
+impl<const SIZE: usize> RamFlash<SIZE> {
+    /// Create an erased flash
+    pub fn new() -> Self {
+        Self { data: [0xFF; SIZE] }
+    }
+}
+
+impl<const SIZE: usize> ErrorType for RamFlash<SIZE> {
+    type Error = NorFlashErrorKind;
+}
+
+impl<const SIZE: usize> ReadNorFlash for RamFlash<SIZE> {
+    const READ_SIZE: usize = 4;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        embedded_storage::nor_flash::check_read(self, offset, bytes.len())?;
+        bytes.copy_from_slice(&self.data[offset as usize..][..bytes.len()]);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        SIZE
+    }
+}
+
+impl<const SIZE: usize> NorFlash for RamFlash<SIZE> {
+    const WRITE_SIZE: usize = 4;
+    const ERASE_SIZE: usize = 4096;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        embedded_storage::nor_flash::check_erase(self, from, to)?;
+        self.data[from as usize..to as usize].fill(0xFF);
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        embedded_storage::nor_flash::check_write(self, offset, bytes.len())?;
+        // Like real NOR flash, writing can only clear bits
+        for (dst, src) in self.data[offset as usize..].iter_mut().zip(bytes) {
+            *dst &= *src;
+        }
+        Ok(())
+    }
+}
+
+impl<const SIZE: usize> MultiwriteNorFlash for RamFlash<SIZE> {}
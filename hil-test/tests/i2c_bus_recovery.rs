@@ -0,0 +1,99 @@
+//! I2C bus recovery test
+//!
+//! The master drives SDA on GPIO2 and SCL on GPIO5. GPIO4 plays a stuck slave
+//! holding SDA low.
+//!
+//! Connect GPIO2 and GPIO4 pins.
+
+//% CHIPS: esp32 esp32c2 esp32c3 esp32c6 esp32h2 esp32s2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::ClockControl,
+    gpio::{AnyOutputOpenDrain, GpioPin, Io, Level, Pull},
+    i2c::{Error, I2C},
+    peripherals::{Peripherals, I2C0},
+    prelude::*,
+    system::SystemControl,
+    Blocking,
+};
+
+// Nothing answers at this address
+const ADDRESS: u8 = 0x42;
+
+struct Context {
+    i2c: I2C<'static, I2C0, Blocking>,
+    sda: AnyOutputOpenDrain<'static>,
+    scl: AnyOutputOpenDrain<'static>,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+        // Only used to read SCL back, the master configured afterwards drives
+        // the pad
+        let scl = AnyOutputOpenDrain::new(unsafe { GpioPin::<5>::steal() }, Level::High, Pull::Up);
+        let sda = AnyOutputOpenDrain::new(io.pins.gpio4, Level::High, Pull::Up);
+
+        let i2c = I2C::new(
+            peripherals.I2C0,
+            io.pins.gpio2,
+            io.pins.gpio5,
+            100.kHz(),
+            &clocks,
+            None,
+        );
+
+        Context { i2c, sda, scl }
+    }
+}
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use defmt::assert_eq;
+
+    use super::*;
+
+    #[init]
+    fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_absent_slave_is_not_acknowledged(mut ctx: Context) {
+        assert_eq!(
+            ctx.i2c.write(ADDRESS, &[1, 2, 3]),
+            Err(Error::AckCheckFailed)
+        );
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_timeout_recovers_bus(mut ctx: Context) {
+        // the stuck slave keeps the master from ever completing the transfer
+        ctx.sda.set_low();
+        assert_eq!(ctx.i2c.write(ADDRESS, &[1, 2, 3]), Err(Error::TimeOut));
+
+        // once the slave lets go, the driver has left both lines idle after a
+        // STOP condition
+        ctx.sda.set_high();
+        assert!(ctx.sda.is_high());
+        assert!(ctx.scl.is_high());
+
+        // and the master runs a complete transfer again instead of timing out
+        assert_eq!(
+            ctx.i2c.write(ADDRESS, &[1, 2, 3]),
+            Err(Error::AckCheckFailed)
+        );
+    }
+}
@@ -0,0 +1,119 @@
+//! I2C slave test
+//!
+//! I2C1 acts as the master of I2C0. Both share SCL on GPIO5, SDA of the
+//! master is on GPIO2 and SDA of the slave on GPIO4.
+//!
+//! Connect GPIO2 and GPIO4 pins.
+
+//% CHIPS: esp32 esp32h2 esp32s2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use embassy_futures::join::join;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::ClockControl,
+    gpio::{GpioPin, Io},
+    i2c::{
+        slave::{Error, I2cSlave, SlaveAddress},
+        I2C,
+    },
+    peripherals::{Peripherals, I2C0, I2C1},
+    prelude::*,
+    system::SystemControl,
+    Async,
+};
+
+const ADDRESS: u8 = 0x42;
+
+struct Context {
+    master: I2C<'static, I2C1, Async>,
+    slave: I2cSlave<'static, I2C0, Async>,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+        // The slave only reads SCL, the master configured last drives it
+        let slave = I2cSlave::new_async(
+            peripherals.I2C0,
+            io.pins.gpio4,
+            unsafe { GpioPin::<5>::steal() },
+            SlaveAddress::SevenBit(ADDRESS),
+            &clocks,
+        );
+        let master = I2C::new_async(
+            peripherals.I2C1,
+            io.pins.gpio2,
+            io.pins.gpio5,
+            100.kHz(),
+            &clocks,
+        );
+
+        Context { master, slave }
+    }
+}
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal::embassy::executor::Executor::new())]
+mod tests {
+    use defmt::assert_eq;
+
+    use super::*;
+
+    #[init]
+    async fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_master_write(mut ctx: Context) {
+        let mut buffer = [0u8; 8];
+        let (sent, received) = join(
+            ctx.master.write(ADDRESS, &[1, 2, 3]),
+            ctx.slave.read(&mut buffer),
+        )
+        .await;
+
+        assert_eq!(sent, Ok(()));
+        assert_eq!(received, Ok(3));
+        assert_eq!(buffer[..3], [1, 2, 3]);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_master_read(mut ctx: Context) {
+        let mut buffer = [0u8; 4];
+        // The slave is polled first, so its data is provided before the
+        // master addresses it
+        let (provided, received) = join(
+            ctx.slave.write(&[4, 5, 6, 7]),
+            ctx.master.read(ADDRESS, &mut buffer),
+        )
+        .await;
+
+        assert_eq!(provided, Ok(()));
+        assert_eq!(received, Ok(()));
+        assert_eq!(buffer, [4, 5, 6, 7]);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_master_write_overrun(mut ctx: Context) {
+        let mut buffer = [0u8; 2];
+        let (_, received) = join(
+            ctx.master.write(ADDRESS, &[1, 2, 3]),
+            ctx.slave.read(&mut buffer),
+        )
+        .await;
+
+        assert_eq!(received, Err(Error::Overrun));
+    }
+}
@@ -0,0 +1,196 @@
+//! I2S loopback test
+//!
+//! The receiver shares BCLK and WS of the transmitter, DOUT is read back
+//! through the wire:
+//! DOUT    GPIO2 => DIN GPIO4
+//!
+//! Connect GPIO2 and GPIO4 pins.
+
+//% CHIPS: esp32 esp32c3 esp32c6 esp32h2 esp32s2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::{ClockControl, Clocks},
+    dma::{Dma, DmaPriority},
+    dma_circular_buffers,
+    gpio::{Gpio2, Gpio4, Io},
+    i2s::{DataFormat, I2s, I2sDuplex, Standard},
+    peripherals::{Peripherals, DMA, I2S0},
+    prelude::*,
+    system::SystemControl,
+};
+
+/// Size of the circular TX and RX buffers in bytes
+const BUFFER_SIZE: usize = 2000;
+
+/// Number of 16-bit samples in the TX buffer
+const SAMPLES: usize = BUFFER_SIZE / 2;
+
+/// Number of 16-bit samples read back
+const RX_SAMPLES: usize = 1000;
+
+struct Context {
+    clocks: Clocks<'static>,
+    i2s: I2S0,
+    dma: DMA,
+    dout: Gpio2,
+    din: Gpio4,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+        Context {
+            clocks,
+            i2s: peripherals.I2S0,
+            dma: peripherals.DMA,
+            dout: io.pins.gpio2,
+            din: io.pins.gpio4,
+        }
+    }
+
+    /// Continuously transmit the 16-bit samples returned by `sample` with the
+    /// given standard and return the samples read back through the wire
+    fn loopback(self, standard: Standard, sample: impl Fn(usize) -> u16) -> [u16; RX_SAMPLES] {
+        let dma = Dma::new(self.dma);
+        #[cfg(any(feature = "esp32", feature = "esp32s2"))]
+        let dma_channel = dma.i2s0channel;
+        #[cfg(not(any(feature = "esp32", feature = "esp32s2")))]
+        let dma_channel = dma.channel0;
+
+        let (tx_buffer, mut tx_descriptors, mut rx_buffer, mut rx_descriptors) =
+            dma_circular_buffers!(BUFFER_SIZE);
+        for (i, bytes) in tx_buffer.chunks_exact_mut(2).enumerate() {
+            bytes.copy_from_slice(&sample(i).to_le_bytes());
+        }
+
+        let i2s = I2s::new(
+            self.i2s,
+            standard,
+            DataFormat::Data16Channel16,
+            16000.Hz(),
+            dma_channel.configure(
+                false,
+                &mut tx_descriptors,
+                &mut rx_descriptors,
+                DmaPriority::Priority0,
+            ),
+            &self.clocks,
+        );
+        let i2s_tx = i2s.i2s_tx.with_dout(self.dout).build();
+        let i2s_rx = i2s.i2s_rx.with_din(self.din).build();
+        let mut duplex = I2sDuplex::new(i2s_tx, i2s_rx);
+
+        let mut transfer = duplex
+            .transfer_circular(&tx_buffer, &mut rx_buffer)
+            .unwrap();
+        let mut data = [0u8; 2 * RX_SAMPLES];
+        let mut received = 0;
+        while received < data.len() {
+            let available = transfer.rx_available().min(data.len() - received);
+            received += transfer.pop(&mut data[received..][..available]).unwrap();
+        }
+        transfer.stop().unwrap();
+
+        core::array::from_fn(|i| u16::from_le_bytes([data[2 * i], data[2 * i + 1]]))
+    }
+}
+
+/// The transmitted sequence 1, 2, .., [SAMPLES]
+fn counter(i: usize) -> u16 {
+    i as u16 + 1
+}
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use defmt::{assert, assert_eq};
+    #[cfg(not(any(feature = "esp32", feature = "esp32s2")))]
+    use esp_hal::i2s::{TdmConfig, TdmFormat};
+
+    use super::*;
+
+    /// Check that the received samples continue the transmitted sequence,
+    /// skipping the samples before the first complete one
+    fn assert_counter(received: &[u16]) {
+        let first = received.iter().position(|&sample| sample != 0).unwrap() + 1;
+        assert!(first < RX_SAMPLES / 2);
+
+        for pair in received[first..].windows(2) {
+            assert_eq!(pair[1], pair[0] % SAMPLES as u16 + 1);
+        }
+    }
+
+    #[init]
+    fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_philips(ctx: Context) {
+        assert_counter(&ctx.loopback(Standard::Philips, counter));
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_msb(ctx: Context) {
+        assert_counter(&ctx.loopback(Standard::Msb, counter));
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_pcm_short(ctx: Context) {
+        assert_counter(&ctx.loopback(Standard::PcmShort, counter));
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_pcm_long(ctx: Context) {
+        assert_counter(&ctx.loopback(Standard::PcmLong, counter));
+    }
+
+    #[test]
+    #[timeout(3)]
+    #[cfg(not(any(feature = "esp32", feature = "esp32s2")))]
+    fn test_tdm(ctx: Context) {
+        let config = TdmConfig::new(4).with_format(TdmFormat::PcmShort);
+        assert_counter(&ctx.loopback(Standard::Tdm(config), counter));
+    }
+
+    #[test]
+    #[timeout(3)]
+    #[cfg(not(any(feature = "esp32", feature = "esp32s2")))]
+    fn test_tdm_slot_mask(ctx: Context) {
+        // only slots 1 and 3 are in the buffers
+        let config = TdmConfig::new(4).with_slot_mask(0b1010).with_slot_bits(32);
+        assert_counter(&ctx.loopback(Standard::Tdm(config), counter));
+    }
+
+    #[test]
+    #[timeout(3)]
+    #[cfg(any(feature = "esp32", feature = "esp32c6", feature = "esp32s3"))]
+    fn test_pdm_positive(ctx: Context) {
+        // a constant level is converted to a PDM signal and back by the
+        // hardware filters
+        let received = ctx.loopback(Standard::Pdm, |_| 0x2000);
+        assert!((received[RX_SAMPLES - 1] as i16) > 0);
+    }
+
+    #[test]
+    #[timeout(3)]
+    #[cfg(any(feature = "esp32", feature = "esp32c6", feature = "esp32s3"))]
+    fn test_pdm_negative(ctx: Context) {
+        let received = ctx.loopback(Standard::Pdm, |_| (-0x2000i16) as u16);
+        assert!((received[RX_SAMPLES - 1] as i16) < 0);
+    }
+}
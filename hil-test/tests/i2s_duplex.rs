@@ -0,0 +1,218 @@
+//! I2S full-duplex test
+//!
+//! The receiver shares BCLK and WS of the transmitter, DOUT is read back
+//! through the wire:
+//! DOUT    GPIO2 => DIN GPIO4
+//!
+//! Connect GPIO2 and GPIO4 pins.
+
+//% CHIPS: esp32 esp32c3 esp32c6 esp32h2 esp32s2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::{ClockControl, Clocks},
+    delay::Delay,
+    dma::{Dma, DmaPriority},
+    dma_circular_buffers,
+    gpio::{Gpio2, Gpio4, Io},
+    i2s::{DataFormat, I2s, I2sDuplex, I2sDuplexEvent, I2sDuplexTransfer, Standard},
+    peripherals::{Peripherals, DMA, I2S0},
+    prelude::*,
+    system::SystemControl,
+    Blocking,
+};
+
+#[cfg(any(feature = "esp32", feature = "esp32s2"))]
+type DmaChannel = esp_hal::dma::I2s0DmaChannel;
+#[cfg(not(any(feature = "esp32", feature = "esp32s2")))]
+type DmaChannel = esp_hal::dma::Channel0;
+
+type Transfer<'t, 'd> = I2sDuplexTransfer<'t, 'd, I2S0, DmaChannel, Blocking>;
+
+/// Size of the circular TX and RX buffers in bytes, about 31 ms of audio
+const BUFFER_SIZE: usize = 2000;
+
+/// Number of 16-bit samples in the TX buffer
+const SAMPLES: usize = BUFFER_SIZE / 2;
+
+struct Context {
+    clocks: Clocks<'static>,
+    delay: Delay,
+    i2s: I2S0,
+    dma: DMA,
+    dout: Gpio2,
+    din: Gpio4,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+        let delay = Delay::new(&clocks);
+
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+        Context {
+            clocks,
+            delay,
+            i2s: peripherals.I2S0,
+            dma: peripherals.DMA,
+            dout: io.pins.gpio2,
+            din: io.pins.gpio4,
+        }
+    }
+
+    /// Run `f` on a duplex transfer which continuously transmits the
+    /// sequence 1, 2, .., [SAMPLES]
+    fn run(self, f: impl FnOnce(&mut Transfer<'_, '_>, Delay)) {
+        let dma = Dma::new(self.dma);
+        #[cfg(any(feature = "esp32", feature = "esp32s2"))]
+        let dma_channel = dma.i2s0channel;
+        #[cfg(not(any(feature = "esp32", feature = "esp32s2")))]
+        let dma_channel = dma.channel0;
+
+        let (tx_buffer, mut tx_descriptors, mut rx_buffer, mut rx_descriptors) =
+            dma_circular_buffers!(BUFFER_SIZE);
+        for (i, bytes) in tx_buffer.chunks_exact_mut(2).enumerate() {
+            bytes.copy_from_slice(&counter(i).to_le_bytes());
+        }
+
+        let i2s = I2s::new(
+            self.i2s,
+            Standard::Philips,
+            DataFormat::Data16Channel16,
+            16000.Hz(),
+            dma_channel.configure(
+                false,
+                &mut tx_descriptors,
+                &mut rx_descriptors,
+                DmaPriority::Priority0,
+            ),
+            &self.clocks,
+        );
+        let i2s_tx = i2s.i2s_tx.with_dout(self.dout).build();
+        let i2s_rx = i2s.i2s_rx.with_din(self.din).build();
+        let mut duplex = I2sDuplex::new(i2s_tx, i2s_rx);
+
+        let mut transfer = duplex
+            .transfer_circular(&tx_buffer, &mut rx_buffer)
+            .unwrap();
+        f(&mut transfer, self.delay);
+        transfer.stop().unwrap();
+    }
+}
+
+fn counter(i: usize) -> u16 {
+    (i % SAMPLES) as u16 + 1
+}
+
+/// Fill `samples` with the next received samples
+fn pop_samples(transfer: &mut Transfer<'_, '_>, samples: &mut [u16]) {
+    let mut data = [0u8; BUFFER_SIZE];
+    let data = &mut data[..2 * samples.len()];
+
+    let mut received = 0;
+    while received < data.len() {
+        let available = transfer.rx_available().min(data.len() - received);
+        received += transfer.pop(&mut data[received..][..available]).unwrap();
+    }
+
+    for (sample, bytes) in samples.iter_mut().zip(data.chunks_exact(2)) {
+        *sample = u16::from_le_bytes([bytes[0], bytes[1]]);
+    }
+}
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use defmt::{assert, assert_eq};
+
+    use super::*;
+
+    #[init]
+    fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_shared_position(ctx: Context) {
+        ctx.run(|transfer, _| {
+            // the whole TX buffer is queued before the first pushed byte
+            assert_eq!(transfer.tx_position(), BUFFER_SIZE as u64);
+            assert_eq!(transfer.rx_position(), 0);
+
+            let mut received = [0u16; SAMPLES];
+            pop_samples(transfer, &mut received);
+            assert_eq!(transfer.rx_position(), BUFFER_SIZE as u64);
+
+            // sample n of the stream is received in the frame it is
+            // transmitted in, the first frame may be incomplete
+            for (n, sample) in received.iter().enumerate().skip(2) {
+                assert_eq!(*sample, counter(n));
+            }
+        });
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_no_events_while_keeping_up(ctx: Context) {
+        ctx.run(|transfer, _| {
+            let mut received = [0u16; SAMPLES / 2];
+            for _ in 0..8 {
+                // the buffer content is sent again
+                let available = transfer.tx_available();
+                if available > 0 {
+                    transfer.push_with(|buffer| buffer.len()).unwrap();
+                }
+                pop_samples(transfer, &mut received);
+            }
+
+            assert!(transfer.events().is_empty());
+        });
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_underrun_and_overrun(ctx: Context) {
+        ctx.run(|transfer, delay| {
+            // neither pushing nor popping for several buffer durations
+            delay.delay_millis(100);
+
+            let events = transfer.events();
+            assert!(events.contains(I2sDuplexEvent::TxUnderrun));
+            assert!(events.contains(I2sDuplexEvent::RxOverrun));
+            assert!(!events.contains(I2sDuplexEvent::TxDescriptorError));
+            assert!(!events.contains(I2sDuplexEvent::RxDescriptorError));
+
+            transfer.clear_events(I2sDuplexEvent::TxUnderrun | I2sDuplexEvent::RxOverrun);
+            assert!(!transfer.events().contains(I2sDuplexEvent::TxUnderrun));
+        });
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_resync(ctx: Context) {
+        ctx.run(|transfer, delay| {
+            delay.delay_millis(100);
+            assert!(transfer.events().contains(I2sDuplexEvent::RxOverrun));
+
+            transfer.resync().unwrap();
+            assert!(transfer.events().is_empty());
+            assert_eq!(transfer.tx_position(), BUFFER_SIZE as u64);
+            assert_eq!(transfer.rx_position(), 0);
+
+            // both directions start from the beginning of their buffers again
+            let mut received = [0u16; SAMPLES];
+            pop_samples(transfer, &mut received);
+            for (n, sample) in received.iter().enumerate().skip(2) {
+                assert_eq!(*sample, counter(n));
+            }
+        });
+    }
+}
@@ -0,0 +1,203 @@
+//! LEDC fade Test
+//!
+//! Following pins are used:
+//! GPIO2
+//!
+//! The pin is driven without anything connected, only the duty of the channel
+//! is checked.
+
+//% CHIPS: esp32 esp32c2 esp32c3 esp32c6 esp32h2 esp32s2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::{ClockControl, Clocks},
+    delay::Delay,
+    embassy,
+    gpio::{Gpio2, Io},
+    ledc::{
+        channel::{self, Channel, ChannelHW, ChannelIFace, FadeStep},
+        timer::{self, Timer, TimerIFace},
+        LSGlobalClkSource,
+        Ledc,
+        LowSpeed,
+    },
+    peripherals::{Peripherals, LEDC},
+    prelude::*,
+    system::SystemControl,
+    timer::timg::TimerGroup,
+};
+
+struct Context {
+    ledc: LEDC,
+    pin: Gpio2,
+    clocks: Clocks<'static>,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+        let timg0 = TimerGroup::new_async(peripherals.TIMG0, &clocks);
+        embassy::init(&clocks, timg0);
+
+        Context {
+            ledc: peripherals.LEDC,
+            pin: io.pins.gpio2,
+            clocks,
+        }
+    }
+}
+
+/// Maximum duty of the 5-bit timer
+const MAX_DUTY: u32 = 31;
+
+/// Configure timer 0 with a 5-bit duty at 24 kHz
+fn configure_timer<'a>(ledc: &'a Ledc<'a>) -> Timer<'a, LowSpeed> {
+    let mut timer = ledc.get_timer::<LowSpeed>(timer::Number::Timer0);
+    timer
+        .configure(timer::config::Config {
+            duty: timer::config::Duty::Duty5Bit,
+            clock_source: timer::LSClockSource::APBClk,
+            frequency: 24.kHz(),
+        })
+        .unwrap();
+    timer
+}
+
+/// Configure channel 0 on `pin`, starting at a duty of 0%
+fn configure_channel<'a>(
+    ledc: &'a Ledc<'a>,
+    timer: &'a Timer<'a, LowSpeed>,
+    pin: &'a mut Gpio2,
+) -> Channel<'a, LowSpeed, Gpio2> {
+    let mut channel = ledc.get_channel(channel::Number::Channel0, pin);
+    channel
+        .configure(channel::config::Config {
+            timer,
+            duty_pct: 0,
+            pin_config: channel::config::PinConfig::PushPull,
+        })
+        .unwrap();
+    channel
+}
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal::embassy::executor::Executor::new())]
+mod tests {
+    use defmt::assert_eq;
+
+    use super::*;
+
+    #[init]
+    fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_fade_to_async(mut ctx: Context) {
+        let mut ledc = Ledc::new_async(&mut ctx.ledc, &ctx.clocks);
+        ledc.set_global_slow_clock(LSGlobalClkSource::APBClk);
+
+        let lstimer0 = configure_timer(&ledc);
+        let channel0 = configure_channel(&ledc, &lstimer0, &mut ctx.pin);
+
+        channel0.fade_to_async(100, 50).await.unwrap();
+        assert_eq!(channel0.is_duty_fade_running(), false);
+        assert_eq!(channel0.get_duty_hw(), MAX_DUTY);
+
+        channel0.fade_to_async(0, 50).await.unwrap();
+        assert_eq!(channel0.get_duty_hw(), 0);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_fade_steps_async(mut ctx: Context) {
+        let mut ledc = Ledc::new_async(&mut ctx.ledc, &ctx.clocks);
+        ledc.set_global_slow_clock(LSGlobalClkSource::APBClk);
+
+        let lstimer0 = configure_timer(&ledc);
+        let channel0 = configure_channel(&ledc, &lstimer0, &mut ctx.pin);
+
+        channel0
+            .fade_steps_async(&[
+                FadeStep {
+                    increase: true,
+                    num: 4,
+                    cycles: 10,
+                    scale: 4,
+                },
+                FadeStep {
+                    increase: false,
+                    num: 2,
+                    cycles: 10,
+                    scale: 3,
+                },
+            ])
+            .await
+            .unwrap();
+        assert_eq!(channel0.get_duty_hw(), 10);
+
+        let invalid = FadeStep {
+            increase: true,
+            num: 0,
+            cycles: 10,
+            scale: 1,
+        };
+        assert_eq!(
+            channel0.fade_steps_async(&[invalid]).await,
+            Err(channel::Error::Fade(channel::FadeError::DutyRange))
+        );
+    }
+
+    #[test]
+    fn test_stop_duty_fade(mut ctx: Context) {
+        let delay = Delay::new(&ctx.clocks);
+
+        let mut ledc = Ledc::new(&mut ctx.ledc, &ctx.clocks);
+        ledc.set_global_slow_clock(LSGlobalClkSource::APBClk);
+
+        let lstimer0 = configure_timer(&ledc);
+        let channel0 = configure_channel(&ledc, &lstimer0, &mut ctx.pin);
+
+        channel0.start_duty_fade(0, 100, 1000).unwrap();
+        delay.delay_millis(300);
+        channel0.stop_duty_fade();
+        delay.delay_millis(1);
+
+        assert_eq!(channel0.is_duty_fade_running(), false);
+        let duty = channel0.get_duty_hw();
+        defmt::assert!(duty > 0 && duty < MAX_DUTY);
+
+        delay.delay_millis(100);
+        assert_eq!(channel0.get_duty_hw(), duty);
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_acknowledge_fade_end(mut ctx: Context) {
+        // without an interrupt handler the interrupt is only raised at the
+        // peripheral
+        let mut ledc = Ledc::new(&mut ctx.ledc, &ctx.clocks);
+        ledc.set_global_slow_clock(LSGlobalClkSource::APBClk);
+
+        let lstimer0 = configure_timer(&ledc);
+        let channel0 = configure_channel(&ledc, &lstimer0, &mut ctx.pin);
+
+        channel0.listen_fade_end();
+        channel0.start_duty_fade(0, 100, 50).unwrap();
+        assert_eq!(channel0.acknowledge_fade_end(), false);
+
+        while channel0.is_duty_fade_running() {}
+        assert_eq!(channel0.acknowledge_fade_end(), true);
+        assert_eq!(channel0.acknowledge_fade_end(), false);
+        assert_eq!(channel0.is_duty_fade_running(), false);
+    }
+}
@@ -0,0 +1,213 @@
+//! MCPWM capture test
+//!
+//! Folowing pins are used:
+//! GPIO2 (signal)
+//! GPIO4 (capture input)
+//!
+//! Connect GPIO2 and GPIO4 pins.
+
+//% CHIPS: esp32 esp32c6 esp32h2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use embassy_futures::join::join;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::ClockControl,
+    delay::Delay,
+    gpio::{Gpio2, Gpio4, Io, Level, Output},
+    mcpwm::{
+        capture::{CaptureConfig, CaptureEdge},
+        timer::{SyncOutput, SyncSource, TimerSyncConfig},
+        McPwm,
+        PeripheralClockConfig,
+    },
+    peripherals::{Peripherals, MCPWM0},
+    prelude::*,
+    system::SystemControl,
+};
+
+struct Context {
+    mcpwm: McPwm<'static, MCPWM0>,
+    signal: Output<'static, Gpio2>,
+    input: Gpio4,
+    delay: Delay,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+        #[cfg(feature = "esp32h2")]
+        let clock_cfg = PeripheralClockConfig::with_frequency(&clocks, 40.MHz()).unwrap();
+        #[cfg(not(feature = "esp32h2"))]
+        let clock_cfg = PeripheralClockConfig::with_frequency(&clocks, 32.MHz()).unwrap();
+        let mut mcpwm = McPwm::new_async(peripherals.MCPWM0, clock_cfg);
+        mcpwm.capture_timer.start();
+
+        Context {
+            mcpwm,
+            signal: Output::new(io.pins.gpio2, Level::Low),
+            input: io.pins.gpio4,
+            delay: Delay::new(&clocks),
+        }
+    }
+}
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal::embassy::executor::Executor::new())]
+mod tests {
+    use defmt::{assert, assert_eq};
+
+    use super::*;
+
+    #[init]
+    async fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_capture_edges(mut ctx: Context) {
+        let mut input = ctx
+            .mcpwm
+            .capture0
+            .with_pin(ctx.input, CaptureConfig::BOTH_EDGES);
+        assert_eq!(input.capture(), None);
+
+        ctx.signal.set_high();
+        let rising = input.wait_for_capture();
+        ctx.delay.delay_micros(100);
+        ctx.signal.set_low();
+        let falling = input.wait_for_capture();
+
+        assert_eq!(rising.edge, CaptureEdge::Rising);
+        assert_eq!(falling.edge, CaptureEdge::Falling);
+        assert!(falling.value.wrapping_sub(rising.value) > 0);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_edge_select(mut ctx: Context) {
+        let mut input = ctx
+            .mcpwm
+            .capture0
+            .with_pin(ctx.input, CaptureConfig::FALLING_EDGE);
+
+        ctx.signal.set_high();
+        ctx.delay.delay_micros(10);
+        assert_eq!(input.capture(), None);
+
+        ctx.signal.set_low();
+        ctx.delay.delay_micros(10);
+        assert_eq!(input.capture().map(|c| c.edge), Some(CaptureEdge::Falling));
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_prescaler(mut ctx: Context) {
+        let mut input = ctx
+            .mcpwm
+            .capture1
+            .with_pin(ctx.input, CaptureConfig::RISING_EDGE.with_prescaler(1));
+
+        // only every second rising edge is captured
+        let mut captures = 0;
+        for _ in 0..4 {
+            ctx.signal.set_high();
+            ctx.delay.delay_micros(10);
+            ctx.signal.set_low();
+            ctx.delay.delay_micros(10);
+            captures += input.capture().is_some() as u32;
+        }
+        assert_eq!(captures, 2);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_software_capture(mut ctx: Context) {
+        let mut input = ctx
+            .mcpwm
+            .capture2
+            .with_pin(ctx.input, CaptureConfig::RISING_EDGE);
+
+        input.trigger_software_capture();
+        let first = input.wait_for_capture();
+        ctx.delay.delay_micros(100);
+        input.trigger_software_capture();
+        let second = input.wait_for_capture();
+
+        assert_eq!(first.edge, CaptureEdge::Rising);
+        assert!(second.value.wrapping_sub(first.value) > 0);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_software_sync(mut ctx: Context) {
+        const PHASE: u32 = 0x4000_0000;
+
+        let mut input = ctx
+            .mcpwm
+            .capture0
+            .with_pin(ctx.input, CaptureConfig::RISING_EDGE);
+
+        ctx.mcpwm.capture_timer.set_sync_phase(PHASE);
+        ctx.mcpwm.capture_timer.software_sync();
+        input.trigger_software_capture();
+        let capture = input.wait_for_capture();
+
+        // the timer continues counting from the phase
+        assert!(capture.value.wrapping_sub(PHASE) < 10_000);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_timer_sync_source(mut ctx: Context) {
+        const PHASE: u32 = 0x4000_0000;
+
+        let mut input = ctx
+            .mcpwm
+            .capture0
+            .with_pin(ctx.input, CaptureConfig::RISING_EDGE);
+
+        ctx.mcpwm
+            .timer0
+            .set_sync_config(TimerSyncConfig::new().with_output(SyncOutput::Software));
+        ctx.mcpwm.capture_timer.set_sync_phase(PHASE);
+        ctx.mcpwm
+            .capture_timer
+            .set_sync_source(Some(SyncSource::Timer0));
+
+        // the phase is only loaded on a sync event of timer 0
+        input.trigger_software_capture();
+        assert!(input.wait_for_capture().value < PHASE);
+
+        ctx.mcpwm.timer0.software_sync();
+        input.trigger_software_capture();
+        assert!(input.wait_for_capture().value.wrapping_sub(PHASE) < 10_000);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_capture_async(mut ctx: Context) {
+        let mut input = ctx
+            .mcpwm
+            .capture0
+            .with_pin(ctx.input, CaptureConfig::RISING_EDGE);
+        let signal = &mut ctx.signal;
+        let delay = ctx.delay;
+
+        let (capture, _) = join(input.wait_for_capture_async(), async {
+            delay.delay_micros(100);
+            signal.set_high();
+        })
+        .await;
+
+        assert_eq!(capture.edge, CaptureEdge::Rising);
+    }
+}
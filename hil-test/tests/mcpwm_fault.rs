@@ -0,0 +1,201 @@
+//! MCPWM fault test
+//!
+//! The A output of operator 0 is read back through the wire. The fault input
+//! F0 reads GPIO5, which is driven by an open-drain output:
+//! PWM0A   GPIO2 => GPIO4
+//! F0      GPIO5
+//!
+//! Connect GPIO2 and GPIO4 pins.
+
+//% CHIPS: esp32 esp32c6 esp32h2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use embassy_futures::{join::join, poll_once};
+use esp_backtrace as _;
+use esp_hal::{
+    clock::ClockControl,
+    delay::Delay,
+    gpio::{AnyOutputOpenDrain, Gpio4, Gpio5, GpioPin, Input, Io, Level, Pull},
+    mcpwm::{
+        fault::{
+            FaultConfig,
+            FaultHandler,
+            FaultPin,
+            FaultPolarity,
+            FaultSource,
+            TripActions,
+            TripKind,
+            TripStatus,
+        },
+        operator::PwmPinConfig,
+        timer::PwmWorkingMode,
+        McPwm,
+        PeripheralClockConfig,
+    },
+    peripherals::{Peripherals, MCPWM0},
+    prelude::*,
+    system::SystemControl,
+};
+
+struct Context {
+    fault_handler: FaultHandler<0, MCPWM0>,
+    fault_pin: FaultPin<'static, Gpio5, MCPWM0, 0>,
+    fault: AnyOutputOpenDrain<'static>,
+    output: Input<'static, Gpio4>,
+    delay: Delay,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+        #[cfg(feature = "esp32h2")]
+        let clock_cfg = PeripheralClockConfig::with_frequency(&clocks, 40.MHz()).unwrap();
+        #[cfg(not(feature = "esp32h2"))]
+        let clock_cfg = PeripheralClockConfig::with_frequency(&clocks, 32.MHz()).unwrap();
+        let mut mcpwm = McPwm::new_async(peripherals.MCPWM0, clock_cfg);
+
+        // The fault input only reads GPIO5, the open-drain output configured
+        // afterwards drives it. A high level signals a fault.
+        let fault_pin = mcpwm
+            .fault0
+            .with_pin(unsafe { GpioPin::<5>::steal() }, FaultPolarity::ActiveHigh);
+        let fault = AnyOutputOpenDrain::new(io.pins.gpio5, Level::Low, Pull::Up);
+
+        // Without any actions the output stays low unless it is tripped
+        mcpwm.operator0.set_timer(&mcpwm.timer0);
+        let _pin = mcpwm
+            .operator0
+            .with_pin_a(io.pins.gpio2, PwmPinConfig::EMPTY);
+        let timer_clock_cfg = clock_cfg
+            .timer_clock_with_frequency(99, PwmWorkingMode::Increase, 20.kHz())
+            .unwrap();
+        mcpwm.timer0.start(timer_clock_cfg);
+
+        Context {
+            fault_handler: mcpwm.fault_handler0,
+            fault_pin,
+            fault,
+            output: Input::new(io.pins.gpio4, Pull::None),
+            delay: Delay::new(&clocks),
+        }
+    }
+}
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal::embassy::executor::Executor::new())]
+mod tests {
+    use defmt::{assert, assert_eq};
+
+    use super::*;
+
+    const NOT_TRIPPED: TripStatus = TripStatus {
+        cycle_by_cycle: false,
+        one_shot: false,
+    };
+
+    #[init]
+    async fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_software_one_shot_trip(mut ctx: Context) {
+        ctx.fault_handler.set_config(
+            FaultConfig::new()
+                .with_one_shot(FaultSource::Software)
+                .with_actions_a(TripActions::FORCE_HIGH),
+        );
+        ctx.delay.delay_micros(100);
+        assert_eq!(ctx.fault_handler.trip_status(), NOT_TRIPPED);
+        assert_eq!(ctx.output.is_low(), true);
+
+        ctx.fault_handler.force_trip(TripKind::OneShot);
+        ctx.delay.delay_micros(100);
+        assert_eq!(ctx.fault_handler.trip_status().one_shot, true);
+        assert_eq!(ctx.output.is_high(), true);
+
+        // a one-shot trip outlasts many timer periods
+        ctx.delay.delay_millis(1);
+        assert_eq!(ctx.output.is_high(), true);
+
+        ctx.fault_handler.clear_one_shot_trip();
+        ctx.delay.delay_micros(100);
+        assert_eq!(ctx.fault_handler.trip_status(), NOT_TRIPPED);
+        assert_eq!(ctx.output.is_low(), true);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_fault_input_cycle_by_cycle_trip(mut ctx: Context) {
+        ctx.fault_handler.set_config(
+            FaultConfig::new()
+                .with_cycle_by_cycle(FaultSource::Fault0)
+                .with_actions_a(TripActions::FORCE_HIGH),
+        );
+        ctx.delay.delay_micros(100);
+        assert_eq!(ctx.fault_pin.is_active(), false);
+        assert_eq!(ctx.output.is_low(), true);
+
+        ctx.fault.set_high();
+        ctx.delay.delay_micros(100);
+        assert_eq!(ctx.fault_pin.is_active(), true);
+        assert_eq!(ctx.fault_handler.trip_status().cycle_by_cycle, true);
+        assert_eq!(ctx.output.is_high(), true);
+
+        // the trip ends at the next timer zero after the fault is gone
+        ctx.fault.set_low();
+        ctx.delay.delay_micros(200);
+        assert_eq!(ctx.fault_handler.trip_status(), NOT_TRIPPED);
+        assert_eq!(ctx.output.is_low(), true);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_unconfigured_source_does_not_trip(mut ctx: Context) {
+        ctx.fault_handler.set_config(
+            FaultConfig::new()
+                .with_one_shot(FaultSource::Fault1)
+                .with_actions_a(TripActions::FORCE_HIGH),
+        );
+
+        ctx.fault.set_high();
+        ctx.fault_handler.force_trip(TripKind::OneShot);
+        ctx.delay.delay_micros(100);
+        assert_eq!(ctx.fault_handler.trip_status(), NOT_TRIPPED);
+        assert_eq!(ctx.output.is_low(), true);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_wait_for_fault_async(mut ctx: Context) {
+        let fault = &mut ctx.fault;
+        let delay = ctx.delay;
+
+        join(ctx.fault_pin.wait_for_fault_async(), async {
+            delay.delay_micros(100);
+            fault.set_high();
+        })
+        .await;
+
+        assert_eq!(ctx.fault_pin.is_active(), true);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_dropped_fault_future_disables_interrupt(mut ctx: Context) {
+        assert!(poll_once(ctx.fault_pin.wait_for_fault_async()).is_pending());
+
+        // SAFETY:
+        // We only read the interrupt enable register
+        let block = unsafe { &*MCPWM0::PTR };
+        assert_eq!(block.int_ena().read().bits(), 0);
+    }
+}
@@ -0,0 +1,97 @@
+//! MCPWM timer sync test
+
+//% CHIPS: esp32 esp32c6 esp32h2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::ClockControl,
+    mcpwm::{
+        timer::{CounterDirection, PwmWorkingMode, SyncOutput, SyncSource, TimerSyncConfig},
+        McPwm,
+        PeripheralClockConfig,
+    },
+    peripherals::{Peripherals, MCPWM0},
+    prelude::*,
+    system::SystemControl,
+};
+
+struct Context {
+    mcpwm: McPwm<'static, MCPWM0>,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+
+        #[cfg(feature = "esp32h2")]
+        let clock_cfg = PeripheralClockConfig::with_frequency(&clocks, 40.MHz()).unwrap();
+        #[cfg(not(feature = "esp32h2"))]
+        let clock_cfg = PeripheralClockConfig::with_frequency(&clocks, 32.MHz()).unwrap();
+        let mut mcpwm = McPwm::new(peripherals.MCPWM0, clock_cfg);
+
+        // a slow timer clock of about 180 kHz, so the counter values can be
+        // checked right after they were set
+        let timer_clock_cfg = clock_cfg
+            .timer_clock_with_frequency(60_000, PwmWorkingMode::Increase, 3.Hz())
+            .unwrap();
+        mcpwm.timer0.start(timer_clock_cfg);
+        mcpwm.timer1.start(timer_clock_cfg);
+
+        Context { mcpwm }
+    }
+}
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use defmt::assert;
+
+    use super::*;
+
+    #[init]
+    fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_set_counter(mut ctx: Context) {
+        ctx.mcpwm
+            .timer0
+            .set_counter(1000, CounterDirection::Increasing);
+
+        let (value, _) = ctx.mcpwm.timer0.status();
+        assert!((1000..1100).contains(&value));
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_set_counter_keeps_sync_config(mut ctx: Context) {
+        ctx.mcpwm
+            .timer0
+            .set_sync_config(TimerSyncConfig::new().with_output(SyncOutput::Software));
+        ctx.mcpwm.timer1.set_sync_config(
+            TimerSyncConfig::new()
+                .with_input(SyncSource::Timer0)
+                .with_phase(30_000, CounterDirection::Increasing),
+        );
+
+        ctx.mcpwm
+            .timer1
+            .set_counter(1000, CounterDirection::Increasing);
+        let (value, _) = ctx.mcpwm.timer1.status();
+        assert!((1000..1100).contains(&value));
+
+        // timer1 still follows the sync output of timer0 and loads its
+        // configured phase
+        ctx.mcpwm.timer0.software_sync();
+        let (value, _) = ctx.mcpwm.timer1.status();
+        assert!((30_000..30_100).contains(&value));
+    }
+}
@@ -0,0 +1,203 @@
+//! NVS tests
+//!
+//! Runs against a captured page and a RAM-backed flash, so the flash chip of
+//! the device is never modified.
+
+//% CHIPS: esp32 esp32c2 esp32c3 esp32c6 esp32h2 esp32s2 esp32s3
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::flash::nvs::{Error, ItemType, Nvs};
+
+// Start of an active page in the format written by ESP-IDF, holding the
+// values of this `nvs_partition_gen.py` input:
+//
+// ```csv
+// key,type,encoding,value
+// config,namespace,,
+// u8val,data,u8,42
+// i32val,data,i32,-100000
+// name,data,string,esp-hal
+// blob,data,hex2bin,0102030405
+// ```
+const REFERENCE_PAGE: [u8; 320] = [
+    0xfe, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x84, 0x2d, 0xba, 0xb9,
+    0xaa, 0xaa, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x00, 0x01, 0x01, 0xff, 0xf1, 0x27, 0xca, 0xdf, 0x63, 0x6f, 0x6e, 0x66, 0x69, 0x67, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x01, 0x01, 0x01, 0xff, 0x22, 0xa5, 0x5f, 0xb4, 0x75, 0x38, 0x76, 0x61, 0x6c, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2a, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x01, 0x14, 0x01, 0xff, 0x79, 0xa3, 0xe5, 0x46, 0x69, 0x33, 0x32, 0x76, 0x61, 0x6c, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x60, 0x79, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x01, 0x21, 0x02, 0xff, 0xfa, 0x19, 0xb6, 0x7d, 0x6e, 0x61, 0x6d, 0x65, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0xff, 0xff, 0xb2, 0x27, 0xc8, 0xf6,
+    0x65, 0x73, 0x70, 0x2d, 0x68, 0x61, 0x6c, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x01, 0x42, 0x02, 0x00, 0xd5, 0xad, 0xda, 0x04, 0x62, 0x6c, 0x6f, 0x62, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0xff, 0xff, 0x16, 0x91, 0xd6, 0x7e,
+    0x01, 0x02, 0x03, 0x04, 0x05, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x01, 0x48, 0x01, 0xff, 0x3e, 0xf1, 0x34, 0x34, 0x62, 0x6c, 0x6f, 0x62, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x01, 0x00, 0xff, 0xff,
+];
+
+const PAGE_SIZE: usize = 4096;
+const RAM_FLASH_SIZE: usize = 3 * PAGE_SIZE;
+
+type RamFlash = common::RamFlash<RAM_FLASH_SIZE>;
+
+fn reference_flash() -> RamFlash {
+    let mut flash = RamFlash::new();
+    flash.data[..REFERENCE_PAGE.len()].copy_from_slice(&REFERENCE_PAGE);
+    flash
+}
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use defmt::assert_eq;
+
+    use super::*;
+
+    #[init]
+    fn init() {}
+
+    #[test]
+    fn test_read_reference_page() {
+        let mut flash = reference_flash();
+        let mut nvs = Nvs::new(&mut flash, 0, RAM_FLASH_SIZE as u32).unwrap();
+
+        assert_eq!(nvs.get::<u8>("config", "u8val"), Ok(42));
+        assert_eq!(nvs.get::<i32>("config", "i32val"), Ok(-100000));
+        assert_eq!(nvs.item_type("config", "name"), Ok(ItemType::Str));
+
+        let mut buffer = [0u8; 16];
+        assert_eq!(nvs.get_str("config", "name", &mut buffer), Ok("esp-hal"));
+        assert_eq!(
+            nvs.get_blob("config", "blob", &mut buffer),
+            Ok(&[1u8, 2, 3, 4, 5][..])
+        );
+
+        assert_eq!(nvs.get::<u32>("config", "u8val"), Err(Error::TypeMismatch));
+        assert_eq!(nvs.get::<u8>("config", "missing"), Err(Error::NotFound));
+        assert_eq!(nvs.get::<u8>("other", "u8val"), Err(Error::NotFound));
+    }
+
+    #[test]
+    fn test_write_matches_reference_page() {
+        let mut flash = RamFlash::new();
+        let mut nvs = Nvs::new(&mut flash, 0, RAM_FLASH_SIZE as u32).unwrap();
+
+        nvs.set("config", "u8val", 42u8).unwrap();
+        nvs.set("config", "i32val", -100000i32).unwrap();
+        nvs.set_str("config", "name", "esp-hal").unwrap();
+        nvs.set_blob("config", "blob", &[1, 2, 3, 4, 5]).unwrap();
+
+        assert_eq!(flash.data[..REFERENCE_PAGE.len()], REFERENCE_PAGE);
+    }
+
+    #[test]
+    fn test_overwrite_and_remove() {
+        let mut flash = RamFlash::new();
+        let mut nvs = Nvs::new(&mut flash, 0, RAM_FLASH_SIZE as u32).unwrap();
+
+        nvs.set("app", "value", 1u16).unwrap();
+        nvs.set("app", "value", 2u16).unwrap();
+        assert_eq!(nvs.get::<u16>("app", "value"), Ok(2));
+
+        // Changing the type replaces the old value
+        nvs.set_str("app", "value", "three").unwrap();
+        assert_eq!(nvs.get::<u16>("app", "value"), Err(Error::TypeMismatch));
+
+        nvs.remove("app", "value").unwrap();
+        assert_eq!(nvs.contains_key("app", "value"), Ok(false));
+        assert_eq!(nvs.remove("app", "value"), Err(Error::NotFound));
+
+        assert_eq!(
+            nvs.set("app", "this key is too long", 0u8),
+            Err(Error::InvalidKey)
+        );
+    }
+
+    #[test]
+    fn test_garbage_collection() {
+        let mut flash = RamFlash::new();
+        let mut nvs = Nvs::new(&mut flash, 0, RAM_FLASH_SIZE as u32).unwrap();
+
+        nvs.set("app", "constant", 0xDEAD_BEEFu32).unwrap();
+
+        // Many more writes than fit into the partition without compaction
+        for i in 0..1000u32 {
+            nvs.set("app", "counter", i).unwrap();
+        }
+
+        assert_eq!(nvs.get::<u32>("app", "counter"), Ok(999));
+        assert_eq!(nvs.get::<u32>("app", "constant"), Ok(0xDEAD_BEEF));
+    }
+
+    #[test]
+    fn test_multi_page_blob() {
+        let mut flash = RamFlash::new();
+        let mut nvs = Nvs::new(&mut flash, 0, RAM_FLASH_SIZE as u32).unwrap();
+
+        // Larger than a single page
+        let mut blob = [0u8; 4200];
+        for (i, byte) in blob.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        nvs.set_blob("app", "blob", &blob).unwrap();
+
+        let mut buffer = [0u8; 4200];
+        assert_eq!(nvs.get_blob("app", "blob", &mut buffer), Ok(&blob[..]));
+
+        // Rewriting the blob switches to the other set of chunk indices
+        blob.reverse();
+        nvs.set_blob("app", "blob", &blob[..1000]).unwrap();
+        assert_eq!(nvs.get_blob("app", "blob", &mut buffer), Ok(&blob[..1000]));
+
+        assert_eq!(
+            nvs.get_blob("app", "blob", &mut buffer[..100]),
+            Err(Error::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn test_reopen() {
+        let mut flash = RamFlash::new();
+
+        let mut nvs = Nvs::new(&mut flash, 0, RAM_FLASH_SIZE as u32).unwrap();
+        nvs.set("app", "first", 1i64).unwrap();
+        nvs.set("other", "second", -2i8).unwrap();
+        drop(nvs);
+
+        let mut nvs = Nvs::new(&mut flash, 0, RAM_FLASH_SIZE as u32).unwrap();
+        assert_eq!(nvs.get::<i64>("app", "first"), Ok(1));
+        assert_eq!(nvs.get::<i8>("other", "second"), Ok(-2));
+
+        // Items are appended after the ones found when opening
+        nvs.set("app", "third", 3u64).unwrap();
+        assert_eq!(nvs.get::<i64>("app", "first"), Ok(1));
+        assert_eq!(nvs.get::<u64>("app", "third"), Ok(3));
+    }
+
+    #[test]
+    fn test_invalid_partition() {
+        let mut flash = RamFlash::new();
+
+        assert_eq!(
+            Nvs::new(&mut flash, 0, PAGE_SIZE as u32).err(),
+            Some(Error::InvalidPartition)
+        );
+        assert_eq!(
+            Nvs::new(&mut flash, 0x100, 2 * PAGE_SIZE as u32).err(),
+            Some(Error::InvalidPartition)
+        );
+    }
+}
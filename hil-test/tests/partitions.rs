@@ -0,0 +1,233 @@
+//! Partition table and OTA data tests
+//!
+//! Runs against captured images and a RAM-backed flash, so the flash chip of
+//! the device is never modified.
+
+//% CHIPS: esp32 esp32c2 esp32c3 esp32c6 esp32h2 esp32s2 esp32s3
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::flash::partitions::{
+    ota::{next_sequence, Ota, OtaImageState, OtaSelectEntry},
+    AppPartitionSubType,
+    DataPartitionSubType,
+    Error,
+    PartitionTable,
+    PartitionType,
+};
+
+// Default two-slot OTA layout, as generated by `gen_esp32part.py`
+const DEFAULT_TABLE: [u8; 192] = [
+    0xaa, 0x50, 0x01, 0x02, 0x00, 0x90, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x6e, 0x76, 0x73, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xaa, 0x50, 0x01, 0x00, 0x00, 0xd0, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x6f, 0x74, 0x61, 0x64,
+    0x61, 0x74, 0x61, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xaa, 0x50, 0x01, 0x01, 0x00, 0xf0, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x70, 0x68, 0x79, 0x5f,
+    0x69, 0x6e, 0x69, 0x74, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xaa, 0x50, 0x00, 0x10, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x10, 0x00, 0x6f, 0x74, 0x61, 0x5f,
+    0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xaa, 0x50, 0x00, 0x11, 0x00, 0x00, 0x11, 0x00, 0x00, 0x00, 0x10, 0x00, 0x6f, 0x74, 0x61, 0x5f,
+    0x31, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xeb, 0xeb, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xb0, 0xec, 0xdc, 0x2f, 0x5a, 0xd6, 0xb3, 0x5f, 0x06, 0x6f, 0xb6, 0x0b, 0xf4, 0xb5, 0x4c, 0x84,
+];
+
+// Small layout which fits into `RamFlash`
+const OTA_TABLE: [u8; 128] = [
+    0xaa, 0x50, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x6f, 0x74, 0x61, 0x64,
+    0x61, 0x74, 0x61, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xaa, 0x50, 0x00, 0x10, 0x00, 0x20, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x6f, 0x74, 0x61, 0x5f,
+    0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xaa, 0x50, 0x00, 0x11, 0x00, 0x40, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x6f, 0x74, 0x61, 0x5f,
+    0x31, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xeb, 0xeb, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xbe, 0xbb, 0x56, 0x74, 0x2d, 0xc0, 0xc4, 0xf7, 0xb6, 0xac, 0xcc, 0xae, 0x87, 0x23, 0xe5, 0x40,
+];
+
+const RAM_FLASH_SIZE: usize = 0x6000;
+
+type RamFlash = common::RamFlash<RAM_FLASH_SIZE>;
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use defmt::assert_eq;
+
+    use super::*;
+
+    #[init]
+    fn init() {}
+
+    #[test]
+    fn test_parse_default_table() {
+        let table = PartitionTable::parse(&DEFAULT_TABLE).unwrap();
+
+        assert_eq!(table.len(), 5);
+
+        let nvs = table
+            .find(PartitionType::Data(DataPartitionSubType::Nvs))
+            .unwrap();
+        assert_eq!(nvs.label_as_str(), "nvs");
+        assert_eq!(nvs.offset(), 0x9000);
+        assert_eq!(nvs.size(), 0x4000);
+
+        let ota_1 = table.find_by_label("ota_1").unwrap();
+        assert_eq!(
+            ota_1.partition_type(),
+            PartitionType::App(AppPartitionSubType::Ota(1))
+        );
+        assert_eq!(ota_1.offset(), 0x110000);
+        assert_eq!(ota_1.size(), 0x100000);
+        assert!(!ota_1.is_encrypted());
+
+        assert!(table.find_by_label("factory").is_none());
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_md5() {
+        let mut corrupted = DEFAULT_TABLE;
+        corrupted[4] = 0xA0;
+
+        assert_eq!(
+            PartitionTable::parse(&corrupted).err(),
+            Some(Error::InvalidMd5)
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_magic() {
+        let mut corrupted = DEFAULT_TABLE;
+        corrupted[32] = 0x00;
+
+        assert_eq!(
+            PartitionTable::parse(&corrupted).err(),
+            Some(Error::InvalidPartitionTable)
+        );
+    }
+
+    #[test]
+    fn test_otadata_entry() {
+        let entry = OtaSelectEntry::new(1, OtaImageState::Undefined);
+        let bytes = entry.to_bytes();
+
+        assert_eq!(bytes[0..4], [0x01, 0x00, 0x00, 0x00]);
+        assert_eq!(bytes[24..28], [0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(bytes[28..32], 0x4743989au32.to_le_bytes());
+
+        let decoded = OtaSelectEntry::from_bytes(&bytes);
+        assert!(decoded.is_valid());
+        assert_eq!(decoded.slot(2), 0);
+
+        assert!(!OtaSelectEntry::from_bytes(&[0xFF; 32]).is_valid());
+    }
+
+    #[test]
+    fn test_next_sequence() {
+        assert_eq!(next_sequence(0, 0, 2), 1);
+        assert_eq!(next_sequence(0, 1, 2), 2);
+        assert_eq!(next_sequence(1, 1, 2), 2);
+        assert_eq!(next_sequence(1, 0, 2), 3);
+        assert_eq!(next_sequence(2, 1, 2), 4);
+        assert_eq!(next_sequence(7, 2, 3), 9);
+    }
+
+    #[test]
+    fn test_ota_update() {
+        let table = PartitionTable::parse(&OTA_TABLE).unwrap();
+        let mut flash = RamFlash::new();
+        let mut ota = Ota::new(&mut flash, &table).unwrap();
+
+        assert_eq!(ota.slot_count(), 2);
+        assert_eq!(ota.current_slot().unwrap(), None);
+        // Without a factory partition the bootloader runs `ota_0`
+        assert_eq!(ota.next_slot().unwrap(), 1);
+
+        let image = [0x5Au8; 0x1802];
+        let mut writer = ota.begin_update().unwrap();
+        assert_eq!(writer.slot(), 1);
+        for chunk in image.chunks(100) {
+            writer.write(chunk).unwrap();
+        }
+        writer.finish().unwrap();
+
+        assert_eq!(ota.current_slot().unwrap(), Some(1));
+        assert_eq!(ota.next_slot().unwrap(), 0);
+
+        let mut writer = ota.begin_update().unwrap();
+        writer.write(&[0xA5; 0x10]).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(ota.current_slot().unwrap(), Some(0));
+
+        ota.set_current_state(OtaImageState::Valid).unwrap();
+        assert_eq!(ota.current_state().unwrap(), Some(OtaImageState::Valid));
+        assert_eq!(ota.current_slot().unwrap(), Some(0));
+
+        let entries = ota.read_entries().unwrap();
+        assert_eq!(entries[0].seq(), 2);
+        assert_eq!(entries[1].seq(), 3);
+        assert_eq!(entries[1].state(), OtaImageState::Valid);
+
+        assert_eq!(flash.data[0x4000..0x5802], image[..]);
+        assert_eq!(flash.data[0x5802..0x5804], [0xFF, 0xFF]);
+        assert_eq!(flash.data[0x2000..0x2010], [0xA5; 0x10]);
+    }
+
+    #[test]
+    fn test_ota_state_of_first_sector() {
+        let table = PartitionTable::parse(&OTA_TABLE).unwrap();
+        let mut flash = RamFlash::new();
+        let mut ota = Ota::new(&mut flash, &table).unwrap();
+
+        // The first selection is written to sector 0, which stays active
+        ota.set_current_slot_with_state(1, OtaImageState::New)
+            .unwrap();
+        ota.set_current_state(OtaImageState::Valid).unwrap();
+
+        assert_eq!(ota.current_slot().unwrap(), Some(1));
+        assert_eq!(ota.current_state().unwrap(), Some(OtaImageState::Valid));
+
+        let entries = ota.read_entries().unwrap();
+        assert_eq!(entries[0].seq(), 2);
+        assert_eq!(entries[0].state(), OtaImageState::Valid);
+        assert!(!entries[1].is_valid());
+    }
+
+    #[test]
+    fn test_ota_rollback() {
+        let table = PartitionTable::parse(&OTA_TABLE).unwrap();
+        let mut flash = RamFlash::new();
+        let mut ota = Ota::new(&mut flash, &table).unwrap();
+
+        ota.set_current_slot_with_state(1, OtaImageState::Valid)
+            .unwrap();
+        ota.set_current_slot_with_state(0, OtaImageState::PendingVerify)
+            .unwrap();
+        assert_eq!(ota.current_slot().unwrap(), Some(0));
+
+        // The new image failed, the previous selection is booted again
+        ota.set_current_state(OtaImageState::Invalid).unwrap();
+        assert_eq!(ota.current_slot().unwrap(), Some(1));
+        assert_eq!(ota.current_state().unwrap(), Some(OtaImageState::Valid));
+
+        assert!(!OtaSelectEntry::new(3, OtaImageState::Aborted).is_valid());
+        assert!(!OtaSelectEntry::new(3, OtaImageState::Invalid).is_valid());
+        assert!(OtaSelectEntry::new(3, OtaImageState::New).is_valid());
+    }
+
+    #[test]
+    fn test_ota_image_too_large() {
+        let table = PartitionTable::parse(&OTA_TABLE).unwrap();
+        let mut flash = RamFlash::new();
+        let mut ota = Ota::new(&mut flash, &table).unwrap();
+
+        let mut writer = ota.begin_update().unwrap();
+        writer.write(&[0u8; 0x1000]).unwrap();
+        writer.write(&[0u8; 0x1000]).unwrap();
+        assert_eq!(writer.write(&[0u8; 1]).err(), Some(Error::ImageTooLarge));
+    }
+}
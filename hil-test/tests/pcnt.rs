@@ -0,0 +1,245 @@
+//! PCNT Test
+//!
+//! Following pins are used:
+//! GPIO2
+//! GPIO4
+//! GPIO5
+//!
+//! GPIO4 drives the pulses counted on GPIO2. The quadrature tests read the B
+//! signal from GPIO5, which is driven by an open-drain output.
+
+//% CHIPS: esp32 esp32c6 esp32h2 esp32s2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::ClockControl,
+    delay::Delay,
+    embassy,
+    gpio::{AnyOutputOpenDrain, Gpio2, Gpio4, Gpio5, GpioPin, Io, Level, Output, Pull},
+    pcnt::{
+        channel::{self, PcntInputConfig, PcntSource},
+        unit::{self, QuadratureMode},
+        Pcnt,
+    },
+    peripherals::Peripherals,
+    system::SystemControl,
+    timer::timg::TimerGroup,
+};
+
+struct Context<'d> {
+    pcnt: Pcnt<'d>,
+    input: Gpio2,
+    output: Output<'d, Gpio4>,
+    b: Gpio5,
+    delay: Delay,
+}
+
+impl<'d> Context<'d> {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+        let timg0 = TimerGroup::new_async(peripherals.TIMG0, &clocks);
+        embassy::init(&clocks, timg0);
+
+        Context {
+            pcnt: Pcnt::new_with_driver_handler(peripherals.PCNT),
+            input: io.pins.gpio2,
+            output: Output::new(io.pins.gpio4, Level::Low),
+            b: io.pins.gpio5,
+            delay: Delay::new(&clocks),
+        }
+    }
+
+    /// Count the rising edges of the input
+    fn counting_unit(&mut self, config: unit::Config) -> unit::Unit {
+        let mut unit = self.pcnt.get_unit(unit::Number::Unit0);
+        unit.configure(config).unwrap();
+
+        unit.get_channel(channel::Number::Channel0).configure(
+            PcntSource::always_high(),
+            PcntSource::from_pin(&mut self.input, PcntInputConfig { pull: Pull::Down }),
+            channel::Config {
+                lctrl_mode: channel::CtrlMode::Keep,
+                hctrl_mode: channel::CtrlMode::Keep,
+                pos_edge: channel::EdgeMode::Increment,
+                neg_edge: channel::EdgeMode::Hold,
+                invert_ctrl: false,
+                invert_sig: false,
+            },
+        );
+
+        unit
+    }
+
+    /// Decode `cycles` cycles of quadrature signals, A leading B if `forward`
+    /// and B leading A otherwise, and return the counter value
+    fn decode_quadrature(&mut self, mode: QuadratureMode, cycles: usize, forward: bool) -> i16 {
+        let mut unit = self.pcnt.get_unit(unit::Number::Unit0);
+        unit.configure(unit::Config {
+            low_limit: -100,
+            high_limit: 100,
+            ..Default::default()
+        })
+        .unwrap();
+
+        unit.configure_quadrature(
+            PcntSource::from_pin(&mut self.input, PcntInputConfig { pull: Pull::Down }),
+            PcntSource::from_pin(
+                unsafe { GpioPin::<5>::steal() },
+                PcntInputConfig { pull: Pull::Up },
+            ),
+            mode,
+        );
+        // configured after the PCNT input, which disables the output
+        let mut b = AnyOutputOpenDrain::new(&mut self.b, Level::Low, Pull::Up);
+        unit.resume();
+
+        let states = if forward {
+            [
+                (Level::High, Level::Low),
+                (Level::High, Level::High),
+                (Level::Low, Level::High),
+                (Level::Low, Level::Low),
+            ]
+        } else {
+            [
+                (Level::Low, Level::High),
+                (Level::High, Level::High),
+                (Level::High, Level::Low),
+                (Level::Low, Level::Low),
+            ]
+        };
+
+        for _ in 0..cycles {
+            for (a_level, b_level) in states {
+                self.output.set_level(a_level);
+                b.set_level(b_level);
+                self.delay.delay_micros(100);
+            }
+        }
+
+        unit.get_value()
+    }
+
+    fn pulse(&mut self, count: usize) {
+        for _ in 0..count {
+            self.output.set_high();
+            self.delay.delay_micros(100);
+            self.output.set_low();
+            self.delay.delay_micros(100);
+        }
+    }
+}
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal::embassy::executor::Executor::new())]
+mod tests {
+    use defmt::assert_eq;
+    use embassy_time::{Duration, Timer};
+
+    use super::*;
+
+    #[init]
+    fn init() -> Context<'static> {
+        Context::init()
+    }
+
+    #[test]
+    fn test_overflow_accumulation(mut ctx: Context<'static>) {
+        let unit = ctx.counting_unit(unit::Config {
+            low_limit: -4,
+            high_limit: 4,
+            ..Default::default()
+        });
+        unit.enable_overflow_accumulation();
+        unit.resume();
+
+        ctx.pulse(10);
+
+        assert_eq!(unit.get_value(), 2);
+        assert_eq!(unit.get_count(), 10);
+
+        unit.clear();
+        assert_eq!(unit.get_count(), 0);
+    }
+
+    #[test]
+    fn test_quadrature_x1(mut ctx: Context<'static>) {
+        let mut unit = ctx.pcnt.get_unit(unit::Number::Unit0);
+        unit.configure(unit::Config {
+            low_limit: -100,
+            high_limit: 100,
+            ..Default::default()
+        })
+        .unwrap();
+
+        // B staying low, A leads B on every rising edge of A
+        unit.configure_quadrature(
+            PcntSource::from_pin(&mut ctx.input, PcntInputConfig { pull: Pull::Down }),
+            PcntSource::always_low(),
+            QuadratureMode::X1,
+        );
+        unit.resume();
+
+        ctx.pulse(5);
+
+        assert_eq!(unit.get_value(), 5);
+    }
+
+    #[test]
+    fn test_quadrature_x2(mut ctx: Context<'static>) {
+        assert_eq!(ctx.decode_quadrature(QuadratureMode::X2, 3, true), 6);
+    }
+
+    #[test]
+    fn test_quadrature_x4(mut ctx: Context<'static>) {
+        assert_eq!(ctx.decode_quadrature(QuadratureMode::X4, 3, true), 12);
+    }
+
+    #[test]
+    fn test_quadrature_reverse(mut ctx: Context<'static>) {
+        assert_eq!(ctx.decode_quadrature(QuadratureMode::X1, 3, false), -3);
+        assert_eq!(ctx.decode_quadrature(QuadratureMode::X2, 3, false), -6);
+        assert_eq!(ctx.decode_quadrature(QuadratureMode::X4, 3, false), -12);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_wait_for_threshold(mut ctx: Context<'static>) {
+        let mut unit = ctx.counting_unit(unit::Config {
+            low_limit: -100,
+            high_limit: 100,
+            thresh0: 3,
+            ..Default::default()
+        });
+        unit.resume();
+
+        let Context { mut output, .. } = ctx;
+
+        let (events, _) = embassy_futures::join::join(
+            unit.wait_for_event_async(unit::Events {
+                thresh0: true,
+                ..Default::default()
+            }),
+            async {
+                for _ in 0..5 {
+                    output.set_high();
+                    Timer::after(Duration::from_millis(1)).await;
+                    output.set_low();
+                    Timer::after(Duration::from_millis(1)).await;
+                }
+            },
+        )
+        .await;
+
+        assert_eq!(events.thresh0, true);
+        assert_eq!(unit.get_value(), 5);
+    }
+}
@@ -0,0 +1,267 @@
+//! RMT Test
+//!
+//! Folowing pins are used:
+//! TX    GPIO2
+//! RX    GPIO4
+//!
+//! Connect TX (GPIO2) and RX (GPIO4) pins.
+//!
+//! The sync group test drives the wire from both pins as open-drain outputs.
+
+//% CHIPS: esp32c3 esp32c6 esp32h2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::ClockControl,
+    gpio::{Gpio2, Gpio4, GpioPin, Io},
+    peripherals::{Peripherals, GPIO, IO_MUX},
+    prelude::*,
+    rmt::{
+        Channel,
+        Error,
+        PulseCode,
+        Rmt,
+        RxChannel,
+        RxChannelConfig,
+        RxChannelCreator,
+        TxChannel,
+        TxChannelConfig,
+        TxChannelCreator,
+        TxSyncGroup,
+    },
+    system::SystemControl,
+    Blocking,
+};
+
+#[cfg(feature = "esp32s3")]
+type RxChannelType = Channel<Blocking, 4>;
+#[cfg(not(feature = "esp32s3"))]
+type RxChannelType = Channel<Blocking, 2>;
+
+struct Context {
+    rmt: Rmt<'static, Blocking>,
+    tx_pin: Gpio2,
+    rx_pin: Gpio4,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+        let rmt = Rmt::new(peripherals.RMT, 1.MHz(), &clocks, None).unwrap();
+
+        Context {
+            rmt,
+            tx_pin: io.pins.gpio2,
+            rx_pin: io.pins.gpio4,
+        }
+    }
+
+    /// Configure channel 0 for transmitting with two memory blocks and an RX
+    /// channel with a single one
+    fn loopback(self) -> (Channel<Blocking, 0>, RxChannelType) {
+        // two blocks hold the whole sequence, the receiver has to wrap around
+        let tx = self
+            .rmt
+            .channel0
+            .configure(
+                self.tx_pin,
+                TxChannelConfig {
+                    clk_divider: 1,
+                    idle_output: true,
+                    idle_output_level: false,
+                    memsize: 2,
+                    ..TxChannelConfig::default()
+                },
+            )
+            .unwrap();
+
+        let rx_config = RxChannelConfig {
+            clk_divider: 1,
+            idle_threshold: 1000,
+            ..RxChannelConfig::default()
+        };
+
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "esp32s3")] {
+                let rx = self.rmt.channel4.configure(self.rx_pin, rx_config).unwrap();
+            } else {
+                let rx = self.rmt.channel2.configure(self.rx_pin, rx_config).unwrap();
+            }
+        }
+
+        (tx, rx)
+    }
+}
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use defmt::{assert, assert_eq};
+
+    use super::*;
+
+    #[init]
+    fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_receive_exceeding_channel_ram(ctx: Context) {
+        let (tx, rx) = ctx.loopback();
+
+        let mut tx_data = [PulseCode {
+            level1: true,
+            length1: 10,
+            level2: false,
+            length2: 20,
+        }; 80];
+        tx_data[79].length2 = 0;
+
+        let mut rx_data = [PulseCode::default(); 100];
+
+        let rx_transaction = rx.receive(&mut rx_data).unwrap();
+        let tx_transaction = tx.transmit(&tx_data);
+
+        rx_transaction.wait().unwrap();
+        tx_transaction.wait().unwrap();
+
+        for code in &rx_data[..79] {
+            assert!(code.level1);
+            assert!(code.length1.abs_diff(10) <= 1);
+            assert!(!code.level2);
+            assert!(code.length2.abs_diff(20) <= 1);
+        }
+        assert_eq!(rx_data[79].length2, 0);
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_borrowed_channel_cannot_be_configured(ctx: Context) {
+        let config = TxChannelConfig {
+            memsize: 2,
+            ..TxChannelConfig::default()
+        };
+        let _tx = ctx.rmt.channel0.configure(ctx.tx_pin, config).unwrap();
+
+        // the memory block of channel 1 is borrowed by channel 0
+        let result = ctx
+            .rmt
+            .channel1
+            .configure(ctx.rx_pin, TxChannelConfig::default());
+        assert!(matches!(result, Err(Error::MemoryBlockNotAvailable)));
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_configured_channel_cannot_be_borrowed(ctx: Context) {
+        let _tx = ctx
+            .rmt
+            .channel1
+            .configure(ctx.rx_pin, TxChannelConfig::default())
+            .unwrap();
+
+        let config = TxChannelConfig {
+            memsize: 2,
+            ..TxChannelConfig::default()
+        };
+        let result = ctx.rmt.channel0.configure(ctx.tx_pin, config);
+        assert!(matches!(result, Err(Error::MemoryBlockNotAvailable)));
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_dropped_channel_releases_memory_block(ctx: Context) {
+        let tx = ctx
+            .rmt
+            .channel1
+            .configure(ctx.rx_pin, TxChannelConfig::default())
+            .unwrap();
+        drop(tx);
+
+        let config = TxChannelConfig {
+            memsize: 2,
+            ..TxChannelConfig::default()
+        };
+        assert!(ctx.rmt.channel0.configure(ctx.tx_pin, config).is_ok());
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_tx_sync_group(ctx: Context) {
+        // the RX channel reads the wire, which both TX channels drive
+        let rx_pin = unsafe { GpioPin::<4>::steal() };
+        let rx_config = RxChannelConfig {
+            clk_divider: 1,
+            idle_threshold: 1000,
+            ..RxChannelConfig::default()
+        };
+
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "esp32s3")] {
+                let rx = ctx.rmt.channel4.configure(rx_pin, rx_config).unwrap();
+            } else {
+                let rx = ctx.rmt.channel2.configure(rx_pin, rx_config).unwrap();
+            }
+        }
+
+        let tx_config = TxChannelConfig {
+            clk_divider: 1,
+            idle_output: true,
+            idle_output_level: true,
+            ..TxChannelConfig::default()
+        };
+        let tx0 = ctx.rmt.channel0.configure(ctx.tx_pin, tx_config).unwrap();
+        let tx1 = ctx.rmt.channel1.configure(ctx.rx_pin, tx_config).unwrap();
+
+        // turn both outputs into open-drain outputs with a pull-up, the wire
+        // is low while any of the channels sends a low level
+        let gpio = unsafe { &*GPIO::PTR };
+        let io_mux = unsafe { &*IO_MUX::PTR };
+        gpio.pin(2).modify(|_, w| w.pad_driver().set_bit());
+        gpio.pin(4).modify(|_, w| w.pad_driver().set_bit());
+        io_mux
+            .gpio(4)
+            .modify(|_, w| w.fun_ie().set_bit().fun_wpu().set_bit());
+
+        // channel 1 goes low right when channel 0 goes high again, so the wire
+        // is low for a single pulse only if both channels start on the same
+        // clock edge
+        let tx0_data = [PulseCode {
+            level1: false,
+            length1: 100,
+            level2: true,
+            length2: 0,
+        }];
+        let tx1_data = [
+            PulseCode {
+                level1: true,
+                length1: 100,
+                level2: false,
+                length2: 100,
+            },
+            PulseCode::default(),
+        ];
+
+        let mut rx_data = [PulseCode::default(); 4];
+        let rx_transaction = rx.receive(&mut rx_data).unwrap();
+
+        let group = TxSyncGroup::new((tx0, tx1));
+        let transaction = group.transmit(&[&tx0_data[..], &tx1_data[..]]).unwrap();
+        transaction.wait().unwrap();
+        rx_transaction.wait().unwrap();
+
+        assert!(!rx_data[0].level1);
+        assert!(rx_data[0].length1.abs_diff(200) <= 2);
+        assert_eq!(rx_data[0].length2, 0);
+    }
+}
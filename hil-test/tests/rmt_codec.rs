@@ -0,0 +1,365 @@
+//! RMT encoder and decoder tests
+//!
+//! Runs against reference waveforms, no pins are used.
+
+//% CHIPS: esp32 esp32c3 esp32c6 esp32h2 esp32s2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::{
+    prelude::*,
+    rmt::{
+        codec::{BytesEncoder, DecodeError, Decoder, Encoder},
+        ir::{
+            NecDecoder,
+            NecEncoder,
+            NecFrame,
+            Rc5Decoder,
+            Rc5Encoder,
+            Rc5Frame,
+            Rc6Decoder,
+            Rc6Encoder,
+            Rc6Frame,
+            SircDecoder,
+            SircEncoder,
+            SircFrame,
+            SircLength,
+        },
+        Error,
+        PulseCode,
+    },
+};
+
+// All waveforms use a resolution of 1 MHz, i.e. the durations are in
+// microseconds, starting with a mark
+
+// NEC, address 0x04, command 0x08
+const NEC_WAVEFORM: [u16; 67] = [
+    9000, 4500, //
+    560, 560, 560, 560, 560, 1690, 560, 560, 560, 560, 560, 560, 560, 560, 560, 560, //
+    560, 1690, 560, 1690, 560, 560, 560, 1690, 560, 1690, 560, 1690, 560, 1690, 560, 1690, //
+    560, 560, 560, 560, 560, 560, 560, 1690, 560, 560, 560, 560, 560, 560, 560, 560, //
+    560, 1690, 560, 1690, 560, 1690, 560, 560, 560, 1690, 560, 1690, 560, 1690, 560, 1690, //
+    560,
+];
+
+// NEC repeat code
+const NEC_REPEAT_WAVEFORM: [u16; 3] = [9000, 2250, 560];
+
+// RC5, address 5, command 35, toggle cleared
+const RC5_WAVEFORM: [u16; 21] = [
+    889, 889, 1778, 889, 889, 889, 889, 1778, 1778, 1778, 889, 889, 1778, 889, 889, 889, 889, 1778,
+    889, 889, 889,
+];
+
+// RC6 mode 0, address 0x00, command 0x0c, toggle cleared
+const RC6_WAVEFORM: [u16; 41] = [
+    2666, 888, //
+    444, 888, 444, 444, 444, 444, 444, 888, 888, //
+    444, 444, 444, 444, 444, 444, 444, 444, 444, 444, 444, 444, 444, 444, 444, 444, //
+    444, 444, 444, 444, 444, 444, 444, 888, 444, 444, 888, 444, 444, 444,
+];
+
+// Sony SIRC 12-bit, address 1, command 21
+const SIRC_WAVEFORM: [u16; 25] = [
+    2400, //
+    600, 1200, 600, 600, 600, 1200, 600, 600, 600, 1200, 600, 600, 600, 600, //
+    600, 1200, 600, 600, 600, 600, 600, 600, 600, 600,
+];
+
+/// Check that `encoder` produces `waveform`, with marks as high level
+fn assert_waveform(mut encoder: impl Encoder, waveform: &[u16]) {
+    let mut index = 0;
+    while let Some(code) = encoder.encode() {
+        for (level, length) in [(code.level1, code.length1), (code.level2, code.length2)] {
+            if length == 0 {
+                break;
+            }
+
+            defmt::assert!(index < waveform.len());
+            defmt::assert_eq!(level, index % 2 == 0);
+            defmt::assert_eq!(length, waveform[index]);
+            index += 1;
+        }
+    }
+
+    defmt::assert_eq!(index, waveform.len());
+}
+
+/// Pulse codes of `waveform` as received from an active-low IR receiver,
+/// with every other pulse 10% too long
+fn received<const N: usize>(waveform: &[u16]) -> [PulseCode; N] {
+    let mut codes = [PulseCode::default(); N];
+    for (idx, length) in waveform.iter().enumerate() {
+        let length = if idx % 2 == 0 {
+            *length
+        } else {
+            *length + *length / 10
+        };
+
+        let code = &mut codes[idx / 2];
+        if idx % 2 == 0 {
+            code.level1 = false;
+            code.length1 = length;
+        } else {
+            code.level2 = true;
+            code.length2 = length;
+        }
+    }
+
+    codes
+}
+
+/// Collect the pulse codes produced by `encoder`
+fn encoded<const N: usize>(mut encoder: impl Encoder) -> [PulseCode; N] {
+    let mut codes = [PulseCode::default(); N];
+    for code in codes.iter_mut() {
+        match encoder.encode() {
+            Some(c) => *code = c,
+            None => break,
+        }
+    }
+    defmt::assert!(encoder.encode().is_none());
+
+    codes
+}
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use defmt::assert_eq;
+
+    use super::*;
+
+    #[init]
+    fn init() {}
+
+    #[test]
+    fn test_nec_encoder() {
+        let frame = NecFrame::Command {
+            address: 0x04,
+            command: 0x08,
+        };
+        assert_waveform(NecEncoder::new(frame, 1.MHz()).unwrap(), &NEC_WAVEFORM);
+        assert_waveform(
+            NecEncoder::new(NecFrame::Repeat, 1.MHz()).unwrap(),
+            &NEC_REPEAT_WAVEFORM,
+        );
+    }
+
+    #[test]
+    fn test_nec_decoder() {
+        let decoder = NecDecoder::new(1.MHz()).unwrap();
+
+        assert_eq!(
+            decoder.decode(&received::<34>(&NEC_WAVEFORM)),
+            Ok(NecFrame::Command {
+                address: 0x04,
+                command: 0x08,
+            })
+        );
+        assert_eq!(
+            decoder.decode(&received::<2>(&NEC_REPEAT_WAVEFORM)),
+            Ok(NecFrame::Repeat)
+        );
+        assert_eq!(
+            decoder.decode(&received::<34>(&NEC_WAVEFORM[..40])),
+            Err(DecodeError::Incomplete)
+        );
+        assert_eq!(
+            decoder.decode(&received::<34>(&NEC_WAVEFORM[2..])),
+            Err(DecodeError::InvalidLeader)
+        );
+
+        let mut corrupted = NEC_WAVEFORM;
+        corrupted[65] = 560;
+        assert_eq!(
+            decoder.decode(&received::<34>(&corrupted)),
+            Err(DecodeError::Checksum)
+        );
+    }
+
+    #[test]
+    fn test_nec_extended_address() {
+        let frame = NecFrame::Command {
+            address: 0x1234,
+            command: 0x56,
+        };
+        let codes = encoded::<34>(NecEncoder::new(frame, 1.MHz()).unwrap());
+
+        assert_eq!(NecDecoder::new(1.MHz()).unwrap().decode(&codes), Ok(frame));
+    }
+
+    #[test]
+    fn test_nec_resolution_too_high() {
+        // the 9 ms leader takes 36000 ticks
+        let frame = NecFrame::Repeat;
+        assert!(NecEncoder::new(frame, 4.MHz()).is_err());
+        assert!(NecDecoder::new(4.MHz()).is_err());
+        assert!(NecEncoder::new(frame, 3.MHz()).is_ok());
+    }
+
+    #[test]
+    fn test_rc5_encoder() {
+        let frame = Rc5Frame {
+            address: 5,
+            command: 35,
+            toggle: false,
+        };
+        assert_waveform(Rc5Encoder::new(frame, 1.MHz()).unwrap(), &RC5_WAVEFORM);
+    }
+
+    #[test]
+    fn test_rc5_decoder() {
+        let decoder = Rc5Decoder::new(1.MHz()).unwrap();
+
+        assert_eq!(
+            decoder.decode(&received::<11>(&RC5_WAVEFORM)),
+            Ok(Rc5Frame {
+                address: 5,
+                command: 35,
+                toggle: false,
+            })
+        );
+        assert_eq!(
+            decoder.decode(&received::<11>(&RC5_WAVEFORM[..15])),
+            Err(DecodeError::Incomplete)
+        );
+
+        // extended command with a trailing zero, whose space merges with the
+        // idle level
+        let frame = Rc5Frame {
+            address: 0x1f,
+            command: 0x7e,
+            toggle: true,
+        };
+        let codes = encoded::<14>(Rc5Encoder::new(frame, 1.MHz()).unwrap());
+
+        assert_eq!(decoder.decode(&codes), Ok(frame));
+    }
+
+    #[test]
+    fn test_rc5_merged_half_bits_too_long() {
+        // a half bit fits into a pulse code, but two merged ones don't
+        let frame = Rc5Frame {
+            address: 5,
+            command: 35,
+            toggle: false,
+        };
+        assert!(Rc5Encoder::new(frame, 30.MHz()).is_err());
+        assert!(Rc5Decoder::new(30.MHz()).is_err());
+    }
+
+    #[test]
+    fn test_rc6_encoder() {
+        let frame = Rc6Frame {
+            address: 0x00,
+            command: 0x0c,
+            toggle: false,
+        };
+        assert_waveform(Rc6Encoder::new(frame, 1.MHz()).unwrap(), &RC6_WAVEFORM);
+    }
+
+    #[test]
+    fn test_rc6_decoder() {
+        let decoder = Rc6Decoder::new(1.MHz()).unwrap();
+
+        assert_eq!(
+            decoder.decode(&received::<21>(&RC6_WAVEFORM)),
+            Ok(Rc6Frame {
+                address: 0x00,
+                command: 0x0c,
+                toggle: false,
+            })
+        );
+
+        let frame = Rc6Frame {
+            address: 0xa5,
+            command: 0xff,
+            toggle: true,
+        };
+        let codes = encoded::<23>(Rc6Encoder::new(frame, 1.MHz()).unwrap());
+
+        assert_eq!(decoder.decode(&codes), Ok(frame));
+    }
+
+    #[test]
+    fn test_sirc_encoder() {
+        let frame = SircFrame {
+            command: 21,
+            address: 1,
+            length: SircLength::Bits12,
+        };
+        assert_waveform(SircEncoder::new(frame, 1.MHz()).unwrap(), &SIRC_WAVEFORM);
+    }
+
+    #[test]
+    fn test_sirc_decoder() {
+        let decoder = SircDecoder::new(1.MHz()).unwrap();
+
+        assert_eq!(
+            decoder.decode(&received::<13>(&SIRC_WAVEFORM)),
+            Ok(SircFrame {
+                command: 21,
+                address: 1,
+                length: SircLength::Bits12,
+            })
+        );
+        assert_eq!(
+            decoder.decode(&received::<13>(&SIRC_WAVEFORM[..21])),
+            Err(DecodeError::Incomplete)
+        );
+
+        let frame = SircFrame {
+            command: 0x55,
+            address: 0x1abc,
+            length: SircLength::Bits20,
+        };
+        let codes = encoded::<21>(SircEncoder::new(frame, 1.MHz()).unwrap());
+
+        assert_eq!(decoder.decode(&codes), Ok(frame));
+    }
+
+    #[test]
+    fn test_sirc_leader_too_long() {
+        // the unit fits into a pulse code, the 4 unit leader doesn't
+        let frame = SircFrame {
+            command: 21,
+            address: 1,
+            length: SircLength::Bits12,
+        };
+        assert!(SircEncoder::new(frame, 20.MHz()).is_err());
+        assert!(SircDecoder::new(20.MHz()).is_err());
+    }
+
+    #[test]
+    fn test_ws2812_encoder() {
+        let mut encoder = BytesEncoder::ws2812(&[0x81, 0x00], 80.MHz()).unwrap();
+
+        for bit in 0..16 {
+            let code = encoder.encode().unwrap();
+            assert!(code.level1);
+            assert!(!code.level2);
+
+            if bit == 0 || bit == 7 {
+                assert_eq!((code.length1, code.length2), (64, 36));
+            } else {
+                assert_eq!((code.length1, code.length2), (32, 68));
+            }
+        }
+
+        assert!(encoder.encode().is_none());
+    }
+
+    #[test]
+    fn test_ws2812_pulse_too_short() {
+        // the 400 ns of a short pulse are less than a tick at 1 MHz
+        assert_eq!(
+            BytesEncoder::ws2812(&[0x81], 1.MHz()).err(),
+            Some(Error::InvalidArgument)
+        );
+    }
+}
@@ -0,0 +1,252 @@
+//! SPI octal half-duplex Test
+//!
+//! Folowing pins are used:
+//! SIO4/SIO7   GPIO2
+//! MIRROR      GPIO4
+//!
+//! Only one data line is connected at a time. The read tests drive it from
+//! GPIO4, the write tests count its rising edges on GPIO4 with the PCNT. In
+//! octal mode every byte is sent in a single clock cycle, bit n on SIOn.
+//!
+//! Connect GPIO2 and GPIO4 pins.
+
+//% CHIPS: esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::{ClockControl, Clocks},
+    dma::{Dma, DmaPriority},
+    dma_buffers,
+    gpio::{Gpio2, Gpio4, Io, Level, Output, Pull},
+    pcnt::{
+        channel::{self, PcntInputConfig, PcntSource},
+        unit,
+        Pcnt,
+    },
+    peripherals::{Peripherals, DMA, PCNT, SPI2},
+    prelude::*,
+    spi::{
+        master::{prelude::*, Address, Command, HalfDuplexReadWrite, Spi},
+        SpiDataMode,
+        SpiMode,
+    },
+    system::SystemControl,
+};
+
+struct Context {
+    spi: SPI2,
+    dma: DMA,
+    pcnt: PCNT,
+    sio: Gpio2,
+    mirror: Gpio4,
+    clocks: Clocks<'static>,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+        Context {
+            spi: peripherals.SPI2,
+            dma: peripherals.DMA,
+            pcnt: peripherals.PCNT,
+            sio: io.pins.gpio2,
+            mirror: io.pins.gpio4,
+            clocks,
+        }
+    }
+}
+
+/// Count the rising edges on GPIO4
+fn counting_unit(pcnt: &Pcnt<'_>, mirror: Gpio4) -> unit::Unit {
+    let mut unit = pcnt.get_unit(unit::Number::Unit0);
+    unit.configure(unit::Config {
+        low_limit: -100,
+        high_limit: 100,
+        ..Default::default()
+    })
+    .unwrap();
+
+    unit.get_channel(channel::Number::Channel0).configure(
+        PcntSource::always_high(),
+        PcntSource::from_pin(mirror, PcntInputConfig { pull: Pull::Down }),
+        channel::Config {
+            lctrl_mode: channel::CtrlMode::Keep,
+            hctrl_mode: channel::CtrlMode::Keep,
+            pos_edge: channel::EdgeMode::Increment,
+            neg_edge: channel::EdgeMode::Hold,
+            invert_ctrl: false,
+            invert_sig: false,
+        },
+    );
+    unit.resume();
+
+    unit
+}
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use defmt::assert_eq;
+
+    use super::*;
+
+    #[init]
+    fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_octal_read(ctx: Context) {
+        let mut mirror = Output::new(ctx.mirror, Level::Low);
+        let mut spi = Spi::new_half_duplex(ctx.spi, 100.kHz(), SpiMode::Mode0, &ctx.clocks)
+            .with_sio4(ctx.sio);
+
+        let mut buffer = [0u8; 4];
+        spi.read(
+            SpiDataMode::Octal,
+            Command::None,
+            Address::None,
+            0,
+            &mut buffer,
+        )
+        .unwrap();
+        for byte in buffer {
+            assert_eq!(byte & 1 << 4, 0);
+        }
+
+        mirror.set_high();
+        spi.read(
+            SpiDataMode::Octal,
+            Command::None,
+            Address::None,
+            0,
+            &mut buffer,
+        )
+        .unwrap();
+        for byte in buffer {
+            assert_eq!(byte & 1 << 4, 1 << 4);
+        }
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_octal_write(ctx: Context) {
+        let mut spi = Spi::new_half_duplex(ctx.spi, 100.kHz(), SpiMode::Mode0, &ctx.clocks)
+            .with_sio4(ctx.sio);
+        let pcnt = Pcnt::new(ctx.pcnt, None);
+        let unit = counting_unit(&pcnt, ctx.mirror);
+
+        // SIO4 only toggles with bit 4
+        spi.write(
+            SpiDataMode::Octal,
+            Command::None,
+            Address::None,
+            0,
+            &[1 << 4, 0, 1 << 4, 0, 1 << 4, 0],
+        )
+        .unwrap();
+        assert_eq!(unit.get_value(), 3);
+
+        spi.write(
+            SpiDataMode::Octal,
+            Command::None,
+            Address::None,
+            0,
+            &[!(1 << 4), 0, !(1 << 4), 0],
+        )
+        .unwrap();
+        assert_eq!(unit.get_value(), 3);
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_octal_read_dma(ctx: Context) {
+        let dma = Dma::new(ctx.dma);
+        let (_, mut tx_descriptors, rx_buffer, mut rx_descriptors) = dma_buffers!(4, 4);
+
+        let mut mirror = Output::new(ctx.mirror, Level::Low);
+        let mut spi = Spi::new_half_duplex(ctx.spi, 100.kHz(), SpiMode::Mode0, &ctx.clocks)
+            .with_sio7(ctx.sio)
+            .with_dma(dma.channel0.configure(
+                false,
+                &mut tx_descriptors,
+                &mut rx_descriptors,
+                DmaPriority::Priority0,
+            ));
+
+        let mut receive = rx_buffer;
+
+        let transfer = spi
+            .read(
+                SpiDataMode::Octal,
+                Command::None,
+                Address::None,
+                0,
+                &mut receive,
+            )
+            .unwrap();
+        transfer.wait().unwrap();
+        for byte in receive.iter() {
+            assert_eq!(byte & 1 << 7, 0);
+        }
+
+        mirror.set_high();
+        let transfer = spi
+            .read(
+                SpiDataMode::Octal,
+                Command::None,
+                Address::None,
+                0,
+                &mut receive,
+            )
+            .unwrap();
+        transfer.wait().unwrap();
+        for byte in receive.iter() {
+            assert_eq!(byte & 1 << 7, 1 << 7);
+        }
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_octal_write_dma(ctx: Context) {
+        let dma = Dma::new(ctx.dma);
+        let (tx_buffer, mut tx_descriptors, _, mut rx_descriptors) = dma_buffers!(6, 4);
+
+        let mut spi = Spi::new_half_duplex(ctx.spi, 100.kHz(), SpiMode::Mode0, &ctx.clocks)
+            .with_sio7(ctx.sio)
+            .with_dma(dma.channel0.configure(
+                false,
+                &mut tx_descriptors,
+                &mut rx_descriptors,
+                DmaPriority::Priority0,
+            ));
+        let pcnt = Pcnt::new(ctx.pcnt, None);
+        let unit = counting_unit(&pcnt, ctx.mirror);
+
+        let mut send = tx_buffer;
+
+        // SIO7 only toggles with bit 7
+        send.copy_from_slice(&[1 << 7, 0, 1 << 7, 0, 1 << 7, 0]);
+        let transfer = spi
+            .write(SpiDataMode::Octal, Command::None, Address::None, 0, &send)
+            .unwrap();
+        transfer.wait().unwrap();
+        assert_eq!(unit.get_value(), 3);
+
+        send.copy_from_slice(&[!(1 << 7), 0, !(1 << 7), 0, !(1 << 7), 0]);
+        let transfer = spi
+            .write(SpiDataMode::Octal, Command::None, Address::None, 0, &send)
+            .unwrap();
+        transfer.wait().unwrap();
+        assert_eq!(unit.get_value(), 3);
+    }
+}
@@ -0,0 +1,264 @@
+//! Shared SPI bus Test
+//!
+//! Every test routes the bus differently, GPIO4 always reads back what is
+//! driven on GPIO2:
+//! - the bit order and frequency tests loop MOSI (GPIO2) back to MISO (GPIO4)
+//! - the mode test reads the idle level of SCLK (GPIO2) on GPIO4
+//! - the CS tests sample the CS line on GPIO2 through MISO (GPIO4)
+//!
+//! Connect GPIO2 and GPIO4 pins.
+
+//% CHIPS: esp32 esp32c2 esp32c3 esp32c6 esp32h2 esp32s2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+use defmt_rtt as _;
+use embedded_hal::spi::{Operation, SpiDevice};
+use esp_backtrace as _;
+use esp_hal::{
+    clock::{ClockControl, Clocks},
+    gpio::{Gpio0, Gpio2, Gpio4, Gpio5, Input, Io, Level, Output, Pull},
+    peripherals::{Peripherals, SPI2},
+    prelude::*,
+    spi::{
+        master::Spi,
+        shared::{CriticalSectionDevice, DeviceConfig, HardwareCs},
+        FullDuplexMode,
+        SpiBitOrder,
+        SpiMode,
+    },
+    system::SystemControl,
+};
+
+type Bus = Mutex<RefCell<Spi<'static, SPI2, FullDuplexMode>>>;
+
+struct Context {
+    spi: SPI2,
+    gpio0: Gpio0,
+    gpio2: Gpio2,
+    gpio4: Gpio4,
+    gpio5: Gpio5,
+    clocks: Clocks<'static>,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+        Context {
+            spi: peripherals.SPI2,
+            gpio0: io.pins.gpio0,
+            gpio2: io.pins.gpio2,
+            gpio4: io.pins.gpio4,
+            gpio5: io.pins.gpio5,
+            clocks,
+        }
+    }
+}
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use defmt::assert_eq;
+
+    use super::*;
+
+    #[init]
+    fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_devices_use_their_own_bit_order(ctx: Context) {
+        let spi = Spi::new(ctx.spi, 1.MHz(), SpiMode::Mode0, &ctx.clocks)
+            .with_sck(ctx.gpio0)
+            .with_mosi(ctx.gpio2)
+            .with_miso(ctx.gpio4);
+        let bus: Bus = Mutex::new(RefCell::new(spi));
+
+        let mut msb_first = CriticalSectionDevice::new(
+            &bus,
+            HardwareCs::Cs0,
+            DeviceConfig::new(1.MHz(), SpiMode::Mode0),
+            &ctx.clocks,
+        );
+        // sends LSB first but receives MSB first, so the loopback mirrors the
+        // bits of each byte
+        let mut mirrored = CriticalSectionDevice::new(
+            &bus,
+            Output::new(ctx.gpio5, Level::High),
+            DeviceConfig::new(1.MHz(), SpiMode::Mode0)
+                .with_bit_order(SpiBitOrder::MSBFirst, SpiBitOrder::LSBFirst),
+            &ctx.clocks,
+        );
+
+        let write = [0x01, 0x80, 0xf0, 0x5a];
+        let mut read = [0u8; 4];
+
+        msb_first.transfer(&mut read, &write).unwrap();
+        assert_eq!(read, write);
+
+        mirrored.transfer(&mut read, &write).unwrap();
+        assert_eq!(read, [0x80, 0x01, 0x0f, 0x5a]);
+
+        msb_first.transfer(&mut read, &write).unwrap();
+        assert_eq!(read, write);
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_devices_use_their_own_mode(ctx: Context) {
+        let sclk = Input::new(ctx.gpio4, Pull::Down);
+        let spi = Spi::new(ctx.spi, 1.MHz(), SpiMode::Mode0, &ctx.clocks)
+            .with_sck(ctx.gpio2)
+            .with_mosi(ctx.gpio0);
+        let bus: Bus = Mutex::new(RefCell::new(spi));
+
+        let mut mode0 = CriticalSectionDevice::new(
+            &bus,
+            HardwareCs::Cs1,
+            DeviceConfig::new(1.MHz(), SpiMode::Mode0),
+            &ctx.clocks,
+        );
+        let mut mode3 = CriticalSectionDevice::new(
+            &bus,
+            HardwareCs::Cs2,
+            DeviceConfig::new(1.MHz(), SpiMode::Mode3),
+            &ctx.clocks,
+        );
+
+        // SCLK idles low in mode 0 and high in mode 3
+        mode3.write(&[0xaa]).unwrap();
+        assert!(sclk.is_high());
+
+        mode0.write(&[0xaa]).unwrap();
+        assert!(sclk.is_low());
+
+        mode3.write(&[0xaa]).unwrap();
+        assert!(sclk.is_high());
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_devices_use_their_own_frequency(ctx: Context) {
+        let spi = Spi::new(ctx.spi, 1.MHz(), SpiMode::Mode0, &ctx.clocks)
+            .with_sck(ctx.gpio0)
+            .with_mosi(ctx.gpio2)
+            .with_miso(ctx.gpio4);
+        let bus: Bus = Mutex::new(RefCell::new(spi));
+
+        let mut fast = CriticalSectionDevice::new(
+            &bus,
+            HardwareCs::Cs0,
+            DeviceConfig::new(1.MHz(), SpiMode::Mode0),
+            &ctx.clocks,
+        );
+        let mut slow = CriticalSectionDevice::new(
+            &bus,
+            Output::new(ctx.gpio5, Level::High),
+            DeviceConfig::new(100.kHz(), SpiMode::Mode0),
+            &ctx.clocks,
+        );
+
+        // 512 bits take 5.12 ms at 100 kHz and 0.512 ms at 1 MHz
+        let write = [0x55u8; 64];
+
+        let t1 = esp_hal::time::current_time();
+        slow.write(&write).unwrap();
+        let t2 = esp_hal::time::current_time();
+        fast.write(&write).unwrap();
+        let t3 = esp_hal::time::current_time();
+
+        assert!((t2 - t1).to_micros() >= 5_000u64);
+        assert!((t3 - t2).to_micros() < 2_000u64);
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_devices_select_their_hardware_cs_line(ctx: Context) {
+        // only CS0 is read back, the other lines don't need to be observed
+        let spi = Spi::new(ctx.spi, 1.MHz(), SpiMode::Mode0, &ctx.clocks)
+            .with_miso(ctx.gpio4)
+            .with_cs(ctx.gpio2)
+            .with_cs1(ctx.gpio5)
+            .with_cs2(ctx.gpio0);
+        let bus: Bus = Mutex::new(RefCell::new(spi));
+
+        let mut cs0 = CriticalSectionDevice::new(
+            &bus,
+            HardwareCs::Cs0,
+            DeviceConfig::new(1.MHz(), SpiMode::Mode0),
+            &ctx.clocks,
+        );
+        let mut cs1 = CriticalSectionDevice::new(
+            &bus,
+            HardwareCs::Cs1,
+            DeviceConfig::new(1.MHz(), SpiMode::Mode0),
+            &ctx.clocks,
+        );
+        let mut cs2 = CriticalSectionDevice::new(
+            &bus,
+            HardwareCs::Cs2,
+            DeviceConfig::new(1.MHz(), SpiMode::Mode0),
+            &ctx.clocks,
+        );
+
+        let mut read = [0x55u8; 4];
+
+        cs1.read(&mut read).unwrap();
+        assert_eq!(read, [0xff; 4]);
+
+        cs0.read(&mut read).unwrap();
+        assert_eq!(read, [0x00; 4]);
+
+        cs2.read(&mut read).unwrap();
+        assert_eq!(read, [0xff; 4]);
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_gpio_cs_stays_asserted_during_transaction(ctx: Context) {
+        let spi = Spi::new(ctx.spi, 1.MHz(), SpiMode::Mode0, &ctx.clocks)
+            .with_sck(ctx.gpio0)
+            .with_miso(ctx.gpio4);
+        let bus: Bus = Mutex::new(RefCell::new(spi));
+
+        let mut gpio_cs = CriticalSectionDevice::new(
+            &bus,
+            Output::new(ctx.gpio2, Level::High),
+            DeviceConfig::new(1.MHz(), SpiMode::Mode0),
+            &ctx.clocks,
+        );
+        let mut other = CriticalSectionDevice::new(
+            &bus,
+            HardwareCs::Cs1,
+            DeviceConfig::new(1.MHz(), SpiMode::Mode0),
+            &ctx.clocks,
+        );
+
+        let mut first = [0x55u8; 4];
+        let mut second = [0x55u8; 4];
+        gpio_cs
+            .transaction(&mut [
+                Operation::Read(&mut first),
+                Operation::Write(&[0xaa; 4]),
+                Operation::DelayNs(10_000),
+                Operation::Read(&mut second),
+            ])
+            .unwrap();
+        assert_eq!(first, [0x00; 4]);
+        assert_eq!(second, [0x00; 4]);
+
+        // released again once the transaction is done
+        other.read(&mut first).unwrap();
+        assert_eq!(first, [0xff; 4]);
+    }
+}
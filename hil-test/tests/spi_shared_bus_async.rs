@@ -0,0 +1,190 @@
+//! Shared SPI bus async Test
+//!
+//! Folowing pins are used:
+//! SCLK    GPIO0
+//! CS      GPIO2
+//! MOSI    GPIO5
+//!
+//! GPIO4 counts the edges of the CS line. Connect GPIO2 and GPIO4 pins.
+
+//% CHIPS: esp32 esp32c2 esp32c3 esp32c6 esp32h2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+use defmt_rtt as _;
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, mutex::Mutex as AsyncMutex};
+use embedded_hal::spi::Operation;
+use embedded_hal_async::spi::SpiDevice;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::{ClockControl, Clocks},
+    dma::{Dma, DmaPriority},
+    dma_descriptors,
+    gpio::{Event, Gpio0, Gpio2, Gpio4, Gpio5, Input, Io, Level, Output, Pull},
+    macros::handler,
+    peripherals::{Peripherals, DMA, SPI2},
+    prelude::*,
+    spi::{
+        master::{prelude::*, Spi},
+        shared::{DeviceConfig, HardwareCs, MutexDevice},
+        SpiMode,
+    },
+    system::SystemControl,
+};
+
+static EDGES: Mutex<RefCell<u32>> = Mutex::new(RefCell::new(0));
+static CS_INPUT: Mutex<RefCell<Option<Input<'static, Gpio4>>>> = Mutex::new(RefCell::new(None));
+
+struct Context {
+    spi: SPI2,
+    dma: DMA,
+    sclk: Gpio0,
+    cs: Gpio2,
+    mosi: Gpio5,
+    clocks: Clocks<'static>,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+
+        let mut io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+        io.set_interrupt_handler(interrupt_handler);
+
+        let mut cs_input = Input::new(io.pins.gpio4, Pull::Up);
+        critical_section::with(|cs| {
+            *EDGES.borrow_ref_mut(cs) = 0;
+            cs_input.listen(Event::AnyEdge);
+            CS_INPUT.borrow_ref_mut(cs).replace(cs_input);
+        });
+
+        Context {
+            spi: peripherals.SPI2,
+            dma: peripherals.DMA,
+            sclk: io.pins.gpio0,
+            cs: io.pins.gpio2,
+            mosi: io.pins.gpio5,
+            clocks,
+        }
+    }
+}
+
+#[handler]
+pub fn interrupt_handler() {
+    critical_section::with(|cs| {
+        *EDGES.borrow_ref_mut(cs) += 1;
+        CS_INPUT
+            .borrow_ref_mut(cs)
+            .as_mut()
+            .map(|pin| pin.clear_interrupt());
+    });
+}
+
+fn cs_edges() -> u32 {
+    critical_section::with(|cs| *EDGES.borrow_ref(cs))
+}
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal::embassy::executor::Executor::new())]
+mod tests {
+    use defmt::assert_eq;
+
+    use super::*;
+
+    #[init]
+    async fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_hardware_cs_stays_asserted_during_transaction(ctx: Context) {
+        let dma = Dma::new(ctx.dma);
+        #[cfg(feature = "esp32")]
+        let dma_channel = dma.spi2channel;
+        #[cfg(not(feature = "esp32"))]
+        let dma_channel = dma.channel0;
+        let (mut tx_descriptors, mut rx_descriptors) = dma_descriptors!(32);
+
+        let spi = Spi::new(ctx.spi, 1.MHz(), SpiMode::Mode0, &ctx.clocks)
+            .with_sck(ctx.sclk)
+            .with_mosi(ctx.mosi)
+            .with_cs1(ctx.cs)
+            .with_dma(dma_channel.configure_for_async(
+                false,
+                &mut tx_descriptors,
+                &mut rx_descriptors,
+                DmaPriority::Priority0,
+            ));
+        let bus = AsyncMutex::<NoopRawMutex, _>::new(spi);
+
+        let mut device = MutexDevice::new(
+            &bus,
+            HardwareCs::Cs1,
+            DeviceConfig::new(1.MHz(), SpiMode::Mode3),
+            &ctx.clocks,
+        );
+
+        let mut read = [0u8; 4];
+        device
+            .transaction(&mut [
+                Operation::Write(&[0xaa; 4]),
+                Operation::DelayNs(10_000),
+                Operation::Read(&mut read),
+                Operation::Write(&[0x55; 4]),
+            ])
+            .await
+            .unwrap();
+
+        // asserted once and released once
+        assert_eq!(cs_edges(), 2);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_gpio_cs_stays_asserted_during_transaction(ctx: Context) {
+        let dma = Dma::new(ctx.dma);
+        #[cfg(feature = "esp32")]
+        let dma_channel = dma.spi2channel;
+        #[cfg(not(feature = "esp32"))]
+        let dma_channel = dma.channel0;
+        let (mut tx_descriptors, mut rx_descriptors) = dma_descriptors!(32);
+
+        let spi = Spi::new(ctx.spi, 1.MHz(), SpiMode::Mode0, &ctx.clocks)
+            .with_sck(ctx.sclk)
+            .with_mosi(ctx.mosi)
+            .with_dma(dma_channel.configure_for_async(
+                false,
+                &mut tx_descriptors,
+                &mut rx_descriptors,
+                DmaPriority::Priority0,
+            ));
+        let bus = AsyncMutex::<NoopRawMutex, _>::new(spi);
+
+        let mut device = MutexDevice::new(
+            &bus,
+            Output::new(ctx.cs, Level::High),
+            DeviceConfig::new(1.MHz(), SpiMode::Mode0),
+            &ctx.clocks,
+        );
+
+        let mut read = [0u8; 4];
+        device
+            .transaction(&mut [
+                Operation::Write(&[0xaa; 4]),
+                Operation::DelayNs(10_000),
+                Operation::Read(&mut read),
+                Operation::Write(&[0x55; 4]),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(cs_edges(), 2);
+    }
+}
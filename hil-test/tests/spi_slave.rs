@@ -0,0 +1,247 @@
+//! SPI slave DMA test
+//!
+//! The master is bit-banged. SCLK, MOSI and CS are open-drain outputs which
+//! are read by the slave on the same pins, MISO of the slave is read back
+//! through the wire:
+//! SCLK    GPIO5
+//! MOSI    GPIO6
+//! CS      GPIO7
+//! MISO    GPIO2 (slave) => GPIO4 (master)
+//!
+//! Connect GPIO2 and GPIO4 pins.
+
+//% CHIPS: esp32c2 esp32c3 esp32c6 esp32h2 esp32s2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use embassy_futures::join::join;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::ClockControl,
+    delay::Delay,
+    dma::{Dma, DmaDescriptor, DmaPriority},
+    gpio::{AnyInput, AnyOutputOpenDrain, GpioPin, Io, Level, Pull},
+    peripherals::{Peripherals, SPI2},
+    prelude::*,
+    spi::{
+        slave::{dma::SpiDma, prelude::*, Spi},
+        SpiMode,
+    },
+    system::SystemControl,
+    Async,
+};
+
+#[cfg(feature = "esp32s2")]
+type DmaChannel = esp_hal::dma::Spi2DmaChannel;
+#[cfg(not(feature = "esp32s2"))]
+type DmaChannel = esp_hal::dma::Channel0;
+
+// Three descriptors, so the buffer can also be used for circular reads
+static mut TX_DESCRIPTORS: [DmaDescriptor; 3] = [DmaDescriptor::EMPTY; 3];
+static mut RX_DESCRIPTORS: [DmaDescriptor; 3] = [DmaDescriptor::EMPTY; 3];
+static mut RX_BUFFER: [u8; 24] = [0; 24];
+
+/// Bit-banged SPI master in mode 0
+struct Master {
+    sclk: AnyOutputOpenDrain<'static>,
+    mosi: AnyOutputOpenDrain<'static>,
+    cs: AnyOutputOpenDrain<'static>,
+    miso: AnyInput<'static>,
+    delay: Delay,
+}
+
+impl Master {
+    fn select(&mut self) {
+        self.cs.set_low();
+        self.delay.delay_micros(10);
+    }
+
+    fn deselect(&mut self) {
+        self.delay.delay_micros(10);
+        self.cs.set_high();
+        self.delay.delay_micros(10);
+    }
+
+    fn exchange(&mut self, byte: u8) -> u8 {
+        let mut received = 0;
+        for bit in (0..8).rev() {
+            if byte & (1 << bit) != 0 {
+                self.mosi.set_high();
+            } else {
+                self.mosi.set_low();
+            }
+            self.delay.delay_micros(5);
+            self.sclk.set_high();
+            received = received << 1 | self.miso.is_high() as u8;
+            self.delay.delay_micros(5);
+            self.sclk.set_low();
+        }
+        received
+    }
+
+    async fn transfer(&mut self, write: &[u8], read: &mut [u8]) {
+        self.select();
+        for (byte, received) in write.iter().zip(read.iter_mut()) {
+            *received = self.exchange(*byte);
+        }
+        self.deselect();
+    }
+}
+
+struct Context {
+    spi: SpiDma<'static, SPI2, DmaChannel, Async>,
+    master: Master,
+    rx_buffer: &'static mut [u8; 24],
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+        let dma = Dma::new(peripherals.DMA);
+        #[cfg(feature = "esp32s2")]
+        let dma_channel = dma.spi2channel;
+        #[cfg(not(feature = "esp32s2"))]
+        let dma_channel = dma.channel0;
+
+        let (tx_descriptors, rx_descriptors, rx_buffer) = unsafe {
+            (
+                &mut *core::ptr::addr_of_mut!(TX_DESCRIPTORS),
+                &mut *core::ptr::addr_of_mut!(RX_DESCRIPTORS),
+                &mut *core::ptr::addr_of_mut!(RX_BUFFER),
+            )
+        };
+
+        // The slave only reads SCLK, MOSI and CS, which are driven by the
+        // master configured afterwards
+        let spi = Spi::new(
+            peripherals.SPI2,
+            unsafe { GpioPin::<5>::steal() },
+            unsafe { GpioPin::<6>::steal() },
+            io.pins.gpio2,
+            unsafe { GpioPin::<7>::steal() },
+            SpiMode::Mode0,
+        )
+        .with_dma(dma_channel.configure_for_async(
+            false,
+            tx_descriptors,
+            rx_descriptors,
+            DmaPriority::Priority0,
+        ));
+
+        let master = Master {
+            sclk: AnyOutputOpenDrain::new(io.pins.gpio5, Level::Low, Pull::Up),
+            mosi: AnyOutputOpenDrain::new(io.pins.gpio6, Level::Low, Pull::Up),
+            cs: AnyOutputOpenDrain::new(io.pins.gpio7, Level::High, Pull::Up),
+            miso: AnyInput::new(io.pins.gpio4, Pull::None),
+            delay: Delay::new(&clocks),
+        };
+
+        Context {
+            spi,
+            master,
+            rx_buffer,
+        }
+    }
+}
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal::embassy::executor::Executor::new())]
+mod tests {
+    use defmt::assert_eq;
+
+    use super::*;
+
+    #[init]
+    async fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_read_ends_with_cs(mut ctx: Context) {
+        let mut buffer = [0u8; 8];
+        let mut ignored = [0u8; 4];
+        let (received, _) = join(
+            ctx.spi.read(&mut buffer),
+            ctx.master.transfer(&[1, 2, 3, 4], &mut ignored),
+        )
+        .await;
+
+        // The master de-asserted CS before the buffer was filled
+        assert_eq!(received, Ok(4));
+        assert_eq!(ctx.spi.received_len(), 4);
+        assert_eq!(buffer[..4], [1, 2, 3, 4]);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_transfer(mut ctx: Context) {
+        let mut slave_received = [0u8; 4];
+        let mut master_received = [0u8; 4];
+        let (received, _) = join(
+            ctx.spi.transfer(&mut slave_received, &[5, 6, 7, 8]),
+            ctx.master
+                .transfer(&[0xA0, 0xA1, 0xA2, 0xA3], &mut master_received),
+        )
+        .await;
+
+        assert_eq!(received, Ok(4));
+        assert_eq!(slave_received, [0xA0, 0xA1, 0xA2, 0xA3]);
+        assert_eq!(master_received, [5, 6, 7, 8]);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_received_len_of_consecutive_reads(mut ctx: Context) {
+        let mut buffer = [0u8; 8];
+        let mut ignored = [0u8; 8];
+
+        let (received, _) = join(
+            ctx.spi.read(&mut buffer),
+            ctx.master.transfer(&[1, 2, 3, 4, 5, 6], &mut ignored),
+        )
+        .await;
+        assert_eq!(received, Ok(6));
+
+        // The slave is re-armed for every read, a shorter transaction
+        // reports its own length
+        let (received, _) = join(
+            ctx.spi.read(&mut buffer),
+            ctx.master.transfer(&[9, 8], &mut ignored),
+        )
+        .await;
+        assert_eq!(received, Ok(2));
+        assert_eq!(ctx.spi.received_len(), 2);
+        assert_eq!(buffer[..2], [9, 8]);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_read_circular(mut ctx: Context) {
+        let mut transfer = ctx.spi.dma_read_circular(&mut ctx.rx_buffer).unwrap();
+
+        // The stream is sent within one transaction, every filled descriptor
+        // becomes available while CS is still asserted
+        ctx.master.select();
+        for byte in 0..16 {
+            ctx.master.exchange(byte);
+        }
+
+        let mut data = [0u8; 24];
+        let mut received = 0;
+        while received < 16 {
+            let available = transfer.available();
+            received += transfer.pop(&mut data[received..][..available]).unwrap();
+        }
+        ctx.master.deselect();
+
+        assert_eq!(received, 16);
+        assert_eq!(data[..16], core::array::from_fn::<u8, 16, _>(|i| i as u8));
+    }
+}
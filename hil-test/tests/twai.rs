@@ -0,0 +1,146 @@
+//! TWAI Test
+//!
+//! Folowing pins are used:
+//! TX    GPIO2
+//! RX    GPIO4
+//!
+//! Connect TX (GPIO2) and RX (GPIO4) pins.
+
+//% CHIPS: esp32 esp32c3 esp32c6 esp32s2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use embedded_hal_02::can::Frame;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::ClockControl,
+    gpio::Io,
+    peripherals::{Peripherals, TWAI0},
+    prelude::*,
+    system::SystemControl,
+    twai::{
+        self,
+        BusError,
+        ErrorKind,
+        EspTwaiFrame,
+        ListenOnly,
+        Normal,
+        SelfTest,
+        StandardId,
+        TwaiEvent,
+        TwaiMode,
+    },
+    Blocking,
+};
+use nb::block;
+
+struct Context {
+    twai: twai::Twai<'static, TWAI0, Blocking, SelfTest>,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+        let mut config = twai::TwaiConfiguration::new_no_transceiver(
+            peripherals.TWAI0,
+            io.pins.gpio2,
+            io.pins.gpio4,
+            &clocks,
+            twai::BaudRate::B1000K,
+            None,
+        )
+        .into_mode(SelfTest);
+        config.set_single_shot(true);
+
+        Context {
+            twai: config.start(),
+        }
+    }
+}
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use defmt::assert_eq;
+
+    use super::*;
+
+    #[init]
+    fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_self_test_receives_own_frame(mut ctx: Context) {
+        let id = StandardId::new(0x42).unwrap();
+        let frame = EspTwaiFrame::new(id.into(), &[1, 2, 3]).unwrap();
+        block!(ctx.twai.transmit(&frame)).unwrap();
+
+        let received = block!(ctx.twai.receive()).unwrap();
+
+        assert_eq!(received.data(), &[1, 2, 3]);
+        assert!(ctx.twai.mode() == TwaiMode::SelfTest);
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_start_does_not_enable_interrupts(_ctx: Context) {
+        // SAFETY:
+        // We only read the interrupt enable register
+        let register_block = unsafe { &*TWAI0::PTR };
+        #[cfg(not(feature = "esp32c6"))]
+        let int_ena = register_block.int_ena().read().bits();
+        #[cfg(feature = "esp32c6")]
+        let int_ena = register_block.interrupt_enable().read().bits();
+
+        assert_eq!(int_ena, 0);
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_poll_event_reports_missing_ack(ctx: Context) {
+        // Without another node on the bus nobody acknowledges the frame
+        let mut twai = ctx.twai.stop().into_mode(Normal).start();
+        assert!(twai.poll_event().is_none());
+
+        let id = StandardId::new(0x42).unwrap();
+        let frame = EspTwaiFrame::new(id.into(), &[1, 2, 3]).unwrap();
+        block!(twai.transmit(&frame)).unwrap();
+
+        let event = loop {
+            if let Some(event) = twai.poll_event() {
+                break event;
+            }
+        };
+        assert!(matches!(
+            event,
+            TwaiEvent::BusError(BusError {
+                kind: ErrorKind::Acknowledge,
+                during_reception: false,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_listen_only_mode(ctx: Context) {
+        let twai = ctx.twai.stop().into_mode(ListenOnly).start();
+        assert!(twai.mode() == TwaiMode::ListenOnly);
+
+        // SAFETY:
+        // We only read the mode register
+        let register_block = unsafe { &*TWAI0::PTR };
+        let mode = register_block.mode().read();
+        assert!(mode.listen_only_mode().bit_is_set());
+        assert!(mode.self_test_mode().bit_is_clear());
+    }
+}
@@ -0,0 +1,105 @@
+//! TWAI async Test
+//!
+//! Folowing pins are used:
+//! TX    GPIO2
+//! RX    GPIO4
+//!
+//! Connect TX (GPIO2) and RX (GPIO4) pins.
+
+//% CHIPS: esp32 esp32c3 esp32c6 esp32s2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::ClockControl,
+    gpio::Io,
+    peripherals::{Peripherals, TWAI0},
+    prelude::*,
+    system::SystemControl,
+    twai::{self, BusError, ErrorKind, EspTwaiFrame, StandardId, TwaiEvent},
+    Async,
+};
+
+struct Context {
+    twai: twai::Twai<'static, TWAI0, Async>,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+        // Without another node on the bus nobody acknowledges the frames
+        let mut config = twai::TwaiConfiguration::new_async_no_transceiver(
+            peripherals.TWAI0,
+            io.pins.gpio2,
+            io.pins.gpio4,
+            &clocks,
+            twai::BaudRate::B1000K,
+        );
+        config.set_single_shot(true);
+
+        Context {
+            twai: config.start(),
+        }
+    }
+}
+
+fn is_missing_ack(event: TwaiEvent) -> bool {
+    matches!(
+        event,
+        TwaiEvent::BusError(BusError {
+            kind: ErrorKind::Acknowledge,
+            during_reception: false,
+            ..
+        })
+    )
+}
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal::embassy::executor::Executor::new())]
+mod tests {
+    use core::{future::poll_fn, pin::Pin};
+
+    use super::*;
+
+    #[init]
+    async fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_event_stream(ctx: Context) {
+        let (mut tx, mut rx) = ctx.twai.split();
+        let mut events = rx.events();
+
+        let id = StandardId::new(0x42).unwrap();
+        let frame = EspTwaiFrame::new(id.into(), &[1, 2, 3]).unwrap();
+        tx.transmit_async(&frame).await.unwrap();
+
+        assert!(is_missing_ack(events.next().await));
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_event_stream_poll_next(ctx: Context) {
+        let (mut tx, mut rx) = ctx.twai.split();
+        let mut events = rx.events();
+
+        let id = StandardId::new(0x42).unwrap();
+        let frame = EspTwaiFrame::new(id.into(), &[1, 2, 3]).unwrap();
+        for _ in 0..2 {
+            tx.transmit_async(&frame).await.unwrap();
+
+            let event = poll_fn(|cx| Pin::new(&mut events).poll_next(cx)).await;
+            assert!(event.is_some_and(is_missing_ack));
+        }
+    }
+}
@@ -0,0 +1,286 @@
+//! UART RS-485, flow control and signal inversion tests
+//!
+//! Folowing pins are used:
+//! TX    GPIP2
+//! RX    GPIO4
+//!
+//! The RS-485 driver enable and the flow control tests route RTS to GPIO2
+//! instead, or drive RX and CTS from GPIO5 configured as open-drain output.
+//! GPIO0 takes the unused outputs.
+//!
+//! Connect TX (GPIO2) and RX (GPIO4) pins.
+
+//% CHIPS: esp32 esp32c2 esp32c3 esp32c6 esp32h2 esp32s2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::{ClockControl, Clocks},
+    delay::Delay,
+    gpio::{AnyOutputOpenDrain, Gpio2, Gpio4, Gpio5, GpioPin, Input, Io, Level, Pull},
+    peripheral::Peripheral,
+    peripherals::{Peripherals, UART0},
+    prelude::*,
+    system::SystemControl,
+    uart::{
+        config::{Config, HwFlowControl, InvertedSignals, Rs485Config, SwFlowControl},
+        AllPins,
+        TxRxPins,
+        Uart,
+    },
+    Blocking,
+};
+use nb::block;
+
+struct Context {
+    clocks: Clocks<'static>,
+    delay: Delay,
+    uart: UART0,
+    tx: Gpio2,
+    rx: Gpio4,
+    line: Gpio5,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+        let delay = Delay::new(&clocks);
+
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+
+        Context {
+            clocks,
+            delay,
+            uart: peripherals.UART0,
+            tx: io.pins.gpio2,
+            rx: io.pins.gpio4,
+            line: io.pins.gpio5,
+        }
+    }
+
+    fn loopback(self, config: Config) -> (Uart<'static, UART0, Blocking>, Delay) {
+        let pins = TxRxPins::new_tx_rx(self.tx, self.rx);
+        let uart = Uart::new_with_config(self.uart, config, Some(pins), &self.clocks, None);
+
+        (uart, self.delay)
+    }
+}
+
+/// Baudrate of the bit-banged frames
+const BAUDRATE: u32 = 9600;
+
+/// Send `byte` as a bit-banged 8N1 frame
+fn send_frame(line: &mut AnyOutputOpenDrain<'_>, delay: &Delay, byte: u8) {
+    let bit_time = 1_000_000 / BAUDRATE;
+
+    line.set_low();
+    delay.delay_micros(bit_time);
+    for bit in 0..8 {
+        line.set_level(Level::from(byte & (1 << bit) != 0));
+        delay.delay_micros(bit_time);
+    }
+    line.set_high();
+    delay.delay_micros(bit_time);
+}
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use defmt::assert_eq;
+
+    use super::*;
+
+    #[init]
+    fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_inverted_loopback(ctx: Context) {
+        let config = Config::default().inverted(InvertedSignals {
+            tx: true,
+            rx: true,
+            ..Default::default()
+        });
+        let (mut uart, _) = ctx.loopback(config);
+
+        uart.write_byte(0x42).ok();
+        assert_eq!(block!(uart.read_byte()), Ok(0x42));
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_inverted_tx_idles_low(ctx: Context) {
+        let config = Config::default().inverted(InvertedSignals {
+            tx: true,
+            ..Default::default()
+        });
+        let pins = TxRxPins::<_, Gpio4> {
+            tx: Some(ctx.tx.into_ref()),
+            rx: None,
+        };
+        let _uart = Uart::new_with_config(ctx.uart, config, Some(pins), &ctx.clocks, None);
+
+        let line = Input::new(ctx.rx, Pull::Up);
+        ctx.delay.delay_millis(1);
+        assert_eq!(line.is_low(), true);
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_rs485_echo_suppression(ctx: Context) {
+        let (mut uart, delay) = ctx.loopback(Config::default().rs485(Rs485Config::default()));
+
+        uart.write_byte(0x42).ok();
+        block!(uart.flush_tx()).ok();
+        delay.delay_millis(1);
+
+        // The transmitted data never reaches the RX FIFO
+        assert!(matches!(uart.read_byte(), Err(nb::Error::WouldBlock)));
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_rs485_receive_while_transmitting(ctx: Context) {
+        let config = Config::default().rs485(Rs485Config {
+            echo_suppression: false,
+            collision_detection: false,
+        });
+        let (mut uart, _) = ctx.loopback(config);
+
+        uart.write_bytes(&[0x12, 0x34]).ok();
+        assert_eq!(block!(uart.read_byte()), Ok(0x12));
+        assert_eq!(block!(uart.read_byte()), Ok(0x34));
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_rs485_no_collision_on_loopback(ctx: Context) {
+        let config = Config::default().rs485(Rs485Config {
+            echo_suppression: true,
+            collision_detection: true,
+        });
+        let (mut uart, _) = ctx.loopback(config);
+        uart.reset_rs485_collision();
+
+        // Collision detection overrides echo suppression
+        uart.write_byte(0x42).ok();
+        assert_eq!(block!(uart.read_byte()), Ok(0x42));
+        assert_eq!(uart.rs485_collision_detected(), false);
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_rs485_driver_enable(ctx: Context) {
+        // RTS drives the wire, TX isn't connected
+        let pins = AllPins::new(
+            unsafe { GpioPin::<0>::steal() },
+            unsafe { GpioPin::<5>::steal() },
+            unsafe { GpioPin::<5>::steal() },
+            ctx.tx,
+        );
+        let config = Config::default().rs485(Rs485Config::default());
+        let mut uart = Uart::new_with_config(ctx.uart, config, Some(pins), &ctx.clocks, None);
+        let driver_enable = Input::new(ctx.rx, Pull::Down);
+
+        ctx.delay.delay_millis(1);
+        assert_eq!(driver_enable.is_low(), true);
+
+        // RTS is high while the byte is sent and low again once it is done
+        uart.write_byte(0x42).ok();
+        assert_eq!(driver_enable.is_high(), true);
+        block!(uart.flush_tx()).ok();
+        assert_eq!(driver_enable.is_low(), true);
+
+        uart.write_bytes(&[0x55; 16]).ok();
+        assert_eq!(driver_enable.is_low(), true);
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_hw_flow_control_cts(ctx: Context) {
+        let pins = AllPins::new(
+            ctx.tx,
+            unsafe { GpioPin::<4>::steal() },
+            unsafe { GpioPin::<5>::steal() },
+            unsafe { GpioPin::<0>::steal() },
+        );
+        let config = Config::default().hw_flow_control(HwFlowControl {
+            rts_threshold: None,
+            cts: true,
+        });
+        let mut uart = Uart::new_with_config(ctx.uart, config, Some(pins), &ctx.clocks, None);
+        let mut cts = AnyOutputOpenDrain::new(ctx.line, Level::High, Pull::Up);
+
+        // Nothing is sent while CTS is deasserted
+        uart.write_byte(0x42).ok();
+        ctx.delay.delay_millis(1);
+        assert!(matches!(uart.read_byte(), Err(nb::Error::WouldBlock)));
+
+        cts.set_low();
+        assert_eq!(block!(uart.read_byte()), Ok(0x42));
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_hw_flow_control_rts(ctx: Context) {
+        // RTS drives the wire, RX is bit-banged
+        let pins = AllPins::new(
+            unsafe { GpioPin::<0>::steal() },
+            unsafe { GpioPin::<5>::steal() },
+            unsafe { GpioPin::<5>::steal() },
+            ctx.tx,
+        );
+        let config = Config::default()
+            .baudrate(BAUDRATE)
+            .hw_flow_control(HwFlowControl {
+                rts_threshold: Some(2),
+                cts: false,
+            });
+        let mut uart = Uart::new_with_config(ctx.uart, config, Some(pins), &ctx.clocks, None);
+        let mut line = AnyOutputOpenDrain::new(ctx.line, Level::High, Pull::Up);
+        let rts = Input::new(ctx.rx, Pull::Down);
+
+        // drop anything received while RX was floating
+        ctx.delay.delay_millis(1);
+        while uart.read_byte().is_ok() {}
+        assert_eq!(rts.is_low(), true);
+
+        // RTS is deasserted once the RX FIFO holds more than 2 bytes
+        for byte in [0x12, 0x34, 0x56] {
+            send_frame(&mut line, &ctx.delay, byte);
+        }
+        ctx.delay.delay_millis(1);
+        assert_eq!(rts.is_high(), true);
+
+        assert_eq!(block!(uart.read_byte()), Ok(0x12));
+        assert_eq!(block!(uart.read_byte()), Ok(0x34));
+        assert_eq!(block!(uart.read_byte()), Ok(0x56));
+        assert_eq!(rts.is_low(), true);
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_sw_flow_control_xoff(ctx: Context) {
+        let flow_control = SwFlowControl::default();
+        let (mut uart, delay) = ctx.loopback(Config::default().sw_flow_control(flow_control));
+
+        uart.write_byte(0x42).ok();
+        assert_eq!(block!(uart.read_byte()), Ok(0x42));
+
+        // Receiving XOFF pauses our own transmitter, and the flow control
+        // character is removed from the received data
+        uart.write_byte(flow_control.xoff_char).ok();
+        delay.delay_millis(1);
+        uart.write_byte(0x43).ok();
+        delay.delay_millis(1);
+
+        assert!(matches!(uart.read_byte(), Err(nb::Error::WouldBlock)));
+    }
+}
@@ -0,0 +1,196 @@
+//! UART DMA Test
+//!
+//! Folowing pins are used:
+//! TX    GPIP2
+//! RX    GPIO4
+//!
+//! Connect TX (GPIO2) and RX (GPIO4) pins.
+
+//% CHIPS: esp32c3 esp32c6 esp32h2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::ClockControl,
+    dma::{Dma, DmaPriority},
+    dma_buffers,
+    dma_circular_buffers,
+    gpio::Io,
+    peripherals::{Peripherals, DMA, UART1, UHCI0},
+    system::SystemControl,
+    uart::{config::Config, TxRxPins, Uart},
+    Blocking,
+};
+
+const SEND: &[u8] = b"Hello ESP32 via UHCI";
+
+struct Context {
+    uart: Uart<'static, UART1, Blocking>,
+    uhci: UHCI0,
+    dma: DMA,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+        let pins = TxRxPins::new_tx_rx(io.pins.gpio2, io.pins.gpio4);
+
+        let uart = Uart::new_with_config(
+            peripherals.UART1,
+            Config::default(),
+            Some(pins),
+            &clocks,
+            None,
+        );
+
+        Context {
+            uart,
+            uhci: peripherals.UHCI0,
+            dma: peripherals.DMA,
+        }
+    }
+}
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use defmt::assert_eq;
+
+    use super::*;
+
+    #[init]
+    fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_dma_send_receive(ctx: Context) {
+        let dma = Dma::new(ctx.dma);
+        let (tx_buffer, mut tx_descriptors, rx_buffer, mut rx_descriptors) =
+            dma_buffers!(SEND.len());
+
+        let mut uart = ctx.uart.with_dma(
+            ctx.uhci,
+            dma.channel0.configure(
+                false,
+                &mut tx_descriptors,
+                &mut rx_descriptors,
+                DmaPriority::Priority0,
+            ),
+        );
+
+        // DMA buffer require a static life-time
+        let mut send = tx_buffer;
+        let mut receive = rx_buffer;
+        send.copy_from_slice(SEND);
+
+        // The message fits into the RX FIFO, so it can be sent before
+        // starting to receive
+        let transfer = uart.dma_write(&mut send).unwrap();
+        transfer.wait().unwrap();
+
+        let transfer = uart.dma_read(&mut receive).unwrap();
+        transfer.wait().unwrap();
+
+        assert_eq!(uart.received_len(), SEND.len());
+        assert_eq!(&receive[..], SEND);
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_dma_receive_until_idle(ctx: Context) {
+        let dma = Dma::new(ctx.dma);
+        let (tx_buffer, mut tx_descriptors, rx_buffer, mut rx_descriptors) =
+            dma_buffers!(SEND.len(), 64);
+
+        let mut uart = ctx.uart.with_dma(
+            ctx.uhci,
+            dma.channel0.configure(
+                false,
+                &mut tx_descriptors,
+                &mut rx_descriptors,
+                DmaPriority::Priority0,
+            ),
+        );
+
+        let mut send = tx_buffer;
+        let mut receive = rx_buffer;
+        send.copy_from_slice(SEND);
+
+        let transfer = uart.dma_write(&mut send).unwrap();
+        transfer.wait().unwrap();
+
+        // the buffer isn't filled, the idle line ends the transfer
+        uart.set_rx_idle_threshold(20).unwrap();
+        let transfer = uart.dma_read(&mut receive).unwrap();
+        transfer.wait().unwrap();
+
+        assert_eq!(uart.received_len(), SEND.len());
+        assert_eq!(&receive[..SEND.len()], SEND);
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_dma_read_circular_partial_pop(ctx: Context) {
+        let dma = Dma::new(ctx.dma);
+        let (tx_buffer, mut tx_descriptors, rx_buffer, mut rx_descriptors) =
+            dma_circular_buffers!(SEND.len(), 96);
+
+        let mut uart = ctx.uart.with_dma(
+            ctx.uhci,
+            dma.channel0.configure(
+                false,
+                &mut tx_descriptors,
+                &mut rx_descriptors,
+                DmaPriority::Priority0,
+            ),
+        );
+
+        let mut send = tx_buffer;
+        let mut receive = rx_buffer;
+        send.copy_from_slice(SEND);
+
+        let transfer = uart.dma_write(&mut send).unwrap();
+        transfer.wait().unwrap();
+
+        // the idle line closes the first descriptor after the message
+        let mut transfer = uart.dma_read_circular(&mut receive).unwrap();
+        while transfer.available() < SEND.len() {}
+
+        let mut data = [0u8; SEND.len()];
+        let (head, tail) = data.split_at_mut(8);
+        assert_eq!(transfer.pop(head).unwrap(), 8);
+        assert_eq!(transfer.available(), SEND.len() - 8);
+        assert_eq!(transfer.pop(tail).unwrap(), SEND.len() - 8);
+        assert_eq!(transfer.available(), 0);
+
+        assert_eq!(&data[..], SEND);
+    }
+
+    #[test]
+    #[timeout(3)]
+    fn test_invalid_idle_threshold(ctx: Context) {
+        let dma = Dma::new(ctx.dma);
+        let (_, mut tx_descriptors, _, mut rx_descriptors) = dma_buffers!(32);
+
+        let mut uart = ctx.uart.with_dma(
+            ctx.uhci,
+            dma.channel0.configure(
+                false,
+                &mut tx_descriptors,
+                &mut rx_descriptors,
+                DmaPriority::Priority0,
+            ),
+        );
+
+        assert!(uart.set_rx_idle_threshold(0x100).is_ok());
+        assert!(uart.set_rx_idle_threshold(0x400).is_err());
+    }
+}
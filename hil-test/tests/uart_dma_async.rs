@@ -0,0 +1,122 @@
+//! UART DMA async Test
+//!
+//! Folowing pins are used:
+//! TX    GPIP2
+//! RX    GPIO4
+//!
+//! Connect TX (GPIO2) and RX (GPIO4) pins.
+
+//% CHIPS: esp32c3 esp32c6 esp32h2 esp32s3
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use esp_backtrace as _;
+use esp_hal::{
+    clock::ClockControl,
+    dma::{Dma, DmaPriority},
+    dma_descriptors,
+    gpio::Io,
+    peripherals::{Peripherals, DMA, UART1, UHCI0},
+    system::SystemControl,
+    uart::{config::Config, TxRxPins, Uart},
+    Blocking,
+};
+
+const SEND: &[u8] = b"Hello ESP32 via UHCI";
+
+struct Context {
+    uart: Uart<'static, UART1, Blocking>,
+    uhci: UHCI0,
+    dma: DMA,
+}
+
+impl Context {
+    pub fn init() -> Self {
+        let peripherals = Peripherals::take();
+        let system = SystemControl::new(peripherals.SYSTEM);
+        let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+        let io = Io::new(peripherals.GPIO, peripherals.IO_MUX);
+        let pins = TxRxPins::new_tx_rx(io.pins.gpio2, io.pins.gpio4);
+
+        let uart = Uart::new_with_config(
+            peripherals.UART1,
+            Config::default(),
+            Some(pins),
+            &clocks,
+            None,
+        );
+
+        Context {
+            uart,
+            uhci: peripherals.UHCI0,
+            dma: peripherals.DMA,
+        }
+    }
+}
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal::embassy::executor::Executor::new())]
+mod tests {
+    use defmt::assert_eq;
+
+    use super::*;
+
+    #[init]
+    async fn init() -> Context {
+        Context::init()
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_dma_write_read_async(ctx: Context) {
+        let dma = Dma::new(ctx.dma);
+        let (mut tx_descriptors, mut rx_descriptors) = dma_descriptors!(64);
+
+        let mut uart = ctx.uart.with_dma(
+            ctx.uhci,
+            dma.channel0.configure_for_async(
+                false,
+                &mut tx_descriptors,
+                &mut rx_descriptors,
+                DmaPriority::Priority0,
+            ),
+        );
+
+        // The message fits into the RX FIFO, so it can be sent before
+        // starting to receive
+        assert_eq!(uart.write_async(SEND).await.unwrap(), SEND.len());
+
+        // the buffer isn't filled, the idle line ends the transfer
+        let mut receive = [0u8; 64];
+        let len = uart.read_async(&mut receive).await.unwrap();
+
+        assert_eq!(len, SEND.len());
+        assert_eq!(&receive[..len], SEND);
+    }
+
+    #[test]
+    #[timeout(3)]
+    async fn test_dma_read_async_full_buffer(ctx: Context) {
+        let dma = Dma::new(ctx.dma);
+        let (mut tx_descriptors, mut rx_descriptors) = dma_descriptors!(64);
+
+        let mut uart = ctx.uart.with_dma(
+            ctx.uhci,
+            dma.channel0.configure_for_async(
+                false,
+                &mut tx_descriptors,
+                &mut rx_descriptors,
+                DmaPriority::Priority0,
+            ),
+        );
+
+        uart.write_async(SEND).await.unwrap();
+
+        // the rest of the message is left in the FIFO
+        let mut receive = [0u8; 8];
+        assert_eq!(uart.read_async(&mut receive).await.unwrap(), 8);
+        assert_eq!(&receive[..], &SEND[..8]);
+    }
+}